@@ -6,6 +6,9 @@ use std::sync::Arc;
 #[cfg(target_os = "windows")]
 use nodio_win32::Win32Context as PlatformContext;
 
+#[cfg(not(target_os = "windows"))]
+use nodio_sim::SimulatedContext as PlatformContext;
+
 pub fn create_nodio_context() -> Arc<RwLock<dyn Context>> {
     PlatformContext::new()
 }
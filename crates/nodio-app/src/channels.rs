@@ -0,0 +1,123 @@
+use eframe::egui;
+use egui::{ComboBox, DragValue, Grid, Response, Ui};
+
+use nodio_core::{ChannelLayout, ChannelMatrix, ChannelPreset, Uuid};
+
+/// Channel matrix of a link being edited. Gains are applied when done dragging them, as the
+/// backend may restart the stream of the link.
+pub struct ChannelMatrixEdit {
+    pub link_id: Uuid,
+    pub src_id: Uuid,
+    pub dst_id: Uuid,
+    /// Names of the linked nodes
+    pub title: String,
+    pub matrix: Option<ChannelMatrix>,
+}
+
+/// Shows the window for editing the channel matrix of a link.
+/// Returns true if the matrix should be applied.
+pub fn channel_matrix_window(
+    ui_ctx: &egui::Context,
+    open: &mut bool,
+    edit: &mut ChannelMatrixEdit,
+) -> bool {
+    let mut changed = false;
+
+    egui::Window::new(format!("Channels: {}", edit.title))
+        .id(egui::Id::new((edit.link_id, "channels")))
+        .open(open)
+        .resizable(false)
+        .show(ui_ctx, |ui| {
+            let mut mapped = edit.matrix.is_some();
+            if ui.checkbox(&mut mapped, "Map channels").changed() {
+                edit.matrix = mapped.then(ChannelMatrix::default);
+                changed = true;
+            }
+
+            if let Some(matrix) = &mut edit.matrix {
+                ui.separator();
+                changed |= matrix_ui(ui, matrix);
+            }
+        });
+
+    changed
+}
+
+fn matrix_ui(ui: &mut Ui, matrix: &mut ChannelMatrix) -> bool {
+    let mut changed = false;
+
+    ui.horizontal(|ui| {
+        let (mut inputs, mut outputs) = (matrix.inputs, matrix.outputs);
+
+        ui.label("From");
+        let inputs_changed = layout_combo(ui, "inputs", &mut inputs);
+        ui.label("to");
+        let outputs_changed = layout_combo(ui, "outputs", &mut outputs);
+
+        if inputs_changed || outputs_changed {
+            matrix.set_layouts(inputs, outputs);
+            changed = true;
+        }
+    });
+
+    ui.horizontal(|ui| {
+        for preset in ChannelPreset::ALL {
+            if ui.button(preset.name()).clicked() {
+                *matrix = ChannelMatrix::preset(preset, matrix.inputs, matrix.outputs);
+                changed = true;
+            }
+        }
+    });
+
+    ui.separator();
+
+    // One row per source channel, one column per target channel
+    Grid::new("channel_matrix").show(ui, |ui| {
+        ui.label("");
+        for name in matrix.outputs.channel_names() {
+            ui.label(*name);
+        }
+        ui.end_row();
+
+        for (input, name) in matrix.inputs.channel_names().iter().enumerate() {
+            ui.label(*name);
+
+            for output in 0..matrix.outputs.channels() {
+                let mut gain = matrix.gain(input, output);
+                let response = ui.add(
+                    DragValue::new(&mut gain)
+                        .clamp_range(0.0..=1.0)
+                        .speed(0.01)
+                        .fixed_decimals(2),
+                );
+
+                if response.changed() {
+                    matrix.set_gain(input, output, gain);
+                }
+                changed |= edit_done(&response);
+            }
+            ui.end_row();
+        }
+    });
+
+    changed
+}
+
+/// Whether a drag value has been released, or was changed by typing.
+fn edit_done(response: &Response) -> bool {
+    response.drag_released() || (response.changed() && !response.dragged())
+}
+
+fn layout_combo(ui: &mut Ui, id: &str, layout: &mut ChannelLayout) -> bool {
+    let mut changed = false;
+
+    ComboBox::from_id_source(id)
+        .selected_text(layout.name())
+        .show_ui(ui, |ui| {
+            for option in ChannelLayout::ALL {
+                changed |= ui.selectable_value(layout, option, option.name()).changed();
+            }
+        });
+
+    changed
+}
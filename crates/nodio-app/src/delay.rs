@@ -0,0 +1,209 @@
+use eframe::egui;
+use egui::{Color32, ComboBox, DragValue, Response, RichText, Ui};
+
+use log::warn;
+use nodio_core::{align_delays, CalibrationStatus, Context, DeviceInfo, Uuid, MAX_LINK_DELAY_MS};
+
+/// Delay of a link being edited. It is applied when done dragging it, as the backend may restart
+/// the stream of the link.
+pub struct DelayEdit {
+    pub link_id: Uuid,
+    pub src_id: Uuid,
+    pub dst_id: Uuid,
+    /// Names of the linked nodes
+    pub title: String,
+    pub delay_ms: f32,
+    /// Input device that listens for the click
+    pub capture_id: Option<Uuid>,
+    pub calibration: Option<Calibration>,
+}
+
+/// Measures the latency of the outputs of a source one after another.
+pub struct Calibration {
+    /// Output devices still to be measured (id, name)
+    pub pending: Vec<(Uuid, String)>,
+    /// Output device being measured
+    pub current: Option<(Uuid, String)>,
+    pub latencies: Vec<(Uuid, f32)>,
+    /// Names of the output devices where the click was not heard
+    pub failed: Vec<String>,
+}
+
+impl Calibration {
+    pub fn new(targets: Vec<(Uuid, String)>) -> Self {
+        Self {
+            pending: targets,
+            current: None,
+            latencies: Vec::new(),
+            failed: Vec::new(),
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.current.is_some() || !self.pending.is_empty()
+    }
+
+    /// Measures the next output when the current one is done. Returns the delays that align the
+    /// outputs once all of them have been measured.
+    pub fn update(&mut self, ctx: &mut dyn Context, capture_id: Uuid) -> Option<Vec<(Uuid, f32)>> {
+        if let Some((target_id, name)) = self.current.take() {
+            match ctx.calibration_status() {
+                Some(CalibrationStatus::Running) => {
+                    self.current = Some((target_id, name));
+                    return None;
+                }
+                Some(CalibrationStatus::Measured(latency_ms)) => {
+                    self.latencies.push((target_id, latency_ms))
+                }
+                _ => self.failed.push(name),
+            }
+
+            ctx.stop_calibration();
+        }
+
+        while !self.pending.is_empty() {
+            let (target_id, name) = self.pending.remove(0);
+
+            match ctx.start_calibration(target_id, capture_id) {
+                Ok(()) => {
+                    self.current = Some((target_id, name));
+                    return None;
+                }
+                Err(err) => {
+                    warn!("Could not calibrate {}: {}", name, err);
+                    self.failed.push(name);
+                }
+            }
+        }
+
+        Some(align_delays(&self.latencies))
+    }
+}
+
+#[derive(Default)]
+pub struct DelayResponse {
+    /// The delay should be applied
+    pub changed: bool,
+    pub calibrate: bool,
+    pub cancel_calibration: bool,
+}
+
+/// Shows the window for editing the delay of a link. Calibration is only offered when Nodio
+/// renders every output link of the source, as the delays are set on those links, and their
+/// shared capture of the source adds the same latency to each of them.
+pub fn delay_window(
+    ui_ctx: &egui::Context,
+    open: &mut bool,
+    edit: &mut DelayEdit,
+    latency_ms: Option<f32>,
+    input_devices: &[DeviceInfo],
+    unrendered_targets: &[String],
+) -> DelayResponse {
+    let mut response = DelayResponse::default();
+
+    egui::Window::new(format!("Delay: {}", edit.title))
+        .id(egui::Id::new((edit.link_id, "delay")))
+        .open(open)
+        .resizable(false)
+        .show(ui_ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Delay");
+                let drag_response = ui.add(
+                    DragValue::new(&mut edit.delay_ms)
+                        .clamp_range(0.0..=MAX_LINK_DELAY_MS)
+                        .speed(1.0)
+                        .max_decimals(1)
+                        .suffix(" ms"),
+                );
+                response.changed |= edit_done(&drag_response);
+            });
+
+            let latency_text = match latency_ms {
+                Some(latency_ms) => format!("Latency: {:.1} ms", latency_ms),
+                None => "Latency: unknown".to_string(),
+            };
+            ui.label(latency_text).on_hover_text(
+                "Time until the audio is played by the device, including the delay. \
+                 Only known for streams that Nodio renders itself.",
+            );
+
+            ui.separator();
+            calibration_ui(ui, edit, input_devices, unrendered_targets, &mut response);
+        });
+
+    response
+}
+
+fn calibration_ui(
+    ui: &mut Ui,
+    edit: &mut DelayEdit,
+    input_devices: &[DeviceInfo],
+    unrendered_targets: &[String],
+    response: &mut DelayResponse,
+) {
+    ui.label("Align the outputs of the source by playing a click on each of them")
+        .on_hover_text("The microphone should be close to the speakers or headphones");
+
+    let selected_text = edit
+        .capture_id
+        .and_then(|capture_id| input_devices.iter().find(|device| device.id == capture_id))
+        .map_or("Select microphone", |device| device.name.as_str());
+
+    ComboBox::from_id_source((edit.link_id, "capture"))
+        .selected_text(selected_text)
+        .width(200.0)
+        .show_ui(ui, |ui| {
+            for device in input_devices {
+                ui.selectable_value(&mut edit.capture_id, Some(device.id), &device.name);
+            }
+        });
+
+    match &edit.calibration {
+        Some(calibration) if calibration.is_running() => {
+            ui.horizontal(|ui| {
+                ui.spinner();
+                if let Some((_, name)) = &calibration.current {
+                    ui.label(format!("Measuring {}…", name));
+                }
+                if ui.button("Cancel").clicked() {
+                    response.cancel_calibration = true;
+                }
+            });
+        }
+        calibration => {
+            for target in unrendered_targets {
+                ui.colored_label(
+                    Color32::YELLOW,
+                    format!(
+                        "Can not calibrate: the system routes the link to {}, which can not be delayed",
+                        target
+                    ),
+                );
+            }
+
+            if ui
+                .add_enabled(
+                    edit.capture_id.is_some() && unrendered_targets.is_empty(),
+                    egui::Button::new("Calibrate"),
+                )
+                .clicked()
+            {
+                response.calibrate = true;
+            }
+
+            if let Some(calibration) = calibration {
+                for name in &calibration.failed {
+                    ui.label(
+                        RichText::new(format!("The click was not heard from {}", name))
+                            .color(Color32::YELLOW),
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// Whether a drag value has been released, or was changed by typing.
+fn edit_done(response: &Response) -> bool {
+    response.drag_released() || (response.changed() && !response.dragged())
+}
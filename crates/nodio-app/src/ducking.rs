@@ -0,0 +1,127 @@
+use eframe::egui;
+use egui::{ComboBox, DragValue, Ui};
+
+use nodio_core::{DuckingRule, Node, Uuid};
+
+/// Shows the window for editing the ducking rules.
+/// Returns true if any of the rules were changed.
+pub fn ducking_window(
+    ui_ctx: &egui::Context,
+    open: &mut bool,
+    rules: &mut Vec<DuckingRule>,
+    nodes: &[Node],
+) -> bool {
+    let mut changed = false;
+
+    egui::Window::new("Ducking")
+        .open(open)
+        .resizable(true)
+        .default_width(360.0)
+        .show(ui_ctx, |ui| {
+            ui.label("Lowers the volume of a node while another node is playing audio.");
+            ui.separator();
+
+            let mut removed_rule = None;
+
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                for (rule_idx, rule) in rules.iter_mut().enumerate() {
+                    ui.push_id(rule.id, |ui| {
+                        if rule_ui(ui, rule, nodes, &mut changed) {
+                            removed_rule = Some(rule_idx);
+                        }
+                    });
+                    ui.separator();
+                }
+            });
+
+            if let Some(rule_idx) = removed_rule {
+                rules.remove(rule_idx);
+                changed = true;
+            }
+
+            if ui.button("Add ducking").clicked() {
+                rules.push(DuckingRule::default());
+                changed = true;
+            }
+        });
+
+    changed
+}
+
+/// Returns true if the rule should be removed.
+fn rule_ui(ui: &mut Ui, rule: &mut DuckingRule, nodes: &[Node], changed: &mut bool) -> bool {
+    let mut remove = false;
+
+    ui.horizontal(|ui| {
+        *changed |= ui.checkbox(&mut rule.enabled, "When").changed();
+        *changed |= node_combo(ui, "trigger", &mut rule.trigger_id, nodes);
+        ui.label("plays");
+
+        if ui.button("Remove").clicked() {
+            remove = true;
+        }
+    });
+
+    ui.horizontal(|ui| {
+        ui.label("lower");
+        *changed |= node_combo(ui, "target", &mut rule.target_id, nodes);
+        ui.label("by");
+        *changed |= ui
+            .add(
+                DragValue::new(&mut rule.attenuation_db)
+                    .clamp_range(0.0..=60.0)
+                    .suffix(" dB"),
+            )
+            .changed();
+    });
+
+    ui.horizontal(|ui| {
+        ui.label("Threshold");
+        *changed |= ui
+            .add(
+                DragValue::new(&mut rule.threshold_db)
+                    .clamp_range(-90.0..=0.0)
+                    .suffix(" dB"),
+            )
+            .changed();
+        ui.label("Attack");
+        *changed |= ui
+            .add(
+                DragValue::new(&mut rule.attack_ms)
+                    .clamp_range(0.0..=5000.0)
+                    .suffix(" ms"),
+            )
+            .changed();
+        ui.label("Release");
+        *changed |= ui
+            .add(
+                DragValue::new(&mut rule.release_ms)
+                    .clamp_range(0.0..=5000.0)
+                    .suffix(" ms"),
+            )
+            .changed();
+    });
+
+    remove
+}
+
+/// Returns true if the selected node was changed.
+fn node_combo(ui: &mut Ui, id_source: &str, node_id: &mut Uuid, nodes: &[Node]) -> bool {
+    let previous_node_id = *node_id;
+
+    let selected_text = nodes
+        .iter()
+        .find(|node| node.id == *node_id)
+        .map(|node| node.display_name.as_str())
+        .unwrap_or("Select node");
+
+    ComboBox::from_id_source(id_source)
+        .selected_text(selected_text)
+        .show_ui(ui, |ui| {
+            for node in nodes {
+                ui.selectable_value(node_id, node.id, &node.display_name);
+            }
+        });
+
+    *node_id != previous_node_id
+}
@@ -0,0 +1,192 @@
+use eframe::egui;
+use egui::{CollapsingHeader, Color32, Label, Slider, Ui};
+
+use nodio_core::{EffectConfig, EffectKind, Uuid};
+use nodio_engine::PluginDescriptor;
+
+pub enum EffectsChange {
+    /// Effects were added, removed, enabled or disabled
+    Effects,
+    Param {
+        effect_id: Uuid,
+        param_idx: usize,
+        value: f32,
+    },
+    /// A plugin was chosen to be added. It is loaded by the caller.
+    AddPlugin(PluginDescriptor),
+}
+
+/// Effects inserted into a link, being edited
+pub struct LinkEffectsEdit {
+    pub link_id: Uuid,
+    pub src_id: Uuid,
+    pub dst_id: Uuid,
+    /// Names of the linked nodes
+    pub title: String,
+    /// Why the last plugin could not be added
+    pub error: Option<String>,
+}
+
+/// Shows the collapsible effects area of a node, with the links of the node that the effects
+/// are not applied to.
+pub fn effects_ui(
+    ui: &mut Ui,
+    node_id: Uuid,
+    effects: &mut Vec<EffectConfig>,
+    plugins: &[PluginDescriptor],
+    unprocessed_links: &[String],
+) -> Option<EffectsChange> {
+    CollapsingHeader::new(if effects.is_empty() {
+        "FX".to_string()
+    } else {
+        format!("FX ({})", effects.len())
+    })
+    .id_source((node_id, "fx"))
+    .show(ui, |ui| {
+        for target in unprocessed_links {
+            ui.colored_label(
+                Color32::YELLOW,
+                format!(
+                    "Not applied to the link to {}, which the system routes",
+                    target
+                ),
+            );
+        }
+
+        effect_list_ui(ui, effects, plugins)
+    })
+    .body_returned
+    .flatten()
+}
+
+/// Shows in place of the effects area of a node whose effects would not be applied.
+pub fn no_effects_ui(ui: &mut Ui) {
+    ui.add_enabled(false, Label::new("FX"))
+        .on_disabled_hover_text(
+            "Effects are not applied to outputs. Add them to the links instead.",
+        );
+}
+
+/// Shows the window for editing the effects of a link. The effects are only applied if the link
+/// is processed by Nodio.
+pub fn link_effects_window(
+    ui_ctx: &egui::Context,
+    open: &mut bool,
+    edit: &LinkEffectsEdit,
+    effects: &mut Vec<EffectConfig>,
+    processed: bool,
+    plugins: &[PluginDescriptor],
+) -> Option<EffectsChange> {
+    egui::Window::new(format!("Effects: {}", edit.title))
+        .id(egui::Id::new((edit.link_id, "effects")))
+        .open(open)
+        .resizable(false)
+        .show(ui_ctx, |ui| {
+            if !processed {
+                ui.colored_label(
+                    Color32::YELLOW,
+                    "The system routes this link itself, so its effects are not applied.",
+                );
+                ui.separator();
+            }
+            if let Some(error) = &edit.error {
+                ui.colored_label(Color32::RED, error);
+            }
+
+            effect_list_ui(ui, effects, plugins)
+        })
+        .and_then(|response| response.inner)
+        .flatten()
+}
+
+fn effect_list_ui(
+    ui: &mut Ui,
+    effects: &mut Vec<EffectConfig>,
+    plugins: &[PluginDescriptor],
+) -> Option<EffectsChange> {
+    let mut change = None;
+
+    let mut removed_effect = None;
+
+    for (effect_idx, effect) in effects.iter_mut().enumerate() {
+        ui.push_id(effect.id, |ui| {
+            ui.horizontal(|ui| {
+                let name = effect.name().to_string();
+                if ui.checkbox(&mut effect.enabled, name).changed() {
+                    change = Some(EffectsChange::Effects);
+                }
+
+                if ui.small_button("✖").on_hover_text("Remove").clicked() {
+                    removed_effect = Some(effect_idx);
+                }
+            });
+
+            let param_count = effect.param_count();
+            if effect.params.len() != param_count {
+                effect.params = (0..param_count).map(|idx| effect.param(idx)).collect();
+            }
+
+            ui.add_enabled_ui(effect.enabled, |ui| {
+                for param_idx in 0..param_count {
+                    let (name, min, max, unit) = match &effect.plugin {
+                        Some(plugin) => {
+                            let info = &plugin.params[param_idx];
+                            (info.name.as_str(), info.min, info.max, "")
+                        }
+                        None => {
+                            let info = &effect.kind.params()[param_idx];
+                            (info.name, info.min, info.max, info.unit)
+                        }
+                    };
+
+                    let value = &mut effect.params[param_idx];
+
+                    if ui
+                        .add(Slider::new(value, min..=max).text(name).suffix(unit))
+                        .changed()
+                    {
+                        change = Some(EffectsChange::Param {
+                            effect_id: effect.id,
+                            param_idx,
+                            value: *value,
+                        });
+                    }
+                }
+            });
+        });
+        ui.separator();
+    }
+
+    if let Some(effect_idx) = removed_effect {
+        effects.remove(effect_idx);
+        change = Some(EffectsChange::Effects);
+    }
+
+    ui.menu_button("Add effect", |ui| {
+        for kind in EffectKind::ALL {
+            if ui.button(kind.name()).clicked() {
+                effects.push(EffectConfig::new(kind));
+                change = Some(EffectsChange::Effects);
+                ui.close_menu();
+            }
+        }
+
+        if !plugins.is_empty() {
+            ui.separator();
+            ui.menu_button("Plugins", |ui| {
+                for plugin in plugins {
+                    let button = ui
+                        .button(format!("{} ({})", plugin.name, plugin.format.name()))
+                        .on_hover_text(&plugin.vendor);
+
+                    if button.clicked() {
+                        change = Some(EffectsChange::AddPlugin(plugin.clone()));
+                        ui.close_menu();
+                    }
+                }
+            });
+        }
+    });
+
+    change
+}
@@ -0,0 +1,69 @@
+use eframe::egui;
+use egui::{ComboBox, DragValue, Slider, Ui};
+
+use nodio_core::{GeneratorConfig, Uuid, Waveform};
+
+/// Shows the settings of a generator node. Returns true if they were changed.
+pub fn generator_ui(ui: &mut Ui, node_id: Uuid, config: &mut GeneratorConfig) -> bool {
+    let mut changed = false;
+
+    ui.horizontal(|ui| {
+        changed |= ui.checkbox(&mut config.enabled, "On").changed();
+
+        ComboBox::from_id_source((node_id, "waveform"))
+            .selected_text(config.waveform.name())
+            .show_ui(ui, |ui| {
+                for waveform in Waveform::ALL {
+                    changed |= ui
+                        .selectable_value(&mut config.waveform, waveform, waveform.name())
+                        .changed();
+                }
+            });
+    });
+
+    let noise = matches!(config.waveform, Waveform::WhiteNoise | Waveform::PinkNoise);
+
+    if !noise {
+        ui.horizontal(|ui| {
+            changed |= ui
+                .add(
+                    DragValue::new(&mut config.frequency_hz)
+                        .clamp_range(20.0..=20000.0)
+                        .speed(1.0)
+                        .suffix(" Hz"),
+                )
+                .changed();
+
+            if config.waveform == Waveform::Sweep {
+                ui.label("to");
+                changed |= ui
+                    .add(
+                        DragValue::new(&mut config.sweep_end_hz)
+                            .clamp_range(20.0..=20000.0)
+                            .speed(10.0)
+                            .suffix(" Hz"),
+                    )
+                    .changed();
+                ui.label("in");
+                changed |= ui
+                    .add(
+                        DragValue::new(&mut config.sweep_secs)
+                            .clamp_range(0.1..=60.0)
+                            .speed(0.1)
+                            .suffix(" s"),
+                    )
+                    .changed();
+            }
+        });
+    }
+
+    changed |= ui
+        .add(
+            Slider::new(&mut config.level_db, -60.0..=0.0)
+                .suffix(" dBFS")
+                .text("Level"),
+        )
+        .changed();
+
+    changed
+}
@@ -1,6 +1,10 @@
 #![deny(clippy::all)]
+use std::cell::RefCell;
+use std::collections::HashMap;
 use std::ops::Sub;
+use std::rc::Rc;
 use std::sync::Arc;
+use std::thread::{self, JoinHandle};
 use std::time::Duration;
 
 use eframe::{egui, App, CreationContext, Frame, NativeOptions, Storage};
@@ -10,15 +14,44 @@ use indexmap::IndexMap;
 use log::{debug, warn};
 use parking_lot::RwLock;
 
+use channels::ChannelMatrixEdit;
+use delay::{Calibration, DelayEdit};
+use effects::{EffectsChange, LinkEffectsEdit};
+use network::NetworkEdit;
 use nodio_api::create_nodio_context;
-use nodio_core::{Context, DeviceInfo, ProcessInfo, Uuid};
+use nodio_core::{
+    evaluate_rules, find_port, port_node_id, validate_link, ConnectionState, Context,
+    DefaultDevice, DeviceInfo, Ducker, DuckingRule, Edit, EffectConfig, Error, GeneratorConfig,
+    History, Link, NetworkConfig, NodeGroup, Note, PlayerConfig, Port, PortDirection, ProcessInfo,
+    RecorderConfig, Rule, Snippet, Soundboard, Uuid, VirtualMicConfig,
+};
 use nodio_core::{Node, NodeKind};
-use nodio_gui_nodes::{AttributeFlags, Context as NodeContext, LinkArgs, PinArgs};
+use nodio_engine::{discover_plugins, plugin_config, PluginDescriptor};
+use nodio_gui_nodes::{
+    AttributeFlags, Context as NodeContext, FrameArgs, LinkArgs, LinkStyle, NoteArgs, PinArgs,
+    StyleFlags,
+};
+use player::PlayerChange;
+use recorder::RecorderChange;
 use slider::VolumeSlider;
+use soundboard::SoundboardUi;
+use triggers::ClipTriggers;
 
 use crate::egui::{Direction, Pos2, Response, Ui};
 
+mod channels;
+mod delay;
+mod ducking;
+mod effects;
+mod generator;
+mod network;
+mod player;
+mod recorder;
+mod rules;
 mod slider;
+mod soundboard;
+mod triggers;
+mod virtual_mic;
 
 fn main() {
     pretty_env_logger::init();
@@ -58,6 +91,40 @@ fn setup_app(setup_ctx: &CreationContext) -> Box<dyn App> {
 
     setup_ctx.egui_ctx.set_fonts(fonts);
 
+    if let Some(zoom) = setup_ctx
+        .storage
+        .and_then(|storage| storage.get_string("zoom"))
+        .and_then(|zoom_json| serde_json::from_str(&zoom_json).ok())
+    {
+        app.node_ctx.set_zoom(zoom);
+    }
+
+    let minimap = setup_ctx
+        .storage
+        .and_then(|storage| storage.get_string("minimap"))
+        .and_then(|minimap_json| serde_json::from_str(&minimap_json).ok())
+        .unwrap_or(true);
+    app.show_minimap(minimap);
+
+    if let Some(link_style) = setup_ctx
+        .storage
+        .and_then(|storage| storage.get_string("link_style"))
+        .and_then(|link_style_json| serde_json::from_str::<String>(&link_style_json).ok())
+        .and_then(|name| {
+            LINK_STYLES
+                .iter()
+                .find(|(_, style_name)| *style_name == name)
+        })
+    {
+        app.node_ctx.style_mut().link_style = link_style.0;
+    }
+
+    app.link_flow = setup_ctx
+        .storage
+        .and_then(|storage| storage.get_string("link_flow"))
+        .and_then(|link_flow_json| serde_json::from_str(&link_flow_json).ok())
+        .unwrap_or(false);
+
     if let Some(nodes_json) = setup_ctx
         .storage
         .and_then(|storage| storage.get_string("nodes"))
@@ -73,19 +140,81 @@ fn setup_app(setup_ctx: &CreationContext) -> Box<dyn App> {
         .and_then(|storage| storage.get_string("links"))
     {
         let mut ctx = app.ctx.write();
-        for (id, start, end) in serde_json::from_str::<Vec<_>>(&links_json).unwrap_or_default() {
-            if app.ui_links.insert(id, (start, end)).is_none() {
-                ctx.connect_node(start, end).ok();
+        for link in serde_json::from_str::<Vec<Link>>(&links_json).unwrap_or_default() {
+            if app
+                .ui_links
+                .insert(link.id, (link.src_id, link.dst_id))
+                .is_none()
+            {
+                link.connect(&mut *ctx).ok();
             }
         }
     }
 
+    if let Some(reroutes_json) = setup_ctx
+        .storage
+        .and_then(|storage| storage.get_string("link_reroutes"))
+    {
+        let link_reroutes: Vec<(Uuid, Vec<(f32, f32)>)> =
+            serde_json::from_str(&reroutes_json).unwrap_or_default();
+        for (link_id, points) in link_reroutes {
+            let points = points.into_iter().map(|(x, y)| pos2(x, y)).collect();
+            app.node_ctx.set_link_reroutes(link_id, points);
+        }
+    }
+
+    if let Some(groups_json) = setup_ctx
+        .storage
+        .and_then(|storage| storage.get_string("groups"))
+    {
+        app.groups = serde_json::from_str(&groups_json).unwrap_or_default();
+    }
+
+    if let Some(notes_json) = setup_ctx
+        .storage
+        .and_then(|storage| storage.get_string("notes"))
+    {
+        app.notes = serde_json::from_str(&notes_json).unwrap_or_default();
+    }
+
+    if let Some(rules_json) = setup_ctx
+        .storage
+        .and_then(|storage| storage.get_string("rules"))
+    {
+        app.rules = serde_json::from_str(&rules_json).unwrap_or_default();
+    }
+
+    if let Some(ducking_json) = setup_ctx
+        .storage
+        .and_then(|storage| storage.get_string("ducking"))
+    {
+        app.ducking_rules = serde_json::from_str(&ducking_json).unwrap_or_default();
+    }
+
+    if let Some(soundboard_json) = setup_ctx
+        .storage
+        .and_then(|storage| storage.get_string("soundboard"))
+    {
+        app.soundboard = serde_json::from_str(&soundboard_json).unwrap_or_default();
+        app.clip_triggers.set_clips(&app.soundboard.clips);
+    }
+
     Box::new(app)
 }
 
+/// Link styles that can be picked in the editor, with their names
+const LINK_STYLES: [(LinkStyle, &str); 3] = [
+    (LinkStyle::Bezier, "Curved"),
+    (LinkStyle::Straight, "Straight"),
+    (LinkStyle::Orthogonal, "Right angles"),
+];
+
 #[derive(Copy, Clone)]
 enum ContextMenuKind {
     Node(Uuid),
+    Group(Uuid),
+    Note(Uuid),
+    Link(Uuid),
     Editor,
 }
 
@@ -94,26 +223,120 @@ struct MyApp {
     node_ctx: NodeContext,
     /// Links between nodes (id, (start -> end))
     ui_links: IndexMap<Uuid, (Uuid, Uuid)>,
+    /// The links as (start -> end), shared with the link validator of the editor
+    validated_links: Rc<RefCell<Vec<(Uuid, Uuid)>>>,
+    /// Frames around nodes, drawn behind them
+    groups: Vec<NodeGroup>,
+    /// Sticky notes, which the editor shows like nodes
+    notes: Vec<Note>,
+    /// Whether links show the level of the audio flowing through them
+    link_flow: bool,
     context_menu_kind: Option<ContextMenuKind>,
-    detached_link: Option<(Uuid, Uuid)>,
+    detached_link: Option<Link>,
+
+    /// Graph edits that can be undone with Ctrl+Z
+    history: History,
+    /// Volume change being dragged, as (node, from, to). Recorded when the slider is released.
+    volume_edit: Option<(Uuid, f32, f32)>,
+
+    rules: Vec<Rule>,
+    rules_window_open: bool,
+
+    ducking_rules: Vec<DuckingRule>,
+    ducker: Ducker,
+    ducking_window_open: bool,
+
+    channel_matrix_edit: Option<ChannelMatrixEdit>,
+    delay_edit: Option<DelayEdit>,
+    link_effects_edit: Option<LinkEffectsEdit>,
+
+    /// Plugins found in the plugin search paths
+    plugins: Vec<PluginDescriptor>,
+    /// Search of the plugin search paths. It loads every plugin library, so it runs on a thread
+    /// instead of holding up the start.
+    plugin_discovery: Option<JoinHandle<Vec<PluginDescriptor>>>,
+
+    /// Paths being entered for the playlists of the file player nodes
+    player_paths: HashMap<Uuid, String>,
+    /// Addresses and session descriptions being entered on the network nodes
+    network_edits: HashMap<Uuid, NetworkEdit>,
+
+    soundboard: Soundboard,
+    soundboard_ui: SoundboardUi,
+    clip_triggers: ClipTriggers,
+    /// Clips to play in the next frame
+    triggered_clips: Vec<Uuid>,
 
     should_save: bool,
 }
 
 impl Default for MyApp {
     fn default() -> Self {
+        let ctx = create_nodio_context();
+        let validated_links = Rc::new(RefCell::new(Vec::new()));
+
+        // Asked about the pins while a link is dragged, so links that would fail are not made
+        let mut node_ctx = NodeContext::default();
+        {
+            let ctx = ctx.clone();
+            let links = validated_links.clone();
+            node_ctx.set_link_validator(move |start, end| {
+                validate_link(ctx.read().nodes(), &links.borrow(), start, end).map_err(|err| {
+                    match err {
+                        Error::CouldNotConnect(reason) => reason,
+                        err => err.to_string(),
+                    }
+                })
+            });
+        }
+
         Self {
-            ctx: create_nodio_context(),
-            node_ctx: NodeContext::default(),
+            ctx,
+            node_ctx,
             ui_links: IndexMap::new(),
+            validated_links,
+            groups: Vec::new(),
+            notes: Vec::new(),
+            link_flow: false,
             context_menu_kind: None,
             detached_link: None,
+            history: History::default(),
+            volume_edit: None,
+            rules: Vec::new(),
+            rules_window_open: false,
+            ducking_rules: Vec::new(),
+            ducker: Ducker::default(),
+            ducking_window_open: false,
+            channel_matrix_edit: None,
+            delay_edit: None,
+            link_effects_edit: None,
+            plugins: Vec::new(),
+            plugin_discovery: Some(thread::spawn(discover_plugins)),
+            player_paths: HashMap::new(),
+            network_edits: HashMap::new(),
+            soundboard: Soundboard::default(),
+            soundboard_ui: SoundboardUi::default(),
+            clip_triggers: ClipTriggers::new(),
+            triggered_clips: Vec::new(),
             should_save: false,
         }
     }
 }
 
 impl MyApp {
+    /// Takes the plugins found by the plugin discovery once it is done.
+    fn poll_plugin_discovery(&mut self) {
+        if !matches!(&self.plugin_discovery, Some(discovery) if discovery.is_finished()) {
+            return;
+        }
+
+        match self.plugin_discovery.take().map(JoinHandle::join) {
+            Some(Ok(plugins)) => self.plugins = plugins,
+            Some(Err(_)) => warn!("Plugin discovery failed"),
+            None => {}
+        }
+    }
+
     fn interact_and_draw(&mut self, ui_ctx: &egui::Context, ui: &mut Ui) {
         let node_count = self.ctx.read().nodes().len();
 
@@ -128,9 +351,45 @@ impl MyApp {
             .align_to_end(true)
             .direction(Direction::BottomUp);
 
+        self.apply_rules(&mut toasts);
+        self.apply_ducking(ui.input().unstable_dt);
+        self.play_triggered_clips(&mut toasts);
+
         self.node_ctx.begin_frame(ui);
 
+        for group in self.groups.iter() {
+            let [r, g, b] = group.color;
+            let args = FrameArgs {
+                title: group.title.clone(),
+                color: Some(Color32::from_rgb(r, g, b)),
+                collapsed: group.collapsed,
+            };
+            self.node_ctx.add_frame(group.id, &group.node_ids, args, ui);
+        }
+
+        for note in self.notes.iter_mut() {
+            let [r, g, b] = note.color;
+            let args = NoteArgs {
+                color: Some(Color32::from_rgb(r, g, b)),
+                origin: Some(pos2(note.pos.0, note.pos.1)),
+                size: Some(note.size.into()),
+            };
+            if self
+                .node_ctx
+                .add_note(note.id, &mut note.text, args, ui)
+                .changed()
+            {
+                self.should_save = true;
+            }
+        }
+
         for node_idx in 0..node_count {
+            let node_data = self.ctx.read().nodes().get(node_idx).cloned().unwrap();
+            let node_ports = if node_data.ports.is_empty() {
+                vec![node_data.default_port()]
+            } else {
+                node_data.ports.clone()
+            };
             let Node {
                 id: node_id,
                 kind: node_kind,
@@ -140,41 +399,161 @@ impl MyApp {
                 peak_values: node_peak_values,
                 display_name: node_display_name,
                 pos: node_pos,
+                effects: mut node_effects,
+                recorder: node_recorder,
+                player: node_player,
+                generator: node_generator,
+                network: node_network,
+                virtual_mic: node_virtual_mic,
                 ..
-            } = self.ctx.read().nodes().get(node_idx).cloned().unwrap();
-
-            let pin_args = match node_kind {
-                NodeKind::Application | NodeKind::InputDevice => PinArgs::default(),
-                NodeKind::OutputDevice => PinArgs {
-                    flags: Some(AttributeFlags::EnableLinkDetachWithDragClick as _),
-                    ..Default::default()
-                },
+            } = node_data;
+
+            let node_ducking_db = self.ducker.attenuation_db(node_id);
+            let recorder_status = self.ctx.read().recorder_status(node_id);
+            let mut node_recorder = node_recorder.unwrap_or_default();
+            let player_status = self.ctx.read().player_status(node_id);
+            let mut node_player = node_player.unwrap_or_default();
+            let mut player_path = self.player_paths.remove(&node_id).unwrap_or_default();
+            let mut node_generator =
+                (node_kind == NodeKind::Generator).then(|| node_generator.unwrap_or_default());
+            let network_status = self.ctx.read().network_status(node_id);
+            let mut node_network = node_network.unwrap_or_default();
+            let mut network_edit = self.network_edits.remove(&node_id).unwrap_or_default();
+            let mut node_virtual_mic = (node_kind == NodeKind::VirtualMicrophone)
+                .then(|| node_virtual_mic.unwrap_or_default());
+            let output_devices = match node_virtual_mic {
+                Some(_) => self.ctx.read().output_devices(),
+                None => Vec::new(),
             };
 
             let header_contents = |ui: &mut Ui| {
                 ui.vertical_centered(|ui| {
                     ui.add_enabled_ui(node_present, move |ui| {
-                        ui.label(format!(
-                            "{}{}",
+                        let label = ui.label(format!(
+                            "{}{}{}",
                             node_display_name,
-                            if node_active { " 🔉" } else { "" }
-                        ))
+                            if node_active { " 🔉" } else { "" },
+                            if node_ducking_db > 0.0 { " ⬇" } else { "" }
+                        ));
+                        if node_ducking_db > 0.0 {
+                            label.on_hover_text(format!("Ducked by {:.1} dB", node_ducking_db));
+                        }
                     });
                 });
             };
 
+            let node_applies_effects = self.ctx.read().applies_node_effects(node_id);
+            let unprocessed_links = self.unprocessed_links(node_id);
+
+            let mut changed_volume = None;
+            let mut changed_effects = None;
+            let mut changed_recorder = None;
+            let mut changed_player = None;
+            let mut changed_generator = None;
+            let mut changed_network = None;
+            let mut changed_virtual_mic = None;
+
             let attr_contents = {
-                let ctx = self.ctx.clone();
+                let changed_volume = &mut changed_volume;
+                let changed_effects = &mut changed_effects;
+                let changed_recorder = &mut changed_recorder;
+                let changed_player = &mut changed_player;
+                let changed_generator = &mut changed_generator;
+                let changed_network = &mut changed_network;
+                let changed_virtual_mic = &mut changed_virtual_mic;
+                let network_edit = &mut network_edit;
+                let player_path = &mut player_path;
+                let plugins = &self.plugins;
+                let unprocessed_links = &unprocessed_links;
+                let zoom = self.node_ctx.zoom();
                 move |ui: &mut Ui| {
                     ui.vertical(|ui| {
                         ui.add_enabled_ui(node_present, |ui| {
-                            ui.spacing_mut().slider_width = 130.0;
+                            ui.spacing_mut().slider_width = 130.0 * zoom;
+
+                            if let Some(status) = &recorder_status {
+                                if let Some(change) =
+                                    recorder::recorder_ui(ui, node_id, &mut node_recorder, status)
+                                {
+                                    *changed_recorder = Some((change, node_recorder));
+                                }
+                                return;
+                            }
+
+                            if let Some(status) = &player_status {
+                                if let Some(change) = player::player_ui(
+                                    ui,
+                                    node_id,
+                                    &mut node_player,
+                                    player_path,
+                                    status,
+                                    zoom,
+                                ) {
+                                    *changed_player = Some((change, node_player));
+                                }
+                                return;
+                            }
+
+                            if let Some(config) = &mut node_generator {
+                                if generator::generator_ui(ui, node_id, config) {
+                                    *changed_generator = Some(config.clone());
+                                }
+                                return;
+                            }
+
+                            if let Some(status) = &network_status {
+                                if network::network_ui(
+                                    ui,
+                                    node_id,
+                                    node_kind == NodeKind::NetworkSender,
+                                    &mut node_network,
+                                    network_edit,
+                                    status,
+                                    zoom,
+                                ) {
+                                    *changed_network = Some(node_network);
+                                }
+                                return;
+                            }
+
+                            if let Some(config) = &mut node_virtual_mic {
+                                if virtual_mic::virtual_mic_ui(
+                                    ui,
+                                    node_id,
+                                    config,
+                                    &output_devices,
+                                    zoom,
+                                ) {
+                                    *changed_virtual_mic = Some(config.clone());
+                                }
+
+                                // The volume is the one of the virtual cable
+                                if VolumeSlider::new(&mut node_volume, node_peak_values)
+                                    .ui(ui)
+                                    .changed()
+                                {
+                                    *changed_volume = Some(node_volume);
+                                }
+                                return;
+                            }
 
                             if VolumeSlider::new(&mut node_volume, node_peak_values)
                                 .ui(ui)
                                 .changed()
                             {
-                                ctx.write().set_volume(node_id, node_volume);
+                                *changed_volume = Some(node_volume);
+                            }
+
+                            if !node_applies_effects {
+                                effects::no_effects_ui(ui);
+                            } else if let Some(change) = effects::effects_ui(
+                                ui,
+                                node_id,
+                                &mut node_effects,
+                                plugins,
+                                unprocessed_links,
+                            ) {
+                                *changed_effects = Some((change, node_effects));
                             }
                         });
                     })
@@ -188,24 +567,195 @@ impl MyApp {
                 .with_origin(pos2(node_pos.0, node_pos.1))
                 .with_header(header_contents);
 
-            match node_kind {
-                NodeKind::Application | NodeKind::InputDevice => {
-                    node.with_output_attribute(node_id, pin_args, attr_contents);
-                }
-                NodeKind::OutputDevice => {
-                    node.with_input_attribute(node_id, pin_args, attr_contents);
+            // The contents of the node are shown next to the first pin, and the other pins are
+            // labeled with their port
+            let mut attr_contents = Some(attr_contents);
+            for port in node_ports {
+                let label = Self::port_label(&port);
+                let contents: Box<dyn FnOnce(&mut Ui) -> Response> = match attr_contents.take() {
+                    Some(attr_contents) => Box::new(attr_contents),
+                    None => Box::new(move |ui: &mut Ui| ui.label(label)),
+                };
+
+                match port.direction {
+                    PortDirection::Output => {
+                        node.with_output_attribute(port.id, PinArgs::default(), contents);
+                    }
+                    PortDirection::Input => {
+                        let pin_args = PinArgs {
+                            flags: Some(AttributeFlags::EnableLinkDetachWithDragClick as _),
+                            ..Default::default()
+                        };
+                        node.with_input_attribute(port.id, pin_args, contents);
+                    }
                 }
             }
 
             node.show(ui);
+
+            if !player_path.is_empty() {
+                self.player_paths.insert(node_id, player_path);
+            }
+
+            if !network_edit.is_empty() {
+                self.network_edits.insert(node_id, network_edit);
+            }
+
+            if let Some(volume) = changed_volume {
+                let from = match self.volume_edit {
+                    Some((edit_node_id, from, _)) if edit_node_id == node_id => from,
+                    _ => {
+                        self.commit_volume_edit();
+                        node_volume
+                    }
+                };
+                self.volume_edit = Some((node_id, from, volume));
+
+                let volume = self.ducker.set_volume(node_id, volume);
+                self.ctx.write().set_volume(node_id, volume);
+            }
+
+            match changed_effects {
+                Some((EffectsChange::Effects, effects)) => {
+                    self.ctx.write().set_effects(node_id, effects);
+                    self.should_save = true;
+                }
+                Some((
+                    EffectsChange::Param {
+                        effect_id,
+                        param_idx,
+                        value,
+                    },
+                    _,
+                )) => {
+                    self.ctx
+                        .write()
+                        .set_effect_param(node_id, effect_id, param_idx, value);
+                    self.should_save = true;
+                }
+                Some((EffectsChange::AddPlugin(descriptor), mut effects)) => {
+                    match plugin_config(&descriptor) {
+                        Ok(plugin) => {
+                            effects.push(EffectConfig::plugin(plugin));
+                            self.ctx.write().set_effects(node_id, effects);
+                            self.should_save = true;
+                        }
+                        Err(err) => {
+                            toasts.error(
+                                format!("Could not load {}: {}", descriptor.name, err),
+                                Duration::from_secs(10),
+                            );
+                        }
+                    }
+                }
+                None => {}
+            }
+
+            match changed_recorder {
+                Some((RecorderChange::Config, config)) => {
+                    self.ctx.write().set_recorder_config(node_id, config);
+                    self.should_save = true;
+                }
+                Some((RecorderChange::State(state), _)) => {
+                    if let Err(err) = self.ctx.write().set_recorder_state(node_id, state) {
+                        warn!("Failed to change recorder state: {}", err);
+                        toasts.error(
+                            format!("Could not record: {}", err),
+                            Duration::from_secs(10),
+                        );
+                    }
+                }
+                None => {}
+            }
+
+            match changed_player {
+                Some((PlayerChange::Config, config)) => {
+                    self.ctx.write().set_player_config(node_id, config);
+                    self.should_save = true;
+                }
+                Some((PlayerChange::Command(command), _)) => {
+                    if let Err(err) = self.ctx.write().player_command(node_id, command) {
+                        warn!("Failed to control player: {}", err);
+                        toasts.error(format!("Could not play: {}", err), Duration::from_secs(10));
+                    }
+                }
+                None => {}
+            }
+
+            if let Some(config) = changed_generator {
+                self.ctx.write().set_generator_config(node_id, config);
+                self.should_save = true;
+            }
+
+            if let Some(config) = changed_network {
+                self.ctx.write().set_network_config(node_id, config);
+                self.should_save = true;
+            }
+
+            if let Some(config) = changed_virtual_mic {
+                self.ctx.write().set_virtual_mic_config(node_id, config);
+                self.should_save = true;
+            }
         }
 
         for (&id, &(start, end)) in self.ui_links.iter() {
-            self.node_ctx
-                .add_link(id, start, end, LinkArgs::default(), ui);
+            let link_args = match self.ctx.read().connection_state(start, end) {
+                Some(ConnectionState::Active) => LinkArgs {
+                    flow: self.link_flow.then(|| self.source_peak(start)),
+                    ..Default::default()
+                },
+                // Waiting for the device or application to become available
+                _ => LinkArgs {
+                    base: Some(Color32::from_gray(90)),
+                    ..Default::default()
+                },
+            };
+
+            self.node_ctx.add_link(id, start, end, link_args, ui);
+        }
+
+        {
+            let mut validated_links = self.validated_links.borrow_mut();
+            validated_links.clear();
+            validated_links.extend(self.ui_links.values().copied());
+        }
+
+        let mut nodes_response = self.node_ctx.end_frame(ui);
+
+        let moves = self
+            .node_ctx
+            .moved_nodes()
+            .iter()
+            .map(|&(node_id, from, to)| Edit::MoveNode {
+                node_id,
+                from: (from.x, from.y),
+                to: (to.x, to.y),
+            })
+            .collect();
+        self.history.push(Edit::Group(moves));
+
+        if self.node_ctx.rerouted_link().is_some() {
+            self.should_save = true;
+        }
+
+        if let Some(group_id) = self.node_ctx.toggled_frame() {
+            if let Some(group) = self.groups.iter_mut().find(|group| group.id == group_id) {
+                group.collapsed = !group.collapsed;
+                self.should_save = true;
+            }
+        }
+
+        if !ui.input().pointer.any_down() {
+            self.commit_volume_edit();
         }
 
-        let nodes_response = self.node_ctx.end_frame(ui);
+        if let Some(text) = self
+            .node_ctx
+            .hovered_link()
+            .and_then(|link_id| self.link_hover_text(link_id))
+        {
+            nodes_response = nodes_response.on_hover_text(text);
+        }
 
         self.context_menu(nodes_response);
 
@@ -213,8 +763,9 @@ impl MyApp {
             debug!("link detached: {}", id);
 
             if let Some((from, to)) = self.ui_links.remove(&id) {
+                let link = Link::from_context(&*self.ctx.read(), id, from, to);
                 self.ctx.write().disconnect_node(from, to);
-                self.detached_link = Some((from, to));
+                self.detached_link = Some(link);
             }
         }
 
@@ -222,24 +773,38 @@ impl MyApp {
             debug!("link dropped: {}", id);
 
             self.should_save = true;
-            self.detached_link = None;
+            if let Some(link) = self.detached_link.take() {
+                self.history.push(Edit::Disconnect(link));
+            }
         }
 
         if let Some((start, end, from_snap)) = self.node_ctx.created_link() {
             debug!("link created: {}, ({} to {})", start, end, from_snap);
 
-            match self.ctx.write().connect_node(start, end) {
+            let result = self.ctx.write().connect_node(start, end);
+            match result {
                 Ok(()) => {
+                    let link_id = Uuid::new_v4();
                     self.ui_links.retain(|_, link| *link != (start, end));
-                    self.ui_links.insert(Uuid::new_v4(), (start, end));
+                    self.ui_links.insert(link_id, (start, end));
+
+                    let connect =
+                        Edit::Connect(Link::from_context(&*self.ctx.read(), link_id, start, end));
+                    match self.detached_link.take() {
+                        Some(detached) => self
+                            .history
+                            .push(Edit::Group(vec![Edit::Disconnect(detached), connect])),
+                        None => self.history.push(connect),
+                    }
                 }
                 Err(err) => {
                     warn!("Failed to connect nodes: {}", err);
 
                     toasts.error(err.to_string(), Duration::from_secs(10));
 
-                    if let Some((from, to)) = self.detached_link.take() {
-                        self.ui_links.insert(Uuid::new_v4(), (from, to));
+                    if let Some(link) = self.detached_link.take() {
+                        self.ui_links
+                            .insert(Uuid::new_v4(), (link.src_id, link.dst_id));
                     }
                 }
             }
@@ -257,18 +822,334 @@ impl MyApp {
             });
         }
 
-        if ui.input().key_pressed(egui::Key::Delete) {
-            self.remove_selected_nodes();
+        if !ui_ctx.wants_keyboard_input() {
+            if ui.input().key_pressed(egui::Key::Delete) {
+                self.remove_selected_nodes();
+            }
+
+            let input = ui.input();
+            let undo_redo = input.modifiers.command && input.key_pressed(egui::Key::Z);
+            let redo = input.modifiers.shift;
+            drop(input);
+
+            match (undo_redo, redo) {
+                (true, false) => self.undo(&mut toasts),
+                (true, true) => self.redo(&mut toasts),
+                _ => {}
+            }
+
+            let input = ui.input();
+            let copy = input.events.iter().any(|event| event == &egui::Event::Copy);
+            let pasted_text = input.events.iter().find_map(|event| match event {
+                egui::Event::Paste(text) => Some(text.clone()),
+                _ => None,
+            });
+            let duplicate = input.modifiers.command && input.key_pressed(egui::Key::D);
+            let paste_pos = input
+                .pointer
+                .hover_pos()
+                .map(|pos| self.node_ctx.screen_space_to_grid_space(pos));
+            drop(input);
+
+            if copy {
+                let snippet = self.copy_selected_nodes();
+                if !snippet.is_empty() {
+                    ui.output().copied_text = snippet.to_text();
+                }
+            }
+
+            if let Some(text) = pasted_text {
+                match Snippet::from_text(&text) {
+                    Ok(snippet) => {
+                        let pos = paste_pos
+                            .map_or_else(|| Self::duplicate_pos(&snippet), |pos| (pos.x, pos.y));
+                        self.paste(&snippet, pos, &mut toasts);
+                    }
+                    Err(err) => {
+                        debug!("Pasted text is not a snippet: {}", err);
+                        toasts.info(
+                            "The clipboard does not contain nodes",
+                            Duration::from_secs(5),
+                        );
+                    }
+                }
+            }
+
+            if duplicate {
+                let snippet = self.copy_selected_nodes();
+                if !snippet.is_empty() {
+                    self.paste(&snippet, Self::duplicate_pos(&snippet), &mut toasts);
+                }
+            }
         }
 
         toasts.show();
     }
 
+    fn undo(&mut self, toasts: &mut Toasts) {
+        self.commit_volume_edit();
+
+        let undone = self.history.undo(&mut *self.ctx.write());
+        if let Some((edit, result)) = undone {
+            self.sync_edit(&edit);
+            self.should_save = true;
+
+            if let Err(err) = result {
+                warn!("Failed to undo: {}", err);
+                toasts.error(format!("Could not undo: {}", err), Duration::from_secs(10));
+            }
+        }
+    }
+
+    fn redo(&mut self, toasts: &mut Toasts) {
+        self.commit_volume_edit();
+
+        let redone = self.history.redo(&mut *self.ctx.write());
+        if let Some((edit, result)) = redone {
+            self.sync_edit(&edit);
+            self.should_save = true;
+
+            if let Err(err) = result {
+                warn!("Failed to redo: {}", err);
+                toasts.error(format!("Could not redo: {}", err), Duration::from_secs(10));
+            }
+        }
+    }
+
+    /// The selected nodes and the links between them.
+    fn copy_selected_nodes(&self) -> Snippet {
+        let node_ids = self.node_ctx.get_selected_nodes();
+        let ctx = self.ctx.read();
+
+        let nodes = ctx
+            .nodes()
+            .iter()
+            .filter(|node| node_ids.contains(&node.id))
+            .map(|node| {
+                let mut node = node.clone();
+                if let Some(pos) = self.node_ctx.node_pos(node.id) {
+                    node.pos = (pos.x, pos.y);
+                }
+                node
+            })
+            .collect();
+
+        let links = self
+            .ui_links
+            .iter()
+            .map(|(&id, &(start, end))| Link::from_context(&*ctx, id, start, end))
+            .collect();
+
+        Snippet::new(nodes, links)
+    }
+
+    /// Where duplicated nodes are placed, next to the originals.
+    fn duplicate_pos(snippet: &Snippet) -> (f32, f32) {
+        let (x, y) = snippet.origin();
+        (x + 30.0, y + 30.0)
+    }
+
+    /// Adds copies of the nodes of the snippet, as one edit that can be undone, and selects them.
+    fn paste(&mut self, snippet: &Snippet, pos: (f32, f32), toasts: &mut Toasts) {
+        let (edit, node_ids) = snippet.paste(&*self.ctx.read(), pos);
+        let result = edit.apply(&mut *self.ctx.write());
+
+        self.sync_edit(&edit);
+        self.history.push(edit);
+        self.node_ctx.select_nodes(&node_ids);
+        self.should_save = true;
+
+        if let Err(err) = result {
+            warn!("Failed to paste: {}", err);
+            toasts.error(format!("Could not paste: {}", err), Duration::from_secs(10));
+        }
+    }
+
+    /// Records the volume change being dragged, if any.
+    fn commit_volume_edit(&mut self) {
+        if let Some((node_id, from, to)) = self.volume_edit.take() {
+            self.history.push(Edit::SetVolume { node_id, from, to });
+        }
+    }
+
+    /// Brings the editor up to date with an edit that has been applied to the context.
+    fn sync_edit(&mut self, edit: &Edit) {
+        match edit {
+            Edit::AddNode { node, links } => {
+                self.node_ctx
+                    .set_node_pos(node.id, pos2(node.pos.0, node.pos.1));
+                for link in links {
+                    self.sync_link(link);
+                }
+            }
+            Edit::RemoveNode { node, .. } => {
+                self.ui_links
+                    .retain(|_, (start, end)| !node.owns_port(*start) && !node.owns_port(*end));
+            }
+            Edit::MoveNode { node_id, to, .. } => {
+                self.node_ctx.set_node_pos(*node_id, pos2(to.0, to.1));
+            }
+            Edit::Connect(link) => self.sync_link(link),
+            Edit::Disconnect(link) => {
+                self.ui_links.remove(&link.id);
+            }
+            Edit::SetVolume { node_id, to, .. } => {
+                // Applied again through the ducker, in case the node is ducked right now
+                let volume = self.ducker.set_volume(*node_id, *to);
+                self.ctx.write().set_volume(*node_id, volume);
+            }
+            Edit::AddNote(note) => {
+                if !self.notes.iter().any(|other| other.id == note.id) {
+                    self.notes.push(note.clone());
+                }
+                self.node_ctx
+                    .set_node_pos(note.id, pos2(note.pos.0, note.pos.1));
+            }
+            Edit::RemoveNote(note) => {
+                self.notes.retain(|other| other.id != note.id);
+            }
+            Edit::Group(edits) => {
+                for edit in edits {
+                    self.sync_edit(edit);
+                }
+            }
+        }
+    }
+
+    /// Shows a link that has been connected by an edit, unless the backend refused it.
+    fn sync_link(&mut self, link: &Link) {
+        let connected = self
+            .ctx
+            .read()
+            .connection_state(link.src_id, link.dst_id)
+            .is_some();
+
+        if connected {
+            let link_nodes = (link.src_id, link.dst_id);
+            self.ui_links.retain(|_, nodes| *nodes != link_nodes);
+            self.ui_links.insert(link.id, link_nodes);
+        }
+    }
+
+    fn apply_rules(&mut self, toasts: &mut Toasts) {
+        let new_processes = self.ctx.write().take_new_processes();
+
+        for process in new_processes {
+            let rule = match evaluate_rules(&self.rules, &process, self.ctx.read().nodes()) {
+                Some(rule) => rule.clone(),
+                None => continue,
+            };
+
+            debug!(
+                "Rule {} matched process {}",
+                rule.pattern, process.display_name
+            );
+
+            let node = Node {
+                kind: NodeKind::Application,
+                display_name: process.display_name,
+                filename: process.filename,
+                pos: rule.pos,
+                process_id: Some(process.pid),
+                ..Default::default()
+            };
+            let node_id = node.id;
+
+            self.ctx.write().add_node(node);
+
+            let output_devices = self.ctx.read().output_devices();
+            let target_devices = rule.target_devices(&output_devices);
+
+            for (device_idx, device) in output_devices
+                .into_iter()
+                .filter(|device| target_devices.contains(&device.id))
+                .enumerate()
+            {
+                if !self.ctx.read().nodes().iter().any(|n| n.id == device.id) {
+                    self.ctx.write().add_node(Node {
+                        id: device.id,
+                        kind: NodeKind::OutputDevice,
+                        display_name: device.name,
+                        pos: (rule.pos.0 + 250.0, rule.pos.1 + device_idx as f32 * 100.0),
+                        ..Default::default()
+                    });
+                }
+
+                match self.ctx.write().connect_node(node_id, device.id) {
+                    Ok(()) => {
+                        self.ui_links.insert(Uuid::new_v4(), (node_id, device.id));
+                    }
+                    Err(err) => {
+                        warn!("Failed to connect nodes: {}", err);
+                        toasts.error(err.to_string(), Duration::from_secs(10));
+                    }
+                }
+            }
+
+            self.should_save = true;
+        }
+    }
+
+    fn play_triggered_clips(&mut self, toasts: &mut Toasts) {
+        let mut clip_ids = std::mem::take(&mut self.triggered_clips);
+        clip_ids.extend(self.clip_triggers.take_triggered(&self.soundboard.clips));
+
+        if clip_ids.is_empty() {
+            return;
+        }
+
+        let player_id = match self.soundboard.player_id {
+            Some(player_id) => player_id,
+            None => {
+                toasts.info(
+                    "Select the file player of the soundboard first",
+                    Duration::from_secs(5),
+                );
+                return;
+            }
+        };
+
+        for clip in self
+            .soundboard
+            .clips
+            .iter()
+            .filter(|clip| clip_ids.contains(&clip.id))
+        {
+            if let Err(err) = self.ctx.write().play_clip(player_id, clip) {
+                warn!("Failed to play clip: {}", err);
+                toasts.error(
+                    format!("Could not play {}: {}", clip.name, err),
+                    Duration::from_secs(10),
+                );
+            }
+        }
+    }
+
+    fn apply_ducking(&mut self, dt: f32) {
+        let volumes = self
+            .ducker
+            .update(&self.ducking_rules, self.ctx.read().nodes(), dt);
+
+        for (node_id, volume) in volumes {
+            self.ctx.write().set_volume(node_id, volume);
+        }
+    }
+
     fn context_menu(&mut self, nodes_response: Response) {
         let context_menu_kind = self
             .context_menu_kind
             .take()
-            .or_else(|| self.node_ctx.hovered_node().map(ContextMenuKind::Node))
+            .or_else(|| {
+                self.node_ctx.hovered_node().map(|node_id| {
+                    if self.notes.iter().any(|note| note.id == node_id) {
+                        ContextMenuKind::Note(node_id)
+                    } else {
+                        ContextMenuKind::Node(node_id)
+                    }
+                })
+            })
+            .or_else(|| self.node_ctx.hovered_frame().map(ContextMenuKind::Group))
+            .or_else(|| self.node_ctx.context_menu_link().map(ContextMenuKind::Link))
             .unwrap_or(ContextMenuKind::Editor);
 
         nodes_response.context_menu(|ui| {
@@ -276,40 +1157,463 @@ impl MyApp {
 
             match context_menu_kind {
                 ContextMenuKind::Node(node_id) => self.node_context_menu_items(ui, node_id),
+                ContextMenuKind::Group(group_id) => self.group_context_menu_items(ui, group_id),
+                ContextMenuKind::Note(note_id) => self.note_context_menu_items(ui, note_id),
+                ContextMenuKind::Link(link_id) => self.link_context_menu_items(ui, link_id),
                 ContextMenuKind::Editor => self.editor_context_menu_items(ui),
             }
         });
     }
 
     fn node_context_menu_items(&mut self, ui: &mut Ui, node_id: Uuid) {
+        // Act on the other nodes too, when multiple nodes selected
+        let mut node_ids = vec![node_id];
+        node_ids.extend(
+            self.node_ctx
+                .get_selected_nodes()
+                .iter()
+                .filter(|&&id| id != node_id),
+        );
+
+        if ui.button("Group").clicked() {
+            self.group_nodes(&node_ids);
+            ui.close_menu();
+        }
+
         if ui.button("Remove").clicked() {
-            self.ctx.write().remove_node(node_id);
-            self.ui_links
-                .retain(|_, (start, end)| *start != node_id && *end != node_id);
+            self.remove_nodes(&node_ids);
+            ui.close_menu();
+        }
+    }
+
+    fn group_context_menu_items(&mut self, ui: &mut Ui, group_id: Uuid) {
+        let group = match self.groups.iter_mut().find(|group| group.id == group_id) {
+            Some(group) => group,
+            None => {
+                ui.close_menu();
+                return;
+            }
+        };
+
+        ui.horizontal(|ui| {
+            if ui.color_edit_button_srgb(&mut group.color).changed() {
+                self.should_save = true;
+            }
+            if ui.text_edit_singleline(&mut group.title).changed() {
+                self.should_save = true;
+            }
+        });
 
-            // Remove other nodes too, when multiple nodes selected
-            self.remove_selected_nodes();
+        let label = if group.collapsed {
+            "Expand"
+        } else {
+            "Collapse"
+        };
+        if ui.button(label).clicked() {
+            group.collapsed = !group.collapsed;
+            self.should_save = true;
+            ui.close_menu();
+        }
 
+        if ui.button("Ungroup").clicked() {
+            self.groups.retain(|group| group.id != group_id);
+            self.should_save = true;
             ui.close_menu();
         }
     }
 
-    fn remove_selected_nodes(&mut self) {
-        for &node_id in self.node_ctx.get_selected_nodes() {
-            self.ctx.write().remove_node(node_id);
+    fn note_context_menu_items(&mut self, ui: &mut Ui, note_id: Uuid) {
+        let note = match self.notes.iter_mut().find(|note| note.id == note_id) {
+            Some(note) => note,
+            None => {
+                ui.close_menu();
+                return;
+            }
+        };
+
+        ui.horizontal(|ui| {
+            if ui.color_edit_button_srgb(&mut note.color).changed() {
+                self.should_save = true;
+            }
+            ui.label("Color");
+        });
+
+        if ui.button("Remove").clicked() {
+            self.remove_nodes(&[note_id]);
+            ui.close_menu();
+        }
+    }
+
+    /// Puts the nodes into a new group, titled after the first one, and selects the group.
+    fn group_nodes(&mut self, node_ids: &[Uuid]) {
+        if node_ids.is_empty() {
+            return;
+        }
+
+        let title = self.node_name(node_ids[0]);
+        NodeGroup::group_nodes(&mut self.groups, &title, node_ids);
+        self.should_save = true;
+    }
+
+    fn link_context_menu_items(&mut self, ui: &mut Ui, link_id: Uuid) {
+        let (start, end) = match self.ui_links.get(&link_id) {
+            Some(&link) => link,
+            None => {
+                ui.close_menu();
+                return;
+            }
+        };
+
+        if ui.button("Channels…").clicked() {
+            self.channel_matrix_edit = Some(ChannelMatrixEdit {
+                link_id,
+                src_id: start,
+                dst_id: end,
+                title: self.link_title(start, end),
+                matrix: self.ctx.read().channel_matrix(start, end),
+            });
+            ui.close_menu();
+        }
+
+        if ui.button("Delay…").clicked() {
+            self.delay_edit = Some(DelayEdit {
+                link_id,
+                src_id: start,
+                dst_id: end,
+                title: self.link_title(start, end),
+                delay_ms: self.ctx.read().link_delay(start, end),
+                capture_id: None,
+                calibration: None,
+            });
+            ui.close_menu();
+        }
+
+        if ui.button("Effects…").clicked() {
+            self.link_effects_edit = Some(LinkEffectsEdit {
+                link_id,
+                src_id: start,
+                dst_id: end,
+                title: self.link_title(start, end),
+                error: None,
+            });
+            ui.close_menu();
+        }
+
+        if ui.button("Remove").clicked() {
+            let edit = Edit::Disconnect(Link::from_context(&*self.ctx.read(), link_id, start, end));
+            edit.apply(&mut *self.ctx.write()).ok();
+            self.sync_edit(&edit);
+            self.history.push(edit);
+            self.should_save = true;
+            ui.close_menu();
+        }
+    }
+
+    fn minimap_shown(&self) -> bool {
+        self.node_ctx.style().flags & StyleFlags::Minimap as usize != 0
+    }
+
+    fn show_minimap(&mut self, shown: bool) {
+        let style = self.node_ctx.style_mut();
+        if shown {
+            style.flags |= StyleFlags::Minimap as usize;
+        } else {
+            style.flags &= !(StyleFlags::Minimap as usize);
+        }
+    }
+
+    /// Peak level of the node of the port that feeds a link
+    fn source_peak(&self, port_id: Uuid) -> f32 {
+        find_port(self.ctx.read().nodes(), port_id)
+            .map_or(0.0, |(node, _)| node.peak_values.0.max(node.peak_values.1))
+    }
+
+    fn node_name(&self, node_id: Uuid) -> String {
+        self.ctx
+            .read()
+            .nodes()
+            .iter()
+            .find(|node| node.id == node_id)
+            .map(|node| node.display_name.clone())
+            .unwrap_or_default()
+    }
+
+    fn link_title(&self, start: Uuid, end: Uuid) -> String {
+        let ctx = self.ctx.read();
+        let start_node_id = port_node_id(ctx.nodes(), start);
+        let end_node_id = port_node_id(ctx.nodes(), end);
+        drop(ctx);
+
+        format!(
+            "{} → {}",
+            self.node_name(start_node_id),
+            self.node_name(end_node_id)
+        )
+    }
+
+    /// Name of a pin that is not the first pin of its node
+    fn port_label(port: &Port) -> String {
+        if port.name.is_empty() {
+            format!("{} ch", port.channels)
+        } else {
+            port.name.clone()
+        }
+    }
+
+    /// Names of the nodes that the active links of a node lead to, where the links are routed by
+    /// the system and its effects are not applied
+    fn unprocessed_links(&self, node_id: Uuid) -> Vec<String> {
+        let ctx = self.ctx.read();
+
+        let targets = self
+            .ui_links
+            .values()
+            .filter(|(start, end)| {
+                port_node_id(ctx.nodes(), *start) == node_id
+                    && ctx.connection_state(*start, *end) == Some(ConnectionState::Active)
+                    && !ctx.processes_link(*start, *end)
+            })
+            .map(|(_, end)| port_node_id(ctx.nodes(), *end))
+            .collect::<Vec<_>>();
+        drop(ctx);
+
+        targets
+            .into_iter()
+            .map(|target_id| self.node_name(target_id))
+            .collect()
+    }
+
+    /// Latency and delay of a link, if it has any
+    fn link_hover_text(&self, link_id: Uuid) -> Option<String> {
+        let &(start, end) = self.ui_links.get(&link_id)?;
+        let ctx = self.ctx.read();
+
+        let mut lines = Vec::new();
+        if let Some(latency_ms) = ctx.link_latency(start, end) {
+            lines.push(format!("Latency: {:.1} ms", latency_ms));
+        }
+
+        let delay_ms = ctx.link_delay(start, end);
+        if delay_ms > 0.0 {
+            lines.push(format!("Delay: {:.1} ms", delay_ms));
+        }
+
+        (!lines.is_empty()).then(|| lines.join("\n"))
+    }
+
+    /// Shows the delay window, and measures the outputs one after another while calibrating.
+    fn delay_window(&mut self, ui_ctx: &egui::Context) {
+        let edit = match &mut self.delay_edit {
+            Some(edit) => edit,
+            None => return,
+        };
+
+        let mut open = self.ui_links.contains_key(&edit.link_id);
+        let latency_ms = self.ctx.read().link_latency(edit.src_id, edit.dst_id);
+        let input_devices = self.ctx.read().input_devices();
+        let targets = {
+            let ctx = self.ctx.read();
             self.ui_links
-                .retain(|_, (start, end)| *start != node_id && *end != node_id);
+                .values()
+                .filter(|(start, _)| *start == edit.src_id)
+                .filter_map(|&(start, end)| {
+                    let (node, _) = find_port(ctx.nodes(), end)?;
+                    (node.kind == NodeKind::OutputDevice).then(|| {
+                        let rendered = ctx.processes_link(start, end);
+                        (end, node.display_name.clone(), rendered)
+                    })
+                })
+                .collect::<Vec<_>>()
+        };
+        let unrendered_targets = targets
+            .iter()
+            .filter(|(_, _, rendered)| !rendered)
+            .map(|(_, name, _)| name.clone())
+            .collect::<Vec<_>>();
+
+        let response = delay::delay_window(
+            ui_ctx,
+            &mut open,
+            edit,
+            latency_ms,
+            &input_devices,
+            &unrendered_targets,
+        );
+
+        if response.changed {
+            self.ctx
+                .write()
+                .set_link_delay(edit.src_id, edit.dst_id, edit.delay_ms);
+            self.should_save = true;
+        }
+
+        if response.calibrate && unrendered_targets.is_empty() {
+            let targets = targets
+                .into_iter()
+                .map(|(target_id, name, _)| (target_id, name))
+                .collect();
+
+            edit.calibration = Some(Calibration::new(targets));
+        }
+
+        let running = edit
+            .calibration
+            .as_ref()
+            .is_some_and(Calibration::is_running);
+
+        if running && (response.cancel_calibration || !open) {
+            self.ctx.write().stop_calibration();
+            edit.calibration = None;
+        }
+
+        if let (Some(calibration), Some(capture_id)) = (&mut edit.calibration, edit.capture_id) {
+            if calibration.is_running() {
+                let mut ctx = self.ctx.write();
+
+                if let Some(delays) = calibration.update(&mut *ctx, capture_id) {
+                    for (target_id, delay_ms) in delays {
+                        ctx.set_link_delay(edit.src_id, target_id, delay_ms);
+                    }
+
+                    edit.delay_ms = ctx.link_delay(edit.src_id, edit.dst_id);
+                    self.should_save = true;
+                }
+            }
+        }
+
+        if !open {
+            self.delay_edit = None;
+        }
+    }
+
+    /// Shows the effects window of a link, and applies the changes made in it.
+    fn link_effects_window(&mut self, ui_ctx: &egui::Context) {
+        let edit = match &mut self.link_effects_edit {
+            Some(edit) => edit,
+            None => return,
+        };
+
+        let mut open = self.ui_links.contains_key(&edit.link_id);
+        let mut effects = self.ctx.read().link_effects(edit.src_id, edit.dst_id);
+        let processed = self.ctx.read().processes_link(edit.src_id, edit.dst_id);
+
+        let change = effects::link_effects_window(
+            ui_ctx,
+            &mut open,
+            edit,
+            &mut effects,
+            processed,
+            &self.plugins,
+        );
+
+        match change {
+            Some(EffectsChange::Effects) => {
+                self.ctx
+                    .write()
+                    .set_link_effects(edit.src_id, edit.dst_id, effects);
+                self.should_save = true;
+            }
+            Some(EffectsChange::Param {
+                effect_id,
+                param_idx,
+                value,
+            }) => {
+                self.ctx.write().set_link_effect_param(
+                    edit.src_id,
+                    edit.dst_id,
+                    effect_id,
+                    param_idx,
+                    value,
+                );
+                self.should_save = true;
+            }
+            Some(EffectsChange::AddPlugin(descriptor)) => match plugin_config(&descriptor) {
+                Ok(plugin) => {
+                    effects.push(EffectConfig::plugin(plugin));
+                    self.ctx
+                        .write()
+                        .set_link_effects(edit.src_id, edit.dst_id, effects);
+                    edit.error = None;
+                    self.should_save = true;
+                }
+                Err(err) => {
+                    edit.error = Some(format!("Could not load {}: {}", descriptor.name, err));
+                }
+            },
+            None => {}
+        }
+
+        if !open {
+            self.link_effects_edit = None;
+        }
+    }
+
+    fn remove_selected_nodes(&mut self) {
+        let node_ids = self.node_ctx.get_selected_nodes().to_vec();
+        self.remove_nodes(&node_ids);
+    }
+
+    /// Removes the nodes and their links, as one edit that can be undone. Notes among them are
+    /// removed too.
+    fn remove_nodes(&mut self, node_ids: &[Uuid]) {
+        let mut edits = Vec::new();
+
+        let notes = self
+            .notes
+            .iter()
+            .filter(|note| node_ids.contains(&note.id))
+            .cloned()
+            .collect::<Vec<_>>();
+        for mut note in notes {
+            if let Some(pos) = self.node_ctx.node_pos(note.id) {
+                note.pos = (pos.x, pos.y);
+            }
+            if let Some(size) = self.node_ctx.note_size(note.id) {
+                note.size = (size.x, size.y);
+            }
+
+            let edit = Edit::RemoveNote(note);
+            self.sync_edit(&edit);
+            edits.push(edit);
+        }
+
+        for &node_id in node_ids {
+            let mut node = match self.ctx.read().nodes().iter().find(|n| n.id == node_id) {
+                Some(node) => node.clone(),
+                None => continue,
+            };
+            if let Some(pos) = self.node_ctx.node_pos(node_id) {
+                node.pos = (pos.x, pos.y);
+            }
+
+            let links = self
+                .ui_links
+                .iter()
+                .filter(|(_, (start, end))| node.owns_port(*start) || node.owns_port(*end))
+                .map(|(&id, &(start, end))| Link::from_context(&*self.ctx.read(), id, start, end))
+                .collect();
+
+            let edit = Edit::RemoveNode { node, links };
+            edit.apply(&mut *self.ctx.write()).ok();
+            self.sync_edit(&edit);
+            edits.push(edit);
+        }
+
+        if !edits.is_empty() {
+            self.history.push(Edit::Group(edits));
+            self.should_save = true;
         }
     }
 
     fn editor_context_menu_items(&mut self, ui: &mut Ui) {
         let mut added_node = None;
 
-        let menu_pos = ui
-            .add_enabled_ui(false, |ui| ui.label("Add node"))
-            .response
-            .rect
-            .min;
+        let menu_pos = self.node_ctx.screen_space_to_grid_space(
+            ui.add_enabled_ui(false, |ui| ui.label("Add node"))
+                .response
+                .rect
+                .min,
+        );
 
         ui.menu_button("Application", |ui| {
             for process in self.ctx.read().application_processes() {
@@ -330,6 +1634,22 @@ impl MyApp {
         });
 
         ui.menu_button("Output device", |ui| {
+            for default_device in DefaultDevice::ALL {
+                Self::device_node_button(
+                    &mut added_node,
+                    menu_pos,
+                    ui,
+                    DeviceInfo {
+                        id: default_device.id(),
+                        name: default_device.display_name().to_string(),
+                        virtual_cable: false,
+                    },
+                    NodeKind::OutputDevice,
+                );
+            }
+
+            ui.separator();
+
             for device in self.ctx.read().output_devices() {
                 Self::device_node_button(
                     &mut added_node,
@@ -341,10 +1661,177 @@ impl MyApp {
             }
         });
 
+        if ui.button("Recorder").clicked() {
+            added_node = Some(Node {
+                kind: NodeKind::Recorder,
+                display_name: "Recorder".to_string(),
+                pos: (menu_pos.x, menu_pos.y),
+                recorder: Some(RecorderConfig::default()),
+                ..Default::default()
+            });
+            ui.close_menu();
+        }
+
+        if ui.button("File player").clicked() {
+            added_node = Some(Node {
+                kind: NodeKind::FilePlayer,
+                display_name: "File player".to_string(),
+                pos: (menu_pos.x, menu_pos.y),
+                player: Some(PlayerConfig::default()),
+                ..Default::default()
+            });
+            ui.close_menu();
+        }
+
+        if ui.button("Generator").clicked() {
+            added_node = Some(Node {
+                kind: NodeKind::Generator,
+                display_name: "Generator".to_string(),
+                pos: (menu_pos.x, menu_pos.y),
+                generator: Some(GeneratorConfig::default()),
+                ..Default::default()
+            });
+            ui.close_menu();
+        }
+
+        if ui.button("Network sender").clicked() {
+            added_node = Some(Node {
+                kind: NodeKind::NetworkSender,
+                display_name: "Network sender".to_string(),
+                pos: (menu_pos.x, menu_pos.y),
+                network: Some(NetworkConfig::default()),
+                ..Default::default()
+            });
+            ui.close_menu();
+        }
+
+        if ui.button("Network receiver").clicked() {
+            added_node = Some(Node {
+                kind: NodeKind::NetworkReceiver,
+                display_name: "Network receiver".to_string(),
+                pos: (menu_pos.x, menu_pos.y),
+                network: Some(NetworkConfig::default()),
+                ..Default::default()
+            });
+            ui.close_menu();
+        }
+
+        if ui.button("Virtual microphone").clicked() {
+            let cable_id = virtual_mic::NEEDS_CABLE
+                .then(|| {
+                    self.ctx
+                        .read()
+                        .output_devices()
+                        .into_iter()
+                        .find(|device| device.virtual_cable)
+                        .map(|device| device.id)
+                })
+                .flatten();
+
+            added_node = Some(Node {
+                kind: NodeKind::VirtualMicrophone,
+                display_name: "Virtual microphone".to_string(),
+                pos: (menu_pos.x, menu_pos.y),
+                virtual_mic: Some(VirtualMicConfig { cable_id }),
+                ..Default::default()
+            });
+            ui.close_menu();
+        }
+
+        if ui.button("Note").clicked() {
+            self.notes.push(Note {
+                pos: (menu_pos.x, menu_pos.y),
+                ..Default::default()
+            });
+            self.should_save = true;
+            ui.close_menu();
+        }
+
         if let Some(node) = added_node {
-            self.ctx.write().add_node(node);
+            let edit = Edit::AddNode {
+                node,
+                links: Vec::new(),
+            };
+            edit.apply(&mut *self.ctx.write()).ok();
+            self.history.push(edit);
+            self.should_save = true;
+        }
+
+        ui.separator();
+
+        if ui.button("Rules").clicked() {
+            self.rules_window_open = true;
+            ui.close_menu();
+        }
+
+        if ui.button("Ducking").clicked() {
+            self.ducking_window_open = true;
+            ui.close_menu();
+        }
+
+        if ui.button("Soundboard").clicked() {
+            self.soundboard_ui.open = true;
+            ui.close_menu();
+        }
+
+        ui.separator();
+
+        if ui
+            .add_enabled(
+                !self.node_ctx.get_selected_nodes().is_empty(),
+                egui::Button::new("Group selected nodes"),
+            )
+            .clicked()
+        {
+            let node_ids = self.node_ctx.get_selected_nodes().to_vec();
+            self.group_nodes(&node_ids);
+            ui.close_menu();
+        }
+
+        if ui.button("Zoom to fit").clicked() {
+            self.node_ctx.zoom_to_fit();
+            ui.close_menu();
+        }
+
+        if ui
+            .add_enabled(
+                !self.node_ctx.get_selected_nodes().is_empty(),
+                egui::Button::new("Zoom to selection"),
+            )
+            .clicked()
+        {
+            self.node_ctx.zoom_to_selection();
+            ui.close_menu();
+        }
+
+        if ui.button("Reset zoom").clicked() {
+            self.node_ctx.set_zoom(1.0);
+            ui.close_menu();
+        }
+
+        let mut minimap = self.minimap_shown();
+        if ui.checkbox(&mut minimap, "Minimap").changed() {
+            self.show_minimap(minimap);
             self.should_save = true;
+            ui.close_menu();
         }
+
+        ui.menu_button("Links", |ui| {
+            let link_style = &mut self.node_ctx.style_mut().link_style;
+            for (style, name) in LINK_STYLES {
+                if ui.radio_value(link_style, style, name).clicked() {
+                    self.should_save = true;
+                    ui.close_menu();
+                }
+            }
+
+            ui.separator();
+
+            if ui.checkbox(&mut self.link_flow, "Audio flow").changed() {
+                self.should_save = true;
+                ui.close_menu();
+            }
+        });
     }
 
     fn application_node_button(
@@ -377,7 +1864,14 @@ impl MyApp {
         device: DeviceInfo,
         node_kind: NodeKind,
     ) {
-        if egui::Button::new(&device.name).wrap(false).ui(ui).clicked() {
+        // Virtual cables are flagged, as they are what virtual microphone nodes play into
+        let text = if device.virtual_cable {
+            format!("🎤 {}", device.name)
+        } else {
+            device.name.clone()
+        };
+
+        if egui::Button::new(text).wrap(false).ui(ui).clicked() {
             added_node.replace(Node {
                 id: device.id,
                 kind: node_kind,
@@ -392,10 +1886,87 @@ impl MyApp {
 
 impl App for MyApp {
     fn update(&mut self, ui_ctx: &egui::Context, _frame: &mut Frame) {
+        self.poll_plugin_discovery();
+
         egui::CentralPanel::default()
             .frame(egui::Frame::none())
             .show(ui_ctx, |ui| self.interact_and_draw(ui_ctx, ui));
 
+        if self.rules_window_open {
+            let output_devices = self.ctx.read().output_devices();
+
+            if rules::rules_window(
+                ui_ctx,
+                &mut self.rules_window_open,
+                &mut self.rules,
+                &output_devices,
+            ) {
+                self.should_save = true;
+            }
+        }
+
+        if self.ducking_window_open {
+            let nodes = self.ctx.read().nodes().to_vec();
+
+            if ducking::ducking_window(
+                ui_ctx,
+                &mut self.ducking_window_open,
+                &mut self.ducking_rules,
+                &nodes,
+            ) {
+                self.should_save = true;
+            }
+        }
+
+        if let Some(edit) = &mut self.channel_matrix_edit {
+            let mut open = self.ui_links.contains_key(&edit.link_id);
+
+            if channels::channel_matrix_window(ui_ctx, &mut open, edit) {
+                self.ctx
+                    .write()
+                    .set_channel_matrix(edit.src_id, edit.dst_id, edit.matrix.clone());
+                self.should_save = true;
+            }
+
+            if !open {
+                self.channel_matrix_edit = None;
+            }
+        }
+
+        self.delay_window(ui_ctx);
+        self.link_effects_window(ui_ctx);
+
+        if self.soundboard_ui.open {
+            let nodes = self.ctx.read().nodes().to_vec();
+            let player_id = self.soundboard.player_id;
+            let playing = player_id
+                .map(|player_id| self.ctx.read().playing_clips(player_id))
+                .unwrap_or_default();
+
+            let response = soundboard::soundboard_window(
+                ui_ctx,
+                &mut self.soundboard_ui,
+                &mut self.soundboard,
+                &nodes,
+                &playing,
+            );
+
+            if response.changed {
+                self.clip_triggers.set_clips(&self.soundboard.clips);
+                self.should_save = true;
+            }
+
+            if let (true, Some(player_id)) = (response.stop_all, player_id) {
+                self.ctx.write().stop_clips(player_id);
+            }
+
+            if response.reconnect_midi {
+                self.clip_triggers.reconnect_midi();
+            }
+
+            self.triggered_clips.extend(response.played);
+        }
+
         ui_ctx.request_repaint();
     }
 
@@ -404,6 +1975,8 @@ impl App for MyApp {
 
         self.should_save = false;
 
+        self.ctx.write().store_effect_states();
+
         let mut nodes = self.ctx.read().nodes().to_vec();
         for node in nodes.iter_mut() {
             if let Some(pos) = self.node_ctx.node_pos(node.id) {
@@ -411,14 +1984,77 @@ impl App for MyApp {
             }
         }
 
-        let links: Vec<(Uuid, Uuid, Uuid)> = self
+        for note in self.notes.iter_mut() {
+            if let Some(pos) = self.node_ctx.node_pos(note.id) {
+                note.pos = (pos.x, pos.y);
+            }
+            if let Some(size) = self.node_ctx.note_size(note.id) {
+                note.size = (size.x, size.y);
+            }
+        }
+
+        let node_ids = nodes
+            .iter()
+            .map(|node| node.id)
+            .chain(self.notes.iter().map(|note| note.id))
+            .collect::<Vec<_>>();
+        NodeGroup::prune(&mut self.groups, &node_ids);
+
+        let links: Vec<Link> = self
             .ui_links
             .iter()
-            .map(|(id, (start, end))| (*id, *start, *end))
+            .map(|(&id, &(start, end))| Link::from_context(&*self.ctx.read(), id, start, end))
+            .collect::<_>();
+
+        let link_reroutes: Vec<(Uuid, Vec<(f32, f32)>)> = self
+            .ui_links
+            .keys()
+            .map(|id| (*id, self.node_ctx.link_reroutes(*id)))
+            .filter(|(_, points)| !points.is_empty())
+            .map(|(id, points)| (id, points.iter().map(|point| (point.x, point.y)).collect()))
             .collect::<_>();
 
+        storage.set_string(
+            "zoom",
+            serde_json::to_string(&self.node_ctx.zoom()).unwrap(),
+        );
+        storage.set_string(
+            "minimap",
+            serde_json::to_string(&self.minimap_shown()).unwrap(),
+        );
+        let link_style = self.node_ctx.style().link_style;
+        if let Some((_, name)) = LINK_STYLES.iter().find(|(style, _)| *style == link_style) {
+            storage.set_string("link_style", serde_json::to_string(name).unwrap());
+        }
+        storage.set_string("link_flow", serde_json::to_string(&self.link_flow).unwrap());
         storage.set_string("nodes", serde_json::to_string_pretty(&nodes).unwrap());
         storage.set_string("links", serde_json::to_string_pretty(&links).unwrap());
+        storage.set_string(
+            "link_reroutes",
+            serde_json::to_string_pretty(&link_reroutes).unwrap(),
+        );
+        storage.set_string(
+            "groups",
+            serde_json::to_string_pretty(&self.groups).unwrap(),
+        );
+        storage.set_string("notes", serde_json::to_string_pretty(&self.notes).unwrap());
+        storage.set_string("rules", serde_json::to_string_pretty(&self.rules).unwrap());
+        storage.set_string(
+            "ducking",
+            serde_json::to_string_pretty(&self.ducking_rules).unwrap(),
+        );
+        storage.set_string(
+            "soundboard",
+            serde_json::to_string_pretty(&self.soundboard).unwrap(),
+        );
+    }
+
+    fn on_exit_event(&mut self) -> bool {
+        // Ducked nodes would otherwise stay lowered after Nodio is closed
+        for (node_id, volume) in self.ducker.release_all() {
+            self.ctx.write().set_volume(node_id, volume);
+        }
+        true
     }
 
     fn auto_save_interval(&self) -> Duration {
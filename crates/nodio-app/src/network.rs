@@ -0,0 +1,142 @@
+use eframe::egui;
+use egui::{Color32, ComboBox, DragValue, RichText, TextEdit, Ui};
+
+use nodio_core::{NetworkConfig, NetworkStatus, RtpPayload, Uuid};
+use nodio_engine::SessionDescription;
+
+/// Text being entered on a network node, which is applied when done.
+#[derive(Default)]
+pub struct NetworkEdit {
+    address: Option<String>,
+    /// Session description pasted into a receiver
+    sdp: String,
+    sdp_error: Option<String>,
+}
+
+impl NetworkEdit {
+    pub fn is_empty(&self) -> bool {
+        self.address.is_none() && self.sdp.is_empty() && self.sdp_error.is_none()
+    }
+}
+
+/// Shows the settings and the state of a network sender or receiver node. Returns true if the
+/// settings were changed.
+pub fn network_ui(
+    ui: &mut Ui,
+    node_id: Uuid,
+    sender: bool,
+    config: &mut NetworkConfig,
+    edit: &mut NetworkEdit,
+    status: &NetworkStatus,
+    zoom: f32,
+) -> bool {
+    let mut changed = false;
+
+    ui.horizontal(|ui| {
+        let address = edit.address.get_or_insert_with(|| config.address.clone());
+        let response = ui.add(
+            TextEdit::singleline(address)
+                .hint_text("239.69.1.10:5004")
+                .desired_width(140.0 * zoom),
+        );
+
+        if response.lost_focus() {
+            let address = edit.address.take().unwrap_or_default();
+            if address.trim() != config.address {
+                config.address = address.trim().to_string();
+                changed = true;
+            }
+        } else if !response.has_focus() {
+            edit.address = None;
+        }
+
+        ComboBox::from_id_source((node_id, "payload"))
+            .width(50.0 * zoom)
+            .selected_text(config.payload.name())
+            .show_ui(ui, |ui| {
+                for payload in RtpPayload::ALL {
+                    if ui
+                        .selectable_value(&mut config.payload, payload, payload.name())
+                        .changed()
+                    {
+                        // Sent with the payload type of the payload
+                        config.payload_type = None;
+                        changed = true;
+                    }
+                }
+            });
+    });
+
+    if sender {
+        ui.horizontal(|ui| {
+            changed |= ui
+                .checkbox(&mut config.announce, "Announce")
+                .on_hover_text("Announce the stream with SAP, so that AES67 devices find it")
+                .changed();
+
+            if let Some(sdp) = &status.sdp {
+                if ui
+                    .button("Copy SDP")
+                    .on_hover_text("Copy the session description for configuring receivers")
+                    .clicked()
+                {
+                    ui.output().copied_text = sdp.clone();
+                }
+            }
+        });
+
+        ui.label(format!("{} packets sent", status.packets));
+    } else {
+        ui.horizontal(|ui| {
+            ui.label("Latency");
+            changed |= ui
+                .add(
+                    DragValue::new(&mut config.latency_ms)
+                        .clamp_range(1..=1000)
+                        .suffix(" ms"),
+                )
+                .changed();
+        });
+
+        ui.horizontal(|ui| {
+            ui.add(
+                TextEdit::singleline(&mut edit.sdp)
+                    .hint_text("Paste SDP")
+                    .desired_width(140.0 * zoom),
+            );
+
+            if ui
+                .add_enabled(!edit.sdp.trim().is_empty(), egui::Button::new("Apply"))
+                .on_hover_text("Take the address and payload from a session description")
+                .clicked()
+            {
+                match SessionDescription::parse(&edit.sdp) {
+                    Ok(description) => {
+                        config.address = description.address.to_string();
+                        config.payload = description.payload;
+                        config.payload_type = Some(description.payload_type);
+                        edit.sdp.clear();
+                        edit.sdp_error = None;
+                        changed = true;
+                    }
+                    Err(err) => edit.sdp_error = Some(err.to_string()),
+                }
+            }
+        });
+
+        if let Some(err) = &edit.sdp_error {
+            ui.label(RichText::new(err).color(Color32::YELLOW));
+        }
+
+        ui.label(format!(
+            "{:.0} ms buffered, {} packets lost",
+            status.buffered_ms, status.lost
+        ));
+    }
+
+    if let Some(err) = &status.error {
+        ui.label(RichText::new(format!("⚠ {}", err)).color(Color32::YELLOW));
+    }
+
+    changed
+}
@@ -0,0 +1,162 @@
+use eframe::egui;
+use egui::{CollapsingHeader, ComboBox, Slider, TextEdit, Ui};
+
+use nodio_core::{LoopMode, PlayerCommand, PlayerConfig, PlayerState, PlayerStatus, Uuid};
+
+pub enum PlayerChange {
+    Config,
+    Command(PlayerCommand),
+}
+
+/// Shows the transport controls and the playlist of a file player node.
+pub fn player_ui(
+    ui: &mut Ui,
+    node_id: Uuid,
+    config: &mut PlayerConfig,
+    new_path: &mut String,
+    status: &PlayerStatus,
+    zoom: f32,
+) -> Option<PlayerChange> {
+    let mut change = None;
+    let stopped = status.state == PlayerState::Stopped;
+
+    ui.horizontal(|ui| {
+        if ui
+            .add_enabled(!stopped, egui::Button::new("⏮"))
+            .on_hover_text("Previous")
+            .clicked()
+        {
+            change = Some(PlayerChange::Command(PlayerCommand::Previous));
+        }
+
+        if status.state == PlayerState::Playing {
+            if ui.button("⏸").on_hover_text("Pause").clicked() {
+                change = Some(PlayerChange::Command(PlayerCommand::Pause));
+            }
+        } else if ui
+            .add_enabled(!config.playlist.is_empty(), egui::Button::new("▶"))
+            .on_hover_text("Play")
+            .clicked()
+        {
+            change = Some(PlayerChange::Command(PlayerCommand::Play));
+        }
+
+        if ui
+            .add_enabled(!stopped, egui::Button::new("⏹"))
+            .on_hover_text("Stop")
+            .clicked()
+        {
+            change = Some(PlayerChange::Command(PlayerCommand::Stop));
+        }
+
+        if ui
+            .add_enabled(!stopped, egui::Button::new("⏭"))
+            .on_hover_text("Next")
+            .clicked()
+        {
+            change = Some(PlayerChange::Command(PlayerCommand::Next));
+        }
+
+        ui.monospace(format!(
+            "{} / {}",
+            format_position(status.position_secs),
+            status
+                .duration_secs
+                .map_or("--:--".to_string(), format_position)
+        ));
+    });
+
+    if let Some(duration) = status.duration_secs {
+        let mut position = status.position_secs.min(duration);
+        let response = ui.add(
+            Slider::new(&mut position, 0.0..=duration)
+                .show_value(false)
+                .text("Position"),
+        );
+
+        if response.drag_released() || (response.changed() && !response.dragged()) {
+            change = Some(PlayerChange::Command(PlayerCommand::Seek(position)));
+        }
+    }
+
+    ui.horizontal(|ui| {
+        ComboBox::from_id_source((node_id, "loop_mode"))
+            .selected_text(config.loop_mode.name())
+            .show_ui(ui, |ui| {
+                for loop_mode in LoopMode::ALL {
+                    if ui
+                        .selectable_value(&mut config.loop_mode, loop_mode, loop_mode.name())
+                        .changed()
+                    {
+                        change = Some(PlayerChange::Config);
+                    }
+                }
+            });
+    });
+
+    if ui
+        .add(
+            Slider::new(&mut config.gain_db, -60.0..=12.0)
+                .suffix(" dB")
+                .text("Gain"),
+        )
+        .changed()
+    {
+        change = Some(PlayerChange::Config);
+    }
+
+    CollapsingHeader::new(format!("Playlist ({})", config.playlist.len()))
+        .id_source((node_id, "playlist"))
+        .show(ui, |ui| {
+            let mut removed = None;
+
+            for (idx, path) in config.playlist.iter().enumerate() {
+                ui.horizontal(|ui| {
+                    if ui.small_button("🗑").on_hover_text("Remove").clicked() {
+                        removed = Some(idx);
+                    }
+
+                    let name = std::path::Path::new(path)
+                        .file_name()
+                        .map_or(path.clone(), |name| name.to_string_lossy().into_owned());
+
+                    if ui
+                        .selectable_label(status.track == Some(idx), name)
+                        .on_hover_text(path)
+                        .double_clicked()
+                    {
+                        change = Some(PlayerChange::Command(PlayerCommand::PlayTrack(idx)));
+                    }
+                });
+            }
+
+            if let Some(idx) = removed {
+                config.playlist.remove(idx);
+                change = Some(PlayerChange::Config);
+            }
+
+            ui.horizontal(|ui| {
+                let response = ui.add(
+                    TextEdit::singleline(new_path)
+                        .hint_text("Path of an audio file")
+                        .desired_width(150.0 * zoom),
+                );
+                let submitted = response.lost_focus() && ui.input().key_pressed(egui::Key::Enter);
+
+                if (ui.button("Add").clicked() || submitted) && !new_path.trim().is_empty() {
+                    config
+                        .playlist
+                        .push(new_path.trim().trim_matches('"').to_string());
+                    new_path.clear();
+                    change = Some(PlayerChange::Config);
+                }
+            });
+        });
+
+    change
+}
+
+fn format_position(secs: f64) -> String {
+    let secs = secs as u64;
+    format!("{:02}:{:02}", secs / 60, secs % 60)
+}
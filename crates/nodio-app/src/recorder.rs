@@ -0,0 +1,148 @@
+use eframe::egui;
+use egui::{CollapsingHeader, ComboBox, DragValue, TextEdit, Ui};
+
+use nodio_core::{RecorderConfig, RecorderState, RecorderStatus, RecordingFormat, Rotation, Uuid};
+
+pub enum RecorderChange {
+    Config,
+    State(RecorderState),
+}
+
+/// Shows the controls and settings of a recorder node.
+pub fn recorder_ui(
+    ui: &mut Ui,
+    node_id: Uuid,
+    config: &mut RecorderConfig,
+    status: &RecorderStatus,
+) -> Option<RecorderChange> {
+    let mut change = None;
+
+    ui.horizontal(|ui| {
+        let recording = status.state == RecorderState::Recording;
+
+        if ui
+            .add_enabled(!recording, egui::Button::new("⏺"))
+            .on_hover_text(if status.state == RecorderState::Paused {
+                "Resume"
+            } else {
+                "Record"
+            })
+            .clicked()
+        {
+            change = Some(RecorderChange::State(RecorderState::Recording));
+        }
+
+        if ui
+            .add_enabled(recording, egui::Button::new("⏸"))
+            .on_hover_text("Pause")
+            .clicked()
+        {
+            change = Some(RecorderChange::State(RecorderState::Paused));
+        }
+
+        if ui
+            .add_enabled(
+                status.state != RecorderState::Stopped,
+                egui::Button::new("⏹"),
+            )
+            .on_hover_text("Stop")
+            .clicked()
+        {
+            change = Some(RecorderChange::State(RecorderState::Stopped));
+        }
+
+        let readout = ui.monospace(format!(
+            "{} {}",
+            format_elapsed(status.elapsed_secs),
+            format_size(status.bytes)
+        ));
+        if let Some(file) = &status.file {
+            readout.on_hover_text(file);
+        }
+    });
+
+    CollapsingHeader::new("Settings")
+        .id_source((node_id, "recorder"))
+        .show(ui, |ui| {
+            ComboBox::from_id_source((node_id, "format"))
+                .selected_text(config.format.name())
+                .show_ui(ui, |ui| {
+                    for format in RecordingFormat::ALL {
+                        if ui
+                            .selectable_value(&mut config.format, format, format.name())
+                            .changed()
+                        {
+                            change = Some(RecorderChange::Config);
+                        }
+                    }
+                });
+
+            ui.horizontal(|ui| {
+                let rotations = [
+                    (Rotation::Never, "Single file"),
+                    (Rotation::Size { megabytes: 100 }, "New file by size"),
+                    (Rotation::Time { minutes: 60 }, "New file by time"),
+                ];
+
+                let selected_text = rotations
+                    .iter()
+                    .find(|(rotation, _)| {
+                        std::mem::discriminant(rotation) == std::mem::discriminant(&config.rotation)
+                    })
+                    .map_or("", |(_, text)| text);
+
+                ComboBox::from_id_source((node_id, "rotation"))
+                    .selected_text(selected_text)
+                    .show_ui(ui, |ui| {
+                        for (rotation, text) in rotations {
+                            let selected = std::mem::discriminant(&rotation)
+                                == std::mem::discriminant(&config.rotation);
+
+                            if ui.selectable_label(selected, text).clicked() && !selected {
+                                config.rotation = rotation;
+                                change = Some(RecorderChange::Config);
+                            }
+                        }
+                    });
+
+                let limit = match &mut config.rotation {
+                    Rotation::Never => None,
+                    Rotation::Size { megabytes } => Some((megabytes, " MB")),
+                    Rotation::Time { minutes } => Some((minutes, " min")),
+                };
+
+                if let Some((value, suffix)) = limit {
+                    if ui
+                        .add(DragValue::new(value).clamp_range(1..=100000).suffix(suffix))
+                        .changed()
+                    {
+                        change = Some(RecorderChange::Config);
+                    }
+                }
+            });
+
+            if ui
+                .add(TextEdit::singleline(&mut config.directory).hint_text("Directory"))
+                .lost_focus()
+            {
+                change = Some(RecorderChange::Config);
+            }
+        });
+
+    change
+}
+
+fn format_elapsed(secs: f64) -> String {
+    let secs = secs as u64;
+    format!("{:02}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+}
+
+fn format_size(bytes: u64) -> String {
+    if bytes < 1024 * 1024 {
+        format!("{:.0} kB", bytes as f64 / 1024.0)
+    } else if bytes < 1024 * 1024 * 1024 {
+        format!("{:.1} MB", bytes as f64 / (1024.0 * 1024.0))
+    } else {
+        format!("{:.2} GB", bytes as f64 / (1024.0 * 1024.0 * 1024.0))
+    }
+}
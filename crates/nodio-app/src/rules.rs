@@ -0,0 +1,110 @@
+use eframe::egui;
+use egui::{DragValue, TextEdit, Ui};
+
+use nodio_core::{DeviceInfo, Rule};
+
+/// Shows the window for editing the automatic routing rules.
+/// Returns true if any of the rules were changed.
+pub fn rules_window(
+    ui_ctx: &egui::Context,
+    open: &mut bool,
+    rules: &mut Vec<Rule>,
+    output_devices: &[DeviceInfo],
+) -> bool {
+    let mut changed = false;
+
+    egui::Window::new("Rules")
+        .open(open)
+        .resizable(true)
+        .default_width(360.0)
+        .show(ui_ctx, |ui| {
+            ui.label("Applications matching a rule are added automatically when they start playing audio.");
+            ui.separator();
+
+            let mut removed_rule = None;
+
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                for (rule_idx, rule) in rules.iter_mut().enumerate() {
+                    ui.push_id(rule.id, |ui| {
+                        if rule_ui(ui, rule, output_devices, &mut changed) {
+                            removed_rule = Some(rule_idx);
+                        }
+                    });
+                    ui.separator();
+                }
+            });
+
+            if let Some(rule_idx) = removed_rule {
+                rules.remove(rule_idx);
+                changed = true;
+            }
+
+            if ui.button("Add rule").clicked() {
+                rules.push(Rule::default());
+                changed = true;
+            }
+        });
+
+    changed
+}
+
+/// Returns true if the rule should be removed.
+fn rule_ui(
+    ui: &mut Ui,
+    rule: &mut Rule,
+    output_devices: &[DeviceInfo],
+    changed: &mut bool,
+) -> bool {
+    let mut remove = false;
+
+    ui.horizontal(|ui| {
+        *changed |= ui.checkbox(&mut rule.enabled, "").changed();
+        *changed |= ui
+            .add(TextEdit::singleline(&mut rule.pattern).hint_text("*chrome*"))
+            .changed();
+
+        if ui.button("Remove").clicked() {
+            remove = true;
+        }
+    });
+
+    ui.horizontal(|ui| {
+        ui.label("Position");
+        *changed |= ui
+            .add(DragValue::new(&mut rule.pos.0).prefix("x: "))
+            .changed();
+        *changed |= ui
+            .add(DragValue::new(&mut rule.pos.1).prefix("y: "))
+            .changed();
+    });
+
+    let mut removed_target = None;
+
+    for (target_idx, target) in rule.targets.iter_mut().enumerate() {
+        ui.horizontal(|ui| {
+            ui.label("Link to");
+            *changed |= ui.text_edit_singleline(target).changed();
+
+            if ui.small_button("✖").clicked() {
+                removed_target = Some(target_idx);
+            }
+        });
+    }
+
+    if let Some(target_idx) = removed_target {
+        rule.targets.remove(target_idx);
+        *changed = true;
+    }
+
+    ui.menu_button("Add output device", |ui| {
+        for device in output_devices {
+            if ui.button(&device.name).clicked() {
+                rule.targets.push(device.name.clone());
+                *changed = true;
+                ui.close_menu();
+            }
+        }
+    });
+
+    remove
+}
@@ -0,0 +1,253 @@
+use eframe::egui;
+use egui::{vec2, Button, Color32, ComboBox, DragValue, RichText, Slider, TextEdit, Ui};
+
+use nodio_core::{Clip, Node, NodeKind, Soundboard, Uuid};
+
+use crate::triggers::validate_hotkey;
+
+/// State of the soundboard window that is not persisted.
+#[derive(Default)]
+pub struct SoundboardUi {
+    pub open: bool,
+    editing: bool,
+    selected_clip: Option<Uuid>,
+}
+
+#[derive(Default)]
+pub struct SoundboardResponse {
+    /// The layout or the clips were changed
+    pub changed: bool,
+    pub played: Vec<Uuid>,
+    pub stop_all: bool,
+    pub reconnect_midi: bool,
+}
+
+/// Shows the soundboard window with a button for each clip.
+pub fn soundboard_window(
+    ui_ctx: &egui::Context,
+    state: &mut SoundboardUi,
+    soundboard: &mut Soundboard,
+    nodes: &[Node],
+    playing: &[Uuid],
+) -> SoundboardResponse {
+    let mut response = SoundboardResponse::default();
+    let mut open = state.open;
+
+    egui::Window::new("Soundboard")
+        .open(&mut open)
+        .resizable(true)
+        .default_width(400.0)
+        .show(ui_ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Play with");
+                response.changed |= player_combo(ui, &mut soundboard.player_id, nodes);
+
+                if ui.button("⏹ Stop all").clicked() {
+                    response.stop_all = true;
+                }
+
+                ui.toggle_value(&mut state.editing, "✏ Edit");
+            });
+
+            if soundboard.player_id.is_none() {
+                ui.label("Clips are played by a file player node, which is linked to the outputs.");
+            }
+
+            ui.separator();
+
+            clip_grid(ui, state, soundboard, playing, &mut response);
+
+            if !state.editing {
+                return;
+            }
+
+            ui.separator();
+
+            ui.horizontal(|ui| {
+                if ui.button("Add clip").clicked() {
+                    let clip = Clip::default();
+                    state.selected_clip = Some(clip.id);
+                    soundboard.clips.push(clip);
+                    response.changed = true;
+                }
+
+                ui.label("Columns");
+                response.changed |= ui
+                    .add(DragValue::new(&mut soundboard.columns).clamp_range(1..=16))
+                    .changed();
+
+                if ui.button("Reconnect MIDI").clicked() {
+                    response.reconnect_midi = true;
+                }
+            });
+
+            let selected_idx = soundboard
+                .clips
+                .iter()
+                .position(|clip| Some(clip.id) == state.selected_clip);
+
+            if let Some(clip_idx) = selected_idx {
+                ui.separator();
+
+                if clip_ui(ui, &mut soundboard.clips[clip_idx], &mut response.changed) {
+                    soundboard.clips.remove(clip_idx);
+                    state.selected_clip = None;
+                    response.changed = true;
+                }
+            }
+        });
+
+    state.open = open;
+
+    response
+}
+
+fn clip_grid(
+    ui: &mut Ui,
+    state: &mut SoundboardUi,
+    soundboard: &Soundboard,
+    playing: &[Uuid],
+    response: &mut SoundboardResponse,
+) {
+    egui::Grid::new("soundboard_clips")
+        .spacing(vec2(4.0, 4.0))
+        .show(ui, |ui| {
+            for (clip_idx, clip) in soundboard.clips.iter().enumerate() {
+                let mut button = Button::new(&clip.name);
+                if playing.contains(&clip.id) {
+                    button = button.fill(Color32::from_rgb(40, 110, 60));
+                }
+                if state.editing && state.selected_clip == Some(clip.id) {
+                    button = button.stroke((1.0, Color32::LIGHT_BLUE));
+                }
+
+                let clip_response = ui.add_sized(vec2(90.0, 48.0), button);
+                let clip_response = match &clip.hotkey {
+                    Some(hotkey) => clip_response.on_hover_text(hotkey),
+                    None => clip_response,
+                };
+
+                if clip_response.clicked() {
+                    if state.editing {
+                        state.selected_clip = Some(clip.id);
+                    } else {
+                        response.played.push(clip.id);
+                    }
+                }
+
+                if (clip_idx + 1) % soundboard.columns.max(1) == 0 {
+                    ui.end_row();
+                }
+            }
+        });
+}
+
+/// Shows the settings of a clip. Returns true if the clip should be removed.
+fn clip_ui(ui: &mut Ui, clip: &mut Clip, changed: &mut bool) -> bool {
+    let mut remove = false;
+
+    egui::Grid::new("soundboard_clip")
+        .num_columns(2)
+        .show(ui, |ui| {
+            ui.label("Name");
+            *changed |= ui.text_edit_singleline(&mut clip.name).changed();
+            ui.end_row();
+
+            ui.label("File");
+            *changed |= ui
+                .add(TextEdit::singleline(&mut clip.path).hint_text("Path of an audio file"))
+                .changed();
+            ui.end_row();
+
+            ui.label("Gain");
+            *changed |= ui
+                .add(Slider::new(&mut clip.gain_db, -60.0..=12.0).suffix(" dB"))
+                .changed();
+            ui.end_row();
+
+            ui.label("Hotkey");
+            ui.horizontal(|ui| {
+                let mut hotkey = clip.hotkey.clone().unwrap_or_default();
+                if ui
+                    .add(TextEdit::singleline(&mut hotkey).hint_text("e.g. Ctrl+Shift+1"))
+                    .changed()
+                {
+                    let hotkey = hotkey.trim();
+                    clip.hotkey = (!hotkey.is_empty()).then(|| hotkey.to_string());
+                    *changed = true;
+                }
+
+                if let Some(err) = clip.hotkey.as_deref().and_then(validate_hotkey) {
+                    ui.label(RichText::new("⚠").color(Color32::YELLOW))
+                        .on_hover_text(err);
+                }
+            });
+            ui.end_row();
+
+            ui.label("MIDI note");
+            *changed |= optional_value(ui, &mut clip.midi_note, 36, 0..=127);
+            ui.end_row();
+
+            ui.label("Choke group")
+                .on_hover_text("Starting the clip stops the other clips of the group");
+            *changed |= optional_value(ui, &mut clip.choke_group, 1, 1..=99);
+            ui.end_row();
+        });
+
+    if ui.button("Remove clip").clicked() {
+        remove = true;
+    }
+
+    remove
+}
+
+/// Checkbox enabling a value. Returns true if the value was changed.
+fn optional_value<T>(
+    ui: &mut Ui,
+    value: &mut Option<T>,
+    default: T,
+    range: std::ops::RangeInclusive<T>,
+) -> bool
+where
+    T: egui::emath::Numeric,
+{
+    let mut changed = false;
+
+    ui.horizontal(|ui| {
+        let mut enabled = value.is_some();
+        if ui.checkbox(&mut enabled, "").changed() {
+            *value = enabled.then_some(default);
+            changed = true;
+        }
+
+        if let Some(value) = value {
+            changed |= ui.add(DragValue::new(value).clamp_range(range)).changed();
+        }
+    });
+
+    changed
+}
+
+/// Returns true if the selected player was changed.
+fn player_combo(ui: &mut Ui, player_id: &mut Option<Uuid>, nodes: &[Node]) -> bool {
+    let previous_player_id = *player_id;
+
+    let selected_text = nodes
+        .iter()
+        .find(|node| Some(node.id) == *player_id)
+        .map(|node| node.display_name.as_str())
+        .unwrap_or("Select file player");
+
+    ComboBox::from_id_source("soundboard_player")
+        .selected_text(selected_text)
+        .show_ui(ui, |ui| {
+            for node in nodes
+                .iter()
+                .filter(|node| node.kind == NodeKind::FilePlayer)
+            {
+                ui.selectable_value(player_id, Some(node.id), &node.display_name);
+            }
+        });
+
+    *player_id != previous_player_id
+}
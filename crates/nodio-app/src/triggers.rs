@@ -0,0 +1,218 @@
+use std::str::FromStr;
+
+use global_hotkey::hotkey::HotKey;
+use global_hotkey::{GlobalHotKeyEvent, GlobalHotKeyManager, HotKeyState};
+use log::warn;
+
+use nodio_core::{Clip, Uuid};
+
+/// Listens for the global hotkeys and MIDI notes that trigger the clips of the soundboard.
+pub struct ClipTriggers {
+    hotkey_manager: Option<GlobalHotKeyManager>,
+    /// Registered hotkeys with the clips they trigger
+    hotkeys: Vec<(HotKey, Vec<Uuid>)>,
+    midi: Option<midi::MidiNotes>,
+}
+
+impl ClipTriggers {
+    pub fn new() -> Self {
+        let hotkey_manager = match GlobalHotKeyManager::new() {
+            Ok(manager) => Some(manager),
+            Err(err) => {
+                warn!("Global hotkeys are not available: {}", err);
+                None
+            }
+        };
+
+        Self {
+            hotkey_manager,
+            hotkeys: Vec::new(),
+            midi: Some(midi::MidiNotes::open()),
+        }
+    }
+
+    /// Registers the hotkeys of the clips in place of the previous ones.
+    pub fn set_clips(&mut self, clips: &[Clip]) {
+        let manager = match &self.hotkey_manager {
+            Some(manager) => manager,
+            None => return,
+        };
+
+        let mut hotkeys: Vec<(HotKey, Vec<Uuid>)> = Vec::new();
+
+        for clip in clips {
+            let hotkey = match clip.hotkey.as_deref().map(HotKey::from_str) {
+                Some(Ok(hotkey)) => hotkey,
+                _ => continue,
+            };
+
+            match hotkeys.iter_mut().find(|(other, _)| *other == hotkey) {
+                Some((_, clip_ids)) => clip_ids.push(clip.id),
+                None => hotkeys.push((hotkey, vec![clip.id])),
+            }
+        }
+
+        if hotkeys == self.hotkeys {
+            return;
+        }
+
+        for (hotkey, _) in self.hotkeys.drain(..) {
+            if let Err(err) = manager.unregister(hotkey) {
+                warn!("Could not unregister hotkey {:?}: {}", hotkey, err);
+            }
+        }
+
+        hotkeys.retain(|(hotkey, _)| match manager.register(*hotkey) {
+            Ok(()) => true,
+            Err(err) => {
+                warn!("Could not register hotkey {:?}: {}", hotkey, err);
+                false
+            }
+        });
+
+        self.hotkeys = hotkeys;
+    }
+
+    /// Reopens the MIDI inputs, e.g. after a device was plugged in.
+    pub fn reconnect_midi(&mut self) {
+        // The inputs are closed first, as they can not be opened twice
+        self.midi = None;
+        self.midi = Some(midi::MidiNotes::open());
+    }
+
+    /// Takes the clips that were triggered since the last call.
+    pub fn take_triggered(&mut self, clips: &[Clip]) -> Vec<Uuid> {
+        let mut triggered = Vec::new();
+
+        while let Ok(event) = GlobalHotKeyEvent::receiver().try_recv() {
+            if event.state != HotKeyState::Pressed {
+                continue;
+            }
+
+            if let Some((_, clip_ids)) = self
+                .hotkeys
+                .iter()
+                .find(|(hotkey, _)| hotkey.id() == event.id)
+            {
+                triggered.extend_from_slice(clip_ids);
+            }
+        }
+
+        for note in self.midi.iter().flat_map(|midi| midi.take_notes()) {
+            triggered.extend(
+                clips
+                    .iter()
+                    .filter(|clip| clip.midi_note == Some(note))
+                    .map(|clip| clip.id),
+            );
+        }
+
+        triggered
+    }
+}
+
+/// Returns an error message if the hotkey can not be used.
+pub fn validate_hotkey(hotkey: &str) -> Option<String> {
+    HotKey::from_str(hotkey).err().map(|err| err.to_string())
+}
+
+#[cfg(windows)]
+mod midi {
+    use std::sync::mpsc::{self, Receiver};
+
+    use log::{info, warn};
+    use midir::{MidiInput, MidiInputConnection};
+
+    const NOTE_ON: u8 = 0x90;
+
+    /// Receives the note-on messages of all MIDI inputs.
+    pub struct MidiNotes {
+        connections: Vec<MidiInputConnection<()>>,
+        receiver: Receiver<u8>,
+    }
+
+    impl MidiNotes {
+        pub fn open() -> Self {
+            let (sender, receiver) = mpsc::channel();
+            let mut connections = Vec::new();
+
+            let port_count = match MidiInput::new("Nodio") {
+                Ok(input) => input.port_count(),
+                Err(err) => {
+                    warn!("MIDI input is not available: {}", err);
+                    0
+                }
+            };
+
+            // Connecting consumes the input, so every port needs an input of its own
+            for port_idx in 0..port_count {
+                let input = match MidiInput::new("Nodio") {
+                    Ok(input) => input,
+                    Err(err) => {
+                        warn!("MIDI input is not available: {}", err);
+                        break;
+                    }
+                };
+
+                let port = match input.ports().into_iter().nth(port_idx) {
+                    Some(port) => port,
+                    None => break,
+                };
+                let port_name = input.port_name(&port).unwrap_or_default();
+
+                let sender = sender.clone();
+                match input.connect(
+                    &port,
+                    "nodio-soundboard",
+                    move |_, message, _| {
+                        if let &[status, note, velocity] = message {
+                            if status & 0xF0 == NOTE_ON && velocity > 0 {
+                                sender.send(note).ok();
+                            }
+                        }
+                    },
+                    (),
+                ) {
+                    Ok(connection) => {
+                        info!("Listening to MIDI input {}", port_name);
+                        connections.push(connection);
+                    }
+                    Err(err) => warn!("Could not open MIDI input {}: {}", port_name, err),
+                }
+            }
+
+            Self {
+                connections,
+                receiver,
+            }
+        }
+
+        pub fn take_notes(&self) -> Vec<u8> {
+            self.receiver.try_iter().collect()
+        }
+    }
+
+    impl Drop for MidiNotes {
+        fn drop(&mut self) {
+            for connection in self.connections.drain(..) {
+                connection.close();
+            }
+        }
+    }
+}
+
+/// MIDI input is only supported on Windows.
+#[cfg(not(windows))]
+mod midi {
+    pub struct MidiNotes;
+
+    impl MidiNotes {
+        pub fn open() -> Self {
+            Self
+        }
+
+        pub fn take_notes(&self) -> Vec<u8> {
+            Vec::new()
+        }
+    }
+}
@@ -0,0 +1,65 @@
+use eframe::egui;
+use egui::{Color32, ComboBox, RichText, Ui};
+
+use nodio_core::{DeviceInfo, Uuid, VirtualMicConfig};
+
+/// Whether the mix has to be played into a virtual cable, as the backend can not create capture
+/// devices itself.
+pub const NEEDS_CABLE: bool = cfg!(windows);
+
+/// Shows the virtual cable of a virtual microphone node. Returns true if it was changed.
+pub fn virtual_mic_ui(
+    ui: &mut Ui,
+    node_id: Uuid,
+    config: &mut VirtualMicConfig,
+    output_devices: &[DeviceInfo],
+    zoom: f32,
+) -> bool {
+    let mut changed = false;
+
+    let none_text = if NEEDS_CABLE {
+        "No cable"
+    } else {
+        "Own capture device"
+    };
+    let selected_text = match config.cable_id {
+        Some(cable_id) => output_devices
+            .iter()
+            .find(|device| device.id == cable_id)
+            .map_or("Missing device", |device| device.name.as_str()),
+        None => none_text,
+    };
+
+    // Detected virtual cables come first
+    let mut devices = output_devices.iter().collect::<Vec<_>>();
+    devices.sort_by_key(|device| !device.virtual_cable);
+
+    ComboBox::from_id_source((node_id, "cable"))
+        .selected_text(selected_text)
+        .width(150.0 * zoom)
+        .show_ui(ui, |ui| {
+            changed |= ui
+                .selectable_value(&mut config.cable_id, None, none_text)
+                .changed();
+
+            for device in devices {
+                let text = if device.virtual_cable {
+                    RichText::new(format!("🎤 {}", device.name))
+                } else {
+                    RichText::new(&device.name).weak()
+                };
+
+                changed |= ui
+                    .selectable_value(&mut config.cable_id, Some(device.id), text)
+                    .changed();
+            }
+        })
+        .response
+        .on_hover_text("Output device of the virtual cable that the mix is played into");
+
+    if NEEDS_CABLE && !output_devices.iter().any(|device| device.virtual_cable) {
+        ui.label(RichText::new("No virtual cable found").color(Color32::YELLOW));
+    }
+
+    changed
+}
@@ -0,0 +1,214 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Serialize, Deserialize)]
+pub enum ChannelLayout {
+    Mono,
+    Stereo,
+    Surround51,
+    Surround71,
+}
+
+impl ChannelLayout {
+    pub const ALL: [ChannelLayout; 4] = [
+        ChannelLayout::Mono,
+        ChannelLayout::Stereo,
+        ChannelLayout::Surround51,
+        ChannelLayout::Surround71,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ChannelLayout::Mono => "Mono",
+            ChannelLayout::Stereo => "Stereo",
+            ChannelLayout::Surround51 => "5.1",
+            ChannelLayout::Surround71 => "7.1",
+        }
+    }
+
+    /// Names of the channels, in the order of interleaved WAVE audio.
+    pub fn channel_names(&self) -> &'static [&'static str] {
+        match self {
+            ChannelLayout::Mono => &["M"],
+            ChannelLayout::Stereo => &["L", "R"],
+            ChannelLayout::Surround51 => &["L", "R", "C", "LFE", "BL", "BR"],
+            ChannelLayout::Surround71 => &["L", "R", "C", "LFE", "BL", "BR", "SL", "SR"],
+        }
+    }
+
+    pub fn channels(&self) -> usize {
+        self.channel_names().len()
+    }
+}
+
+#[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq)]
+pub enum ChannelPreset {
+    /// Every channel to the channel of the same position
+    Straight,
+    SwapLeftRight,
+    /// All channels summed into the left and right channels
+    MonoSum,
+    LeftOnly,
+    RightOnly,
+}
+
+impl ChannelPreset {
+    pub const ALL: [ChannelPreset; 5] = [
+        ChannelPreset::Straight,
+        ChannelPreset::SwapLeftRight,
+        ChannelPreset::MonoSum,
+        ChannelPreset::LeftOnly,
+        ChannelPreset::RightOnly,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ChannelPreset::Straight => "Straight",
+            ChannelPreset::SwapLeftRight => "Swap L/R",
+            ChannelPreset::MonoSum => "Mono",
+            ChannelPreset::LeftOnly => "Left only",
+            ChannelPreset::RightOnly => "Right only",
+        }
+    }
+}
+
+/// Gains from the channels of a link's source to the channels of its target.
+#[derive(Debug, Clone, PartialOrd, PartialEq, Serialize, Deserialize)]
+pub struct ChannelMatrix {
+    pub inputs: ChannelLayout,
+    pub outputs: ChannelLayout,
+    /// Gain of every input channel, for one output channel after another
+    gains: Vec<f32>,
+}
+
+impl Default for ChannelMatrix {
+    fn default() -> Self {
+        Self::preset(
+            ChannelPreset::Straight,
+            ChannelLayout::Stereo,
+            ChannelLayout::Stereo,
+        )
+    }
+}
+
+impl ChannelMatrix {
+    pub fn new(inputs: ChannelLayout, outputs: ChannelLayout) -> Self {
+        Self {
+            inputs,
+            outputs,
+            gains: vec![0.0; inputs.channels() * outputs.channels()],
+        }
+    }
+
+    pub fn preset(preset: ChannelPreset, inputs: ChannelLayout, outputs: ChannelLayout) -> Self {
+        let mut matrix = Self::new(inputs, outputs);
+        let (input_count, output_count) = (inputs.channels(), outputs.channels());
+        // The left and right channels, or the only channel of mono audio
+        let right = |channels: usize| channels.min(2) - 1;
+
+        match preset {
+            ChannelPreset::Straight => {
+                for channel in 0..input_count.min(output_count) {
+                    matrix.set_gain(channel, channel, 1.0);
+                }
+            }
+            ChannelPreset::SwapLeftRight => {
+                for channel in 0..input_count.min(output_count) {
+                    let output = match channel {
+                        0 => right(output_count),
+                        1 => 0,
+                        _ => channel,
+                    };
+                    matrix.set_gain(channel, output, 1.0);
+                }
+            }
+            ChannelPreset::MonoSum => {
+                let gain = 1.0 / input_count as f32;
+                for input in 0..input_count {
+                    matrix.set_gain(input, 0, gain);
+                    matrix.set_gain(input, right(output_count), gain);
+                }
+            }
+            ChannelPreset::LeftOnly => matrix.set_gain(0, 0, 1.0),
+            ChannelPreset::RightOnly => {
+                matrix.set_gain(right(input_count), right(output_count), 1.0)
+            }
+        }
+
+        matrix
+    }
+
+    /// Gain from an input to an output channel. Channels outside of the layouts are silent.
+    pub fn gain(&self, input: usize, output: usize) -> f32 {
+        if input >= self.inputs.channels() || output >= self.outputs.channels() {
+            return 0.0;
+        }
+
+        self.gains
+            .get(output * self.inputs.channels() + input)
+            .copied()
+            .unwrap_or(0.0)
+    }
+
+    pub fn set_gain(&mut self, input: usize, output: usize, gain: f32) {
+        if input >= self.inputs.channels() || output >= self.outputs.channels() {
+            return;
+        }
+
+        if let Some(value) = self.gains.get_mut(output * self.inputs.channels() + input) {
+            *value = gain;
+        }
+    }
+
+    /// Changes the layouts, keeping the gains of the channels that are in both.
+    pub fn set_layouts(&mut self, inputs: ChannelLayout, outputs: ChannelLayout) {
+        let mut matrix = Self::new(inputs, outputs);
+
+        for output in 0..outputs.channels() {
+            for input in 0..inputs.channels() {
+                matrix.set_gain(input, output, self.gain(input, output));
+            }
+        }
+
+        *self = matrix;
+    }
+
+    /// The matrix that maps like this matrix followed by the next one. The outputs of this
+    /// matrix are matched to the inputs of the next one by position.
+    pub fn then(&self, next: &ChannelMatrix) -> ChannelMatrix {
+        let mut matrix = Self::new(self.inputs, next.outputs);
+
+        for output in 0..next.outputs.channels() {
+            for input in 0..self.inputs.channels() {
+                let gain = (0..next.inputs.channels())
+                    .map(|channel| self.gain(input, channel) * next.gain(channel, output))
+                    .sum();
+                matrix.set_gain(input, output, gain);
+            }
+        }
+
+        matrix
+    }
+
+    /// Maps interleaved audio with `input_channels` channels to `output_channels` channels.
+    /// The stream channels are matched to the layouts by position.
+    pub fn apply(
+        &self,
+        input: &[f32],
+        input_channels: usize,
+        output: &mut [f32],
+        output_channels: usize,
+    ) {
+        for (in_frame, out_frame) in input
+            .chunks_exact(input_channels)
+            .zip(output.chunks_exact_mut(output_channels))
+        {
+            for (out_channel, out_sample) in out_frame.iter_mut().enumerate() {
+                *out_sample = in_frame
+                    .iter()
+                    .enumerate()
+                    .map(|(in_channel, sample)| sample * self.gain(in_channel, out_channel))
+                    .sum();
+            }
+        }
+    }
+}
@@ -0,0 +1,158 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{port_node_id, Context, Edit, Error, Link, Node, NodeKind, Result};
+
+/// Copied nodes and the links between them, which can be pasted as new nodes. Shared as text,
+/// e.g. to post a part of a routing setup in a chat.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Snippet {
+    pub nodes: Vec<Node>,
+    pub links: Vec<Link>,
+}
+
+impl Snippet {
+    /// Keeps only the links between the given nodes. The state of the nodes in the backend,
+    /// like the process of an application, is not copied.
+    pub fn new(nodes: Vec<Node>, links: Vec<Link>) -> Self {
+        let nodes = nodes
+            .into_iter()
+            .map(|node| Node {
+                process_id: None,
+                active: false,
+                present: false,
+                peak_values: (0.0, 0.0),
+                ..node
+            })
+            .collect::<Vec<_>>();
+
+        let links = links
+            .into_iter()
+            .filter(|link| {
+                let has_node = |port_id| {
+                    let node_id = port_node_id(&nodes, port_id);
+                    nodes.iter().any(|node| node.id == node_id)
+                };
+                has_node(link.src_id) && has_node(link.dst_id)
+            })
+            .collect();
+
+        Self { nodes, links }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    pub fn to_text(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+
+    pub fn from_text(text: &str) -> Result<Self> {
+        serde_json::from_str(text.trim())
+            .map_err(|err| Error::Other(format!("Not a copy of nodes: {}", err)))
+    }
+
+    /// Top left corner of the copied nodes.
+    pub fn origin(&self) -> (f32, f32) {
+        self.nodes
+            .iter()
+            .map(|node| node.pos)
+            .reduce(|(x1, y1), (x2, y2)| (x1.min(x2), y1.min(y2)))
+            .unwrap_or_default()
+    }
+
+    /// The edit that adds copies of the nodes and links with their top left corner at `pos`,
+    /// and the ids of the pasted nodes.
+    ///
+    /// The copies and their ports get new ids, except for device and application nodes, which
+    /// stand for the devices and processes themselves: those are only added if the context does
+    /// not have a node for them yet, and linked to as they are otherwise. Links the context has
+    /// already are left as they are.
+    pub fn paste(&self, ctx: &dyn Context, pos: (f32, f32)) -> (Edit, Vec<Uuid>) {
+        let origin = self.origin();
+        let mut ids = HashMap::new();
+        let mut port_ids = HashMap::new();
+        let mut edits = Vec::new();
+
+        for node in self.nodes.iter() {
+            let is_device = matches!(node.kind, NodeKind::InputDevice | NodeKind::OutputDevice);
+            let existing = ctx.nodes().iter().find(|other| match node.kind {
+                NodeKind::InputDevice | NodeKind::OutputDevice => other.id == node.id,
+                NodeKind::Application => {
+                    other.kind == NodeKind::Application
+                        && other.display_name == node.display_name
+                        && other.filename == node.filename
+                }
+                _ => false,
+            });
+            if let Some(existing) = existing {
+                ids.insert(node.id, existing.id);
+                port_ids.extend(
+                    node.ports
+                        .iter()
+                        .zip(existing.ports.iter())
+                        .map(|(port, other)| (port.id, other.id)),
+                );
+                port_ids.insert(node.id, existing.id);
+                continue;
+            }
+
+            let mut copy = node.clone();
+            if !is_device {
+                copy.id = Uuid::new_v4();
+                for port in copy.ports.iter_mut() {
+                    // The port of the kind has the id of the node
+                    port.id = if port.id == node.id {
+                        copy.id
+                    } else {
+                        Uuid::new_v4()
+                    };
+                }
+            }
+            port_ids.extend(
+                node.ports
+                    .iter()
+                    .zip(copy.ports.iter())
+                    .map(|(port, copied)| (port.id, copied.id)),
+            );
+            // Copied before nodes had ports, linked at the port of their kind
+            port_ids.insert(node.id, copy.id);
+            copy.pos = (pos.0 + node.pos.0 - origin.0, pos.1 + node.pos.1 - origin.1);
+
+            ids.insert(node.id, copy.id);
+            edits.push(Edit::AddNode {
+                node: copy,
+                links: Vec::new(),
+            });
+        }
+
+        for link in self.links.iter() {
+            if let (Some(&src_id), Some(&dst_id)) =
+                (port_ids.get(&link.src_id), port_ids.get(&link.dst_id))
+            {
+                if ctx.connection_state(src_id, dst_id).is_some() {
+                    continue;
+                }
+
+                edits.push(Edit::Connect(Link {
+                    id: Uuid::new_v4(),
+                    src_id,
+                    dst_id,
+                    ..link.clone()
+                }));
+            }
+        }
+
+        let node_ids = self
+            .nodes
+            .iter()
+            .filter_map(|node| ids.get(&node.id))
+            .copied()
+            .collect();
+
+        (Edit::Group(edits), node_ids)
+    }
+}
@@ -0,0 +1,171 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{ChannelMatrix, EffectConfig};
+
+#[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Serialize, Deserialize)]
+pub enum ConnectionState {
+    /// The connection is applied by the backend and audio is being routed.
+    Active,
+    /// The connection is waiting for its source or target to become available.
+    Pending,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Connection {
+    pub src_id: Uuid,
+    pub dst_id: Uuid,
+    pub state: ConnectionState,
+    /// Mapping of the source channels to the target channels, if not passed through as is
+    pub channel_matrix: Option<ChannelMatrix>,
+    /// Delay added to the audio of the connection (ms)
+    pub delay_ms: f32,
+    /// Effects inserted into the audio of the connection, after the effects of its source
+    pub effects: Vec<EffectConfig>,
+}
+
+/// Changes needed to bring the connections up to date with the available nodes.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Reconciliation {
+    /// Pending connections whose source and target are both available again.
+    pub apply: Vec<(Uuid, Uuid)>,
+    /// Active connections whose source or target is no longer available.
+    pub suspend: Vec<(Uuid, Uuid)>,
+}
+
+impl Reconciliation {
+    pub fn is_empty(&self) -> bool {
+        self.apply.is_empty() && self.suspend.is_empty()
+    }
+}
+
+/// Keeps track of the connections requested by the user, and whether the backend
+/// currently has them applied.
+#[derive(Debug, Default)]
+pub struct Connections {
+    connections: Vec<Connection>,
+}
+
+impl Connections {
+    pub fn insert(&mut self, src_id: Uuid, dst_id: Uuid, state: ConnectionState) {
+        match self.get_mut(src_id, dst_id) {
+            Some(conn) => conn.state = state,
+            None => self.connections.push(Connection {
+                src_id,
+                dst_id,
+                state,
+                channel_matrix: None,
+                delay_ms: 0.0,
+                effects: Vec::new(),
+            }),
+        }
+    }
+
+    pub fn remove(&mut self, src_id: Uuid, dst_id: Uuid) -> Option<Connection> {
+        self.connections
+            .iter()
+            .position(|conn| conn.src_id == src_id && conn.dst_id == dst_id)
+            .map(|idx| self.connections.remove(idx))
+    }
+
+    pub fn set_state(&mut self, src_id: Uuid, dst_id: Uuid, state: ConnectionState) {
+        if let Some(conn) = self.get_mut(src_id, dst_id) {
+            conn.state = state;
+        }
+    }
+
+    pub fn state(&self, src_id: Uuid, dst_id: Uuid) -> Option<ConnectionState> {
+        self.connections
+            .iter()
+            .find(|conn| conn.src_id == src_id && conn.dst_id == dst_id)
+            .map(|conn| conn.state)
+    }
+
+    pub fn set_channel_matrix(
+        &mut self,
+        src_id: Uuid,
+        dst_id: Uuid,
+        channel_matrix: Option<ChannelMatrix>,
+    ) {
+        if let Some(conn) = self.get_mut(src_id, dst_id) {
+            conn.channel_matrix = channel_matrix;
+        }
+    }
+
+    pub fn channel_matrix(&self, src_id: Uuid, dst_id: Uuid) -> Option<&ChannelMatrix> {
+        self.connections
+            .iter()
+            .find(|conn| conn.src_id == src_id && conn.dst_id == dst_id)
+            .and_then(|conn| conn.channel_matrix.as_ref())
+    }
+
+    pub fn set_delay(&mut self, src_id: Uuid, dst_id: Uuid, delay_ms: f32) {
+        if let Some(conn) = self.get_mut(src_id, dst_id) {
+            conn.delay_ms = delay_ms;
+        }
+    }
+
+    pub fn delay(&self, src_id: Uuid, dst_id: Uuid) -> f32 {
+        self.connections
+            .iter()
+            .find(|conn| conn.src_id == src_id && conn.dst_id == dst_id)
+            .map_or(0.0, |conn| conn.delay_ms)
+    }
+
+    pub fn set_effects(&mut self, src_id: Uuid, dst_id: Uuid, effects: Vec<EffectConfig>) {
+        if let Some(conn) = self.get_mut(src_id, dst_id) {
+            conn.effects = effects;
+        }
+    }
+
+    pub fn effects(&self, src_id: Uuid, dst_id: Uuid) -> &[EffectConfig] {
+        self.connections
+            .iter()
+            .find(|conn| conn.src_id == src_id && conn.dst_id == dst_id)
+            .map_or(&[], |conn| conn.effects.as_slice())
+    }
+
+    pub fn effects_mut(&mut self, src_id: Uuid, dst_id: Uuid) -> Option<&mut Vec<EffectConfig>> {
+        self.get_mut(src_id, dst_id).map(|conn| &mut conn.effects)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Connection> {
+        self.connections.iter()
+    }
+
+    /// Connections from or to the given node.
+    pub fn of_node(&self, node_id: Uuid) -> Vec<Connection> {
+        self.connections
+            .iter()
+            .filter(|conn| conn.src_id == node_id || conn.dst_id == node_id)
+            .cloned()
+            .collect()
+    }
+
+    /// Compares the connection states against the currently available nodes.
+    pub fn reconcile(&self, is_available: impl Fn(Uuid) -> bool) -> Reconciliation {
+        let mut reconciliation = Reconciliation::default();
+
+        for conn in self.connections.iter() {
+            let available = is_available(conn.src_id) && is_available(conn.dst_id);
+
+            match conn.state {
+                ConnectionState::Active if !available => {
+                    reconciliation.suspend.push((conn.src_id, conn.dst_id));
+                }
+                ConnectionState::Pending if available => {
+                    reconciliation.apply.push((conn.src_id, conn.dst_id));
+                }
+                _ => {}
+            }
+        }
+
+        reconciliation
+    }
+
+    fn get_mut(&mut self, src_id: Uuid, dst_id: Uuid) -> Option<&mut Connection> {
+        self.connections
+            .iter_mut()
+            .find(|conn| conn.src_id == src_id && conn.dst_id == dst_id)
+    }
+}
@@ -0,0 +1,34 @@
+use uuid::Uuid;
+
+/// Virtual output device that follows the system default output device of a role.
+/// Connections to it are moved to the new device when the default device changes.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum DefaultDevice {
+    Output,
+    Communications,
+}
+
+impl DefaultDevice {
+    pub const ALL: [DefaultDevice; 2] = [DefaultDevice::Output, DefaultDevice::Communications];
+
+    /// Node id of the virtual device.
+    pub fn id(self) -> Uuid {
+        match self {
+            DefaultDevice::Output => Uuid::from_u128(0x6e6f6469_6f00_4000_8000_000000000001),
+            DefaultDevice::Communications => {
+                Uuid::from_u128(0x6e6f6469_6f00_4000_8000_000000000002)
+            }
+        }
+    }
+
+    pub fn from_id(id: Uuid) -> Option<Self> {
+        Self::ALL.into_iter().find(|device| device.id() == id)
+    }
+
+    pub fn display_name(self) -> &'static str {
+        match self {
+            DefaultDevice::Output => "System Default Output",
+            DefaultDevice::Communications => "Default Communications",
+        }
+    }
+}
@@ -0,0 +1,216 @@
+use std::cmp::Ordering;
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::Node;
+
+/// Lowers the volume of the target node while the trigger node is playing audio.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DuckingRule {
+    pub id: Uuid,
+    pub enabled: bool,
+    pub trigger_id: Uuid,
+    pub target_id: Uuid,
+    /// Peak level (dBFS) above which the trigger node is considered to be playing.
+    pub threshold_db: f32,
+    /// How much the target node is lowered (dB).
+    pub attenuation_db: f32,
+    /// Time to reach the full attenuation (ms).
+    pub attack_ms: f32,
+    /// Time to recover from the full attenuation (ms).
+    pub release_ms: f32,
+}
+
+impl Default for DuckingRule {
+    fn default() -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            enabled: true,
+            trigger_id: Uuid::nil(),
+            target_id: Uuid::nil(),
+            threshold_db: -40.0,
+            attenuation_db: 12.0,
+            attack_ms: 50.0,
+            release_ms: 500.0,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct DuckState {
+    target_id: Uuid,
+    attenuation_db: f32,
+    /// Volume of the node without ducking
+    base_volume: f32,
+    /// Release of the rule that last triggered, as (attenuation, time)
+    release: (f32, f32),
+}
+
+/// Keeps track of the nodes that are currently ducked.
+#[derive(Debug, Default)]
+pub struct Ducker {
+    states: Vec<DuckState>,
+}
+
+impl Ducker {
+    /// Advances the ducking by `dt` seconds using the current peak values of the nodes.
+    /// Returns the volumes to set for the nodes whose ducking changed.
+    pub fn update(&mut self, rules: &[DuckingRule], nodes: &[Node], dt: f32) -> Vec<(Uuid, f32)> {
+        let mut targets = self
+            .states
+            .iter()
+            .map(|state| state.target_id)
+            .collect::<Vec<_>>();
+
+        for rule in rules.iter().filter(|rule| rule.enabled) {
+            if !targets.contains(&rule.target_id) {
+                targets.push(rule.target_id);
+            }
+        }
+
+        let mut volumes = Vec::new();
+
+        for target_id in targets {
+            let target = match nodes.iter().find(|node| node.id == target_id) {
+                Some(node) => node,
+                None => {
+                    self.states.retain(|state| state.target_id != target_id);
+                    continue;
+                }
+            };
+
+            let triggered_rule = rules
+                .iter()
+                .filter(|rule| {
+                    rule.enabled
+                        && rule.target_id == target_id
+                        && rule.trigger_id != target_id
+                        && peak_db(nodes, rule.trigger_id) >= rule.threshold_db
+                })
+                .max_by(|a, b| {
+                    a.attenuation_db
+                        .partial_cmp(&b.attenuation_db)
+                        .unwrap_or(Ordering::Equal)
+                });
+
+            let state_idx = match self.states.iter().position(|s| s.target_id == target_id) {
+                Some(idx) => idx,
+                None if triggered_rule.is_some() => {
+                    self.states.push(DuckState {
+                        target_id,
+                        attenuation_db: 0.0,
+                        base_volume: target.volume,
+                        release: (0.0, 0.0),
+                    });
+                    self.states.len() - 1
+                }
+                None => continue,
+            };
+
+            let state = &mut self.states[state_idx];
+            let previous_db = state.attenuation_db;
+
+            state.attenuation_db = match triggered_rule {
+                Some(rule) => {
+                    let attenuation_db = rule.attenuation_db.max(0.0);
+                    state.release = (attenuation_db.max(previous_db), rule.release_ms);
+
+                    if attenuation_db > previous_db {
+                        ramp(
+                            previous_db,
+                            attenuation_db,
+                            (attenuation_db, rule.attack_ms),
+                            dt,
+                        )
+                    } else {
+                        ramp(previous_db, attenuation_db, state.release, dt)
+                    }
+                }
+                None => ramp(previous_db, 0.0, state.release, dt),
+            };
+
+            if state.attenuation_db != previous_db {
+                volumes.push((
+                    target_id,
+                    state.base_volume * db_to_gain(-state.attenuation_db),
+                ));
+            }
+
+            if state.attenuation_db <= 0.0 {
+                self.states.remove(state_idx);
+            }
+        }
+
+        volumes
+    }
+
+    /// Current attenuation of the node (dB), or zero if the node is not ducked.
+    pub fn attenuation_db(&self, node_id: Uuid) -> f32 {
+        self.states
+            .iter()
+            .find(|state| state.target_id == node_id)
+            .map(|state| state.attenuation_db)
+            .unwrap_or(0.0)
+    }
+
+    pub fn is_ducked(&self, node_id: Uuid) -> bool {
+        self.attenuation_db(node_id) > 0.0
+    }
+
+    /// Stops ducking all nodes at once, e.g. on exit.
+    /// Returns the volumes to set for the nodes that were ducked.
+    pub fn release_all(&mut self) -> Vec<(Uuid, f32)> {
+        self.states
+            .drain(..)
+            .map(|state| (state.target_id, state.base_volume))
+            .collect()
+    }
+
+    /// Sets the volume the node returns to after ducking.
+    /// Returns the volume that should be applied to the node right now.
+    pub fn set_volume(&mut self, node_id: Uuid, volume: f32) -> f32 {
+        match self
+            .states
+            .iter_mut()
+            .find(|state| state.target_id == node_id)
+        {
+            Some(state) => {
+                state.base_volume = volume;
+                volume * db_to_gain(-state.attenuation_db)
+            }
+            None => volume,
+        }
+    }
+}
+
+pub fn db_to_gain(db: f32) -> f32 {
+    10.0_f32.powf(db / 20.0)
+}
+
+pub fn gain_to_db(gain: f32) -> f32 {
+    20.0 * gain.log10()
+}
+
+fn peak_db(nodes: &[Node], node_id: Uuid) -> f32 {
+    nodes
+        .iter()
+        .find(|node| node.id == node_id)
+        .map(|node| gain_to_db(node.peak_values.0.max(node.peak_values.1)))
+        .unwrap_or(f32::NEG_INFINITY)
+}
+
+/// Moves `from` towards `to` at the rate of `range` dB per `time_ms`.
+fn ramp(from: f32, to: f32, (range, time_ms): (f32, f32), dt: f32) -> f32 {
+    if time_ms <= 0.0 {
+        return to;
+    }
+
+    let step = range * dt * 1000.0 / time_ms;
+
+    if to > from {
+        (from + step).min(to)
+    } else {
+        (from - step).max(to)
+    }
+}
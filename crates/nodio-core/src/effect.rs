@@ -0,0 +1,241 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Serialize, Deserialize)]
+pub enum EffectKind {
+    ParametricEq,
+    Compressor,
+    Limiter,
+    NoiseGate,
+    GainPan,
+    /// Third-party plugin, see `EffectConfig::plugin`
+    Plugin,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ParamInfo {
+    pub name: &'static str,
+    pub min: f32,
+    pub max: f32,
+    pub default: f32,
+    pub unit: &'static str,
+}
+
+const fn param(
+    name: &'static str,
+    min: f32,
+    max: f32,
+    default: f32,
+    unit: &'static str,
+) -> ParamInfo {
+    ParamInfo {
+        name,
+        min,
+        max,
+        default,
+        unit,
+    }
+}
+
+const PARAMETRIC_EQ_PARAMS: [ParamInfo; 7] = [
+    param("Low freq", 20.0, 1000.0, 100.0, "Hz"),
+    param("Low gain", -24.0, 24.0, 0.0, "dB"),
+    param("Mid freq", 100.0, 10000.0, 1000.0, "Hz"),
+    param("Mid gain", -24.0, 24.0, 0.0, "dB"),
+    param("Mid Q", 0.1, 10.0, 1.0, ""),
+    param("High freq", 1000.0, 20000.0, 8000.0, "Hz"),
+    param("High gain", -24.0, 24.0, 0.0, "dB"),
+];
+
+const COMPRESSOR_PARAMS: [ParamInfo; 5] = [
+    param("Threshold", -60.0, 0.0, -20.0, "dB"),
+    param("Ratio", 1.0, 20.0, 4.0, ":1"),
+    param("Attack", 0.1, 200.0, 10.0, "ms"),
+    param("Release", 5.0, 2000.0, 100.0, "ms"),
+    param("Makeup", 0.0, 24.0, 0.0, "dB"),
+];
+
+const LIMITER_PARAMS: [ParamInfo; 2] = [
+    param("Ceiling", -24.0, 0.0, -1.0, "dB"),
+    param("Release", 1.0, 1000.0, 50.0, "ms"),
+];
+
+const NOISE_GATE_PARAMS: [ParamInfo; 4] = [
+    param("Threshold", -90.0, 0.0, -50.0, "dB"),
+    param("Attack", 0.1, 100.0, 1.0, "ms"),
+    param("Hold", 0.0, 1000.0, 50.0, "ms"),
+    param("Release", 5.0, 2000.0, 100.0, "ms"),
+];
+
+const GAIN_PAN_PARAMS: [ParamInfo; 2] = [
+    param("Gain", -60.0, 24.0, 0.0, "dB"),
+    param("Pan", -1.0, 1.0, 0.0, ""),
+];
+
+impl EffectKind {
+    /// Built-in effects
+    pub const ALL: [EffectKind; 5] = [
+        EffectKind::ParametricEq,
+        EffectKind::Compressor,
+        EffectKind::Limiter,
+        EffectKind::NoiseGate,
+        EffectKind::GainPan,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            EffectKind::ParametricEq => "Parametric EQ",
+            EffectKind::Compressor => "Compressor",
+            EffectKind::Limiter => "Limiter",
+            EffectKind::NoiseGate => "Noise gate",
+            EffectKind::GainPan => "Gain / pan",
+            EffectKind::Plugin => "Plugin",
+        }
+    }
+
+    pub fn params(self) -> &'static [ParamInfo] {
+        match self {
+            EffectKind::ParametricEq => &PARAMETRIC_EQ_PARAMS,
+            EffectKind::Compressor => &COMPRESSOR_PARAMS,
+            EffectKind::Limiter => &LIMITER_PARAMS,
+            EffectKind::NoiseGate => &NOISE_GATE_PARAMS,
+            EffectKind::GainPan => &GAIN_PAN_PARAMS,
+            EffectKind::Plugin => &[],
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Serialize, Deserialize)]
+pub enum PluginFormat {
+    Clap,
+    Lv2,
+}
+
+impl PluginFormat {
+    pub fn name(self) -> &'static str {
+        match self {
+            PluginFormat::Clap => "CLAP",
+            PluginFormat::Lv2 => "LV2",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialOrd, PartialEq, Serialize, Deserialize)]
+pub struct PluginParamInfo {
+    /// Id of the parameter in the plugin, the index of its port for LV2
+    pub id: u32,
+    pub name: String,
+    pub min: f32,
+    pub max: f32,
+    pub default: f32,
+}
+
+/// Third-party plugin hosted in the effects chain.
+#[derive(Debug, Clone, PartialOrd, PartialEq, Serialize, Deserialize)]
+pub struct PluginConfig {
+    pub format: PluginFormat,
+    /// Path of the plugin library (CLAP) or bundle (LV2)
+    pub path: String,
+    /// Plugin id (CLAP) or URI (LV2)
+    pub plugin_id: String,
+    pub name: String,
+    #[serde(default)]
+    pub params: Vec<PluginParamInfo>,
+    /// State saved by the plugin, restored when the plugin is loaded
+    #[serde(default)]
+    pub state: Vec<u8>,
+}
+
+/// Effect in the effects chain of a node.
+#[derive(Debug, Clone, PartialOrd, PartialEq, Serialize, Deserialize)]
+pub struct EffectConfig {
+    pub id: Uuid,
+    pub kind: EffectKind,
+    pub enabled: bool,
+    /// Parameter values in the order of `EffectKind::params`, or `PluginConfig::params`
+    pub params: Vec<f32>,
+    #[serde(default)]
+    pub plugin: Option<PluginConfig>,
+}
+
+impl EffectConfig {
+    pub fn new(kind: EffectKind) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            kind,
+            enabled: true,
+            params: kind.params().iter().map(|param| param.default).collect(),
+            plugin: None,
+        }
+    }
+
+    pub fn plugin(plugin: PluginConfig) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            kind: EffectKind::Plugin,
+            enabled: true,
+            params: plugin.params.iter().map(|param| param.default).collect(),
+            plugin: Some(plugin),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        match &self.plugin {
+            Some(plugin) => &plugin.name,
+            None => self.kind.name(),
+        }
+    }
+
+    pub fn param_count(&self) -> usize {
+        match &self.plugin {
+            Some(plugin) => plugin.params.len(),
+            None => self.kind.params().len(),
+        }
+    }
+
+    /// Sets the value of a parameter. Returns false if there is no such parameter.
+    pub fn set_param(&mut self, idx: usize, value: f32) -> bool {
+        let param_count = self.param_count();
+        if idx >= param_count {
+            return false;
+        }
+
+        if self.params.len() != param_count {
+            self.params = (0..param_count).map(|idx| self.param(idx)).collect();
+        }
+        self.params[idx] = value;
+
+        true
+    }
+
+    /// Value of the parameter clamped to its range, or the default value if it is missing.
+    pub fn param(&self, idx: usize) -> f32 {
+        let (min, max, default) = match &self.plugin {
+            Some(plugin) => {
+                let info = &plugin.params[idx];
+                (info.min, info.max, info.default)
+            }
+            None => {
+                let info = &self.kind.params()[idx];
+                (info.min, info.max, info.default)
+            }
+        };
+
+        self.params
+            .get(idx)
+            .map(|value| value.clamp(min, max))
+            .unwrap_or(default)
+    }
+}
+
+/// Stores the states saved by the processed effects in their plugin configs, so that the plugins
+/// are restored with them.
+pub fn apply_effect_states(effects: &mut [EffectConfig], states: &[(Uuid, Vec<u8>)]) {
+    for effect in effects.iter_mut() {
+        if let Some((_, state)) = states.iter().find(|(id, _)| *id == effect.id) {
+            if let Some(plugin) = effect.plugin.as_mut() {
+                plugin.state = state.clone();
+            }
+        }
+    }
+}
@@ -0,0 +1,59 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Serialize, Deserialize)]
+pub enum Waveform {
+    Sine,
+    WhiteNoise,
+    PinkNoise,
+    /// Logarithmic sine sweep, repeated
+    Sweep,
+    /// A tone that is played in one channel after another
+    ChannelId,
+}
+
+impl Waveform {
+    pub const ALL: [Waveform; 5] = [
+        Waveform::Sine,
+        Waveform::WhiteNoise,
+        Waveform::PinkNoise,
+        Waveform::Sweep,
+        Waveform::ChannelId,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Waveform::Sine => "Sine",
+            Waveform::WhiteNoise => "White noise",
+            Waveform::PinkNoise => "Pink noise",
+            Waveform::Sweep => "Sweep",
+            Waveform::ChannelId => "Channel identification",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialOrd, PartialEq, Serialize, Deserialize)]
+pub struct GeneratorConfig {
+    pub enabled: bool,
+    pub waveform: Waveform,
+    /// Frequency of the tones, and the start frequency of the sweep (Hz)
+    pub frequency_hz: f32,
+    /// End frequency of the sweep (Hz)
+    pub sweep_end_hz: f32,
+    /// Duration of one sweep (s)
+    pub sweep_secs: f32,
+    /// Peak level of the tones and RMS level of the noise (dBFS)
+    pub level_db: f32,
+}
+
+impl Default for GeneratorConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            waveform: Waveform::Sine,
+            frequency_hz: 1000.0,
+            sweep_end_hz: 20000.0,
+            sweep_secs: 10.0,
+            level_db: -20.0,
+        }
+    }
+}
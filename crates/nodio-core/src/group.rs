@@ -0,0 +1,55 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// A titled frame around nodes in the editor, e.g. "Voice chat" or "Game". The nodes of the group
+/// are moved together, and a collapsed group is shown as only its title.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NodeGroup {
+    pub id: Uuid,
+    pub title: String,
+    /// Color of the frame as sRGB
+    pub color: [u8; 3],
+    pub node_ids: Vec<Uuid>,
+    #[serde(default)]
+    pub collapsed: bool,
+}
+
+impl Default for NodeGroup {
+    fn default() -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            title: "Group".to_string(),
+            color: [58, 78, 110],
+            node_ids: Vec::new(),
+            collapsed: false,
+        }
+    }
+}
+
+impl NodeGroup {
+    /// Puts the nodes into a new group. A node is in one group at a time, so the nodes are taken
+    /// out of the groups they were in, and the groups left empty are removed.
+    pub fn group_nodes(groups: &mut Vec<NodeGroup>, title: &str, node_ids: &[Uuid]) -> Uuid {
+        for group in groups.iter_mut() {
+            group.node_ids.retain(|node_id| !node_ids.contains(node_id));
+        }
+        groups.retain(|group| !group.node_ids.is_empty());
+
+        let group = NodeGroup {
+            title: title.to_string(),
+            node_ids: node_ids.to_vec(),
+            ..Default::default()
+        };
+        let group_id = group.id;
+        groups.push(group);
+        group_id
+    }
+
+    /// Forgets the nodes that have been removed, and the groups left without nodes.
+    pub fn prune(groups: &mut Vec<NodeGroup>, node_ids: &[Uuid]) {
+        for group in groups.iter_mut() {
+            group.node_ids.retain(|node_id| node_ids.contains(node_id));
+        }
+        groups.retain(|group| !group.node_ids.is_empty());
+    }
+}
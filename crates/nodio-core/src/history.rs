@@ -0,0 +1,230 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{ChannelMatrix, Context, EffectConfig, Node, Note, Result};
+
+/// Number of edits that can be undone
+pub const HISTORY_LIMIT: usize = 100;
+
+/// A link from an output port to an input port, with the settings it is restored with. Links
+/// are saved in this form too. Links saved before they had settings, as (id, start, end), are
+/// read with the default settings.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Link {
+    /// Id of the link in the editor
+    pub id: Uuid,
+    /// Output port the link starts at
+    pub src_id: Uuid,
+    /// Input port the link ends at
+    pub dst_id: Uuid,
+    #[serde(default)]
+    pub channel_matrix: Option<ChannelMatrix>,
+    #[serde(default)]
+    pub delay_ms: f32,
+    #[serde(default)]
+    pub effects: Vec<EffectConfig>,
+}
+
+impl Link {
+    /// The link with its current settings in the context.
+    pub fn from_context(ctx: &dyn Context, id: Uuid, src_id: Uuid, dst_id: Uuid) -> Self {
+        Self {
+            id,
+            src_id,
+            dst_id,
+            channel_matrix: ctx.channel_matrix(src_id, dst_id),
+            delay_ms: ctx.link_delay(src_id, dst_id),
+            effects: ctx.link_effects(src_id, dst_id),
+        }
+    }
+
+    /// Connects the ports of the link, and applies its settings.
+    pub fn connect(&self, ctx: &mut dyn Context) -> Result<()> {
+        ctx.connect_node(self.src_id, self.dst_id)?;
+
+        if self.channel_matrix.is_some() {
+            ctx.set_channel_matrix(self.src_id, self.dst_id, self.channel_matrix.clone());
+        }
+        if self.delay_ms > 0.0 {
+            ctx.set_link_delay(self.src_id, self.dst_id, self.delay_ms);
+        }
+        if !self.effects.is_empty() {
+            ctx.set_link_effects(self.src_id, self.dst_id, self.effects.clone());
+        }
+
+        Ok(())
+    }
+}
+
+/// A change to the graph that can be reverted.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Edit {
+    /// Adds a node and connects the links, e.g. when restoring a removed node.
+    AddNode {
+        node: Node,
+        links: Vec<Link>,
+    },
+    /// Removes a node, which also removes its links.
+    RemoveNode {
+        node: Node,
+        links: Vec<Link>,
+    },
+    MoveNode {
+        node_id: Uuid,
+        from: (f32, f32),
+        to: (f32, f32),
+    },
+    Connect(Link),
+    Disconnect(Link),
+    SetVolume {
+        node_id: Uuid,
+        from: f32,
+        to: f32,
+    },
+    /// Adds a sticky note. Notes are kept by the editor rather than the context, which the edit
+    /// leaves as it is.
+    AddNote(Note),
+    RemoveNote(Note),
+    /// Edits that are undone and redone together, e.g. removing several nodes.
+    Group(Vec<Edit>),
+}
+
+impl Edit {
+    /// Applies the edit to the context. Every part of the edit is applied even if some fail,
+    /// and the first error is returned.
+    pub fn apply(&self, ctx: &mut dyn Context) -> Result<()> {
+        match self {
+            Edit::AddNode { node, links } => {
+                ctx.add_node(node.clone());
+                links
+                    .iter()
+                    .map(|link| link.connect(ctx))
+                    .fold(Ok(()), Result::and)
+            }
+            Edit::RemoveNode { node, .. } => {
+                ctx.remove_node(node.id);
+                Ok(())
+            }
+            Edit::MoveNode { node_id, to, .. } => {
+                if let Some(node) = ctx.nodes_mut().iter_mut().find(|n| n.id == *node_id) {
+                    node.pos = *to;
+                }
+                Ok(())
+            }
+            Edit::Connect(link) => link.connect(ctx),
+            Edit::Disconnect(link) => {
+                ctx.disconnect_node(link.src_id, link.dst_id);
+                Ok(())
+            }
+            Edit::SetVolume { node_id, to, .. } => {
+                ctx.set_volume(*node_id, *to);
+                Ok(())
+            }
+            Edit::AddNote(_) | Edit::RemoveNote(_) => Ok(()),
+            Edit::Group(edits) => {
+                // Links may run between the nodes of the group, so they are connected once every
+                // node is added. Such a link is listed by both of its nodes.
+                let mut links: Vec<&Link> = Vec::new();
+                let result = edits
+                    .iter()
+                    .map(|edit| match edit {
+                        Edit::AddNode {
+                            node,
+                            links: node_links,
+                        } => {
+                            ctx.add_node(node.clone());
+                            for link in node_links {
+                                if !links.iter().any(|l| l.id == link.id) {
+                                    links.push(link);
+                                }
+                            }
+                            Ok(())
+                        }
+                        edit => edit.apply(ctx),
+                    })
+                    .fold(Ok(()), Result::and);
+
+                links
+                    .into_iter()
+                    .map(|link| link.connect(ctx))
+                    .fold(result, Result::and)
+            }
+        }
+    }
+
+    /// The edit that reverts this one.
+    pub fn inverse(&self) -> Edit {
+        match self.clone() {
+            Edit::AddNode { node, links } => Edit::RemoveNode { node, links },
+            Edit::RemoveNode { node, links } => Edit::AddNode { node, links },
+            Edit::MoveNode { node_id, from, to } => Edit::MoveNode {
+                node_id,
+                from: to,
+                to: from,
+            },
+            Edit::Connect(link) => Edit::Disconnect(link),
+            Edit::Disconnect(link) => Edit::Connect(link),
+            Edit::SetVolume { node_id, from, to } => Edit::SetVolume {
+                node_id,
+                from: to,
+                to: from,
+            },
+            Edit::AddNote(note) => Edit::RemoveNote(note),
+            Edit::RemoveNote(note) => Edit::AddNote(note),
+            Edit::Group(edits) => Edit::Group(edits.iter().rev().map(Edit::inverse).collect()),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        matches!(self, Edit::Group(edits) if edits.iter().all(Edit::is_empty))
+    }
+}
+
+/// Edits that can be undone and redone.
+#[derive(Debug, Default)]
+pub struct History {
+    undo: Vec<Edit>,
+    redo: Vec<Edit>,
+}
+
+impl History {
+    /// Records an edit that has been applied. The edits that were undone can no longer be redone.
+    pub fn push(&mut self, edit: Edit) {
+        if edit.is_empty() {
+            return;
+        }
+
+        self.redo.clear();
+        self.undo.push(edit);
+
+        if self.undo.len() > HISTORY_LIMIT {
+            self.undo.remove(0);
+        }
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    /// Reverts the last edit. Returns the edit that was applied to revert it, and whether it
+    /// could be applied.
+    pub fn undo(&mut self, ctx: &mut dyn Context) -> Option<(Edit, Result<()>)> {
+        let edit = self.undo.pop()?;
+        let inverse = edit.inverse();
+        let result = inverse.apply(ctx);
+        self.redo.push(edit);
+        Some((inverse, result))
+    }
+
+    /// Applies the last undone edit again. Returns the edit, and whether it could be applied.
+    pub fn redo(&mut self, ctx: &mut dyn Context) -> Option<(Edit, Result<()>)> {
+        let edit = self.redo.pop()?;
+        let result = edit.apply(ctx);
+        self.undo.push(edit.clone());
+        Some((edit, result))
+    }
+}
@@ -0,0 +1,28 @@
+use uuid::Uuid;
+
+/// Longest delay that can be set on a link (ms)
+pub const MAX_LINK_DELAY_MS: f32 = 2000.0;
+
+#[derive(Debug, Copy, Clone, PartialOrd, PartialEq)]
+pub enum CalibrationStatus {
+    /// Playing the click and listening for it
+    Running,
+    /// Time from playing the click until it was captured (ms)
+    Measured(f32),
+    /// The click was not heard by the capture device
+    Failed,
+}
+
+/// Delays for the links of a source that make the audio arrive at all targets at the same time,
+/// given the measured latency of every target (ms).
+pub fn align_delays(latencies: &[(Uuid, f32)]) -> Vec<(Uuid, f32)> {
+    let slowest = latencies
+        .iter()
+        .map(|(_, latency)| *latency)
+        .fold(0.0, f32::max);
+
+    latencies
+        .iter()
+        .map(|(id, latency)| (*id, (slowest - latency).min(MAX_LINK_DELAY_MS)))
+        .collect()
+}
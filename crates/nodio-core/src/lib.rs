@@ -1,6 +1,45 @@
 #![deny(clippy::all)]
+mod channel_matrix;
+mod clipboard;
+mod connection;
+mod default_device;
+mod ducking;
+mod effect;
+mod generator;
+mod group;
+mod history;
+mod latency;
+mod network;
+mod note;
+mod player;
+mod port;
+mod recorder;
 mod result;
+mod rules;
+mod soundboard;
+mod virtual_mic;
+pub use channel_matrix::{ChannelLayout, ChannelMatrix, ChannelPreset};
+pub use clipboard::Snippet;
+pub use connection::{Connection, ConnectionState, Connections, Reconciliation};
+pub use default_device::DefaultDevice;
+pub use ducking::{db_to_gain, gain_to_db, Ducker, DuckingRule};
+pub use effect::{
+    apply_effect_states, EffectConfig, EffectKind, ParamInfo, PluginConfig, PluginFormat,
+    PluginParamInfo,
+};
+pub use generator::{GeneratorConfig, Waveform};
+pub use group::NodeGroup;
+pub use history::{Edit, History, Link, HISTORY_LIMIT};
+pub use latency::{align_delays, CalibrationStatus, MAX_LINK_DELAY_MS};
+pub use network::{NetworkConfig, NetworkStatus, RtpPayload};
+pub use note::Note;
+pub use player::{next_track, LoopMode, PlayerCommand, PlayerConfig, PlayerState, PlayerStatus};
+pub use port::{find_port, port_node_id, validate_link, MediaType, Port, PortDirection};
+pub use recorder::{RecorderConfig, RecorderState, RecorderStatus, RecordingFormat, Rotation};
 pub use result::{Error, Result};
+pub use rules::{evaluate_rules, glob_match, Rule};
+pub use soundboard::{Clip, Soundboard};
+pub use virtual_mic::{is_virtual_cable, VirtualMicConfig};
 
 use serde::{Deserialize, Serialize};
 pub use uuid::Uuid;
@@ -10,12 +49,72 @@ pub trait Context {
     fn remove_node(&mut self, node_id: Uuid);
     fn nodes(&self) -> &[Node];
     fn nodes_mut(&mut self) -> &mut [Node];
-    fn connect_node(&mut self, node_id: Uuid, target_id: Uuid) -> Result<()>;
-    fn disconnect_node(&mut self, node_id: Uuid, target_id: Uuid);
+    /// Links an output port to an input port. The audio is routed between the nodes of the
+    /// ports, and the connection is looked up by the ports of either end.
+    fn connect_node(&mut self, port_id: Uuid, target_port_id: Uuid) -> Result<()>;
+    fn disconnect_node(&mut self, port_id: Uuid, target_port_id: Uuid);
+    fn connection_state(&self, port_id: Uuid, target_port_id: Uuid) -> Option<ConnectionState>;
+    /// Sets how the channels of the source are mapped to the target, `None` passes them through.
+    fn set_channel_matrix(
+        &mut self,
+        port_id: Uuid,
+        target_port_id: Uuid,
+        channel_matrix: Option<ChannelMatrix>,
+    );
+    fn channel_matrix(&self, port_id: Uuid, target_port_id: Uuid) -> Option<ChannelMatrix>;
+    /// Delays the audio of a connection, e.g. to align devices with different latencies.
+    fn set_link_delay(&mut self, port_id: Uuid, target_port_id: Uuid, delay_ms: f32);
+    fn link_delay(&self, port_id: Uuid, target_port_id: Uuid) -> f32;
+    /// Effects inserted into the audio of a link, after the effects of its source node. They
+    /// only apply to links that the backend processes, see `processes_link`.
+    fn set_link_effects(&mut self, port_id: Uuid, target_port_id: Uuid, effects: Vec<EffectConfig>);
+    fn link_effects(&self, port_id: Uuid, target_port_id: Uuid) -> Vec<EffectConfig>;
+    fn set_link_effect_param(
+        &mut self,
+        port_id: Uuid,
+        target_port_id: Uuid,
+        effect_id: Uuid,
+        param_idx: usize,
+        value: f32,
+    );
+    /// Whether the audio of an active link passes through the backend, so that its effects,
+    /// channel matrix and delay apply. Links that the system routes by itself are only switched.
+    fn processes_link(&self, port_id: Uuid, target_port_id: Uuid) -> bool;
+    /// End-to-end latency of an active connection as far as the backend can tell, including its
+    /// delay (ms). `None` if the backend does not process the audio of the connection.
+    fn link_latency(&self, port_id: Uuid, target_port_id: Uuid) -> Option<f32>;
+    /// Plays a click on the target and listens for it on the capture device to measure latency.
+    fn start_calibration(&mut self, target_id: Uuid, capture_id: Uuid) -> Result<()>;
+    fn calibration_status(&self) -> Option<CalibrationStatus>;
+    fn stop_calibration(&mut self);
     fn set_volume(&mut self, node_id: Uuid, volume: f32);
+    fn set_effects(&mut self, node_id: Uuid, effects: Vec<EffectConfig>);
+    /// Whether the effects of a node apply to its links that the backend processes. They do not
+    /// for sinks, which take the audio of their links as it comes.
+    fn applies_node_effects(&self, node_id: Uuid) -> bool;
+    fn set_effect_param(&mut self, node_id: Uuid, effect_id: Uuid, param_idx: usize, value: f32);
+    /// Writes the current state of hosted plugins to the effects of the nodes.
+    fn store_effect_states(&mut self);
+    fn set_recorder_config(&mut self, node_id: Uuid, config: RecorderConfig);
+    fn set_recorder_state(&mut self, node_id: Uuid, state: RecorderState) -> Result<()>;
+    fn recorder_status(&self, node_id: Uuid) -> Option<RecorderStatus>;
+    fn set_player_config(&mut self, node_id: Uuid, config: PlayerConfig);
+    fn player_command(&mut self, node_id: Uuid, command: PlayerCommand) -> Result<()>;
+    fn player_status(&self, node_id: Uuid) -> Option<PlayerStatus>;
+    fn set_generator_config(&mut self, node_id: Uuid, config: GeneratorConfig);
+    fn set_network_config(&mut self, node_id: Uuid, config: NetworkConfig);
+    fn network_status(&self, node_id: Uuid) -> Option<NetworkStatus>;
+    fn set_virtual_mic_config(&mut self, node_id: Uuid, config: VirtualMicConfig);
+    /// Plays a clip on a file player node, mixed with its playlist and the other clips.
+    fn play_clip(&mut self, node_id: Uuid, clip: &Clip) -> Result<()>;
+    fn stop_clips(&mut self, node_id: Uuid);
+    /// Ids of the clips that a file player node is playing.
+    fn playing_clips(&self, node_id: Uuid) -> Vec<Uuid>;
     fn application_processes(&self) -> Vec<ProcessInfo>;
     fn input_devices(&self) -> Vec<DeviceInfo>;
     fn output_devices(&self) -> Vec<DeviceInfo>;
+    /// Takes the processes whose audio sessions have appeared since the last call.
+    fn take_new_processes(&mut self) -> Vec<ProcessInfo>;
 }
 
 #[derive(Debug, Clone, PartialOrd, PartialEq, Serialize, Deserialize)]
@@ -26,6 +125,26 @@ pub struct Node {
     pub filename: String,
 
     pub pos: (f32, f32),
+    /// Inputs and outputs of the node, shown as its pins
+    #[serde(default)]
+    pub ports: Vec<Port>,
+    #[serde(default)]
+    pub effects: Vec<EffectConfig>,
+    /// Settings of a recorder node
+    #[serde(default)]
+    pub recorder: Option<RecorderConfig>,
+    /// Settings of a file player node
+    #[serde(default)]
+    pub player: Option<PlayerConfig>,
+    /// Settings of a generator node
+    #[serde(default)]
+    pub generator: Option<GeneratorConfig>,
+    /// Settings of a network sender or receiver node
+    #[serde(default)]
+    pub network: Option<NetworkConfig>,
+    /// Settings of a virtual microphone node
+    #[serde(default)]
+    pub virtual_mic: Option<VirtualMicConfig>,
 
     #[serde(skip)]
     pub process_id: Option<u32>,
@@ -49,6 +168,13 @@ impl Default for Node {
             display_name: String::new(),
             filename: String::new(),
             pos: (0.0, 0.0),
+            ports: Vec::new(),
+            effects: Vec::new(),
+            recorder: None,
+            player: None,
+            generator: None,
+            network: None,
+            virtual_mic: None,
             process_id: None,
             active: false,
             present: false,
@@ -64,13 +190,29 @@ pub enum NodeKind {
     Application,
     OutputDevice,
     InputDevice,
+    /// Writes the audio of the linked source into files
+    Recorder,
+    /// Plays audio files
+    FilePlayer,
+    /// Generates test signals
+    Generator,
+    /// Sends the audio of the linked source to the network over RTP
+    NetworkSender,
+    /// Plays an RTP stream received from the network
+    NetworkReceiver,
+    /// Exposes the mix of the linked sources as a capture device, for use as a microphone
+    VirtualMicrophone,
 }
 
+#[derive(Debug, Clone)]
 pub struct DeviceInfo {
     pub id: Uuid,
     pub name: String,
+    /// Whether the device is the input end of a virtual audio cable
+    pub virtual_cable: bool,
 }
 
+#[derive(Debug, Clone)]
 pub struct ProcessInfo {
     pub pid: u32,
     pub display_name: String,
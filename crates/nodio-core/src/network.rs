@@ -0,0 +1,94 @@
+use serde::{Deserialize, Serialize};
+
+/// Encoding of the audio in the RTP packets. AES67 devices support L24 at least.
+#[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Serialize, Deserialize)]
+pub enum RtpPayload {
+    /// 16 bit big endian PCM
+    L16,
+    /// 24 bit big endian PCM
+    L24,
+}
+
+impl RtpPayload {
+    pub const ALL: [RtpPayload; 2] = [RtpPayload::L16, RtpPayload::L24];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            RtpPayload::L16 => "L16",
+            RtpPayload::L24 => "L24",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|payload| payload.name().eq_ignore_ascii_case(name))
+    }
+
+    pub fn bytes_per_sample(&self) -> usize {
+        match self {
+            RtpPayload::L16 => 2,
+            RtpPayload::L24 => 3,
+        }
+    }
+
+    /// Dynamic RTP payload type that Nodio sends the payload with. Each payload has its own, so
+    /// that a receiver can tell them apart.
+    pub fn payload_type(&self) -> u8 {
+        match self {
+            RtpPayload::L16 => 97,
+            RtpPayload::L24 => 96,
+        }
+    }
+}
+
+/// Settings of a network sender or receiver node.
+#[derive(Debug, Clone, PartialOrd, PartialEq, Serialize, Deserialize)]
+pub struct NetworkConfig {
+    /// Address the sender sends to and the receiver listens on, e.g. "239.69.1.10:5004" for a
+    /// multicast group or "192.168.1.20:5004" for a single receiver
+    pub address: String,
+    pub payload: RtpPayload,
+    /// RTP payload type of the stream, if taken from the session description of another sender.
+    /// `None` for the payload type that Nodio sends the payload with.
+    #[serde(default)]
+    pub payload_type: Option<u8>,
+    /// Audio buffered by the receiver against network jitter (ms)
+    pub latency_ms: u32,
+    /// Whether the sender announces the stream with SAP, so that AES67 devices can find it
+    pub announce: bool,
+}
+
+impl Default for NetworkConfig {
+    fn default() -> Self {
+        Self {
+            address: "239.69.1.10:5004".to_string(),
+            payload: RtpPayload::L24,
+            payload_type: None,
+            latency_ms: 20,
+            announce: true,
+        }
+    }
+}
+
+impl NetworkConfig {
+    /// The RTP payload type that the packets of the stream carry
+    pub fn rtp_payload_type(&self) -> u8 {
+        self.payload_type
+            .unwrap_or_else(|| self.payload.payload_type())
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NetworkStatus {
+    /// Packets sent or received
+    pub packets: u64,
+    /// Packets that did not arrive, or arrived too late to be played
+    pub lost: u64,
+    /// Audio waiting in the jitter buffer (ms)
+    pub buffered_ms: f32,
+    /// Why the stream can not be sent or received
+    pub error: Option<String>,
+    /// Session description of the stream sent, for configuring receivers
+    pub sdp: Option<String>,
+}
@@ -0,0 +1,26 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// A sticky note in the editor, e.g. "Don't unplug this, OBS depends on it". Notes are only
+/// annotations and take no part in the routing.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Note {
+    pub id: Uuid,
+    pub text: String,
+    /// Color of the note as sRGB
+    pub color: [u8; 3],
+    pub pos: (f32, f32),
+    pub size: (f32, f32),
+}
+
+impl Default for Note {
+    fn default() -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            text: String::new(),
+            color: [230, 200, 90],
+            pos: (0.0, 0.0),
+            size: (180.0, 120.0),
+        }
+    }
+}
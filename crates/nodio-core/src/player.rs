@@ -0,0 +1,94 @@
+use serde::{Deserialize, Serialize};
+
+/// What is played after the end of a track.
+#[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Serialize, Deserialize)]
+pub enum LoopMode {
+    /// The playlist is played once
+    Off,
+    /// The current track is repeated
+    Track,
+    /// The playlist is repeated
+    Playlist,
+}
+
+impl LoopMode {
+    pub const ALL: [LoopMode; 3] = [LoopMode::Off, LoopMode::Track, LoopMode::Playlist];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            LoopMode::Off => "No loop",
+            LoopMode::Track => "Loop track",
+            LoopMode::Playlist => "Loop playlist",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialOrd, PartialEq, Serialize, Deserialize)]
+pub struct PlayerConfig {
+    /// Paths of the audio files, played in order
+    pub playlist: Vec<String>,
+    pub loop_mode: LoopMode,
+    pub gain_db: f32,
+}
+
+impl Default for PlayerConfig {
+    fn default() -> Self {
+        Self {
+            playlist: Vec::new(),
+            loop_mode: LoopMode::Off,
+            gain_db: 0.0,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum PlayerCommand {
+    Play,
+    Pause,
+    Stop,
+    Next,
+    Previous,
+    /// Starts playing the track at the given playlist index
+    PlayTrack(usize),
+    /// Moves to the given position in the current track, in seconds
+    Seek(f64),
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum PlayerState {
+    Stopped,
+    Playing,
+    Paused,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlayerStatus {
+    pub state: PlayerState,
+    /// Playlist index of the current track
+    pub track: Option<usize>,
+    pub position_secs: f64,
+    /// Length of the current track, if known
+    pub duration_secs: Option<f64>,
+}
+
+impl Default for PlayerStatus {
+    fn default() -> Self {
+        Self {
+            state: PlayerState::Stopped,
+            track: None,
+            position_secs: 0.0,
+            duration_secs: None,
+        }
+    }
+}
+
+/// The track to play after `track` ends, or `None` when the playback ends.
+pub fn next_track(track: usize, len: usize, loop_mode: LoopMode) -> Option<usize> {
+    match loop_mode {
+        _ if len == 0 => None,
+        LoopMode::Track => Some(track.min(len - 1)),
+        LoopMode::Off if track + 1 >= len => None,
+        LoopMode::Off => Some(track + 1),
+        LoopMode::Playlist => Some((track + 1) % len),
+    }
+}
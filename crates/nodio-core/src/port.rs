@@ -0,0 +1,202 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{ChannelLayout, ChannelMatrix, ChannelPreset, Error, Node, NodeKind, Result};
+
+/// Whether audio enters or leaves a node through a port
+#[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Serialize, Deserialize)]
+pub enum PortDirection {
+    Input,
+    Output,
+}
+
+/// What a port carries. Links are only made between ports of the same media type.
+#[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Serialize, Deserialize)]
+pub enum MediaType {
+    Audio,
+}
+
+/// An input or output of a node, shown as a pin in the editor. Links go from an output port to
+/// an input port.
+#[derive(Debug, Clone, PartialOrd, PartialEq, Serialize, Deserialize)]
+pub struct Port {
+    pub id: Uuid,
+    /// Shown next to the pin, e.g. "Sidechain". Empty for the only port of a node.
+    #[serde(default)]
+    pub name: String,
+    pub direction: PortDirection,
+    pub channels: u16,
+    pub media: MediaType,
+}
+
+impl Port {
+    /// Fails if a link from this port to the target port is not allowed.
+    pub fn check_link(&self, target: &Port) -> Result<()> {
+        if self.direction != PortDirection::Output {
+            return Err(Error::CouldNotConnect(
+                "Links start at an output".to_string(),
+            ));
+        }
+        if target.direction != PortDirection::Input {
+            return Err(Error::CouldNotConnect("Links end at an input".to_string()));
+        }
+        if self.media != target.media {
+            return Err(Error::CouldNotConnect(format!(
+                "Cannot link {:?} to {:?}",
+                self.media, target.media
+            )));
+        }
+        Ok(())
+    }
+
+    /// Channel matrix of a link from this port: the stereo audio of the node fitted to the
+    /// channels of the port, followed by the channel matrix of the link. A mono port carries the
+    /// sum of the channels, on the left and right channel of the target.
+    pub fn link_matrix(&self, matrix: Option<&ChannelMatrix>) -> Option<ChannelMatrix> {
+        let downmix = (self.channels == 1).then(|| {
+            ChannelMatrix::preset(
+                ChannelPreset::MonoSum,
+                ChannelLayout::Stereo,
+                ChannelLayout::Stereo,
+            )
+        });
+
+        match (downmix, matrix) {
+            (Some(downmix), Some(matrix)) => Some(downmix.then(matrix)),
+            (Some(downmix), None) => Some(downmix),
+            (None, matrix) => matrix.cloned(),
+        }
+    }
+}
+
+impl NodeKind {
+    /// Direction of the port of the nodes of this kind
+    pub fn port_direction(self) -> PortDirection {
+        match self {
+            NodeKind::Application
+            | NodeKind::InputDevice
+            | NodeKind::FilePlayer
+            | NodeKind::Generator
+            | NodeKind::NetworkReceiver => PortDirection::Output,
+            NodeKind::OutputDevice
+            | NodeKind::Recorder
+            | NodeKind::NetworkSender
+            | NodeKind::VirtualMicrophone => PortDirection::Input,
+        }
+    }
+}
+
+impl Node {
+    /// The stereo audio port of the kind of the node, with the id of the node. Nodes that do not
+    /// list their ports get it when they are added, so links saved before nodes had ports still
+    /// connect.
+    pub fn default_port(&self) -> Port {
+        Port {
+            id: self.id,
+            name: String::new(),
+            direction: self.kind.port_direction(),
+            channels: 2,
+            media: MediaType::Audio,
+        }
+    }
+
+    /// Adds the ports of its kind that the node does not list: the default port, and the
+    /// "Monitor" output of an output device, which carries what the device plays so that it can
+    /// be recorded or sent to the network. Listed ports keep their ids.
+    pub fn add_missing_ports(&mut self) {
+        if self.ports.is_empty() {
+            self.ports.push(self.default_port());
+        }
+
+        if self.kind == NodeKind::OutputDevice
+            && !self
+                .ports
+                .iter()
+                .any(|port| port.direction == PortDirection::Output)
+        {
+            self.ports.push(Port {
+                id: Uuid::new_v4(),
+                name: "Monitor".to_string(),
+                direction: PortDirection::Output,
+                channels: 2,
+                media: MediaType::Audio,
+            });
+        }
+    }
+
+    pub fn port(&self, port_id: Uuid) -> Option<&Port> {
+        self.ports.iter().find(|port| port.id == port_id)
+    }
+
+    /// Whether a link end is at this node, including links to its default port from before it
+    /// listed its ports.
+    pub fn owns_port(&self, port_id: Uuid) -> bool {
+        port_id == self.id || self.port(port_id).is_some()
+    }
+}
+
+/// The node with the port, and the port.
+pub fn find_port(nodes: &[Node], port_id: Uuid) -> Option<(&Node, &Port)> {
+    nodes
+        .iter()
+        .find_map(|node| node.port(port_id).map(|port| (node, port)))
+}
+
+/// The node that an end of a link belongs to. Ids that are not ports of a node are taken as
+/// node ids.
+pub fn port_node_id(nodes: &[Node], port_id: Uuid) -> Uuid {
+    find_port(nodes, port_id).map_or(port_id, |(node, _)| node.id)
+}
+
+/// Fails with the reason if a link from the port to the target port is not allowed: a port is
+/// not found, the ports do not fit together, their nodes are linked already, or the audio would
+/// flow back to where it comes from. The links are given as (port, target port).
+pub fn validate_link(
+    nodes: &[Node],
+    links: &[(Uuid, Uuid)],
+    port_id: Uuid,
+    target_port_id: Uuid,
+) -> Result<()> {
+    match (find_port(nodes, port_id), find_port(nodes, target_port_id)) {
+        (Some((_, port)), Some((_, target_port))) => port.check_link(target_port)?,
+        _ => return Err(Error::CouldNotConnect("No such port found".to_string())),
+    }
+
+    if links.contains(&(port_id, target_port_id)) {
+        return Err(Error::CouldNotConnect(
+            "The ports are linked already".to_string(),
+        ));
+    }
+
+    // Two nodes are linked through a single pair of ports, as the backends route by node
+    let node_id = port_node_id(nodes, port_id);
+    let target_id = port_node_id(nodes, target_port_id);
+    if links.iter().any(|&(start, end)| {
+        port_node_id(nodes, start) == node_id && port_node_id(nodes, end) == target_id
+    }) {
+        return Err(Error::CouldNotConnect(
+            "The nodes are linked already through other ports".to_string(),
+        ));
+    }
+
+    // Follows the links downstream of the target, looking for the source
+    let mut reached = vec![target_id];
+    let mut idx = 0;
+    while let Some(&reached_id) = reached.get(idx) {
+        if reached_id == node_id {
+            return Err(Error::CouldNotConnect(
+                "The link would make a loop".to_string(),
+            ));
+        }
+
+        for &(start, end) in links {
+            let end_node_id = port_node_id(nodes, end);
+            if port_node_id(nodes, start) == reached_id && !reached.contains(&end_node_id) {
+                reached.push(end_node_id);
+            }
+        }
+        idx += 1;
+    }
+
+    Ok(())
+}
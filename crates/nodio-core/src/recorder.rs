@@ -0,0 +1,100 @@
+use std::env;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Serialize, Deserialize)]
+pub enum RecordingFormat {
+    Wav16,
+    Wav24,
+    WavFloat,
+    Flac16,
+    Flac24,
+}
+
+impl RecordingFormat {
+    pub const ALL: [RecordingFormat; 5] = [
+        RecordingFormat::Wav16,
+        RecordingFormat::Wav24,
+        RecordingFormat::WavFloat,
+        RecordingFormat::Flac16,
+        RecordingFormat::Flac24,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            RecordingFormat::Wav16 => "WAV 16 bit",
+            RecordingFormat::Wav24 => "WAV 24 bit",
+            RecordingFormat::WavFloat => "WAV 32 bit float",
+            RecordingFormat::Flac16 => "FLAC 16 bit",
+            RecordingFormat::Flac24 => "FLAC 24 bit",
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            RecordingFormat::Wav16 | RecordingFormat::Wav24 | RecordingFormat::WavFloat => "wav",
+            RecordingFormat::Flac16 | RecordingFormat::Flac24 => "flac",
+        }
+    }
+}
+
+/// When a recording continues in a new file.
+#[derive(Debug, Copy, Clone, PartialOrd, PartialEq, Serialize, Deserialize)]
+pub enum Rotation {
+    Never,
+    Size { megabytes: u32 },
+    Time { minutes: u32 },
+}
+
+#[derive(Debug, Clone, PartialOrd, PartialEq, Serialize, Deserialize)]
+pub struct RecorderConfig {
+    /// Directory the recordings are written to
+    pub directory: String,
+    pub format: RecordingFormat,
+    pub rotation: Rotation,
+}
+
+impl Default for RecorderConfig {
+    fn default() -> Self {
+        let home = env::var_os("USERPROFILE")
+            .or_else(|| env::var_os("HOME"))
+            .map(PathBuf::from)
+            .unwrap_or_default();
+
+        Self {
+            directory: home.join("Music").to_string_lossy().into_owned(),
+            format: RecordingFormat::Wav16,
+            rotation: Rotation::Never,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum RecorderState {
+    Stopped,
+    Recording,
+    Paused,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecorderStatus {
+    pub state: RecorderState,
+    /// Recorded time of the current session, excluding pauses
+    pub elapsed_secs: f64,
+    /// Bytes written in the current session, over all files
+    pub bytes: u64,
+    /// File currently written to
+    pub file: Option<String>,
+}
+
+impl Default for RecorderStatus {
+    fn default() -> Self {
+        Self {
+            state: RecorderState::Stopped,
+            elapsed_secs: 0.0,
+            bytes: 0,
+            file: None,
+        }
+    }
+}
@@ -2,7 +2,7 @@ use std::fmt::{Display, Formatter};
 
 pub type Result<T> = std::result::Result<T, Error>;
 
-#[derive(Clone)]
+#[derive(Debug, Clone)]
 pub enum Error {
     NoSuchDevice,
     CouldNotConnect(String),
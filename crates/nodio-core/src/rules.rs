@@ -0,0 +1,105 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{DeviceInfo, Node, NodeKind, ProcessInfo};
+
+/// Rule for automatically adding an application node when a matching process starts
+/// playing audio, and linking it to the matching output devices.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Rule {
+    pub id: Uuid,
+    pub enabled: bool,
+    /// Glob pattern (`*` and `?`) matched against the process display name and filename.
+    pub pattern: String,
+    /// Position of the added node.
+    pub pos: (f32, f32),
+    /// Glob patterns matched against output device names.
+    pub targets: Vec<String>,
+}
+
+impl Default for Rule {
+    fn default() -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            enabled: true,
+            pattern: String::new(),
+            pos: (0.0, 0.0),
+            targets: Vec::new(),
+        }
+    }
+}
+
+impl Rule {
+    pub fn matches(&self, process: &ProcessInfo) -> bool {
+        self.enabled
+            && !self.pattern.is_empty()
+            && (glob_match(&self.pattern, &process.display_name)
+                || glob_match(&self.pattern, &process.filename))
+    }
+
+    /// Returns the ids of the devices matching any of the rule targets.
+    pub fn target_devices(&self, devices: &[DeviceInfo]) -> Vec<Uuid> {
+        devices
+            .iter()
+            .filter(|device| {
+                self.targets
+                    .iter()
+                    .any(|target| glob_match(target, &device.name))
+            })
+            .map(|device| device.id)
+            .collect()
+    }
+}
+
+/// Returns the first enabled rule matching the given process, unless the process
+/// already has a node.
+pub fn evaluate_rules<'a>(
+    rules: &'a [Rule],
+    process: &ProcessInfo,
+    nodes: &[Node],
+) -> Option<&'a Rule> {
+    let has_node = nodes.iter().any(|node| {
+        node.kind == NodeKind::Application
+            && (node.process_id == Some(process.pid)
+                || (node.display_name == process.display_name && node.filename == process.filename))
+    });
+
+    if has_node {
+        return None;
+    }
+
+    rules.iter().find(|rule| rule.matches(process))
+}
+
+/// Case-insensitive glob matching, where `*` matches any sequence of characters and
+/// `?` matches any single character.
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern = pattern.to_lowercase().chars().collect::<Vec<_>>();
+    let text = text.to_lowercase().chars().collect::<Vec<_>>();
+
+    let (mut p, mut t) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                Some((star_p, star_t)) => {
+                    p = star_p + 1;
+                    t = star_t + 1;
+                    backtrack = Some((star_p, star_t + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
@@ -0,0 +1,54 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// A short audio file that is played by a button of the soundboard.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Clip {
+    pub id: Uuid,
+    pub name: String,
+    pub path: String,
+    pub gain_db: f32,
+    /// Global hotkey, e.g. "Ctrl+Shift+A" or "F13"
+    #[serde(default)]
+    pub hotkey: Option<String>,
+    /// Note of a note-on message from a MIDI input
+    #[serde(default)]
+    pub midi_note: Option<u8>,
+    /// Starting a clip stops the other clips of its choke group
+    #[serde(default)]
+    pub choke_group: Option<u32>,
+}
+
+impl Default for Clip {
+    fn default() -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            name: "Clip".to_string(),
+            path: String::new(),
+            gain_db: 0.0,
+            hotkey: None,
+            midi_note: None,
+            choke_group: None,
+        }
+    }
+}
+
+/// Layout of the soundboard panel.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Soundboard {
+    /// File player node the clips are played by, which is linked to the outputs
+    pub player_id: Option<Uuid>,
+    /// Buttons per row
+    pub columns: usize,
+    pub clips: Vec<Clip>,
+}
+
+impl Default for Soundboard {
+    fn default() -> Self {
+        Self {
+            player_id: None,
+            columns: 4,
+            clips: Vec::new(),
+        }
+    }
+}
@@ -0,0 +1,28 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Name fragments of the render endpoints of common virtual cable drivers (lowercase).
+const VIRTUAL_CABLE_NAMES: [&str; 7] = [
+    "cable input",
+    "virtual cable",
+    "virtual audio cable",
+    "vb-audio",
+    "voicemeeter",
+    "blackhole",
+    "soundflower",
+];
+
+#[derive(Debug, Clone, Default, PartialOrd, PartialEq, Serialize, Deserialize)]
+pub struct VirtualMicConfig {
+    /// Output device of the virtual cable that the mix is played into, on platforms where Nodio
+    /// can not create a capture device itself. Applications record from the other end of the cable.
+    pub cable_id: Option<Uuid>,
+}
+
+/// Whether an output device is the input end of a virtual audio cable, judging by its name.
+pub fn is_virtual_cable(device_name: &str) -> bool {
+    let name = device_name.to_lowercase();
+    VIRTUAL_CABLE_NAMES
+        .iter()
+        .any(|fragment| name.contains(fragment))
+}
@@ -0,0 +1,80 @@
+use nodio_core::{ChannelLayout, ChannelMatrix, ChannelPreset};
+
+fn map(matrix: &ChannelMatrix, frame: &[f32], output_channels: usize) -> Vec<f32> {
+    let mut output = vec![0.0; output_channels];
+    matrix.apply(frame, frame.len(), &mut output, output_channels);
+    output
+}
+
+#[test]
+fn stereo_presets() {
+    let preset =
+        |preset| ChannelMatrix::preset(preset, ChannelLayout::Stereo, ChannelLayout::Stereo);
+
+    assert_eq!(
+        map(&preset(ChannelPreset::Straight), &[0.2, 0.4], 2),
+        [0.2, 0.4]
+    );
+    assert_eq!(
+        map(&preset(ChannelPreset::SwapLeftRight), &[0.2, 0.4], 2),
+        [0.4, 0.2]
+    );
+    assert_eq!(
+        map(&preset(ChannelPreset::LeftOnly), &[0.2, 0.4], 2),
+        [0.2, 0.0]
+    );
+    assert_eq!(
+        map(&preset(ChannelPreset::RightOnly), &[0.2, 0.4], 2),
+        [0.0, 0.4]
+    );
+
+    let mono = map(&preset(ChannelPreset::MonoSum), &[0.2, 0.4], 2);
+    assert!(
+        mono.iter().all(|sample| (sample - 0.3).abs() < 1e-6),
+        "{:?}",
+        mono
+    );
+}
+
+#[test]
+fn routes_center_channel() {
+    let mut matrix = ChannelMatrix::new(ChannelLayout::Surround51, ChannelLayout::Stereo);
+    matrix.set_gain(2, 1, 1.0);
+
+    let frame = [0.1, 0.2, 0.5, 0.0, 0.0, 0.0];
+    assert_eq!(map(&matrix, &frame, 2), [0.0, 0.5]);
+}
+
+#[test]
+fn keeps_gains_when_changing_layouts() {
+    let mut matrix = ChannelMatrix::preset(
+        ChannelPreset::SwapLeftRight,
+        ChannelLayout::Stereo,
+        ChannelLayout::Stereo,
+    );
+    matrix.set_layouts(ChannelLayout::Surround51, ChannelLayout::Stereo);
+
+    assert_eq!(matrix.gain(0, 1), 1.0);
+    assert_eq!(matrix.gain(1, 0), 1.0);
+    assert_eq!(matrix.gain(2, 0), 0.0);
+    // Outside of the layouts
+    assert_eq!(matrix.gain(0, 2), 0.0);
+}
+
+#[test]
+fn chains_matrices() {
+    let mono_sum = ChannelMatrix::preset(
+        ChannelPreset::MonoSum,
+        ChannelLayout::Stereo,
+        ChannelLayout::Stereo,
+    );
+    let mut left_only = ChannelMatrix::preset(
+        ChannelPreset::Straight,
+        ChannelLayout::Stereo,
+        ChannelLayout::Stereo,
+    );
+    left_only.set_gain(1, 1, 0.0);
+
+    let matrix = mono_sum.then(&left_only);
+    assert_eq!(map(&matrix, &[0.2, 0.4], 2), [0.3, 0.0]);
+}
@@ -0,0 +1,165 @@
+use nodio_core::{db_to_gain, Ducker, DuckingRule, Node, NodeKind, Uuid};
+
+const DT: f32 = 0.01;
+
+struct Setup {
+    nodes: Vec<Node>,
+    rules: Vec<DuckingRule>,
+    ducker: Ducker,
+    voice: Uuid,
+    music: Uuid,
+}
+
+fn setup() -> Setup {
+    let voice = Node {
+        kind: NodeKind::Application,
+        display_name: "Voice chat".to_string(),
+        ..Default::default()
+    };
+    let music = Node {
+        kind: NodeKind::Application,
+        display_name: "Music".to_string(),
+        volume: 0.8,
+        ..Default::default()
+    };
+
+    let rules = vec![DuckingRule {
+        trigger_id: voice.id,
+        target_id: music.id,
+        threshold_db: -30.0,
+        attenuation_db: 12.0,
+        attack_ms: 100.0,
+        release_ms: 200.0,
+        ..Default::default()
+    }];
+
+    Setup {
+        voice: voice.id,
+        music: music.id,
+        nodes: vec![voice, music],
+        rules,
+        ducker: Ducker::default(),
+    }
+}
+
+impl Setup {
+    /// Runs the ducker with the trigger peaking at the given level for each step,
+    /// applying the returned volumes to the nodes like the app does.
+    fn run(&mut self, peaks: &[f32]) -> Vec<f32> {
+        let mut music_volumes = Vec::new();
+
+        for &peak in peaks {
+            let voice = self.nodes.iter_mut().find(|n| n.id == self.voice).unwrap();
+            voice.peak_values = (peak, peak);
+
+            for (node_id, volume) in self.ducker.update(&self.rules, &self.nodes, DT) {
+                self.nodes
+                    .iter_mut()
+                    .find(|n| n.id == node_id)
+                    .unwrap()
+                    .volume = volume;
+            }
+
+            music_volumes.push(self.music_volume());
+        }
+
+        music_volumes
+    }
+
+    fn music_volume(&self) -> f32 {
+        self.nodes
+            .iter()
+            .find(|n| n.id == self.music)
+            .unwrap()
+            .volume
+    }
+}
+
+fn assert_close(a: f32, b: f32) {
+    assert!((a - b).abs() < 1e-4, "{} != {}", a, b);
+}
+
+#[test]
+fn quiet_trigger_does_not_duck() {
+    let mut setup = setup();
+
+    let volumes = setup.run(&[0.0, 0.01, 0.02, 0.0]);
+
+    assert!(volumes.iter().all(|&v| v == 0.8));
+    assert!(!setup.ducker.is_ducked(setup.music));
+}
+
+#[test]
+fn attack_ramps_down_to_the_attenuation() {
+    let mut setup = setup();
+
+    // 12 dB over 100 ms is 1.2 dB per 10 ms step
+    let volumes = setup.run(&[0.5; 15]);
+
+    assert_close(volumes[0], 0.8 * db_to_gain(-1.2));
+    assert_close(volumes[4], 0.8 * db_to_gain(-6.0));
+    assert_close(volumes[9], 0.8 * db_to_gain(-12.0));
+    assert_close(volumes[14], 0.8 * db_to_gain(-12.0));
+    assert_close(setup.ducker.attenuation_db(setup.music), 12.0);
+}
+
+#[test]
+fn release_recovers_the_original_volume() {
+    let mut setup = setup();
+    setup.run(&[0.5; 10]);
+
+    // 12 dB over 200 ms is 0.6 dB per 10 ms step
+    let volumes = setup.run(&[0.0; 25]);
+
+    assert_close(volumes[0], 0.8 * db_to_gain(-11.4));
+    assert_close(volumes[9], 0.8 * db_to_gain(-6.0));
+    assert_close(volumes[19], 0.8);
+    assert_close(volumes[24], 0.8);
+    assert!(!setup.ducker.is_ducked(setup.music));
+}
+
+#[test]
+fn volume_changed_while_ducked_is_restored() {
+    let mut setup = setup();
+    setup.run(&[0.5; 10]);
+
+    let volume = setup.ducker.set_volume(setup.music, 0.5);
+    assert_close(volume, 0.5 * db_to_gain(-12.0));
+
+    let volumes = setup.run(&[0.0; 20]);
+    assert_close(*volumes.last().unwrap(), 0.5);
+}
+
+#[test]
+fn release_all_restores_the_ducked_volumes() {
+    let mut setup = setup();
+    setup.run(&[0.5; 10]);
+
+    assert_eq!(setup.ducker.release_all(), vec![(setup.music, 0.8)]);
+    assert!(!setup.ducker.is_ducked(setup.music));
+}
+
+#[test]
+fn disabled_rule_does_not_duck() {
+    let mut setup = setup();
+    setup.rules[0].enabled = false;
+
+    let volumes = setup.run(&[0.5; 5]);
+
+    assert!(volumes.iter().all(|&v| v == 0.8));
+}
+
+#[test]
+fn removed_target_is_forgotten() {
+    let mut setup = setup();
+    setup.run(&[0.5; 5]);
+
+    let music = setup.music;
+    setup.nodes.retain(|n| n.id != music);
+
+    let volumes = setup.ducker.update(&setup.rules, &setup.nodes, DT);
+
+    assert!(volumes.is_empty());
+
+    assert!(!setup.ducker.is_ducked(music));
+}
@@ -0,0 +1,49 @@
+use nodio_core::{NodeGroup, Uuid};
+
+#[test]
+fn grouped_nodes_leave_their_old_groups() {
+    let (a, b, c) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+    let mut groups = Vec::new();
+
+    let voice_chat = NodeGroup::group_nodes(&mut groups, "Voice chat", &[a, b]);
+    let game = NodeGroup::group_nodes(&mut groups, "Game", &[b, c]);
+
+    assert_eq!(groups.len(), 2);
+    assert_eq!(groups[0].id, voice_chat);
+    assert_eq!(groups[0].node_ids, vec![a]);
+    assert_eq!(groups[1].id, game);
+    assert_eq!(groups[1].title, "Game");
+    assert_eq!(groups[1].node_ids, vec![b, c]);
+
+    // The first group is left empty
+    NodeGroup::group_nodes(&mut groups, "All", &[a, b, c]);
+    assert_eq!(groups.len(), 1);
+    assert_eq!(groups[0].title, "All");
+}
+
+#[test]
+fn removed_nodes_are_pruned() {
+    let node_id = Uuid::new_v4();
+    let mut groups = Vec::new();
+    NodeGroup::group_nodes(&mut groups, "Game", &[node_id, Uuid::new_v4()]);
+    NodeGroup::group_nodes(&mut groups, "Voice chat", &[Uuid::new_v4()]);
+
+    NodeGroup::prune(&mut groups, &[node_id]);
+
+    assert_eq!(groups.len(), 1);
+    assert_eq!(groups[0].node_ids, vec![node_id]);
+}
+
+#[test]
+fn collapsed_defaults_to_false() {
+    let group = NodeGroup {
+        collapsed: true,
+        ..Default::default()
+    };
+    let mut json = serde_json::to_value(&group).unwrap();
+    json.as_object_mut().unwrap().remove("collapsed");
+
+    let parsed: NodeGroup = serde_json::from_value(json).unwrap();
+    assert!(!parsed.collapsed);
+    assert_eq!(parsed.node_ids, group.node_ids);
+}
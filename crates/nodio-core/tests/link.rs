@@ -0,0 +1,42 @@
+use nodio_core::{
+    ChannelLayout, ChannelMatrix, ChannelPreset, EffectConfig, EffectKind, Link, Uuid,
+};
+
+#[test]
+fn saved_links_keep_their_settings() {
+    let link = Link {
+        id: Uuid::new_v4(),
+        src_id: Uuid::new_v4(),
+        dst_id: Uuid::new_v4(),
+        channel_matrix: Some(ChannelMatrix::preset(
+            ChannelPreset::SwapLeftRight,
+            ChannelLayout::Stereo,
+            ChannelLayout::Stereo,
+        )),
+        delay_ms: 40.0,
+        effects: vec![EffectConfig::new(EffectKind::GainPan)],
+    };
+
+    let json = serde_json::to_string(&[&link]).unwrap();
+    let parsed: Vec<Link> = serde_json::from_str(&json).unwrap();
+    assert_eq!(parsed, [link]);
+}
+
+#[test]
+fn links_saved_without_settings_are_read() {
+    let (id, src_id, dst_id) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+    let json = serde_json::to_string(&[(id, src_id, dst_id)]).unwrap();
+
+    let parsed: Vec<Link> = serde_json::from_str(&json).unwrap();
+    assert_eq!(
+        parsed,
+        [Link {
+            id,
+            src_id,
+            dst_id,
+            channel_matrix: None,
+            delay_ms: 0.0,
+            effects: Vec::new(),
+        }]
+    );
+}
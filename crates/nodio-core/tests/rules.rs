@@ -0,0 +1,112 @@
+use nodio_core::{evaluate_rules, glob_match, DeviceInfo, Node, NodeKind, ProcessInfo, Rule, Uuid};
+
+fn chrome() -> ProcessInfo {
+    ProcessInfo {
+        pid: 1234,
+        display_name: "Google Chrome".to_string(),
+        filename: r"C:\Program Files\Google\Chrome\Application\chrome.exe".to_string(),
+    }
+}
+
+fn rule(pattern: &str, targets: &[&str]) -> Rule {
+    Rule {
+        pattern: pattern.to_string(),
+        targets: targets.iter().map(|t| t.to_string()).collect(),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn glob_matching() {
+    assert!(glob_match("*chrome*", "chrome.exe"));
+    assert!(glob_match("*Chrome*", r"C:\Apps\CHROME.EXE"));
+    assert!(glob_match("chrome.exe", "chrome.exe"));
+    assert!(glob_match("chr?me*", "chrome.exe"));
+    assert!(glob_match("*", ""));
+    assert!(glob_match("a*b*c", "aXXbYYc"));
+    assert!(glob_match("a*b", "abab"));
+
+    assert!(!glob_match("chrome", "chrome.exe"));
+    assert!(!glob_match("?", ""));
+    assert!(!glob_match("a*b*c", "aXXbYY"));
+}
+
+#[test]
+fn matches_display_name_or_filename() {
+    assert!(rule("*chrome.exe", &[]).matches(&chrome()));
+    assert!(rule("google*", &[]).matches(&chrome()));
+    assert!(!rule("*firefox*", &[]).matches(&chrome()));
+    assert!(!rule("", &[]).matches(&chrome()));
+
+    let disabled = Rule {
+        enabled: false,
+        ..rule("*chrome*", &[])
+    };
+    assert!(!disabled.matches(&chrome()));
+}
+
+#[test]
+fn first_matching_rule_wins() {
+    let rules = [
+        rule("*firefox*", &["Speakers"]),
+        rule("*chrome*", &["Headphones"]),
+        rule("*", &["Speakers"]),
+    ];
+
+    let matched = evaluate_rules(&rules, &chrome(), &[]).unwrap();
+    assert_eq!(matched.id, rules[1].id);
+}
+
+#[test]
+fn skips_processes_that_already_have_a_node() {
+    let rules = [rule("*chrome*", &["Headphones"])];
+    let process = chrome();
+
+    let by_pid = Node {
+        kind: NodeKind::Application,
+        process_id: Some(process.pid),
+        ..Default::default()
+    };
+    assert!(evaluate_rules(&rules, &process, &[by_pid]).is_none());
+
+    let by_name = Node {
+        kind: NodeKind::Application,
+        display_name: process.display_name.clone(),
+        filename: process.filename.clone(),
+        ..Default::default()
+    };
+    assert!(evaluate_rules(&rules, &process, &[by_name]).is_none());
+
+    let other = Node {
+        kind: NodeKind::Application,
+        display_name: "Spotify".to_string(),
+        filename: "spotify.exe".to_string(),
+        ..Default::default()
+    };
+    assert!(evaluate_rules(&rules, &process, &[other]).is_some());
+}
+
+#[test]
+fn resolves_target_devices() {
+    let devices = [
+        DeviceInfo {
+            id: Uuid::new_v4(),
+            name: "Headphones (USB Audio)".to_string(),
+            virtual_cable: false,
+        },
+        DeviceInfo {
+            id: Uuid::new_v4(),
+            name: "Speakers (Realtek)".to_string(),
+            virtual_cable: false,
+        },
+    ];
+
+    let rule = rule("*chrome*", &["headphones*"]);
+    assert_eq!(rule.target_devices(&devices), vec![devices[0].id]);
+
+    let rule = Rule {
+        targets: vec!["*".to_string()],
+        ..rule
+    };
+    assert_eq!(rule.target_devices(&devices).len(), 2);
+}
@@ -0,0 +1,16 @@
+use nodio_core::is_virtual_cable;
+
+#[test]
+fn detects_virtual_cables() {
+    assert!(is_virtual_cable("CABLE Input (VB-Audio Virtual Cable)"));
+    assert!(is_virtual_cable(
+        "VoiceMeeter Input (VB-Audio VoiceMeeter VAIO)"
+    ));
+    assert!(is_virtual_cable("Line 1 (Virtual Audio Cable)"));
+    assert!(is_virtual_cable("BlackHole 2ch"));
+
+    assert!(!is_virtual_cable(
+        "Speakers (Realtek High Definition Audio)"
+    ));
+    assert!(!is_virtual_cable("Headphones (USB Audio)"));
+}
@@ -0,0 +1,109 @@
+use std::f64::consts::TAU;
+
+use nodio_core::CalibrationStatus;
+
+use crate::stream::{AudioSink, AudioSource};
+
+/// Time from the start until the click is played (s)
+const CLICK_AT_SECS: f64 = 0.5;
+const CLICK_SECS: f64 = 0.005;
+const CLICK_HZ: f64 = 2000.0;
+const CLICK_LEVEL: f32 = 0.5;
+/// Time after the click until the calibration fails (s)
+const TIMEOUT_SECS: f64 = 3.0;
+/// Lowest level that is taken as the click, above the noise captured before it
+const MIN_THRESHOLD: f32 = 0.02;
+
+/// Measures the latency of an output device, by playing a click and finding it in the audio
+/// captured by an input device, e.g. a microphone next to the speakers.
+///
+/// Used as the source of the output and the sink of the input at the same time. Both are
+/// expected to start together, as the frames read and written are counted from the start.
+pub struct Calibrator {
+    sample_rate: u32,
+    channels: usize,
+    rendered: u64,
+    captured: u64,
+    /// Loudest frame captured before the click
+    noise_floor: f32,
+    /// Captured frame at which the click was heard
+    onset: Option<u64>,
+}
+
+impl Calibrator {
+    pub fn new(sample_rate: u32, channels: usize) -> Self {
+        Self {
+            sample_rate,
+            channels: channels.max(1),
+            rendered: 0,
+            captured: 0,
+            noise_floor: 0.0,
+            onset: None,
+        }
+    }
+
+    pub fn status(&self) -> CalibrationStatus {
+        let click_frame = self.frames(CLICK_AT_SECS);
+
+        match self.onset {
+            Some(onset) => CalibrationStatus::Measured(
+                (onset - click_frame) as f32 * 1000.0 / self.sample_rate as f32,
+            ),
+            None if self.captured > click_frame + self.frames(TIMEOUT_SECS) => {
+                CalibrationStatus::Failed
+            }
+            None => CalibrationStatus::Running,
+        }
+    }
+
+    pub fn read(&mut self, buffer: &mut [f32]) -> usize {
+        let click_start = self.frames(CLICK_AT_SECS);
+        let click_end = click_start + self.frames(CLICK_SECS);
+
+        for frame in buffer.chunks_mut(self.channels) {
+            let sample = if (click_start..click_end).contains(&self.rendered) {
+                let time = (self.rendered - click_start) as f64 / self.sample_rate as f64;
+                (time * CLICK_HZ * TAU).sin() as f32 * CLICK_LEVEL
+            } else {
+                0.0
+            };
+
+            frame.iter_mut().for_each(|s| *s = sample);
+            self.rendered += 1;
+        }
+
+        buffer.len() / self.channels
+    }
+
+    pub fn write(&mut self, samples: &[f32]) {
+        let click_frame = self.frames(CLICK_AT_SECS);
+
+        for frame in samples.chunks(self.channels) {
+            let level = frame.iter().fold(0.0f32, |peak, s| peak.max(s.abs()));
+
+            if self.captured < click_frame {
+                self.noise_floor = self.noise_floor.max(level);
+            } else if self.onset.is_none() && level > (self.noise_floor * 2.0).max(MIN_THRESHOLD) {
+                self.onset = Some(self.captured);
+            }
+
+            self.captured += 1;
+        }
+    }
+
+    fn frames(&self, secs: f64) -> u64 {
+        (secs * self.sample_rate as f64) as u64
+    }
+}
+
+impl AudioSource for Calibrator {
+    fn read(&mut self, buffer: &mut [f32]) -> usize {
+        Calibrator::read(self, buffer)
+    }
+}
+
+impl AudioSink for Calibrator {
+    fn write(&mut self, samples: &[f32]) {
+        Calibrator::write(self, samples)
+    }
+}
@@ -0,0 +1,55 @@
+use std::collections::VecDeque;
+
+/// Delays interleaved audio by a whole number of frames.
+pub struct DelayLine {
+    channels: usize,
+    samples: VecDeque<f32>,
+}
+
+impl DelayLine {
+    pub fn new(channels: usize, delay_frames: usize) -> Self {
+        let mut delay_line = Self {
+            channels: channels.max(1),
+            samples: VecDeque::new(),
+        };
+        delay_line.set_delay(delay_frames);
+        delay_line
+    }
+
+    /// Delay line for a delay in milliseconds.
+    pub fn with_delay_ms(channels: usize, sample_rate: u32, delay_ms: f32) -> Self {
+        Self::new(channels, delay_frames(delay_ms, sample_rate))
+    }
+
+    pub fn delay(&self) -> usize {
+        self.samples.len() / self.channels
+    }
+
+    /// Changes the delay. A longer delay inserts silence, a shorter one skips audio.
+    pub fn set_delay(&mut self, delay_frames: usize) {
+        let len = delay_frames * self.channels;
+
+        while self.samples.len() < len {
+            self.samples.push_front(0.0);
+        }
+
+        while self.samples.len() > len {
+            self.samples.pop_front();
+        }
+    }
+
+    pub fn process(&mut self, buffer: &mut [f32]) {
+        if self.samples.is_empty() {
+            return;
+        }
+
+        for sample in buffer.iter_mut() {
+            self.samples.push_back(*sample);
+            *sample = self.samples.pop_front().unwrap_or(0.0);
+        }
+    }
+}
+
+pub fn delay_frames(delay_ms: f32, sample_rate: u32) -> usize {
+    (delay_ms.max(0.0) / 1000.0 * sample_rate as f32).round() as usize
+}
@@ -0,0 +1,104 @@
+use std::f64::consts::PI;
+
+/// Biquad filter coefficients, normalized by a0.
+/// Formulas from the Audio EQ Cookbook by Robert Bristow-Johnson.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Biquad {
+    b0: f64,
+    b1: f64,
+    b2: f64,
+    a1: f64,
+    a2: f64,
+}
+
+impl Default for Biquad {
+    fn default() -> Self {
+        Self {
+            b0: 1.0,
+            b1: 0.0,
+            b2: 0.0,
+            a1: 0.0,
+            a2: 0.0,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Default)]
+pub struct BiquadState {
+    z1: f64,
+    z2: f64,
+}
+
+impl Biquad {
+    pub fn peaking(sample_rate: f32, freq: f32, q: f32, gain_db: f32) -> Self {
+        let a = 10.0_f64.powf(gain_db as f64 / 40.0);
+        let (cos_w0, alpha) = Self::omega(sample_rate, freq, q as f64);
+
+        Self::normalized(
+            1.0 + alpha * a,
+            -2.0 * cos_w0,
+            1.0 - alpha * a,
+            1.0 + alpha / a,
+            -2.0 * cos_w0,
+            1.0 - alpha / a,
+        )
+    }
+
+    pub fn low_shelf(sample_rate: f32, freq: f32, gain_db: f32) -> Self {
+        let a = 10.0_f64.powf(gain_db as f64 / 40.0);
+        let (cos_w0, alpha) = Self::omega(sample_rate, freq, std::f64::consts::FRAC_1_SQRT_2);
+        let sqrt_a_alpha = 2.0 * a.sqrt() * alpha;
+
+        Self::normalized(
+            a * ((a + 1.0) - (a - 1.0) * cos_w0 + sqrt_a_alpha),
+            2.0 * a * ((a - 1.0) - (a + 1.0) * cos_w0),
+            a * ((a + 1.0) - (a - 1.0) * cos_w0 - sqrt_a_alpha),
+            (a + 1.0) + (a - 1.0) * cos_w0 + sqrt_a_alpha,
+            -2.0 * ((a - 1.0) + (a + 1.0) * cos_w0),
+            (a + 1.0) + (a - 1.0) * cos_w0 - sqrt_a_alpha,
+        )
+    }
+
+    pub fn high_shelf(sample_rate: f32, freq: f32, gain_db: f32) -> Self {
+        let a = 10.0_f64.powf(gain_db as f64 / 40.0);
+        let (cos_w0, alpha) = Self::omega(sample_rate, freq, std::f64::consts::FRAC_1_SQRT_2);
+        let sqrt_a_alpha = 2.0 * a.sqrt() * alpha;
+
+        Self::normalized(
+            a * ((a + 1.0) + (a - 1.0) * cos_w0 + sqrt_a_alpha),
+            -2.0 * a * ((a - 1.0) + (a + 1.0) * cos_w0),
+            a * ((a + 1.0) + (a - 1.0) * cos_w0 - sqrt_a_alpha),
+            (a + 1.0) - (a - 1.0) * cos_w0 + sqrt_a_alpha,
+            2.0 * ((a - 1.0) - (a + 1.0) * cos_w0),
+            (a + 1.0) - (a - 1.0) * cos_w0 - sqrt_a_alpha,
+        )
+    }
+
+    /// Processes one sample (transposed direct form II).
+    pub fn process(&self, state: &mut BiquadState, x: f32) -> f32 {
+        let x = x as f64;
+        let y = self.b0 * x + state.z1;
+
+        state.z1 = self.b1 * x - self.a1 * y + state.z2;
+        state.z2 = self.b2 * x - self.a2 * y;
+
+        y as f32
+    }
+
+    fn omega(sample_rate: f32, freq: f32, q: f64) -> (f64, f64) {
+        let freq = (freq as f64).clamp(1.0, sample_rate as f64 * 0.49);
+        let w0 = 2.0 * PI * freq / sample_rate as f64;
+
+        (w0.cos(), w0.sin() / (2.0 * q.max(0.01)))
+    }
+
+    fn normalized(b0: f64, b1: f64, b2: f64, a0: f64, a1: f64, a2: f64) -> Self {
+        Self {
+            b0: b0 / a0,
+            b1: b1 / a0,
+            b2: b2 / a0,
+            a1: a1 / a0,
+            a2: a2 / a0,
+        }
+    }
+}
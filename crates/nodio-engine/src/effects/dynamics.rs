@@ -0,0 +1,211 @@
+use nodio_core::{db_to_gain, gain_to_db, EffectConfig, EffectKind};
+
+use super::{time_coefficient, Effect};
+
+/// Peak level of a frame across all channels, so that the channels are processed linked.
+fn frame_peak(frame: &[f32]) -> f32 {
+    frame
+        .iter()
+        .fold(0.0, |peak, sample| peak.max(sample.abs()))
+}
+
+/// Feed-forward compressor with a hard knee.
+pub struct Compressor {
+    sample_rate: f32,
+    params: Vec<f32>,
+    attack: f32,
+    release: f32,
+    /// Smoothed gain reduction (dB)
+    reduction_db: f32,
+}
+
+impl Compressor {
+    pub fn new(sample_rate: f32) -> Self {
+        let mut compressor = Self {
+            sample_rate,
+            params: EffectConfig::new(EffectKind::Compressor).params,
+            attack: 0.0,
+            release: 0.0,
+            reduction_db: 0.0,
+        };
+        compressor.update_coefficients();
+        compressor
+    }
+
+    fn update_coefficients(&mut self) {
+        self.attack = time_coefficient(self.params[2], self.sample_rate);
+        self.release = time_coefficient(self.params[3], self.sample_rate);
+    }
+}
+
+impl Effect for Compressor {
+    fn set_param(&mut self, idx: usize, value: f32) {
+        if let Some(param) = self.params.get_mut(idx) {
+            *param = value;
+            self.update_coefficients();
+        }
+    }
+
+    fn param(&self, idx: usize) -> f32 {
+        self.params.get(idx).copied().unwrap_or_default()
+    }
+
+    fn process(&mut self, block: &mut [f32], channels: usize) {
+        let threshold_db = self.params[0];
+        let slope = 1.0 - 1.0 / self.params[1].max(1.0);
+        let makeup_db = self.params[4];
+
+        for frame in block.chunks_exact_mut(channels) {
+            let level_db = gain_to_db(frame_peak(frame)).max(-120.0);
+            let target_db = (level_db - threshold_db).max(0.0) * slope;
+
+            let coefficient = if target_db > self.reduction_db {
+                self.attack
+            } else {
+                self.release
+            };
+            self.reduction_db = target_db + (self.reduction_db - target_db) * coefficient;
+
+            let gain = db_to_gain(makeup_db - self.reduction_db);
+            for sample in frame.iter_mut() {
+                *sample *= gain;
+            }
+        }
+    }
+
+    fn reset(&mut self) {
+        self.reduction_db = 0.0;
+    }
+}
+
+/// Peak limiter with an instant attack, so that the output never exceeds the ceiling.
+pub struct Limiter {
+    sample_rate: f32,
+    params: Vec<f32>,
+    release: f32,
+    gain: f32,
+}
+
+impl Limiter {
+    pub fn new(sample_rate: f32) -> Self {
+        let mut limiter = Self {
+            sample_rate,
+            params: EffectConfig::new(EffectKind::Limiter).params,
+            release: 0.0,
+            gain: 1.0,
+        };
+        limiter.update_coefficients();
+        limiter
+    }
+
+    fn update_coefficients(&mut self) {
+        self.release = time_coefficient(self.params[1], self.sample_rate);
+    }
+}
+
+impl Effect for Limiter {
+    fn set_param(&mut self, idx: usize, value: f32) {
+        if let Some(param) = self.params.get_mut(idx) {
+            *param = value;
+            self.update_coefficients();
+        }
+    }
+
+    fn param(&self, idx: usize) -> f32 {
+        self.params.get(idx).copied().unwrap_or_default()
+    }
+
+    fn process(&mut self, block: &mut [f32], channels: usize) {
+        let ceiling = db_to_gain(self.params[0]);
+
+        for frame in block.chunks_exact_mut(channels) {
+            let peak = frame_peak(frame);
+            let max_gain = if peak > ceiling { ceiling / peak } else { 1.0 };
+
+            self.gain = (1.0 + (self.gain - 1.0) * self.release).min(max_gain);
+
+            for sample in frame.iter_mut() {
+                *sample *= self.gain;
+            }
+        }
+    }
+
+    fn reset(&mut self) {
+        self.gain = 1.0;
+    }
+}
+
+/// Mutes the signal while its level stays below the threshold.
+pub struct NoiseGate {
+    sample_rate: f32,
+    params: Vec<f32>,
+    attack: f32,
+    release: f32,
+    hold_samples: u32,
+    /// Samples left until the gate starts to close
+    hold_left: u32,
+    gain: f32,
+}
+
+impl NoiseGate {
+    pub fn new(sample_rate: f32) -> Self {
+        let mut gate = Self {
+            sample_rate,
+            params: EffectConfig::new(EffectKind::NoiseGate).params,
+            attack: 0.0,
+            release: 0.0,
+            hold_samples: 0,
+            hold_left: 0,
+            gain: 0.0,
+        };
+        gate.update_coefficients();
+        gate
+    }
+
+    fn update_coefficients(&mut self) {
+        self.attack = time_coefficient(self.params[1], self.sample_rate);
+        self.hold_samples = (self.params[2] * 0.001 * self.sample_rate) as u32;
+        self.release = time_coefficient(self.params[3], self.sample_rate);
+    }
+}
+
+impl Effect for NoiseGate {
+    fn set_param(&mut self, idx: usize, value: f32) {
+        if let Some(param) = self.params.get_mut(idx) {
+            *param = value;
+            self.update_coefficients();
+        }
+    }
+
+    fn param(&self, idx: usize) -> f32 {
+        self.params.get(idx).copied().unwrap_or_default()
+    }
+
+    fn process(&mut self, block: &mut [f32], channels: usize) {
+        let threshold = db_to_gain(self.params[0]);
+
+        for frame in block.chunks_exact_mut(channels) {
+            if frame_peak(frame) >= threshold {
+                self.hold_left = self.hold_samples;
+            } else {
+                self.hold_left = self.hold_left.saturating_sub(1);
+            }
+
+            let open = self.hold_left > 0 || frame_peak(frame) >= threshold;
+            self.gain = if open {
+                1.0 + (self.gain - 1.0) * self.attack
+            } else {
+                self.gain * self.release
+            };
+
+            for sample in frame.iter_mut() {
+                *sample *= self.gain;
+            }
+        }
+    }
+
+    fn reset(&mut self) {
+        self.hold_left = 0;
+        self.gain = 0.0;
+    }
+}
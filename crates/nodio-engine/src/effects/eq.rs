@@ -0,0 +1,67 @@
+use nodio_core::{EffectConfig, EffectKind};
+
+use super::biquad::{Biquad, BiquadState};
+use super::Effect;
+
+/// Three band equalizer with a low shelf, a peaking mid band and a high shelf.
+pub struct ParametricEq {
+    sample_rate: f32,
+    params: Vec<f32>,
+    bands: [Biquad; 3],
+    /// Filter states of each band, per channel
+    states: Vec<[BiquadState; 3]>,
+}
+
+impl ParametricEq {
+    pub fn new(sample_rate: f32) -> Self {
+        let mut eq = Self {
+            sample_rate,
+            params: EffectConfig::new(EffectKind::ParametricEq).params,
+            bands: Default::default(),
+            states: Vec::new(),
+        };
+        eq.update_bands();
+        eq
+    }
+
+    fn update_bands(&mut self) {
+        let p = &self.params;
+
+        self.bands = [
+            Biquad::low_shelf(self.sample_rate, p[0], p[1]),
+            Biquad::peaking(self.sample_rate, p[2], p[4], p[3]),
+            Biquad::high_shelf(self.sample_rate, p[5], p[6]),
+        ];
+    }
+}
+
+impl Effect for ParametricEq {
+    fn set_param(&mut self, idx: usize, value: f32) {
+        if let Some(param) = self.params.get_mut(idx) {
+            *param = value;
+            self.update_bands();
+        }
+    }
+
+    fn param(&self, idx: usize) -> f32 {
+        self.params.get(idx).copied().unwrap_or_default()
+    }
+
+    fn process(&mut self, block: &mut [f32], channels: usize) {
+        if self.states.len() < channels {
+            self.states.resize(channels, Default::default());
+        }
+
+        for frame in block.chunks_exact_mut(channels) {
+            for (sample, states) in frame.iter_mut().zip(self.states.iter_mut()) {
+                for (band, state) in self.bands.iter().zip(states.iter_mut()) {
+                    *sample = band.process(state, *sample);
+                }
+            }
+        }
+    }
+
+    fn reset(&mut self) {
+        self.states.clear();
+    }
+}
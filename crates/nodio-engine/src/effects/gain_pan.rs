@@ -0,0 +1,51 @@
+use nodio_core::{db_to_gain, EffectConfig, EffectKind};
+
+use super::Effect;
+
+/// Gain, and balance between the first two channels.
+pub struct GainPan {
+    params: Vec<f32>,
+}
+
+impl GainPan {
+    pub fn new() -> Self {
+        Self {
+            params: EffectConfig::new(EffectKind::GainPan).params,
+        }
+    }
+}
+
+impl Effect for GainPan {
+    fn set_param(&mut self, idx: usize, value: f32) {
+        if let Some(param) = self.params.get_mut(idx) {
+            *param = value;
+        }
+    }
+
+    fn param(&self, idx: usize) -> f32 {
+        self.params.get(idx).copied().unwrap_or_default()
+    }
+
+    fn process(&mut self, block: &mut [f32], channels: usize) {
+        let gain = db_to_gain(self.params[0]);
+        let pan = self.params[1].clamp(-1.0, 1.0);
+
+        let left_gain = gain * (1.0 - pan.max(0.0));
+        let right_gain = gain * (1.0 + pan.min(0.0));
+
+        for frame in block.chunks_exact_mut(channels) {
+            if channels >= 2 {
+                frame[0] *= left_gain;
+                frame[1] *= right_gain;
+
+                for sample in frame[2..].iter_mut() {
+                    *sample *= gain;
+                }
+            } else {
+                frame[0] *= gain;
+            }
+        }
+    }
+
+    fn reset(&mut self) {}
+}
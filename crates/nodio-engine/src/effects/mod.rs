@@ -0,0 +1,156 @@
+mod biquad;
+mod dynamics;
+mod eq;
+mod gain_pan;
+
+use nodio_core::{EffectConfig, EffectKind, Uuid};
+
+use crate::plugin::load_plugin;
+
+use dynamics::{Compressor, Limiter, NoiseGate};
+use eq::ParametricEq;
+use gain_pan::GainPan;
+
+/// Audio effect processing interleaved f32 samples.
+pub trait Effect: Send {
+    /// Sets a parameter, indexed in the order of `EffectConfig::params`.
+    fn set_param(&mut self, idx: usize, value: f32);
+    /// Value of a parameter, or 0 for an index past the parameters of the effect.
+    fn param(&self, idx: usize) -> f32;
+    /// Processes a block of interleaved samples in place.
+    fn process(&mut self, block: &mut [f32], channels: usize);
+    /// Clears the internal state, e.g. filter history and envelopes.
+    fn reset(&mut self);
+
+    /// State to be saved in the layout, for effects that have state beyond their parameters.
+    fn save_state(&mut self) -> Option<Vec<u8>> {
+        None
+    }
+
+    fn load_state(&mut self, _state: &[u8]) {}
+}
+
+pub fn create_effect(config: &EffectConfig, sample_rate: f32) -> Box<dyn Effect> {
+    let mut effect: Box<dyn Effect> = match config.kind {
+        EffectKind::ParametricEq => Box::new(ParametricEq::new(sample_rate)),
+        EffectKind::Compressor => Box::new(Compressor::new(sample_rate)),
+        EffectKind::Limiter => Box::new(Limiter::new(sample_rate)),
+        EffectKind::NoiseGate => Box::new(NoiseGate::new(sample_rate)),
+        EffectKind::GainPan => Box::new(GainPan::new()),
+        EffectKind::Plugin => match &config.plugin {
+            Some(plugin) => {
+                let mut effect = load_plugin(plugin, sample_rate);
+                if !plugin.state.is_empty() {
+                    effect.load_state(&plugin.state);
+                }
+                effect
+            }
+            // Nothing to load, so the audio is passed through
+            None => Box::new(GainPan::new()),
+        },
+    };
+
+    for idx in 0..config.param_count() {
+        effect.set_param(idx, config.param(idx));
+    }
+
+    effect
+}
+
+/// Effects of a node, processed in order.
+pub struct EffectChain {
+    sample_rate: f32,
+    effects: Vec<(EffectConfig, Box<dyn Effect>)>,
+}
+
+impl EffectChain {
+    pub fn new(sample_rate: f32) -> Self {
+        Self {
+            sample_rate,
+            effects: Vec::new(),
+        }
+    }
+
+    /// Updates the chain to match the given effects. Effects that already exist in the
+    /// chain keep their state, so parameters can be changed while audio is playing.
+    pub fn set_effects(&mut self, configs: &[EffectConfig]) {
+        let mut effects = Vec::with_capacity(configs.len());
+
+        for config in configs {
+            let existing = self
+                .effects
+                .iter()
+                .position(|(c, _)| c.id == config.id && c.kind == config.kind)
+                .map(|idx| self.effects.swap_remove(idx));
+
+            let effect = match existing {
+                Some((previous_config, mut effect)) => {
+                    for idx in 0..config.param_count() {
+                        effect.set_param(idx, config.param(idx));
+                    }
+
+                    if config.enabled && !previous_config.enabled {
+                        effect.reset();
+                    }
+
+                    effect
+                }
+                None => create_effect(config, self.sample_rate),
+            };
+
+            effects.push((config.clone(), effect));
+        }
+
+        self.effects = effects;
+    }
+
+    /// Changes a single parameter, e.g. for automation.
+    pub fn set_param(&mut self, effect_id: Uuid, param_idx: usize, value: f32) {
+        if let Some((config, effect)) = self
+            .effects
+            .iter_mut()
+            .find(|(config, _)| config.id == effect_id)
+        {
+            if config.set_param(param_idx, value) {
+                effect.set_param(param_idx, config.param(param_idx));
+            }
+        }
+    }
+
+    /// States of the effects that save one, by effect id.
+    pub fn save_states(&mut self) -> Vec<(Uuid, Vec<u8>)> {
+        self.effects
+            .iter_mut()
+            .filter_map(|(config, effect)| effect.save_state().map(|state| (config.id, state)))
+            .collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        !self.effects.iter().any(|(config, _)| config.enabled)
+    }
+
+    pub fn process(&mut self, block: &mut [f32], channels: usize) {
+        if channels == 0 {
+            return;
+        }
+
+        for (_, effect) in self.effects.iter_mut().filter(|(config, _)| config.enabled) {
+            effect.process(block, channels);
+        }
+    }
+
+    pub fn reset(&mut self) {
+        for (_, effect) in self.effects.iter_mut() {
+            effect.reset();
+        }
+    }
+}
+
+/// Coefficient of a one-pole smoother reaching ~63% of a step in the given time.
+fn time_coefficient(time_ms: f32, sample_rate: f32) -> f32 {
+    if time_ms <= 0.0 {
+        0.0
+    } else {
+        (-1.0 / (time_ms * 0.001 * sample_rate)).exp()
+    }
+}
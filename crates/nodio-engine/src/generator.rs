@@ -0,0 +1,173 @@
+use std::f64::consts::TAU;
+
+use nodio_core::{db_to_gain, GeneratorConfig, Waveform};
+
+use crate::stream::AudioSource;
+
+/// Length of the tone of each channel with [`Waveform::ChannelId`], in seconds. The tones of the
+/// channels follow each other with a pause of the same length.
+pub const CHANNEL_ID_TONE_SECS: f64 = 0.5;
+
+/// Seed of the noise, so that every generator produces the same signal
+const NOISE_SEED: u32 = 0x9E37_79B9;
+
+/// Generates test signals: tones, noise, sweeps and channel identification.
+///
+/// The output only depends on the configuration and the number of samples read, which makes it
+/// usable for tests.
+pub struct SignalGenerator {
+    config: GeneratorConfig,
+    sample_rate: u32,
+    channels: usize,
+    /// Frames generated since the waveform was selected
+    frame: u64,
+    /// Phase of the tones in cycles, kept across frequency changes to avoid clicks
+    phase: f64,
+    noise: Noise,
+}
+
+impl SignalGenerator {
+    pub fn new(config: GeneratorConfig, sample_rate: u32, channels: usize) -> Self {
+        Self {
+            config,
+            sample_rate,
+            channels: channels.max(1),
+            frame: 0,
+            phase: 0.0,
+            noise: Noise::new(),
+        }
+    }
+
+    pub fn config(&self) -> &GeneratorConfig {
+        &self.config
+    }
+
+    pub fn set_config(&mut self, config: GeneratorConfig) {
+        if config.waveform != self.config.waveform {
+            self.frame = 0;
+            self.phase = 0.0;
+            self.noise = Noise::new();
+        }
+
+        self.config = config;
+    }
+
+    /// Fills the buffer with interleaved samples, returning the number of frames generated. The
+    /// buffer is silent when the generator is disabled.
+    pub fn read(&mut self, buffer: &mut [f32]) -> usize {
+        if !self.config.enabled {
+            buffer.iter_mut().for_each(|sample| *sample = 0.0);
+            return 0;
+        }
+
+        let sample_rate = self.sample_rate as f64;
+        let level = db_to_gain(self.config.level_db);
+        let frequency = (self.config.frequency_hz as f64).clamp(1.0, sample_rate / 2.0);
+
+        for frame in buffer.chunks_mut(self.channels) {
+            let time = self.frame as f64 / sample_rate;
+
+            match self.config.waveform {
+                Waveform::Sine => {
+                    let sample = self.tone(frequency) * level;
+                    frame.iter_mut().for_each(|out| *out = sample);
+                }
+                Waveform::WhiteNoise => {
+                    // Uniform noise has an RMS of 1/sqrt(3)
+                    let sample = self.noise.white() * level * 3f32.sqrt();
+                    frame.iter_mut().for_each(|out| *out = sample);
+                }
+                Waveform::PinkNoise => {
+                    let sample = self.noise.pink() * level;
+                    frame.iter_mut().for_each(|out| *out = sample);
+                }
+                Waveform::Sweep => {
+                    let end = (self.config.sweep_end_hz as f64).clamp(1.0, sample_rate / 2.0);
+                    let duration = (self.config.sweep_secs as f64).max(0.1);
+                    let progress = (time % duration) / duration;
+                    let frequency = frequency * (end / frequency).powf(progress);
+
+                    let sample = self.tone(frequency) * level;
+                    frame.iter_mut().for_each(|out| *out = sample);
+                }
+                Waveform::ChannelId => {
+                    let active = channel_id_active(time, frame.len());
+                    let sample = self.tone(frequency) * level;
+
+                    for (channel, out) in frame.iter_mut().enumerate() {
+                        *out = if active == Some(channel) { sample } else { 0.0 };
+                    }
+                }
+            }
+
+            self.frame += 1;
+        }
+
+        buffer.len() / self.channels
+    }
+
+    /// Next sample of a sine at the given frequency.
+    fn tone(&mut self, frequency: f64) -> f32 {
+        let sample = (self.phase * TAU).sin() as f32;
+        self.phase = (self.phase + frequency / self.sample_rate as f64).fract();
+        sample
+    }
+}
+
+impl AudioSource for SignalGenerator {
+    fn read(&mut self, buffer: &mut [f32]) -> usize {
+        SignalGenerator::read(self, buffer)
+    }
+}
+
+/// The channel that plays the tone at the given time with [`Waveform::ChannelId`], if any.
+pub fn channel_id_active(time_secs: f64, channels: usize) -> Option<usize> {
+    let slot = (time_secs / CHANNEL_ID_TONE_SECS) as usize % (channels * 2);
+    let (channel, pause) = (slot / 2, slot % 2);
+    (pause == 0).then_some(channel)
+}
+
+/// Deterministic noise source.
+struct Noise {
+    state: u32,
+    /// State of the pink noise filter
+    pink: [f32; 7],
+}
+
+impl Noise {
+    fn new() -> Self {
+        Self {
+            state: NOISE_SEED,
+            pink: [0.0; 7],
+        }
+    }
+
+    /// Uniform white noise in -1..1.
+    fn white(&mut self) -> f32 {
+        // xorshift32
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 17;
+        self.state ^= self.state << 5;
+
+        (self.state as f64 / u32::MAX as f64 * 2.0 - 1.0) as f32
+    }
+
+    /// Pink noise with an RMS of about 1, made by filtering white noise with Paul Kellet's
+    /// "refined" filter.
+    fn pink(&mut self) -> f32 {
+        let white = self.white();
+        let b = &mut self.pink;
+
+        b[0] = 0.99886 * b[0] + white * 0.0555179;
+        b[1] = 0.99332 * b[1] + white * 0.0750759;
+        b[2] = 0.96900 * b[2] + white * 0.153852;
+        b[3] = 0.86650 * b[3] + white * 0.3104856;
+        b[4] = 0.55000 * b[4] + white * 0.5329522;
+        b[5] = -0.7616 * b[5] - white * 0.0168980;
+        let pink = b[0] + b[1] + b[2] + b[3] + b[4] + b[5] + b[6] + white * 0.5362;
+        b[6] = white * 0.115926;
+
+        // The filter has a gain of about 3.5 for uniform noise with an RMS of 1/sqrt(3)
+        pink * 0.5
+    }
+}
@@ -0,0 +1,25 @@
+//! Platform independent audio processing used by the backends.
+#![deny(clippy::all)]
+mod calibration;
+mod delay;
+mod effects;
+mod generator;
+mod network;
+mod player;
+mod plugin;
+mod recorder;
+mod ring;
+mod stream;
+
+pub use calibration::Calibrator;
+pub use delay::{delay_frames, DelayLine};
+pub use effects::{create_effect, Effect, EffectChain};
+pub use generator::{channel_id_active, SignalGenerator, CHANNEL_ID_TONE_SECS};
+pub use network::{RtpReceiver, RtpSender, SessionDescription};
+pub use player::{decode_file, FilePlayer};
+pub use plugin::{
+    discover_plugins, discover_plugins_in, plugin_config, search_paths, PluginDescriptor,
+};
+pub use recorder::Recorder;
+pub use ring::{ring_buffer, Consumer, Producer};
+pub use stream::{AudioSink, AudioSource, BufferedSink};
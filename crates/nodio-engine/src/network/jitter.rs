@@ -0,0 +1,157 @@
+use std::collections::BTreeMap;
+
+/// Orders the received packets by their timestamp and holds them back by the latency, so that
+/// late and reordered packets can still be played.
+pub struct JitterBuffer {
+    channels: usize,
+    /// Frames buffered before playback starts
+    latency: u64,
+    /// Frames buffered beyond the latency before playback skips ahead, e.g. when the sender's
+    /// clock runs faster
+    max_excess: u64,
+    /// Samples of the packets by their unwrapped timestamp
+    packets: BTreeMap<u64, Vec<f32>>,
+    /// Unwrapped timestamp of the last packet received
+    last_timestamp: Option<u64>,
+    /// Timestamp of the next frame to play, `None` while buffering
+    position: Option<u64>,
+    /// Packets that were missing or late when they were due
+    lost: u64,
+}
+
+impl JitterBuffer {
+    pub fn new(channels: usize, latency: u64, max_excess: u64) -> Self {
+        Self {
+            channels,
+            latency,
+            max_excess,
+            packets: BTreeMap::new(),
+            last_timestamp: None,
+            position: None,
+            lost: 0,
+        }
+    }
+
+    pub fn push(&mut self, timestamp: u32, samples: Vec<f32>) {
+        let frames = (samples.len() / self.channels) as u64;
+        if frames == 0 {
+            return;
+        }
+
+        let timestamp = self.unwrap_timestamp(timestamp);
+
+        if let Some(position) = self.position {
+            if timestamp + frames <= position {
+                self.lost += 1;
+                return;
+            }
+        }
+
+        self.packets.insert(timestamp, samples);
+
+        if self.buffered() > self.latency + self.max_excess {
+            self.skip_to_latency();
+        }
+    }
+
+    /// Fills the buffer with the audio that is due, returning the number of frames that were
+    /// received. Missing audio is silence.
+    pub fn read(&mut self, buffer: &mut [f32]) -> usize {
+        buffer.iter_mut().for_each(|sample| *sample = 0.0);
+
+        let start = match self.position {
+            Some(position) => position,
+            None if !self.packets.is_empty() && self.buffered() >= self.latency => {
+                *self.packets.keys().next().unwrap()
+            }
+            None => return 0,
+        };
+
+        let channels = self.channels;
+        let end = start + (buffer.len() / channels) as u64;
+        // End of the audio written to the buffer so far
+        let mut cursor = start;
+        let mut count = 0;
+
+        while let Some((&timestamp, samples)) = self.packets.iter().next() {
+            let packet_end = timestamp + (samples.len() / channels) as u64;
+
+            if packet_end <= start {
+                self.packets.remove(&timestamp);
+                continue;
+            }
+            if timestamp >= end {
+                break;
+            }
+
+            if timestamp > cursor {
+                self.lost += 1;
+            }
+
+            let from = timestamp.max(start);
+            let to = packet_end.min(end);
+            let src =
+                ((from - timestamp) as usize * channels)..((to - timestamp) as usize * channels);
+            let dst = (from - start) as usize * channels;
+            buffer[dst..dst + src.len()].copy_from_slice(&samples[src]);
+
+            count += (to - from) as usize;
+            cursor = to;
+
+            if packet_end > end {
+                break;
+            }
+            self.packets.remove(&timestamp);
+        }
+
+        // The buffer ran dry, so it fills up to the latency again before playing on
+        self.position = (!self.packets.is_empty() || cursor == end).then_some(end);
+
+        count
+    }
+
+    /// Frames from the next frame to play to the end of the newest packet.
+    pub fn buffered(&self) -> u64 {
+        let first = match self.packets.iter().next() {
+            Some((&timestamp, _)) => self.position.unwrap_or(timestamp),
+            None => return 0,
+        };
+
+        let (&last, samples) = self.packets.iter().next_back().unwrap();
+        (last + (samples.len() / self.channels) as u64).saturating_sub(first)
+    }
+
+    pub fn lost(&self) -> u64 {
+        self.lost
+    }
+
+    /// Restarts playback at the current position of the stream.
+    pub fn clear(&mut self) {
+        self.packets.clear();
+        self.last_timestamp = None;
+        self.position = None;
+    }
+
+    fn skip_to_latency(&mut self) {
+        let (&last, samples) = self.packets.iter().next_back().unwrap();
+        let position = (last + (samples.len() / self.channels) as u64).saturating_sub(self.latency);
+
+        self.packets = self.packets.split_off(&position);
+        self.position = Some(position);
+    }
+
+    /// Extends the 32 bit timestamp, which wraps around, relative to the previous one.
+    fn unwrap_timestamp(&mut self, timestamp: u32) -> u64 {
+        let unwrapped = match self.last_timestamp {
+            // Starts high enough that earlier timestamps of reordered packets stay positive
+            None => (1 << 32) + timestamp as u64,
+            Some(last) => {
+                let delta = timestamp.wrapping_sub(last as u32) as i32;
+                last.wrapping_add(delta as i64 as u64)
+            }
+        };
+
+        self.last_timestamp = Some(unwrapped);
+        unwrapped
+    }
+}
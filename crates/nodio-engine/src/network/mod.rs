@@ -0,0 +1,9 @@
+mod jitter;
+mod receiver;
+mod rtp;
+mod sdp;
+mod sender;
+
+pub use receiver::RtpReceiver;
+pub use sdp::SessionDescription;
+pub use sender::RtpSender;
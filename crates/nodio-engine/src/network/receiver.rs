@@ -0,0 +1,253 @@
+use std::io::ErrorKind;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, ToSocketAddrs, UdpSocket};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use log::{info, warn};
+use nodio_core::{Error, NetworkConfig, NetworkStatus, Result, RtpPayload};
+use parking_lot::Mutex;
+
+use super::jitter::JitterBuffer;
+use super::rtp::{decode_samples, RtpHeader};
+use crate::stream::AudioSource;
+
+/// How long the network thread waits for packets before checking whether it should stop
+const RECEIVE_TIMEOUT: Duration = Duration::from_millis(50);
+/// Audio buffered beyond the latency before playback skips ahead, in seconds
+const MAX_EXCESS_SECS: f64 = 0.5;
+/// Largest packet that is received
+const MAX_PACKET_LEN: usize = 9000;
+
+/// Receives an RTP stream on a background thread. The stream must have the channels of the
+/// receiver and be sent at its sample rate.
+pub struct RtpReceiver {
+    config: NetworkConfig,
+    sample_rate: u32,
+    channels: usize,
+    shared: Arc<Shared>,
+    local_addr: Option<SocketAddr>,
+    thread: Option<JoinHandle<()>>,
+}
+
+/// State shared between the network thread, the audio thread and the UI.
+struct Shared {
+    jitter: Mutex<JitterBuffer>,
+    packets: AtomicU64,
+    stop: AtomicBool,
+    error: Mutex<Option<String>>,
+}
+
+impl Drop for RtpReceiver {
+    fn drop(&mut self) {
+        self.close();
+    }
+}
+
+impl RtpReceiver {
+    pub fn new(config: NetworkConfig, sample_rate: u32, channels: usize) -> Self {
+        let mut receiver = Self {
+            config,
+            sample_rate,
+            channels,
+            shared: Arc::new(Shared::new(channels, 0, 0)),
+            local_addr: None,
+            thread: None,
+        };
+        receiver.open();
+        receiver
+    }
+
+    pub fn set_config(&mut self, config: NetworkConfig) {
+        if config != self.config {
+            self.close();
+            self.config = config;
+            self.open();
+        }
+    }
+
+    /// Address the receiver listens on, `None` if the socket could not be opened.
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        self.local_addr
+    }
+
+    /// Fills the buffer with interleaved samples, returning the number of frames that were
+    /// received. The buffer is silent while the jitter buffer fills up.
+    pub fn read(&mut self, buffer: &mut [f32]) -> usize {
+        self.shared.jitter.lock().read(buffer)
+    }
+
+    pub fn status(&self) -> NetworkStatus {
+        let jitter = self.shared.jitter.lock();
+
+        NetworkStatus {
+            packets: self.shared.packets.load(Ordering::Relaxed),
+            lost: jitter.lost(),
+            buffered_ms: (jitter.buffered() as f64 * 1000.0 / self.sample_rate as f64) as f32,
+            error: self.shared.error.lock().clone(),
+            sdp: None,
+        }
+    }
+
+    fn open(&mut self) {
+        let latency = self.sample_rate as u64 * self.config.latency_ms as u64 / 1000;
+        let max_excess = (self.sample_rate as f64 * MAX_EXCESS_SECS) as u64;
+        self.shared = Arc::new(Shared::new(self.channels, latency, max_excess));
+
+        let socket = match open_socket(&self.config.address) {
+            Ok(socket) => socket,
+            Err(err) => {
+                warn!("Could not receive from {}: {}", self.config.address, err);
+                *self.shared.error.lock() = Some(err.to_string());
+                return;
+            }
+        };
+
+        self.local_addr = socket.local_addr().ok();
+        info!("Receiving on {}", self.config.address);
+
+        self.thread = Some(thread::spawn({
+            let shared = self.shared.clone();
+            let payload = self.config.payload;
+            let payload_type = self.config.rtp_payload_type();
+            let channels = self.channels;
+
+            move || receive(socket, &shared, payload, payload_type, channels)
+        }));
+    }
+
+    fn close(&mut self) {
+        self.shared.stop.store(true, Ordering::Relaxed);
+        self.local_addr = None;
+
+        if let Some(thread) = self.thread.take() {
+            thread.join().ok();
+        }
+    }
+}
+
+impl AudioSource for RtpReceiver {
+    fn read(&mut self, buffer: &mut [f32]) -> usize {
+        RtpReceiver::read(self, buffer)
+    }
+}
+
+impl Shared {
+    fn new(channels: usize, latency: u64, max_excess: u64) -> Self {
+        Self {
+            jitter: Mutex::new(JitterBuffer::new(channels, latency, max_excess)),
+            packets: AtomicU64::new(0),
+            stop: AtomicBool::new(false),
+            error: Mutex::new(None),
+        }
+    }
+}
+
+fn receive(
+    socket: UdpSocket,
+    shared: &Shared,
+    payload: RtpPayload,
+    payload_type: u8,
+    channels: usize,
+) {
+    let mut packet = vec![0; MAX_PACKET_LEN];
+    let mut ssrc = None;
+    // Packets of another payload type would be decoded as noise
+    let mut mismatched = false;
+
+    while !shared.stop.load(Ordering::Relaxed) {
+        let len = match socket.recv(&mut packet) {
+            Ok(len) => len,
+            Err(err) if matches!(err.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {
+                continue
+            }
+            Err(err) => {
+                warn!("Could not receive stream: {}", err);
+                *shared.error.lock() = Some(err.to_string());
+                break;
+            }
+        };
+
+        let (header, data) = match RtpHeader::parse(&packet[..len]) {
+            Some(packet) => packet,
+            None => continue,
+        };
+
+        if header.payload_type != payload_type {
+            if !mismatched {
+                warn!(
+                    "Dropping packets of payload type {}, expected {}",
+                    header.payload_type, payload_type
+                );
+                *shared.error.lock() = Some(format!(
+                    "The stream has payload type {}, not {} of {}",
+                    header.payload_type,
+                    payload_type,
+                    payload.name()
+                ));
+                mismatched = true;
+            }
+            continue;
+        }
+        if mismatched {
+            *shared.error.lock() = None;
+            mismatched = false;
+        }
+
+        let mut samples = Vec::with_capacity(data.len() / payload.bytes_per_sample());
+        decode_samples(payload, data, &mut samples);
+        samples.truncate(samples.len() - samples.len() % channels);
+
+        let mut jitter = shared.jitter.lock();
+
+        // A restarted sender starts a new timeline
+        if ssrc != Some(header.ssrc) {
+            jitter.clear();
+            ssrc = Some(header.ssrc);
+        }
+
+        jitter.push(header.timestamp, samples);
+        shared.packets.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Binds the address, joining its group if it is a multicast address.
+fn open_socket(address: &str) -> Result<UdpSocket> {
+    let address = address
+        .to_socket_addrs()
+        .ok()
+        .and_then(|mut addresses| addresses.next())
+        .ok_or_else(|| Error::Other(format!("Invalid address: {}", address)))?;
+    let io_error = |err: std::io::Error| Error::Other(err.to_string());
+
+    let socket = match address.ip() {
+        IpAddr::V4(group) if group.is_multicast() => {
+            let socket = UdpSocket::bind(SocketAddr::new(
+                Ipv4Addr::UNSPECIFIED.into(),
+                address.port(),
+            ))
+            .map_err(io_error)?;
+            socket
+                .join_multicast_v4(&group, &Ipv4Addr::UNSPECIFIED)
+                .map_err(io_error)?;
+            socket
+        }
+        IpAddr::V6(group) if group.is_multicast() => {
+            let socket = UdpSocket::bind(SocketAddr::new(
+                Ipv6Addr::UNSPECIFIED.into(),
+                address.port(),
+            ))
+            .map_err(io_error)?;
+            socket.join_multicast_v6(&group, 0).map_err(io_error)?;
+            socket
+        }
+        _ => UdpSocket::bind(address).map_err(io_error)?,
+    };
+
+    socket
+        .set_read_timeout(Some(RECEIVE_TIMEOUT))
+        .map_err(io_error)?;
+
+    Ok(socket)
+}
@@ -0,0 +1,87 @@
+use nodio_core::RtpPayload;
+
+const VERSION: u8 = 2;
+const HEADER_LEN: usize = 12;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct RtpHeader {
+    pub payload_type: u8,
+    pub sequence: u16,
+    /// Position of the first frame of the packet, in samples of the stream
+    pub timestamp: u32,
+    pub ssrc: u32,
+}
+
+impl RtpHeader {
+    pub fn write(&self, packet: &mut Vec<u8>) {
+        packet.push(VERSION << 6);
+        packet.push(self.payload_type & 0x7F);
+        packet.extend_from_slice(&self.sequence.to_be_bytes());
+        packet.extend_from_slice(&self.timestamp.to_be_bytes());
+        packet.extend_from_slice(&self.ssrc.to_be_bytes());
+    }
+
+    /// Splits a packet into its header and payload. Returns `None` for invalid packets.
+    pub fn parse(packet: &[u8]) -> Option<(RtpHeader, &[u8])> {
+        if packet.len() < HEADER_LEN || packet[0] >> 6 != VERSION {
+            return None;
+        }
+
+        let padding = packet[0] & 0x20 != 0;
+        let extension = packet[0] & 0x10 != 0;
+        let csrc_count = (packet[0] & 0x0F) as usize;
+
+        let header = RtpHeader {
+            payload_type: packet[1] & 0x7F,
+            sequence: u16::from_be_bytes([packet[2], packet[3]]),
+            timestamp: u32::from_be_bytes([packet[4], packet[5], packet[6], packet[7]]),
+            ssrc: u32::from_be_bytes([packet[8], packet[9], packet[10], packet[11]]),
+        };
+
+        let mut start = HEADER_LEN + csrc_count * 4;
+        if extension {
+            let words = packet.get(start + 2..start + 4)?;
+            start += 4 + u16::from_be_bytes([words[0], words[1]]) as usize * 4;
+        }
+
+        let mut end = packet.len();
+        if padding {
+            end = end.checked_sub(*packet.last()? as usize)?;
+        }
+
+        Some((header, packet.get(start..end)?))
+    }
+}
+
+/// Appends the samples to a packet as big endian PCM.
+pub fn encode_samples(payload: RtpPayload, samples: &[f32], packet: &mut Vec<u8>) {
+    for &sample in samples {
+        let sample = sample.clamp(-1.0, 1.0);
+
+        match payload {
+            RtpPayload::L16 => {
+                let value = (sample * i16::MAX as f32).round() as i16;
+                packet.extend_from_slice(&value.to_be_bytes());
+            }
+            RtpPayload::L24 => {
+                let value = (sample * 8_388_607.0).round() as i32;
+                packet.extend_from_slice(&value.to_be_bytes()[1..]);
+            }
+        }
+    }
+}
+
+/// Appends the samples of a packet payload.
+pub fn decode_samples(payload: RtpPayload, data: &[u8], samples: &mut Vec<f32>) {
+    match payload {
+        RtpPayload::L16 => samples.extend(
+            data.chunks_exact(2)
+                .map(|bytes| i16::from_be_bytes([bytes[0], bytes[1]]) as f32 / 32768.0),
+        ),
+        RtpPayload::L24 => samples.extend(data.chunks_exact(3).map(|bytes| {
+            // Sign extended by shifting back from the top byte
+            let value = i32::from_be_bytes([bytes[0], bytes[1], bytes[2], 0]) >> 8;
+            value as f32 / 8_388_608.0
+        })),
+    }
+}
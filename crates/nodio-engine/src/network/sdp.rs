@@ -0,0 +1,191 @@
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, UdpSocket};
+
+use nodio_core::{Error, Result, RtpPayload};
+
+/// Multicast group and port of SAP announcements, as used by AES67 devices
+pub const SAP_ADDRESS: SocketAddr =
+    SocketAddr::new(IpAddr::V4(Ipv4Addr::new(239, 255, 255, 255)), 9875);
+
+/// Hop limit of multicast streams
+pub const MULTICAST_TTL: u32 = 16;
+
+/// What a receiver needs to know about a stream, as carried by SDP.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SessionDescription {
+    pub name: String,
+    /// Address of the sender
+    pub origin: IpAddr,
+    /// Address the stream is sent to
+    pub address: SocketAddr,
+    pub payload: RtpPayload,
+    /// RTP payload type of the packets
+    pub payload_type: u8,
+    pub sample_rate: u32,
+    pub channels: usize,
+    /// Duration of the audio in a packet
+    pub packet_ms: f32,
+}
+
+impl SessionDescription {
+    /// Writes the description as SDP, with the attributes AES67 devices expect.
+    pub fn to_sdp(&self, session_id: u32) -> String {
+        let connection = if self.address.ip().is_multicast() {
+            format!("{}/{}", self.address.ip(), MULTICAST_TTL)
+        } else {
+            self.address.ip().to_string()
+        };
+
+        [
+            "v=0".to_string(),
+            format!(
+                "o=- {} {} IN {} {}",
+                session_id,
+                session_id,
+                ip_version(self.origin),
+                self.origin
+            ),
+            format!("s={}", self.name),
+            format!("c=IN {} {}", ip_version(self.address.ip()), connection),
+            "t=0 0".to_string(),
+            format!(
+                "m=audio {} RTP/AVP {}",
+                self.address.port(),
+                self.payload_type
+            ),
+            format!(
+                "a=rtpmap:{} {}/{}/{}",
+                self.payload_type,
+                self.payload.name(),
+                self.sample_rate,
+                self.channels
+            ),
+            format!("a=ptime:{}", self.packet_ms),
+            "a=mediaclk:direct=0".to_string(),
+            String::new(),
+        ]
+        .join("\r\n")
+    }
+
+    /// Reads the first audio stream of an SDP session description.
+    pub fn parse(sdp: &str) -> Result<Self> {
+        let invalid = |what: &str| Error::Other(format!("Invalid session description: {}", what));
+
+        let mut name = String::new();
+        let mut origin = None;
+        let mut connection = None;
+        let mut media = None;
+        let mut rtpmap = None;
+        let mut packet_ms = 1.0;
+
+        for line in sdp.lines().map(str::trim) {
+            let (kind, value) = match line.split_once('=') {
+                Some(pair) => pair,
+                None => continue,
+            };
+
+            match kind {
+                "s" => name = value.to_string(),
+                "o" => origin = value.split_whitespace().nth(5).map(str::to_string),
+                "c" if connection.is_none() || media.is_some() => {
+                    connection = value
+                        .split_whitespace()
+                        .nth(2)
+                        .map(|address| address.split('/').next().unwrap_or_default().to_string())
+                }
+                "m" if media.is_none() => {
+                    let fields = value.split_whitespace().collect::<Vec<_>>();
+                    if let ["audio", port, _, payload_type, ..] = fields[..] {
+                        media = Some((port.to_string(), payload_type.to_string()));
+                    }
+                }
+                "a" if media.is_some() => {
+                    if let Some(map) = value.strip_prefix("rtpmap:") {
+                        let (payload_type, format) = map.split_once(' ').unwrap_or_default();
+                        if Some(payload_type) == media.as_ref().map(|(_, pt)| pt.as_str()) {
+                            rtpmap = Some(format.to_string());
+                        }
+                    } else if let Some(ptime) = value.strip_prefix("ptime:") {
+                        packet_ms = ptime.trim().parse().map_err(|_| invalid("ptime"))?;
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let (port, payload_type) = media.ok_or_else(|| invalid("no audio stream"))?;
+        let port: u16 = port.parse().map_err(|_| invalid("port"))?;
+        let payload_type = payload_type.parse().map_err(|_| invalid("payload type"))?;
+        let ip: IpAddr = connection
+            .ok_or_else(|| invalid("no connection address"))?
+            .parse()
+            .map_err(|_| invalid("connection address"))?;
+        let origin = origin.and_then(|origin| origin.parse().ok()).unwrap_or(ip);
+
+        let rtpmap = rtpmap.ok_or_else(|| invalid("no rtpmap"))?;
+        let mut format = rtpmap.split('/');
+        let payload = format
+            .next()
+            .and_then(RtpPayload::from_name)
+            .ok_or_else(|| Error::Other(format!("Unsupported payload: {}", rtpmap)))?;
+        let sample_rate = format
+            .next()
+            .and_then(|rate| rate.parse().ok())
+            .ok_or_else(|| invalid("sample rate"))?;
+        let channels = match format.next() {
+            Some(channels) => channels.parse().map_err(|_| invalid("channels"))?,
+            None => 1,
+        };
+
+        Ok(Self {
+            name,
+            origin,
+            address: SocketAddr::new(ip, port),
+            payload,
+            payload_type,
+            sample_rate,
+            channels,
+            packet_ms,
+        })
+    }
+}
+
+fn ip_version(ip: IpAddr) -> &'static str {
+    match ip {
+        IpAddr::V4(_) => "IP4",
+        IpAddr::V6(_) => "IP6",
+    }
+}
+
+/// Address of the interface that packets to the destination are sent from.
+pub fn local_ip_for(destination: SocketAddr) -> IpAddr {
+    let unspecified = match destination {
+        SocketAddr::V4(_) => IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+        SocketAddr::V6(_) => IpAddr::V6(std::net::Ipv6Addr::UNSPECIFIED),
+    };
+
+    // Connecting a UDP socket only selects the route, nothing is sent
+    UdpSocket::bind(SocketAddr::new(unspecified, 0))
+        .and_then(|socket| {
+            socket.connect(destination)?;
+            socket.local_addr()
+        })
+        .map_or(unspecified, |address| address.ip())
+}
+
+/// SAP packet announcing a session, or deleting the announcement.
+pub fn sap_packet(sdp: &str, origin: Ipv4Addr, message_id: u16, delete: bool) -> Vec<u8> {
+    const MIME_TYPE: &[u8] = b"application/sdp\0";
+
+    // Version 1, IPv4 origin, no authentication, no encryption or compression
+    let mut flags = 1 << 5;
+    if delete {
+        flags |= 1 << 2;
+    }
+
+    let mut packet = vec![flags, 0];
+    packet.extend_from_slice(&message_id.to_be_bytes());
+    packet.extend_from_slice(&origin.octets());
+    packet.extend_from_slice(MIME_TYPE);
+    packet.extend_from_slice(sdp.as_bytes());
+    packet
+}
@@ -0,0 +1,263 @@
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, ToSocketAddrs, UdpSocket};
+use std::time::{Duration, Instant};
+
+use log::{info, warn};
+use nodio_core::{Error, NetworkConfig, NetworkStatus, Result, Uuid};
+
+use super::rtp::{encode_samples, RtpHeader};
+use super::sdp::{local_ip_for, sap_packet, SessionDescription, MULTICAST_TTL, SAP_ADDRESS};
+use crate::stream::AudioSink;
+
+/// Duration of the audio in a packet, the default of AES67
+const PACKET_MS: u32 = 1;
+/// How often the stream is announced
+const SAP_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Sends the audio written to it as an RTP stream.
+pub struct RtpSender {
+    config: NetworkConfig,
+    name: String,
+    sample_rate: u32,
+    channels: usize,
+    stream: Option<Stream>,
+    error: Option<String>,
+    /// Samples of the next packet
+    pending: Vec<f32>,
+    packet: Vec<u8>,
+    packets: u64,
+}
+
+struct Stream {
+    socket: UdpSocket,
+    destination: SocketAddr,
+    /// Address of the interface the stream is sent from
+    origin: IpAddr,
+    ssrc: u32,
+    sequence: u16,
+    timestamp: u32,
+    last_announcement: Option<Instant>,
+}
+
+impl Drop for RtpSender {
+    fn drop(&mut self) {
+        self.close();
+    }
+}
+
+impl RtpSender {
+    pub fn new(config: NetworkConfig, name: &str, sample_rate: u32, channels: usize) -> Self {
+        let mut sender = Self {
+            config,
+            name: name.to_string(),
+            sample_rate,
+            channels,
+            stream: None,
+            error: None,
+            pending: Vec::new(),
+            packet: Vec::new(),
+            packets: 0,
+        };
+        sender.open();
+        sender
+    }
+
+    pub fn set_config(&mut self, config: NetworkConfig) {
+        if config != self.config {
+            self.close();
+            self.config = config;
+            self.open();
+        }
+    }
+
+    /// Sets the name the stream is announced with.
+    pub fn set_name(&mut self, name: &str) {
+        if name != self.name {
+            self.name = name.to_string();
+
+            if let Some(stream) = &mut self.stream {
+                stream.last_announcement = None;
+            }
+        }
+    }
+
+    /// Describes the stream as sent, `None` if it can not be sent.
+    pub fn description(&self) -> Option<SessionDescription> {
+        self.stream.as_ref().map(|stream| SessionDescription {
+            name: self.name.clone(),
+            origin: stream.origin,
+            address: stream.destination,
+            payload: self.config.payload,
+            payload_type: self.config.rtp_payload_type(),
+            sample_rate: self.sample_rate,
+            channels: self.channels,
+            packet_ms: PACKET_MS as f32,
+        })
+    }
+
+    pub fn write(&mut self, mut samples: &[f32]) {
+        if self.stream.is_none() {
+            return;
+        }
+
+        let packet_len = (self.sample_rate * PACKET_MS / 1000) as usize * self.channels;
+
+        while !samples.is_empty() {
+            let len = (packet_len - self.pending.len()).min(samples.len());
+            self.pending.extend_from_slice(&samples[..len]);
+            samples = &samples[len..];
+
+            if self.pending.len() == packet_len {
+                if let Err(err) = self.send_packet() {
+                    warn!("Could not send stream {}: {}", self.name, err);
+                    self.error = Some(err.to_string());
+                    self.stream = None;
+                    return;
+                }
+            }
+        }
+
+        self.announce_if_due();
+    }
+
+    pub fn status(&self) -> NetworkStatus {
+        NetworkStatus {
+            packets: self.packets,
+            error: self.error.clone(),
+            sdp: self.stream.as_ref().and_then(|stream| {
+                self.description()
+                    .map(|description| description.to_sdp(stream.ssrc))
+            }),
+            ..NetworkStatus::default()
+        }
+    }
+
+    fn open(&mut self) {
+        self.pending.clear();
+
+        match open_socket(&self.config.address) {
+            Ok((socket, destination)) => {
+                info!("Sending {} to {}", self.name, destination);
+
+                self.stream = Some(Stream {
+                    socket,
+                    destination,
+                    origin: local_ip_for(destination),
+                    ssrc: Uuid::new_v4().as_u128() as u32,
+                    sequence: 0,
+                    timestamp: 0,
+                    last_announcement: None,
+                });
+                self.error = None;
+            }
+            Err(err) => {
+                warn!(
+                    "Could not send {} to {}: {}",
+                    self.name, self.config.address, err
+                );
+                self.error = Some(err.to_string());
+            }
+        }
+    }
+
+    fn close(&mut self) {
+        let stream = match self.stream.take() {
+            Some(stream) => stream,
+            None => return,
+        };
+
+        if stream.last_announcement.is_some() {
+            if let Some(packet) = self.sap_packet(&stream, true) {
+                stream.socket.send_to(&packet, SAP_ADDRESS).ok();
+            }
+        }
+    }
+
+    fn send_packet(&mut self) -> std::io::Result<()> {
+        let stream = self.stream.as_mut().unwrap();
+
+        self.packet.clear();
+        RtpHeader {
+            payload_type: self.config.rtp_payload_type(),
+            sequence: stream.sequence,
+            timestamp: stream.timestamp,
+            ssrc: stream.ssrc,
+        }
+        .write(&mut self.packet);
+        encode_samples(self.config.payload, &self.pending, &mut self.packet);
+
+        stream.socket.send_to(&self.packet, stream.destination)?;
+
+        stream.sequence = stream.sequence.wrapping_add(1);
+        stream.timestamp = stream
+            .timestamp
+            .wrapping_add((self.pending.len() / self.channels) as u32);
+        self.pending.clear();
+        self.packets += 1;
+
+        Ok(())
+    }
+
+    fn announce_if_due(&mut self) {
+        let stream = match &self.stream {
+            Some(stream) if self.config.announce => stream,
+            _ => return,
+        };
+
+        if matches!(stream.last_announcement, Some(last) if last.elapsed() < SAP_INTERVAL) {
+            return;
+        }
+
+        if let Some(packet) = self.sap_packet(stream, false) {
+            if let Err(err) = stream.socket.send_to(&packet, SAP_ADDRESS) {
+                warn!("Could not announce stream {}: {}", self.name, err);
+            }
+        }
+
+        if let Some(stream) = &mut self.stream {
+            stream.last_announcement = Some(Instant::now());
+        }
+    }
+
+    /// SAP only announces streams of IPv4 senders.
+    fn sap_packet(&self, stream: &Stream, delete: bool) -> Option<Vec<u8>> {
+        let description = self.description()?;
+        let origin = match description.origin {
+            IpAddr::V4(origin) => origin,
+            IpAddr::V6(_) => return None,
+        };
+
+        let sdp = description.to_sdp(stream.ssrc);
+        Some(sap_packet(&sdp, origin, stream.ssrc as u16, delete))
+    }
+}
+
+impl AudioSink for RtpSender {
+    fn write(&mut self, samples: &[f32]) {
+        RtpSender::write(self, samples);
+    }
+}
+
+/// Resolves the destination and opens a socket for sending to it.
+fn open_socket(address: &str) -> Result<(UdpSocket, SocketAddr)> {
+    let destination = address
+        .to_socket_addrs()
+        .ok()
+        .and_then(|mut addresses| addresses.next())
+        .ok_or_else(|| Error::Other(format!("Invalid address: {}", address)))?;
+
+    let unspecified = match destination {
+        SocketAddr::V4(_) => IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+        SocketAddr::V6(_) => IpAddr::V6(Ipv6Addr::UNSPECIFIED),
+    };
+
+    let socket = UdpSocket::bind(SocketAddr::new(unspecified, 0))
+        .map_err(|err| Error::Other(err.to_string()))?;
+
+    if destination.ip().is_multicast() && destination.is_ipv4() {
+        socket
+            .set_multicast_ttl_v4(MULTICAST_TTL)
+            .map_err(|err| Error::Other(err.to_string()))?;
+    }
+
+    Ok((socket, destination))
+}
@@ -0,0 +1,263 @@
+use std::fs::File;
+use std::path::Path;
+
+use log::warn;
+use nodio_core::{Error, Result};
+use symphonia::core::audio::SampleBuffer;
+use symphonia::core::codecs::{Decoder, DecoderOptions, CODEC_TYPE_NULL};
+use symphonia::core::errors::Error as DecodeError;
+use symphonia::core::formats::{FormatOptions, FormatReader, SeekMode, SeekTo};
+use symphonia::core::io::MediaSourceStream;
+use symphonia::core::meta::MetadataOptions;
+use symphonia::core::probe::Hint;
+use symphonia::core::units::{Time, TimeBase};
+
+/// Decodes the whole file into interleaved samples with the given sample rate and channels.
+pub fn decode_file(path: &Path, sample_rate: u32, channels: usize) -> Result<Vec<f32>> {
+    let mut decoder = TrackDecoder::open(path, sample_rate, channels)?;
+    let mut samples = Vec::new();
+
+    while decoder.decode_into(&mut samples)? {}
+
+    Ok(samples)
+}
+
+/// Decodes an audio file packet by packet, converting it to the output format.
+pub struct TrackDecoder {
+    format: Box<dyn FormatReader>,
+    decoder: Box<dyn Decoder>,
+    track_id: u32,
+    time_base: Option<TimeBase>,
+    sample_rate: u32,
+    channels: usize,
+    out_channels: usize,
+    /// Length in frames of the source
+    frames: Option<u64>,
+    /// Frames to drop after seeking, to reach the exact position
+    skip_frames: u64,
+    resampler: Resampler,
+    sample_buf: Option<SampleBuffer<f32>>,
+    mapped: Vec<f32>,
+}
+
+impl TrackDecoder {
+    pub fn open(path: &Path, out_sample_rate: u32, out_channels: usize) -> Result<Self> {
+        let file = File::open(path).map_err(|err| Error::Other(err.to_string()))?;
+        let stream = MediaSourceStream::new(Box::new(file), Default::default());
+
+        let mut hint = Hint::new();
+        if let Some(extension) = path.extension().and_then(|ext| ext.to_str()) {
+            hint.with_extension(extension);
+        }
+
+        let probed = symphonia::default::get_probe()
+            .format(
+                &hint,
+                stream,
+                &FormatOptions::default(),
+                &MetadataOptions::default(),
+            )
+            .map_err(decode_error)?;
+
+        let format = probed.format;
+        let track = format
+            .tracks()
+            .iter()
+            .find(|track| track.codec_params.codec != CODEC_TYPE_NULL)
+            .ok_or_else(|| Error::Other("No audio track found".to_string()))?;
+
+        let params = &track.codec_params;
+        let sample_rate = params
+            .sample_rate
+            .ok_or_else(|| Error::Other("Unknown sample rate".to_string()))?;
+        let channels = params.channels.map_or(1, |channels| channels.count());
+
+        let decoder = symphonia::default::get_codecs()
+            .make(params, &DecoderOptions::default())
+            .map_err(decode_error)?;
+
+        Ok(Self {
+            track_id: track.id,
+            time_base: params.time_base,
+            sample_rate,
+            channels,
+            out_channels,
+            frames: params.n_frames,
+            skip_frames: 0,
+            resampler: Resampler::new(sample_rate, out_sample_rate, out_channels),
+            sample_buf: None,
+            mapped: Vec::new(),
+            format,
+            decoder,
+        })
+    }
+
+    pub fn duration_secs(&self) -> Option<f64> {
+        self.frames
+            .map(|frames| frames as f64 / self.sample_rate as f64)
+    }
+
+    /// Decodes the next packet, appending the converted samples to `out`.
+    /// Returns `false` at the end of the file.
+    pub fn decode_into(&mut self, out: &mut Vec<f32>) -> Result<bool> {
+        loop {
+            let packet = match self.format.next_packet() {
+                Ok(packet) => packet,
+                Err(DecodeError::IoError(err))
+                    if err.kind() == std::io::ErrorKind::UnexpectedEof =>
+                {
+                    return Ok(false)
+                }
+                Err(DecodeError::ResetRequired) => return Ok(false),
+                Err(err) => return Err(decode_error(err)),
+            };
+
+            if packet.track_id() != self.track_id {
+                continue;
+            }
+
+            let decoded = match self.decoder.decode(&packet) {
+                Ok(decoded) => decoded,
+                Err(DecodeError::DecodeError(err)) => {
+                    warn!("Skipping malformed packet: {}", err);
+                    continue;
+                }
+                Err(err) => return Err(decode_error(err)),
+            };
+
+            let spec = *decoded.spec();
+            let sample_buf = match &mut self.sample_buf {
+                Some(buf) if buf.capacity() >= decoded.capacity() * spec.channels.count() => buf,
+                sample_buf => sample_buf.insert(SampleBuffer::new(decoded.capacity() as u64, spec)),
+            };
+            sample_buf.copy_interleaved_ref(decoded);
+
+            let mut samples = sample_buf.samples();
+            if self.skip_frames > 0 {
+                let frames = (samples.len() / self.channels) as u64;
+                let skipped = self.skip_frames.min(frames);
+                self.skip_frames -= skipped;
+                samples = &samples[skipped as usize * self.channels..];
+            }
+
+            map_channels(samples, self.channels, self.out_channels, &mut self.mapped);
+            self.resampler.process(&self.mapped, out);
+
+            return Ok(true);
+        }
+    }
+
+    /// Moves to the given position, returning the position reached in seconds.
+    pub fn seek(&mut self, secs: f64) -> Result<f64> {
+        let seeked = self
+            .format
+            .seek(
+                SeekMode::Accurate,
+                SeekTo::Time {
+                    time: Time::from(secs.max(0.0)),
+                    track_id: Some(self.track_id),
+                },
+            )
+            .map_err(decode_error)?;
+
+        self.decoder.reset();
+        self.resampler.reset();
+        self.skip_frames = seeked.required_ts.saturating_sub(seeked.actual_ts);
+
+        Ok(match self.time_base {
+            Some(time_base) => {
+                let time = time_base.calc_time(seeked.required_ts);
+                time.seconds as f64 + time.frac
+            }
+            None => seeked.required_ts as f64 / self.sample_rate as f64,
+        })
+    }
+}
+
+fn decode_error(err: DecodeError) -> Error {
+    Error::Other(err.to_string())
+}
+
+/// Converts interleaved samples to another channel count. Mono is copied to all channels
+/// and downmixing to mono averages the channels; otherwise channels are matched by index.
+fn map_channels(input: &[f32], channels: usize, out_channels: usize, out: &mut Vec<f32>) {
+    out.clear();
+
+    if channels == out_channels {
+        out.extend_from_slice(input);
+        return;
+    }
+
+    for frame in input.chunks_exact(channels) {
+        if out_channels == 1 {
+            out.push(frame.iter().sum::<f32>() / channels as f32);
+        } else {
+            out.extend((0..out_channels).map(|ch| frame[ch % channels]));
+        }
+    }
+}
+
+/// Linear interpolation sample rate converter.
+struct Resampler {
+    /// Source frames per output frame
+    step: f64,
+    channels: usize,
+    /// Position of the next output frame, relative to the last frame of the previous block
+    pos: f64,
+    last_frame: Vec<f32>,
+}
+
+impl Resampler {
+    fn new(in_rate: u32, out_rate: u32, channels: usize) -> Self {
+        Self {
+            step: in_rate as f64 / out_rate as f64,
+            channels,
+            pos: 1.0,
+            last_frame: vec![0.0; channels],
+        }
+    }
+
+    fn reset(&mut self) {
+        self.pos = 1.0;
+        self.last_frame.iter_mut().for_each(|sample| *sample = 0.0);
+    }
+
+    fn process(&mut self, input: &[f32], out: &mut Vec<f32>) {
+        if self.step == 1.0 {
+            out.extend_from_slice(input);
+            return;
+        }
+
+        let channels = self.channels;
+        let frames = input.len() / channels;
+        if frames == 0 {
+            return;
+        }
+
+        // Frame 0 is the last frame of the previous block, frame i + 1 is input frame i
+        let sample = |frame: usize, ch: usize| {
+            if frame == 0 {
+                self.last_frame[ch]
+            } else {
+                input[(frame - 1) * channels + ch]
+            }
+        };
+
+        while self.pos < frames as f64 {
+            let idx = self.pos as usize;
+            let frac = (self.pos - idx as f64) as f32;
+
+            for ch in 0..channels {
+                let a = sample(idx, ch);
+                let b = sample(idx + 1, ch);
+                out.push(a + (b - a) * frac);
+            }
+
+            self.pos += self.step;
+        }
+
+        self.pos -= frames as f64;
+        self.last_frame
+            .copy_from_slice(&input[(frames - 1) * channels..frames * channels]);
+    }
+}
@@ -0,0 +1,560 @@
+mod decoder;
+
+use std::collections::{HashMap, VecDeque};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use log::warn;
+use nodio_core::{
+    db_to_gain, next_track, Clip, LoopMode, PlayerCommand, PlayerConfig, PlayerState, PlayerStatus,
+    Result, Uuid,
+};
+use parking_lot::Mutex;
+
+use crate::ring::{ring_buffer, Consumer, Producer};
+
+pub use decoder::decode_file;
+use decoder::TrackDecoder;
+
+/// Length of the decoded audio buffered ahead of playback, in seconds
+const BUFFER_SECS: f64 = 0.5;
+/// How long the decoder waits for commands while the buffer is full
+const DECODER_WAIT: Duration = Duration::from_millis(5);
+
+/// Plays the files of a playlist. The files are decoded on a background thread into a ring
+/// buffer, which is read by the audio thread with [`FilePlayer::read`] without taking any lock.
+///
+/// Clips are decoded as a whole and mixed on top of the playlist, any number at a time. The
+/// decoded clips are handed to the reader through a channel.
+pub struct FilePlayer {
+    channels: usize,
+    consumer: Consumer,
+    /// Clips being played
+    voices: Vec<Voice>,
+    /// Clips decoded to be played
+    new_voices: Receiver<Voice>,
+    shared: Arc<Shared>,
+    sender: Sender<Message>,
+    thread: Option<JoinHandle<()>>,
+}
+
+enum Message {
+    Command(PlayerCommand),
+    Config(PlayerConfig),
+    Clip(Clip),
+    Quit,
+}
+
+/// State shared between the audio thread, the decoder thread and the UI.
+struct Shared {
+    sample_rate: u32,
+    channels: usize,
+    paused: AtomicBool,
+    /// Linear gain, as the bits of an `f32`
+    gain: AtomicU32,
+    /// Samples taken from the ring buffer, played or skipped
+    consumed: AtomicU64,
+    /// Samples before this position are outdated and skipped by the reader
+    flush_to: AtomicU64,
+    /// What is played from which sample on, oldest first. Shared by the decoder and the UI.
+    segments: Mutex<VecDeque<Segment>>,
+}
+
+struct Voice {
+    clip_id: Uuid,
+    choke_group: Option<u32>,
+    samples: Arc<[f32]>,
+    /// Next sample to play
+    pos: usize,
+    gain: f32,
+}
+
+#[derive(Debug, Copy, Clone)]
+struct Segment {
+    /// Sample position in the stream of the ring buffer
+    start: u64,
+    /// `None` when playback stopped
+    track: Option<usize>,
+    /// Position in the track at `start`, in seconds
+    position: f64,
+    duration: Option<f64>,
+}
+
+impl Drop for FilePlayer {
+    fn drop(&mut self) {
+        self.sender.send(Message::Quit).ok();
+
+        if let Some(thread) = self.thread.take() {
+            thread.join().ok();
+        }
+    }
+}
+
+impl FilePlayer {
+    pub fn new(config: PlayerConfig, sample_rate: u32, channels: usize) -> Self {
+        let capacity = (sample_rate as f64 * BUFFER_SECS) as usize * channels;
+        let (producer, consumer) = ring_buffer(capacity);
+
+        let shared = Arc::new(Shared {
+            sample_rate,
+            channels,
+            paused: AtomicBool::new(false),
+            gain: AtomicU32::new(db_to_gain(config.gain_db).to_bits()),
+            consumed: AtomicU64::new(0),
+            flush_to: AtomicU64::new(0),
+            segments: Mutex::new(VecDeque::new()),
+        });
+
+        let (sender, receiver) = mpsc::channel();
+        let (voice_sender, new_voices) = mpsc::channel();
+
+        let thread = thread::spawn({
+            let shared = shared.clone();
+            move || {
+                DecoderThread {
+                    config,
+                    shared,
+                    producer,
+                    receiver,
+                    voices: voice_sender,
+                    decoder: None,
+                    track: None,
+                    produced: 0,
+                    pending: Vec::new(),
+                    pending_offset: 0,
+                    clips: HashMap::new(),
+                }
+                .run()
+            }
+        });
+
+        Self {
+            channels,
+            consumer,
+            voices: Vec::new(),
+            new_voices,
+            shared,
+            sender,
+            thread: Some(thread),
+        }
+    }
+
+    pub fn set_config(&mut self, config: PlayerConfig) {
+        self.shared
+            .gain
+            .store(db_to_gain(config.gain_db).to_bits(), Ordering::Relaxed);
+        self.sender.send(Message::Config(config)).ok();
+    }
+
+    pub fn command(&mut self, command: PlayerCommand) {
+        match command {
+            PlayerCommand::Pause => {
+                self.shared.paused.store(true, Ordering::Relaxed);
+                return;
+            }
+            PlayerCommand::Play | PlayerCommand::Stop | PlayerCommand::PlayTrack(_) => {
+                self.shared.paused.store(false, Ordering::Relaxed);
+            }
+            _ => {}
+        }
+
+        self.sender.send(Message::Command(command)).ok();
+    }
+
+    /// Starts playing a clip. A clip with a choke group stops the clips of the same group.
+    pub fn play_clip(&mut self, clip: &Clip) {
+        self.sender.send(Message::Clip(clip.clone())).ok();
+    }
+
+    pub fn stop_clips(&mut self) {
+        self.new_voices.try_iter().for_each(drop);
+        self.voices.clear();
+    }
+
+    pub fn playing_clips(&mut self) -> Vec<Uuid> {
+        self.take_new_voices();
+
+        let mut clip_ids: Vec<Uuid> = Vec::new();
+
+        for voice in self.voices.iter() {
+            if !clip_ids.contains(&voice.clip_id) {
+                clip_ids.push(voice.clip_id);
+            }
+        }
+
+        clip_ids
+    }
+
+    /// Fills the buffer with interleaved samples, returning the number of frames taken from the
+    /// files and clips. The rest of the buffer is silence.
+    pub fn read(&mut self, buffer: &mut [f32]) -> usize {
+        let shared = &self.shared;
+
+        let mut consumed = shared.consumed.load(Ordering::Acquire);
+        let flush_to = shared.flush_to.load(Ordering::Acquire);
+        if consumed < flush_to {
+            consumed += self.consumer.skip((flush_to - consumed) as usize) as u64;
+        }
+
+        let mut count = 0;
+        if !shared.paused.load(Ordering::Relaxed) {
+            let len = buffer.len() - buffer.len() % self.channels;
+            count = self.consumer.pop(&mut buffer[..len]);
+            consumed += count as u64;
+
+            let gain = f32::from_bits(shared.gain.load(Ordering::Relaxed));
+            if gain != 1.0 {
+                buffer[..count]
+                    .iter_mut()
+                    .for_each(|sample| *sample *= gain);
+            }
+        }
+
+        buffer[count..].iter_mut().for_each(|sample| *sample = 0.0);
+        shared.consumed.store(consumed, Ordering::Release);
+
+        self.take_new_voices();
+        for voice in self.voices.iter_mut() {
+            let samples = &voice.samples[voice.pos..];
+            let len = samples.len().min(buffer.len());
+
+            for (out, sample) in buffer[..len].iter_mut().zip(samples) {
+                *out += sample * voice.gain;
+            }
+
+            voice.pos += len;
+            count = count.max(len);
+        }
+
+        self.voices.retain(|voice| voice.pos < voice.samples.len());
+
+        count / self.channels
+    }
+
+    /// Starts playing the clips the decoder has handed over.
+    fn take_new_voices(&mut self) {
+        for voice in self.new_voices.try_iter() {
+            if let Some(group) = voice.choke_group {
+                self.voices.retain(|other| other.choke_group != Some(group));
+            }
+            self.voices.push(voice);
+        }
+    }
+
+    pub fn status(&self) -> PlayerStatus {
+        let shared = &self.shared;
+        let mut segments = shared.segments.lock();
+
+        let consumed = shared.consumed.load(Ordering::Acquire);
+        let position = consumed.max(shared.flush_to.load(Ordering::Acquire));
+
+        // Segments that started before the current one are no longer needed
+        while segments.len() > 1 && segments[1].start <= position {
+            segments.pop_front();
+        }
+
+        let segment = match segments.front() {
+            Some(segment) if segment.start <= position => *segment,
+            _ => return PlayerStatus::default(),
+        };
+
+        let track = match segment.track {
+            Some(track) => track,
+            None => return PlayerStatus::default(),
+        };
+
+        let samples_per_sec = shared.sample_rate as f64 * shared.channels as f64;
+
+        PlayerStatus {
+            state: if shared.paused.load(Ordering::Relaxed) {
+                PlayerState::Paused
+            } else {
+                PlayerState::Playing
+            },
+            track: Some(track),
+            position_secs: segment.position + (position - segment.start) as f64 / samples_per_sec,
+            duration_secs: segment.duration,
+        }
+    }
+}
+
+struct DecoderThread {
+    config: PlayerConfig,
+    shared: Arc<Shared>,
+    producer: Producer,
+    receiver: Receiver<Message>,
+    voices: Sender<Voice>,
+    decoder: Option<TrackDecoder>,
+    track: Option<usize>,
+    /// Samples pushed into the ring buffer
+    produced: u64,
+    /// Decoded samples that did not fit into the ring buffer yet
+    pending: Vec<f32>,
+    pending_offset: usize,
+    /// Decoded clips by path, so that they start without delay when played again
+    clips: HashMap<String, Arc<[f32]>>,
+}
+
+impl DecoderThread {
+    fn run(mut self) {
+        loop {
+            let message = if self.decoder.is_none() && self.pending_offset >= self.pending.len() {
+                match self.receiver.recv() {
+                    Ok(message) => Some(message),
+                    Err(_) => return,
+                }
+            } else if self.fill() {
+                match self.receiver.try_recv() {
+                    Ok(message) => Some(message),
+                    Err(mpsc::TryRecvError::Empty) => None,
+                    Err(mpsc::TryRecvError::Disconnected) => return,
+                }
+            } else {
+                match self.receiver.recv_timeout(DECODER_WAIT) {
+                    Ok(message) => Some(message),
+                    Err(RecvTimeoutError::Timeout) => None,
+                    Err(RecvTimeoutError::Disconnected) => return,
+                }
+            };
+
+            match message {
+                Some(Message::Command(command)) => self.handle_command(command),
+                Some(Message::Config(config)) => self.set_config(config),
+                Some(Message::Clip(clip)) => self.play_clip(clip),
+                Some(Message::Quit) => return,
+                None => {}
+            }
+        }
+    }
+
+    /// Moves decoded audio into the ring buffer, decoding more when needed.
+    /// Returns `false` when the ring buffer is full.
+    fn fill(&mut self) -> bool {
+        if self.pending_offset >= self.pending.len() {
+            self.pending.clear();
+            self.pending_offset = 0;
+
+            if let Some(decoder) = &mut self.decoder {
+                match decoder.decode_into(&mut self.pending) {
+                    Ok(true) => {}
+                    Ok(false) => self.end_of_track(),
+                    Err(err) => {
+                        warn!("Could not decode track: {}", err);
+                        self.end_of_track();
+                    }
+                }
+            }
+        }
+
+        let channels = self.shared.channels;
+        let free = self.producer.free() - self.producer.free() % channels;
+        let pending = &self.pending[self.pending_offset..];
+        let count = pending.len().min(free);
+
+        self.producer.push(&pending[..count]);
+        self.pending_offset += count;
+        self.produced += count as u64;
+
+        count > 0 || pending.is_empty()
+    }
+
+    fn handle_command(&mut self, command: PlayerCommand) {
+        let len = self.config.playlist.len();
+
+        match command {
+            PlayerCommand::Play => {
+                if self.track.is_none() {
+                    self.play_from(0);
+                }
+            }
+            PlayerCommand::Pause => {}
+            PlayerCommand::Stop => self.stop(),
+            PlayerCommand::Next => {
+                if let Some(track) = self.track {
+                    // Skipping always moves on, also when the track is looped
+                    let loop_mode = match self.config.loop_mode {
+                        LoopMode::Track => LoopMode::Off,
+                        loop_mode => loop_mode,
+                    };
+                    match next_track(track, len, loop_mode) {
+                        Some(track) => self.play_from(track),
+                        None => self.stop(),
+                    }
+                }
+            }
+            PlayerCommand::Previous => {
+                if let Some(track) = self.track {
+                    let previous = match track {
+                        0 if self.config.loop_mode == LoopMode::Playlist => len.saturating_sub(1),
+                        track => track.saturating_sub(1),
+                    };
+                    self.play_from(previous);
+                }
+            }
+            PlayerCommand::PlayTrack(track) => {
+                if track < len {
+                    self.play_from(track);
+                }
+            }
+            PlayerCommand::Seek(secs) => {
+                if let (Some(decoder), Some(track)) = (&mut self.decoder, self.track) {
+                    match decoder.seek(secs) {
+                        Ok(position) => {
+                            let duration = decoder.duration_secs();
+                            self.flush(Some(track), position, duration);
+                        }
+                        Err(err) => warn!("Could not seek: {}", err),
+                    }
+                }
+            }
+        }
+    }
+
+    fn play_clip(&mut self, clip: Clip) {
+        let samples = match self.clips.get(&clip.path) {
+            Some(samples) => samples.clone(),
+            None => match decode_file(
+                Path::new(&clip.path),
+                self.shared.sample_rate,
+                self.shared.channels,
+            ) {
+                Ok(samples) => {
+                    let samples: Arc<[f32]> = samples.into();
+                    self.clips.insert(clip.path.clone(), samples.clone());
+                    samples
+                }
+                Err(err) => {
+                    warn!("Could not decode clip {}: {}", clip.path, err);
+                    return;
+                }
+            },
+        };
+
+        // The reader stops the clips of the same choke group when it takes the voice
+        self.voices
+            .send(Voice {
+                clip_id: clip.id,
+                choke_group: clip.choke_group,
+                samples,
+                pos: 0,
+                gain: db_to_gain(clip.gain_db),
+            })
+            .ok();
+    }
+
+    fn set_config(&mut self, config: PlayerConfig) {
+        let changed_track = match self.track {
+            Some(track) => config.playlist.get(track) != self.config.playlist.get(track),
+            None => false,
+        };
+
+        self.config = config;
+
+        if changed_track {
+            self.stop();
+        }
+    }
+
+    /// Starts playing the given track, or the next one that can be opened.
+    fn play_from(&mut self, track: usize) {
+        self.pending.clear();
+        self.pending_offset = 0;
+
+        let len = self.config.playlist.len();
+        let mut track = Some(track);
+        // Gives up after every track failed once
+        for _ in 0..len {
+            let index = match track {
+                Some(index) => index,
+                None => break,
+            };
+
+            match self.open(index) {
+                Ok(decoder) => {
+                    let duration = decoder.duration_secs();
+                    self.decoder = Some(decoder);
+                    self.track = Some(index);
+                    self.flush(Some(index), 0.0, duration);
+                    return;
+                }
+                Err(err) => {
+                    warn!("Could not open {}: {}", self.config.playlist[index], err);
+                    track =
+                        next_track(index, len, self.config.loop_mode).filter(|&next| next != index);
+                }
+            }
+        }
+
+        self.stop();
+    }
+
+    fn open(&self, track: usize) -> Result<TrackDecoder> {
+        TrackDecoder::open(
+            Path::new(&self.config.playlist[track]),
+            self.shared.sample_rate,
+            self.shared.channels,
+        )
+    }
+
+    fn stop(&mut self) {
+        self.decoder = None;
+        self.track = None;
+        self.pending.clear();
+        self.pending_offset = 0;
+        self.flush(None, 0.0, None);
+    }
+
+    /// Drops the buffered audio and starts a new segment.
+    fn flush(&mut self, track: Option<usize>, position: f64, duration: Option<f64>) {
+        let mut segments = self.shared.segments.lock();
+
+        self.shared.flush_to.store(self.produced, Ordering::Release);
+        segments.push_back(Segment {
+            start: self.produced,
+            track,
+            position,
+            duration,
+        });
+    }
+
+    /// Continues with the next track without dropping the buffered audio.
+    fn end_of_track(&mut self) {
+        let track = match self.track {
+            Some(track) => track,
+            None => return,
+        };
+
+        let next = next_track(track, self.config.playlist.len(), self.config.loop_mode)
+            .and_then(|next| self.open(next).ok().map(|decoder| (next, decoder)));
+
+        let segment = match next {
+            Some((next, decoder)) => {
+                let duration = decoder.duration_secs();
+                self.decoder = Some(decoder);
+                self.track = Some(next);
+                Segment {
+                    start: self.produced,
+                    track: Some(next),
+                    position: 0.0,
+                    duration,
+                }
+            }
+            None => {
+                self.decoder = None;
+                self.track = None;
+                Segment {
+                    start: self.produced,
+                    track: None,
+                    position: 0.0,
+                    duration: None,
+                }
+            }
+        };
+
+        self.shared.segments.lock().push_back(segment);
+    }
+}
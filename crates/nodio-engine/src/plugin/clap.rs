@@ -0,0 +1,616 @@
+use std::ffi::{c_char, c_void, CStr, CString};
+use std::mem::size_of;
+use std::path::{Path, PathBuf};
+use std::ptr::{null, null_mut};
+
+use clap_sys::audio_buffer::clap_audio_buffer;
+use clap_sys::entry::clap_plugin_entry;
+use clap_sys::events::{
+    clap_event_header, clap_event_param_value, clap_input_events, clap_output_events,
+    CLAP_CORE_EVENT_SPACE_ID, CLAP_EVENT_PARAM_VALUE,
+};
+use clap_sys::ext::audio_ports::{
+    clap_audio_port_info, clap_plugin_audio_ports, CLAP_EXT_AUDIO_PORTS,
+};
+use clap_sys::ext::params::{
+    clap_param_info, clap_plugin_params, CLAP_EXT_PARAMS, CLAP_PARAM_IS_HIDDEN,
+    CLAP_PARAM_IS_READONLY,
+};
+use clap_sys::ext::state::{clap_plugin_state, CLAP_EXT_STATE};
+use clap_sys::factory::plugin_factory::{clap_plugin_factory, CLAP_PLUGIN_FACTORY_ID};
+use clap_sys::host::clap_host;
+use clap_sys::plugin::clap_plugin;
+use clap_sys::process::{clap_process, CLAP_PROCESS_ERROR};
+use clap_sys::stream::{clap_istream, clap_ostream};
+use clap_sys::version::{clap_version_is_compatible, CLAP_VERSION};
+use libloading::Library;
+use log::{debug, warn};
+use parking_lot::{const_mutex, Mutex};
+
+use nodio_core::{Error, PluginConfig, PluginFormat, PluginParamInfo, Result};
+
+use super::PluginDescriptor;
+use crate::Effect;
+
+/// Largest block passed to the plugin at once
+const MAX_FRAMES: u32 = 4096;
+
+static HOST: clap_host = clap_host {
+    clap_version: CLAP_VERSION,
+    host_data: null_mut(),
+    name: c"Nodio".as_ptr(),
+    vendor: c"Nodio".as_ptr(),
+    url: c"https://github.com/urholaukkarinen/nodio".as_ptr(),
+    version: c"0.1.0".as_ptr(),
+    get_extension: Some(host_get_extension),
+    request_restart: Some(host_request),
+    request_process: Some(host_request),
+    request_callback: Some(host_request),
+};
+
+unsafe extern "C" fn host_get_extension(
+    _host: *const clap_host,
+    _id: *const c_char,
+) -> *const c_void {
+    null()
+}
+
+unsafe extern "C" fn host_request(_host: *const clap_host) {}
+
+/// Loaded plugin libraries. A library is initialized once and kept loaded until exit,
+/// as its entry may only be deinitialized after all of its plugins are destroyed.
+static LIBRARIES: Mutex<Vec<(PathBuf, &'static ClapLibrary)>> = const_mutex(Vec::new());
+
+struct ClapLibrary {
+    _library: Library,
+    factory: *const clap_plugin_factory,
+}
+
+unsafe impl Send for ClapLibrary {}
+unsafe impl Sync for ClapLibrary {}
+
+impl ClapLibrary {
+    fn get(path: &Path) -> Result<&'static ClapLibrary> {
+        let mut libraries = LIBRARIES.lock();
+
+        if let Some((_, library)) = libraries.iter().find(|(p, _)| p == path) {
+            return Ok(library);
+        }
+
+        let library: &'static ClapLibrary = Box::leak(Box::new(unsafe { Self::load(path)? }));
+        libraries.push((path.to_path_buf(), library));
+
+        Ok(library)
+    }
+
+    unsafe fn load(path: &Path) -> Result<Self> {
+        let library = Library::new(path).map_err(|err| Error::Other(err.to_string()))?;
+
+        let entry: *const clap_plugin_entry = *library
+            .get::<*const clap_plugin_entry>(b"clap_entry\0")
+            .map_err(|err| Error::Other(err.to_string()))?;
+
+        if entry.is_null() || !clap_version_is_compatible((*entry).clap_version) {
+            return Err(Error::Other("Incompatible CLAP version".to_string()));
+        }
+
+        let plugin_path = CString::new(path.to_string_lossy().as_bytes())
+            .map_err(|err| Error::Other(err.to_string()))?;
+
+        match (*entry).init {
+            Some(init) if init(plugin_path.as_ptr()) => {}
+            _ => {
+                return Err(Error::Other(
+                    "Plugin entry failed to initialize".to_string(),
+                ))
+            }
+        }
+
+        let factory = match (*entry).get_factory {
+            Some(get_factory) => {
+                get_factory(CLAP_PLUGIN_FACTORY_ID.as_ptr()) as *const clap_plugin_factory
+            }
+            None => null(),
+        };
+
+        if factory.is_null() {
+            return Err(Error::Other("No plugin factory".to_string()));
+        }
+
+        Ok(Self {
+            _library: library,
+            factory,
+        })
+    }
+
+    unsafe fn descriptors(&self, path: &Path) -> Vec<PluginDescriptor> {
+        let factory = &*self.factory;
+
+        let (get_count, get_descriptor) =
+            match (factory.get_plugin_count, factory.get_plugin_descriptor) {
+                (Some(get_count), Some(get_descriptor)) => (get_count, get_descriptor),
+                _ => return Vec::new(),
+            };
+
+        (0..get_count(self.factory))
+            .map(|idx| get_descriptor(self.factory, idx))
+            .filter(|descriptor| !descriptor.is_null())
+            .map(|descriptor| PluginDescriptor {
+                format: PluginFormat::Clap,
+                path: path.to_path_buf(),
+                id: c_str(&(*descriptor).id),
+                name: c_str(&(*descriptor).name),
+                vendor: c_str(&(*descriptor).vendor),
+            })
+            .collect()
+    }
+
+    /// Creates and initializes an instance of the plugin.
+    unsafe fn create_plugin(&self, plugin_id: &str) -> Result<*const clap_plugin> {
+        let plugin_id = CString::new(plugin_id).map_err(|err| Error::Other(err.to_string()))?;
+
+        let plugin = match (*self.factory).create_plugin {
+            Some(create_plugin) => create_plugin(self.factory, &HOST, plugin_id.as_ptr()),
+            None => null(),
+        };
+
+        if plugin.is_null() {
+            return Err(Error::Other("Could not create plugin".to_string()));
+        }
+
+        match (*plugin).init {
+            Some(init) if init(plugin) => Ok(plugin),
+            _ => {
+                if let Some(destroy) = (*plugin).destroy {
+                    destroy(plugin);
+                }
+                Err(Error::Other("Plugin failed to initialize".to_string()))
+            }
+        }
+    }
+}
+
+unsafe fn c_str(ptr: &*const c_char) -> String {
+    if ptr.is_null() {
+        String::new()
+    } else {
+        CStr::from_ptr(*ptr).to_string_lossy().into_owned()
+    }
+}
+
+unsafe fn extension<T>(plugin: *const clap_plugin, id: &CStr) -> *const T {
+    match (*plugin).get_extension {
+        Some(get_extension) => get_extension(plugin, id.as_ptr()) as *const T,
+        None => null(),
+    }
+}
+
+/// Finds the plugins in the `.clap` files of the directory and its subdirectories.
+pub fn discover(dir: &Path, plugins: &mut Vec<PluginDescriptor>) {
+    let entries = match dir.read_dir() {
+        Ok(entries) => entries,
+        Err(err) => {
+            warn!("Could not read {}: {}", dir.display(), err);
+            return;
+        }
+    };
+
+    for path in entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+    {
+        if path.extension().and_then(|ext| ext.to_str()) == Some("clap") {
+            let library_path = library_path(&path);
+
+            match ClapLibrary::get(&library_path) {
+                Ok(library) => plugins.extend(unsafe { library.descriptors(&library_path) }),
+                Err(err) => warn!("Could not load {}: {:?}", path.display(), err),
+            }
+        } else if path.is_dir() {
+            discover(&path, plugins);
+        }
+    }
+}
+
+/// Path of the plugin binary. On macOS plugins are bundles with the binary inside.
+fn library_path(path: &Path) -> PathBuf {
+    match path.file_stem() {
+        Some(name) if path.is_dir() => path.join("Contents").join("MacOS").join(name),
+        _ => path.to_path_buf(),
+    }
+}
+
+pub fn plugin_config(descriptor: &PluginDescriptor) -> Result<PluginConfig> {
+    let library = ClapLibrary::get(&descriptor.path)?;
+
+    unsafe {
+        let plugin = library.create_plugin(&descriptor.id)?;
+        let params = param_infos(plugin);
+
+        if let Some(destroy) = (*plugin).destroy {
+            destroy(plugin);
+        }
+
+        Ok(PluginConfig {
+            format: PluginFormat::Clap,
+            path: descriptor.path.to_string_lossy().into_owned(),
+            plugin_id: descriptor.id.clone(),
+            name: descriptor.name.clone(),
+            params,
+            state: Vec::new(),
+        })
+    }
+}
+
+/// Parameters of the plugin that can be changed by the user.
+unsafe fn param_infos(plugin: *const clap_plugin) -> Vec<PluginParamInfo> {
+    let params = extension::<clap_plugin_params>(plugin, CLAP_EXT_PARAMS);
+    if params.is_null() {
+        return Vec::new();
+    }
+
+    let (count, get_info) = match ((*params).count, (*params).get_info) {
+        (Some(count), Some(get_info)) => (count, get_info),
+        _ => return Vec::new(),
+    };
+
+    (0..count(plugin))
+        .filter_map(|idx| {
+            let mut info: clap_param_info = std::mem::zeroed();
+
+            if get_info(plugin, idx, &mut info)
+                && info.flags & (CLAP_PARAM_IS_HIDDEN | CLAP_PARAM_IS_READONLY) == 0
+            {
+                Some(PluginParamInfo {
+                    id: info.id,
+                    name: CStr::from_ptr(info.name.as_ptr())
+                        .to_string_lossy()
+                        .into_owned(),
+                    min: info.min_value as f32,
+                    max: info.max_value as f32,
+                    default: info.default_value as f32,
+                })
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// Channel count of the main audio input or output port of the plugin.
+unsafe fn main_port_channels(plugin: *const clap_plugin, is_input: bool) -> usize {
+    let ports = extension::<clap_plugin_audio_ports>(plugin, CLAP_EXT_AUDIO_PORTS);
+    if ports.is_null() {
+        return 2;
+    }
+
+    let (count, get) = match ((*ports).count, (*ports).get) {
+        (Some(count), Some(get)) => (count, get),
+        _ => return 2,
+    };
+
+    if count(plugin, is_input) == 0 {
+        return 0;
+    }
+
+    let mut info: clap_audio_port_info = std::mem::zeroed();
+    if get(plugin, 0, is_input, &mut info) {
+        info.channel_count as usize
+    } else {
+        2
+    }
+}
+
+/// CLAP plugin in an effects chain.
+pub struct ClapEffect {
+    plugin: *const clap_plugin,
+    state: *const clap_plugin_state,
+
+    param_ids: Vec<u32>,
+    values: Vec<f32>,
+    /// Parameter changes sent to the plugin with the next block
+    events: Vec<clap_event_param_value>,
+
+    /// Non-interleaved channels of the main ports
+    inputs: Vec<Vec<f32>>,
+    outputs: Vec<Vec<f32>>,
+    input_ptrs: Vec<*mut f32>,
+    output_ptrs: Vec<*mut f32>,
+
+    processing: bool,
+    steady_time: i64,
+}
+
+unsafe impl Send for ClapEffect {}
+
+impl ClapEffect {
+    pub fn new(config: &PluginConfig, sample_rate: f32) -> Result<Self> {
+        let library = ClapLibrary::get(Path::new(&config.path))?;
+
+        unsafe {
+            let plugin = library.create_plugin(&config.plugin_id)?;
+
+            let activated = match (*plugin).activate {
+                Some(activate) => activate(plugin, sample_rate as f64, 1, MAX_FRAMES),
+                None => false,
+            };
+
+            if !activated {
+                if let Some(destroy) = (*plugin).destroy {
+                    destroy(plugin);
+                }
+                return Err(Error::Other("Plugin failed to activate".to_string()));
+            }
+
+            let input_channels = main_port_channels(plugin, true);
+            let output_channels = main_port_channels(plugin, false);
+
+            debug!(
+                "Loaded plugin {} with {} inputs and {} outputs",
+                config.name, input_channels, output_channels
+            );
+
+            Ok(Self {
+                plugin,
+                state: extension::<clap_plugin_state>(plugin, CLAP_EXT_STATE),
+                param_ids: config.params.iter().map(|param| param.id).collect(),
+                values: config.params.iter().map(|param| param.default).collect(),
+                events: Vec::new(),
+                inputs: vec![Vec::with_capacity(MAX_FRAMES as usize); input_channels],
+                outputs: vec![Vec::with_capacity(MAX_FRAMES as usize); output_channels],
+                input_ptrs: Vec::with_capacity(input_channels),
+                output_ptrs: Vec::with_capacity(output_channels),
+                processing: false,
+                steady_time: 0,
+            })
+        }
+    }
+
+    unsafe fn process_block(&mut self, block: &mut [f32], channels: usize) {
+        if !self.processing {
+            self.processing = match (*self.plugin).start_processing {
+                Some(start_processing) => start_processing(self.plugin),
+                None => true,
+            };
+
+            if !self.processing {
+                return;
+            }
+        }
+
+        let frames = block.len() / channels;
+
+        for (channel, input) in self.inputs.iter_mut().enumerate() {
+            let channel = channel.min(channels - 1);
+
+            input.clear();
+            input.extend(block.chunks_exact(channels).map(|frame| frame[channel]));
+        }
+
+        for output in self.outputs.iter_mut() {
+            output.clear();
+            output.resize(frames, 0.0);
+        }
+
+        self.input_ptrs.clear();
+        self.input_ptrs
+            .extend(self.inputs.iter_mut().map(|input| input.as_mut_ptr()));
+        self.output_ptrs.clear();
+        self.output_ptrs
+            .extend(self.outputs.iter_mut().map(|output| output.as_mut_ptr()));
+
+        let audio_inputs = clap_audio_buffer {
+            data32: self.input_ptrs.as_mut_ptr(),
+            data64: null_mut(),
+            channel_count: self.inputs.len() as u32,
+            latency: 0,
+            constant_mask: 0,
+        };
+        let mut audio_outputs = clap_audio_buffer {
+            data32: self.output_ptrs.as_mut_ptr(),
+            data64: null_mut(),
+            channel_count: self.outputs.len() as u32,
+            latency: 0,
+            constant_mask: 0,
+        };
+
+        let in_events = clap_input_events {
+            ctx: &mut self.events as *mut Vec<clap_event_param_value> as *mut c_void,
+            size: Some(input_events_size),
+            get: Some(input_events_get),
+        };
+        let out_events = clap_output_events {
+            ctx: null_mut(),
+            try_push: Some(output_events_try_push),
+        };
+
+        let process = clap_process {
+            steady_time: self.steady_time,
+            frames_count: frames as u32,
+            transport: null(),
+            audio_inputs: &audio_inputs,
+            audio_outputs: &mut audio_outputs,
+            audio_inputs_count: if self.inputs.is_empty() { 0 } else { 1 },
+            audio_outputs_count: if self.outputs.is_empty() { 0 } else { 1 },
+            in_events: &in_events,
+            out_events: &out_events,
+        };
+
+        let status = match (*self.plugin).process {
+            Some(process_fn) => process_fn(self.plugin, &process),
+            None => CLAP_PROCESS_ERROR,
+        };
+
+        self.events.clear();
+        self.steady_time += frames as i64;
+
+        if status == CLAP_PROCESS_ERROR || self.outputs.is_empty() {
+            return;
+        }
+
+        let last_output = self.outputs.len() - 1;
+
+        for (frame_idx, frame) in block.chunks_exact_mut(channels).enumerate() {
+            for (channel, sample) in frame.iter_mut().enumerate() {
+                *sample = self.outputs[channel.min(last_output)][frame_idx];
+            }
+        }
+    }
+}
+
+impl Effect for ClapEffect {
+    fn set_param(&mut self, idx: usize, value: f32) {
+        if let (Some(&param_id), Some(current)) =
+            (self.param_ids.get(idx), self.values.get_mut(idx))
+        {
+            *current = value;
+
+            self.events.retain(|event| event.param_id != param_id);
+            self.events.push(clap_event_param_value {
+                header: clap_event_header {
+                    size: size_of::<clap_event_param_value>() as u32,
+                    time: 0,
+                    space_id: CLAP_CORE_EVENT_SPACE_ID,
+                    type_: CLAP_EVENT_PARAM_VALUE,
+                    flags: 0,
+                },
+                param_id,
+                cookie: null_mut(),
+                note_id: -1,
+                port_index: -1,
+                channel: -1,
+                key: -1,
+                value: value as f64,
+            });
+        }
+    }
+
+    fn param(&self, idx: usize) -> f32 {
+        self.values.get(idx).copied().unwrap_or_default()
+    }
+
+    fn process(&mut self, block: &mut [f32], channels: usize) {
+        for chunk in block.chunks_mut(MAX_FRAMES as usize * channels) {
+            unsafe { self.process_block(chunk, channels) };
+        }
+    }
+
+    fn reset(&mut self) {
+        unsafe {
+            if let Some(reset) = (*self.plugin).reset {
+                reset(self.plugin);
+            }
+        }
+    }
+
+    fn save_state(&mut self) -> Option<Vec<u8>> {
+        if self.state.is_null() {
+            return None;
+        }
+
+        let mut data = Vec::new();
+        let stream = clap_ostream {
+            ctx: &mut data as *mut Vec<u8> as *mut c_void,
+            write: Some(ostream_write),
+        };
+
+        unsafe {
+            match (*self.state).save {
+                Some(save) if save(self.plugin, &stream) => Some(data),
+                _ => None,
+            }
+        }
+    }
+
+    fn load_state(&mut self, state: &[u8]) {
+        if self.state.is_null() {
+            return;
+        }
+
+        let mut data = state;
+        let stream = clap_istream {
+            ctx: &mut data as *mut &[u8] as *mut c_void,
+            read: Some(istream_read),
+        };
+
+        unsafe {
+            if let Some(load) = (*self.state).load {
+                if !load(self.plugin, &stream) {
+                    warn!("Plugin could not load its state");
+                }
+            }
+        }
+    }
+}
+
+impl Drop for ClapEffect {
+    fn drop(&mut self) {
+        unsafe {
+            let plugin = &*self.plugin;
+
+            if self.processing {
+                if let Some(stop_processing) = plugin.stop_processing {
+                    stop_processing(self.plugin);
+                }
+            }
+            if let Some(deactivate) = plugin.deactivate {
+                deactivate(self.plugin);
+            }
+            if let Some(destroy) = plugin.destroy {
+                destroy(self.plugin);
+            }
+        }
+    }
+}
+
+unsafe extern "C" fn input_events_size(list: *const clap_input_events) -> u32 {
+    let events = &*((*list).ctx as *const Vec<clap_event_param_value>);
+    events.len() as u32
+}
+
+unsafe extern "C" fn input_events_get(
+    list: *const clap_input_events,
+    index: u32,
+) -> *const clap_event_header {
+    let events = &*((*list).ctx as *const Vec<clap_event_param_value>);
+
+    match events.get(index as usize) {
+        Some(event) => &event.header,
+        None => null(),
+    }
+}
+
+/// Events from the plugin, e.g. parameter changes from its own GUI, are not used.
+unsafe extern "C" fn output_events_try_push(
+    _list: *const clap_output_events,
+    _event: *const clap_event_header,
+) -> bool {
+    true
+}
+
+unsafe extern "C" fn ostream_write(
+    stream: *const clap_ostream,
+    buffer: *const c_void,
+    size: u64,
+) -> i64 {
+    let data = &mut *((*stream).ctx as *mut Vec<u8>);
+    data.extend_from_slice(std::slice::from_raw_parts(
+        buffer as *const u8,
+        size as usize,
+    ));
+    size as i64
+}
+
+unsafe extern "C" fn istream_read(
+    stream: *const clap_istream,
+    buffer: *mut c_void,
+    size: u64,
+) -> i64 {
+    let data = &mut *((*stream).ctx as *mut &[u8]);
+    let len = (size as usize).min(data.len());
+
+    std::ptr::copy_nonoverlapping(data.as_ptr(), buffer as *mut u8, len);
+    *data = &data[len..];
+
+    len as i64
+}
@@ -0,0 +1,988 @@
+use std::ffi::{c_char, c_void, CStr, CString};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::ptr::{null, null_mut};
+
+use libloading::Library;
+use log::{debug, warn};
+use nodio_core::{Error, PluginConfig, PluginFormat, PluginParamInfo, Result};
+use parking_lot::{const_mutex, Mutex};
+
+use super::PluginDescriptor;
+use crate::Effect;
+
+const RDF_TYPE: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#type";
+const LV2_PLUGIN: &str = "http://lv2plug.in/ns/lv2core#Plugin";
+const LV2_BINARY: &str = "http://lv2plug.in/ns/lv2core#binary";
+const LV2_PORT: &str = "http://lv2plug.in/ns/lv2core#port";
+const LV2_INDEX: &str = "http://lv2plug.in/ns/lv2core#index";
+const LV2_NAME: &str = "http://lv2plug.in/ns/lv2core#name";
+const LV2_SYMBOL: &str = "http://lv2plug.in/ns/lv2core#symbol";
+const LV2_DEFAULT: &str = "http://lv2plug.in/ns/lv2core#default";
+const LV2_MINIMUM: &str = "http://lv2plug.in/ns/lv2core#minimum";
+const LV2_MAXIMUM: &str = "http://lv2plug.in/ns/lv2core#maximum";
+const LV2_PORT_PROPERTY: &str = "http://lv2plug.in/ns/lv2core#portProperty";
+const LV2_CONNECTION_OPTIONAL: &str = "http://lv2plug.in/ns/lv2core#connectionOptional";
+const LV2_REQUIRED_FEATURE: &str = "http://lv2plug.in/ns/lv2core#requiredFeature";
+const LV2_INPUT_PORT: &str = "http://lv2plug.in/ns/lv2core#InputPort";
+const LV2_AUDIO_PORT: &str = "http://lv2plug.in/ns/lv2core#AudioPort";
+const LV2_CONTROL_PORT: &str = "http://lv2plug.in/ns/lv2core#ControlPort";
+const ATOM_PORT: &str = "http://lv2plug.in/ns/ext/atom#AtomPort";
+const ATOM_SEQUENCE: &str = "http://lv2plug.in/ns/ext/atom#Sequence";
+const ATOM_CHUNK: &str = "http://lv2plug.in/ns/ext/atom#Chunk";
+const URID_MAP: &str = "http://lv2plug.in/ns/ext/urid#map";
+const URID_UNMAP: &str = "http://lv2plug.in/ns/ext/urid#unmap";
+const DOAP_NAME: &str = "http://usefulinc.com/ns/doap#name";
+const DOAP_MAINTAINER: &str = "http://usefulinc.com/ns/doap#maintainer";
+const FOAF_NAME: &str = "http://xmlns.com/foaf/0.1/name";
+
+/// Features that are provided to the plugins, or that the host fulfils as is
+const SUPPORTED_FEATURES: [&str; 5] = [
+    URID_MAP,
+    URID_UNMAP,
+    "http://lv2plug.in/ns/lv2core#isLive",
+    "http://lv2plug.in/ns/lv2core#inPlaceBroken",
+    "http://lv2plug.in/ns/lv2core#hardRTCapable",
+];
+
+/// Largest block passed to the plugin at once
+const MAX_FRAMES: usize = 4096;
+/// Size of the buffers of the atom ports, in bytes
+const ATOM_BUFFER_SIZE: usize = 8192;
+
+/// Finds the plugins of the `.lv2` bundles in the directory.
+pub fn discover(dir: &Path, plugins: &mut Vec<PluginDescriptor>) {
+    let entries = match dir.read_dir() {
+        Ok(entries) => entries,
+        Err(err) => {
+            warn!("Could not read {}: {}", dir.display(), err);
+            return;
+        }
+    };
+
+    for bundle in entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+    {
+        if bundle.extension().and_then(|ext| ext.to_str()) != Some("lv2")
+            || !bundle.join("manifest.ttl").is_file()
+        {
+            continue;
+        }
+
+        let triples = match read_bundle(&bundle) {
+            Some(triples) => triples,
+            None => continue,
+        };
+
+        for triple in triples
+            .iter()
+            .filter(|t| t.predicate == RDF_TYPE && t.object == LV2_PLUGIN)
+        {
+            let uri = &triple.subject;
+
+            if plugins.iter().any(|plugin| &plugin.id == uri) {
+                continue;
+            }
+
+            let name = object(&triples, uri, DOAP_NAME)
+                .unwrap_or_else(|| uri.rsplit(['/', '#']).next().unwrap_or(uri).to_string());
+            let vendor = object(&triples, uri, DOAP_MAINTAINER)
+                .and_then(|maintainer| object(&triples, &maintainer, FOAF_NAME))
+                .unwrap_or_default();
+
+            plugins.push(PluginDescriptor {
+                format: PluginFormat::Lv2,
+                path: bundle.clone(),
+                id: uri.clone(),
+                name,
+                vendor,
+            });
+        }
+    }
+}
+
+/// Reads the triples of all Turtle files of the bundle. The plugin data is usually split
+/// between the manifest and the files it refers to.
+fn read_bundle(bundle: &Path) -> Option<Vec<Triple>> {
+    let mut blank_nodes = 0;
+
+    let triples = bundle
+        .read_dir()
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().and_then(|ext| ext.to_str()) == Some("ttl"))
+        .filter_map(|path| fs::read_to_string(path).ok())
+        .flat_map(|text| parse_turtle(&text, &mut blank_nodes))
+        .collect();
+
+    Some(triples)
+}
+
+fn object(triples: &[Triple], subject: &str, predicate: &str) -> Option<String> {
+    objects(triples, subject, predicate).next()
+}
+
+fn objects<'a>(
+    triples: &'a [Triple],
+    subject: &'a str,
+    predicate: &'a str,
+) -> impl Iterator<Item = String> + 'a {
+    triples
+        .iter()
+        .filter(move |t| t.subject == subject && t.predicate == predicate)
+        .map(|t| t.object.clone())
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum PortKind {
+    Audio,
+    Control,
+    Atom,
+    Other,
+}
+
+#[derive(Debug, Clone)]
+struct PortInfo {
+    index: u32,
+    kind: PortKind,
+    input: bool,
+    name: String,
+    min: f32,
+    max: f32,
+    default: f32,
+    optional: bool,
+}
+
+/// What the bundle tells about a plugin: its library, ports and the features it needs.
+struct PluginData {
+    binary: PathBuf,
+    ports: Vec<PortInfo>,
+    required_features: Vec<String>,
+}
+
+impl PluginData {
+    fn read(bundle: &Path, uri: &str) -> Result<Self> {
+        let triples = read_bundle(bundle)
+            .ok_or_else(|| Error::Other(format!("Could not read {}", bundle.display())))?;
+
+        let binary = object(&triples, uri, LV2_BINARY)
+            .ok_or_else(|| Error::Other("The plugin has no binary".to_string()))?;
+        let binary = bundle.join(binary.strip_prefix("file://").unwrap_or(&binary));
+
+        let number = |subject: &str, predicate: &str| {
+            object(&triples, subject, predicate).and_then(|value| value.parse::<f32>().ok())
+        };
+
+        let mut ports = Vec::new();
+        for port in objects(&triples, uri, LV2_PORT) {
+            let types: Vec<String> = objects(&triples, &port, RDF_TYPE).collect();
+            let has_type = |port_type: &str| types.iter().any(|t| t == port_type);
+
+            let index = match number(&port, LV2_INDEX) {
+                Some(index) => index as u32,
+                None => continue,
+            };
+            let kind = if has_type(LV2_AUDIO_PORT) {
+                PortKind::Audio
+            } else if has_type(LV2_CONTROL_PORT) {
+                PortKind::Control
+            } else if has_type(ATOM_PORT) {
+                PortKind::Atom
+            } else {
+                PortKind::Other
+            };
+            let min = number(&port, LV2_MINIMUM).unwrap_or(0.0);
+            let max = number(&port, LV2_MAXIMUM).unwrap_or(1.0);
+
+            ports.push(PortInfo {
+                index,
+                kind,
+                input: has_type(LV2_INPUT_PORT),
+                name: object(&triples, &port, LV2_NAME)
+                    .or_else(|| object(&triples, &port, LV2_SYMBOL))
+                    .unwrap_or_else(|| format!("Port {}", index)),
+                min,
+                max,
+                default: number(&port, LV2_DEFAULT).unwrap_or(min),
+                optional: objects(&triples, &port, LV2_PORT_PROPERTY)
+                    .any(|property| property == LV2_CONNECTION_OPTIONAL),
+            });
+        }
+        ports.sort_by_key(|port| port.index);
+
+        Ok(Self {
+            binary,
+            ports,
+            required_features: objects(&triples, uri, LV2_REQUIRED_FEATURE).collect(),
+        })
+    }
+
+    /// Parameters of the plugin, its control inputs.
+    fn params(&self) -> Vec<PluginParamInfo> {
+        self.ports
+            .iter()
+            .filter(|port| port.kind == PortKind::Control && port.input)
+            .map(|port| PluginParamInfo {
+                id: port.index,
+                name: port.name.clone(),
+                min: port.min,
+                max: port.max,
+                default: port.default,
+            })
+            .collect()
+    }
+
+    /// Fails if the plugin needs something that Nodio does not provide.
+    fn check_supported(&self) -> Result<()> {
+        if let Some(feature) = self
+            .required_features
+            .iter()
+            .find(|feature| !SUPPORTED_FEATURES.contains(&feature.as_str()))
+        {
+            return Err(Error::Other(format!(
+                "The plugin requires the unsupported feature {}",
+                feature
+            )));
+        }
+
+        if let Some(port) = self
+            .ports
+            .iter()
+            .find(|port| port.kind == PortKind::Other && !port.optional)
+        {
+            return Err(Error::Other(format!(
+                "The plugin has the unsupported port {}",
+                port.name
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+pub fn plugin_config(descriptor: &PluginDescriptor) -> Result<PluginConfig> {
+    let data = PluginData::read(&descriptor.path, &descriptor.id)?;
+    data.check_supported()?;
+    // Fails early for plugins that can not be loaded
+    Lv2Library::get(&data.binary)?.descriptor(&descriptor.id)?;
+
+    Ok(PluginConfig {
+        format: PluginFormat::Lv2,
+        path: descriptor.path.to_string_lossy().into_owned(),
+        plugin_id: descriptor.id.clone(),
+        name: descriptor.name.clone(),
+        params: data.params(),
+        state: Vec::new(),
+    })
+}
+
+#[repr(C)]
+struct Lv2Feature {
+    uri: *const c_char,
+    data: *mut c_void,
+}
+
+#[repr(C)]
+struct Lv2Descriptor {
+    uri: *const c_char,
+    instantiate: Option<
+        unsafe extern "C" fn(
+            descriptor: *const Lv2Descriptor,
+            sample_rate: f64,
+            bundle_path: *const c_char,
+            features: *const *const Lv2Feature,
+        ) -> *mut c_void,
+    >,
+    connect_port: Option<unsafe extern "C" fn(instance: *mut c_void, port: u32, data: *mut c_void)>,
+    activate: Option<unsafe extern "C" fn(instance: *mut c_void)>,
+    run: Option<unsafe extern "C" fn(instance: *mut c_void, sample_count: u32)>,
+    deactivate: Option<unsafe extern "C" fn(instance: *mut c_void)>,
+    cleanup: Option<unsafe extern "C" fn(instance: *mut c_void)>,
+    extension_data: Option<unsafe extern "C" fn(uri: *const c_char) -> *const c_void>,
+}
+
+type Lv2DescriptorFn = unsafe extern "C" fn(index: u32) -> *const Lv2Descriptor;
+
+#[repr(C)]
+struct UridMap {
+    handle: *mut c_void,
+    map: unsafe extern "C" fn(handle: *mut c_void, uri: *const c_char) -> u32,
+}
+
+#[repr(C)]
+struct UridUnmap {
+    handle: *mut c_void,
+    unmap: unsafe extern "C" fn(handle: *mut c_void, urid: u32) -> *const c_char,
+}
+
+/// URIs mapped to integers for the plugins. The URID of a URI is its index plus one.
+static URIDS: Mutex<Vec<CString>> = const_mutex(Vec::new());
+
+fn map_uri(uri: &CStr) -> u32 {
+    let mut urids = URIDS.lock();
+
+    match urids.iter().position(|mapped| mapped.as_c_str() == uri) {
+        Some(idx) => idx as u32 + 1,
+        None => {
+            urids.push(uri.to_owned());
+            urids.len() as u32
+        }
+    }
+}
+
+unsafe extern "C" fn urid_map(_handle: *mut c_void, uri: *const c_char) -> u32 {
+    if uri.is_null() {
+        return 0;
+    }
+    map_uri(CStr::from_ptr(uri))
+}
+
+unsafe extern "C" fn urid_unmap(_handle: *mut c_void, urid: u32) -> *const c_char {
+    // The strings stay in place when the list grows, and are never removed
+    match URIDS.lock().get((urid as usize).wrapping_sub(1)) {
+        Some(uri) => uri.as_ptr(),
+        None => null(),
+    }
+}
+
+static URID_MAP_DATA: UridMap = UridMap {
+    handle: null_mut(),
+    map: urid_map,
+};
+
+static URID_UNMAP_DATA: UridUnmap = UridUnmap {
+    handle: null_mut(),
+    unmap: urid_unmap,
+};
+
+static URID_MAP_FEATURE: Lv2Feature = Lv2Feature {
+    uri: c"http://lv2plug.in/ns/ext/urid#map".as_ptr(),
+    data: &URID_MAP_DATA as *const UridMap as *mut c_void,
+};
+
+static URID_UNMAP_FEATURE: Lv2Feature = Lv2Feature {
+    uri: c"http://lv2plug.in/ns/ext/urid#unmap".as_ptr(),
+    data: &URID_UNMAP_DATA as *const UridUnmap as *mut c_void,
+};
+
+/// Features passed to the plugins, which may keep them for as long as they live
+static FEATURES: Features = Features([&URID_MAP_FEATURE, &URID_UNMAP_FEATURE, null()]);
+
+struct Features([*const Lv2Feature; 3]);
+
+// The features are never changed
+unsafe impl Sync for Features {}
+unsafe impl Sync for Lv2Feature {}
+unsafe impl Sync for UridMap {}
+unsafe impl Sync for UridUnmap {}
+
+/// Loaded plugin libraries, kept loaded until exit like the CLAP libraries.
+static LIBRARIES: Mutex<Vec<(PathBuf, &'static Lv2Library)>> = const_mutex(Vec::new());
+
+struct Lv2Library {
+    _library: Library,
+    descriptor_fn: Lv2DescriptorFn,
+}
+
+impl Lv2Library {
+    fn get(path: &Path) -> Result<&'static Lv2Library> {
+        let mut libraries = LIBRARIES.lock();
+
+        if let Some((_, library)) = libraries.iter().find(|(p, _)| p == path) {
+            return Ok(library);
+        }
+
+        let library: &'static Lv2Library = Box::leak(Box::new(unsafe { Self::load(path)? }));
+        libraries.push((path.to_path_buf(), library));
+
+        Ok(library)
+    }
+
+    unsafe fn load(path: &Path) -> Result<Self> {
+        let library = Library::new(path).map_err(|err| Error::Other(err.to_string()))?;
+
+        let descriptor_fn = *library
+            .get::<Lv2DescriptorFn>(b"lv2_descriptor\0")
+            .map_err(|err| Error::Other(err.to_string()))?;
+
+        Ok(Self {
+            _library: library,
+            descriptor_fn,
+        })
+    }
+
+    /// Descriptor of the plugin with the URI in the library.
+    fn descriptor(&self, uri: &str) -> Result<&'static Lv2Descriptor> {
+        for idx in 0.. {
+            let descriptor = unsafe { (self.descriptor_fn)(idx) };
+            if descriptor.is_null() {
+                break;
+            }
+
+            let descriptor = unsafe { &*descriptor };
+            if !descriptor.uri.is_null()
+                && unsafe { CStr::from_ptr(descriptor.uri) }.to_bytes() == uri.as_bytes()
+            {
+                return Ok(descriptor);
+            }
+        }
+
+        Err(Error::Other(format!("{} not found in its library", uri)))
+    }
+}
+
+/// Buffer of an atom port. Inputs get an empty sequence and outputs the space for one, before
+/// each block.
+struct AtomBuffer {
+    input: bool,
+    /// 64-bit words, as atoms are 64-bit aligned
+    data: Box<[u64]>,
+}
+
+impl AtomBuffer {
+    fn clear(&mut self, sequence_type: u32, chunk_type: u32) {
+        // The header of an atom is its size and type. The body of an empty sequence, its unit
+        // and padding, is zero.
+        let (size, atom_type) = if self.input {
+            (8, sequence_type)
+        } else {
+            (ATOM_BUFFER_SIZE as u32 - 8, chunk_type)
+        };
+
+        let header = self.data.as_mut_ptr() as *mut u32;
+        unsafe {
+            *header = size;
+            *header.add(1) = atom_type;
+        }
+        self.data[1] = 0;
+    }
+}
+
+/// LV2 plugin in an effects chain.
+pub struct Lv2Effect {
+    descriptor: &'static Lv2Descriptor,
+    instance: *mut c_void,
+
+    /// Port index of each parameter
+    param_ports: Vec<u32>,
+    /// Values of all control ports, by port index
+    controls: Box<[f32]>,
+
+    /// Non-interleaved channels of the audio ports
+    inputs: Vec<Box<[f32]>>,
+    outputs: Vec<Box<[f32]>>,
+    atoms: Vec<AtomBuffer>,
+    sequence_type: u32,
+    chunk_type: u32,
+}
+
+unsafe impl Send for Lv2Effect {}
+
+impl Lv2Effect {
+    pub fn new(config: &PluginConfig, sample_rate: f32) -> Result<Self> {
+        let bundle = Path::new(&config.path);
+        let data = PluginData::read(bundle, &config.plugin_id)?;
+        data.check_supported()?;
+        let descriptor = Lv2Library::get(&data.binary)?.descriptor(&config.plugin_id)?;
+
+        let instantiate = descriptor
+            .instantiate
+            .ok_or_else(|| Error::Other("The plugin can not be instantiated".to_string()))?;
+        let connect_port = match descriptor.connect_port {
+            Some(connect_port) if descriptor.run.is_some() => connect_port,
+            _ => return Err(Error::Other("The plugin can not be run".to_string())),
+        };
+
+        // The bundle path is given with a trailing separator
+        let mut bundle_path = bundle.to_string_lossy().into_owned();
+        if !bundle_path.ends_with(std::path::MAIN_SEPARATOR) {
+            bundle_path.push(std::path::MAIN_SEPARATOR);
+        }
+        let bundle_path = CString::new(bundle_path).map_err(|err| Error::Other(err.to_string()))?;
+
+        let instance = unsafe {
+            instantiate(
+                descriptor,
+                sample_rate as f64,
+                bundle_path.as_ptr(),
+                FEATURES.0.as_ptr(),
+            )
+        };
+        if instance.is_null() {
+            return Err(Error::Other("The plugin failed to instantiate".to_string()));
+        }
+
+        let port_count = data
+            .ports
+            .iter()
+            .map(|port| port.index + 1)
+            .max()
+            .unwrap_or(0);
+        let mut controls = vec![0.0; port_count as usize].into_boxed_slice();
+        for port in data
+            .ports
+            .iter()
+            .filter(|port| port.kind == PortKind::Control)
+        {
+            controls[port.index as usize] = port.default;
+        }
+
+        let mut effect = Self {
+            descriptor,
+            instance,
+            param_ports: config.params.iter().map(|param| param.id).collect(),
+            controls,
+            inputs: Vec::new(),
+            outputs: Vec::new(),
+            atoms: Vec::new(),
+            sequence_type: map_uri(&CString::new(ATOM_SEQUENCE).unwrap()),
+            chunk_type: map_uri(&CString::new(ATOM_CHUNK).unwrap()),
+        };
+
+        for (param, value) in config.params.iter().map(|param| (param.id, param.default)) {
+            if let Some(control) = effect.controls.get_mut(param as usize) {
+                *control = value;
+            }
+        }
+
+        // The buffers are connected once, and stay in place until the plugin is dropped
+        for port in &data.ports {
+            let location: *mut c_void = match port.kind {
+                PortKind::Control => &mut effect.controls[port.index as usize] as *mut f32 as _,
+                PortKind::Audio => {
+                    let mut buffer = vec![0.0; MAX_FRAMES].into_boxed_slice();
+                    let location = buffer.as_mut_ptr() as _;
+                    if port.input {
+                        effect.inputs.push(buffer);
+                    } else {
+                        effect.outputs.push(buffer);
+                    }
+                    location
+                }
+                PortKind::Atom => {
+                    let mut buffer = AtomBuffer {
+                        input: port.input,
+                        data: vec![0; ATOM_BUFFER_SIZE / 8].into_boxed_slice(),
+                    };
+                    let location = buffer.data.as_mut_ptr() as _;
+                    effect.atoms.push(buffer);
+                    location
+                }
+                PortKind::Other => null_mut(),
+            };
+
+            unsafe { connect_port(instance, port.index, location) };
+        }
+
+        if let Some(activate) = descriptor.activate {
+            unsafe { activate(instance) };
+        }
+
+        debug!(
+            "Loaded plugin {} with {} inputs and {} outputs",
+            config.name,
+            effect.inputs.len(),
+            effect.outputs.len()
+        );
+
+        Ok(effect)
+    }
+
+    unsafe fn process_block(&mut self, block: &mut [f32], channels: usize) {
+        let run = match self.descriptor.run {
+            Some(run) => run,
+            None => return,
+        };
+        let frames = block.len() / channels;
+
+        for (channel, input) in self.inputs.iter_mut().enumerate() {
+            let channel = channel.min(channels - 1);
+
+            for (sample, frame) in input.iter_mut().zip(block.chunks_exact(channels)) {
+                *sample = frame[channel];
+            }
+        }
+
+        for atom in self.atoms.iter_mut() {
+            atom.clear(self.sequence_type, self.chunk_type);
+        }
+
+        run(self.instance, frames as u32);
+
+        if self.outputs.is_empty() {
+            return;
+        }
+
+        let last_output = self.outputs.len() - 1;
+
+        for (frame_idx, frame) in block.chunks_exact_mut(channels).enumerate() {
+            for (channel, sample) in frame.iter_mut().enumerate() {
+                *sample = self.outputs[channel.min(last_output)][frame_idx];
+            }
+        }
+    }
+}
+
+impl Effect for Lv2Effect {
+    fn set_param(&mut self, idx: usize, value: f32) {
+        if let Some(control) = self
+            .param_ports
+            .get(idx)
+            .and_then(|&port| self.controls.get_mut(port as usize))
+        {
+            *control = value;
+        }
+    }
+
+    fn param(&self, idx: usize) -> f32 {
+        self.param_ports
+            .get(idx)
+            .and_then(|&port| self.controls.get(port as usize))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    fn process(&mut self, block: &mut [f32], channels: usize) {
+        for chunk in block.chunks_mut(MAX_FRAMES * channels) {
+            unsafe { self.process_block(chunk, channels) };
+        }
+    }
+
+    /// Plugins are reset by deactivating and activating them again.
+    fn reset(&mut self) {
+        unsafe {
+            if let (Some(deactivate), Some(activate)) =
+                (self.descriptor.deactivate, self.descriptor.activate)
+            {
+                deactivate(self.instance);
+                activate(self.instance);
+            }
+        }
+    }
+}
+
+impl Drop for Lv2Effect {
+    fn drop(&mut self) {
+        unsafe {
+            if let Some(deactivate) = self.descriptor.deactivate {
+                deactivate(self.instance);
+            }
+            if let Some(cleanup) = self.descriptor.cleanup {
+                cleanup(self.instance);
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Triple {
+    subject: String,
+    predicate: String,
+    object: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Iri(String),
+    Literal(String),
+    Name(String),
+    Punct(char),
+}
+
+/// Reads the triples of a Turtle document, which is enough for finding plugins, their names
+/// and ports. Only the subset of Turtle that LV2 bundles use is read:
+/// - `@prefix` and `PREFIX` directives, and the prefixed names they expand. `@base` is
+///   ignored, so relative IRIs stay relative to the bundle.
+/// - `a`, predicate lists, object lists and blank node property lists, whose nodes get names
+///   generated with the counter.
+/// - Literals in single, double and triple quotes. They lose their language tags and
+///   datatypes, and only the `\n` and `\t` escapes are decoded. Numbers and booleans are
+///   read as names.
+/// - Collections, which are skipped.
+///
+/// A statement that cannot be read is skipped up to the next `.`.
+fn parse_turtle(text: &str, blank_nodes: &mut usize) -> Vec<Triple> {
+    let tokens = tokenize(text);
+    let mut prefixes: Vec<(String, String)> = Vec::new();
+    let mut triples = Vec::new();
+
+    let mut idx = 0;
+    while idx < tokens.len() {
+        match &tokens[idx] {
+            Token::Name(name) if name == "@prefix" || name.eq_ignore_ascii_case("prefix") => {
+                if let (Some(Token::Name(prefix)), Some(Token::Iri(iri))) =
+                    (tokens.get(idx + 1), tokens.get(idx + 2))
+                {
+                    prefixes.push((prefix.trim_end_matches(':').to_string(), iri.clone()));
+                }
+                idx += 3;
+                if tokens.get(idx) == Some(&Token::Punct('.')) {
+                    idx += 1;
+                }
+            }
+            Token::Name(name) if name.starts_with('@') => {
+                // @base and other directives
+                while idx < tokens.len() && tokens[idx] != Token::Punct('.') {
+                    idx += 1;
+                }
+                idx += 1;
+            }
+            _ => {
+                let mut parser = Parser {
+                    tokens: &tokens,
+                    idx,
+                    prefixes: &prefixes,
+                    triples: &mut triples,
+                    blank_nodes,
+                };
+                parser.statement();
+                idx = parser.idx;
+            }
+        }
+    }
+
+    triples
+}
+
+struct Parser<'a> {
+    tokens: &'a [Token],
+    idx: usize,
+    prefixes: &'a [(String, String)],
+    triples: &'a mut Vec<Triple>,
+    blank_nodes: &'a mut usize,
+}
+
+impl<'a> Parser<'a> {
+    fn next(&mut self) -> Option<&'a Token> {
+        let token = self.tokens.get(self.idx);
+        self.idx += 1;
+        token
+    }
+
+    fn peek(&self) -> Option<&'a Token> {
+        self.tokens.get(self.idx)
+    }
+
+    fn statement(&mut self) {
+        match self.term() {
+            Some(subject) => self.predicate_objects(&subject),
+            None => self.skip_statement(),
+        }
+
+        if self.peek() == Some(&Token::Punct('.')) {
+            self.idx += 1;
+        }
+    }
+
+    fn skip_statement(&mut self) {
+        while let Some(token) = self.next() {
+            if token == &Token::Punct('.') {
+                break;
+            }
+        }
+    }
+
+    fn predicate_objects(&mut self, subject: &str) {
+        loop {
+            let predicate = match self.peek() {
+                Some(Token::Name(name)) if name == "a" => {
+                    self.idx += 1;
+                    RDF_TYPE.to_string()
+                }
+                Some(Token::Iri(_)) | Some(Token::Name(_)) => match self.term() {
+                    Some(predicate) => predicate,
+                    None => return,
+                },
+                _ => return,
+            };
+
+            loop {
+                let object = match self.term() {
+                    Some(object) => object,
+                    None => return,
+                };
+
+                self.triples.push(Triple {
+                    subject: subject.to_string(),
+                    predicate: predicate.clone(),
+                    object,
+                });
+
+                if self.peek() == Some(&Token::Punct(',')) {
+                    self.idx += 1;
+                } else {
+                    break;
+                }
+            }
+
+            if self.peek() == Some(&Token::Punct(';')) {
+                self.idx += 1;
+                // A trailing ';' is allowed before the end of the statement
+                while self.peek() == Some(&Token::Punct(';')) {
+                    self.idx += 1;
+                }
+            } else {
+                return;
+            }
+        }
+    }
+
+    fn term(&mut self) -> Option<String> {
+        match self.next()? {
+            Token::Iri(iri) => Some(iri.clone()),
+            Token::Literal(literal) => Some(literal.clone()),
+            Token::Name(name) => Some(self.expand(name)),
+            Token::Punct('[') => {
+                *self.blank_nodes += 1;
+                let node = format!("_:b{}", self.blank_nodes);
+
+                self.predicate_objects(&node);
+                if self.peek() == Some(&Token::Punct(']')) {
+                    self.idx += 1;
+                }
+
+                Some(node)
+            }
+            Token::Punct('(') => {
+                // Collections are skipped
+                let mut depth = 1;
+                while depth > 0 {
+                    match self.next()? {
+                        Token::Punct('(') => depth += 1,
+                        Token::Punct(')') => depth -= 1,
+                        _ => {}
+                    }
+                }
+                Some(String::new())
+            }
+            Token::Punct(_) => None,
+        }
+    }
+
+    fn expand(&self, name: &str) -> String {
+        if let Some((prefix, local)) = name.split_once(':') {
+            if let Some((_, iri)) = self.prefixes.iter().find(|(p, _)| p == prefix) {
+                return format!("{}{}", iri, local);
+            }
+        }
+
+        name.to_string()
+    }
+}
+
+fn tokenize(text: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut chars = text.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '#' => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        break;
+                    }
+                }
+            }
+            '<' => {
+                chars.next();
+                let iri: String = chars.by_ref().take_while(|&c| c != '>').collect();
+                tokens.push(Token::Iri(iri));
+            }
+            '"' | '\'' => {
+                chars.next();
+                let mut literal = String::new();
+
+                let long = {
+                    let mut lookahead = chars.clone();
+                    lookahead.next() == Some(c) && lookahead.next() == Some(c)
+                };
+
+                if long {
+                    chars.next();
+                    chars.next();
+
+                    let closing = format!("{}{}", c, c);
+                    for next in chars.by_ref() {
+                        if next == c && literal.ends_with(closing.as_str()) {
+                            literal.truncate(literal.len() - 2);
+                            break;
+                        }
+                        literal.push(next);
+                    }
+                } else {
+                    while let Some(next) = chars.next() {
+                        match next {
+                            '\\' => {
+                                if let Some(escaped) = chars.next() {
+                                    literal.push(match escaped {
+                                        'n' => '\n',
+                                        't' => '\t',
+                                        other => other,
+                                    });
+                                }
+                            }
+                            next if next == c => break,
+                            next => literal.push(next),
+                        }
+                    }
+                }
+
+                // Language tag or datatype
+                if chars.peek() == Some(&'@') || chars.peek() == Some(&'^') {
+                    while let Some(&next) = chars.peek() {
+                        if next.is_whitespace() || ",;.]".contains(next) {
+                            break;
+                        }
+                        chars.next();
+                        if next == '<' {
+                            for c in chars.by_ref() {
+                                if c == '>' {
+                                    break;
+                                }
+                            }
+                        }
+                    }
+                }
+
+                tokens.push(Token::Literal(literal));
+            }
+            ',' | ';' | '[' | ']' | '(' | ')' => {
+                chars.next();
+                tokens.push(Token::Punct(c));
+            }
+            '.' => {
+                chars.next();
+                tokens.push(Token::Punct('.'));
+            }
+            _ => {
+                let mut name = String::new();
+
+                while let Some(&next) = chars.peek() {
+                    if next.is_whitespace() || ",;[]()<\"".contains(next) {
+                        break;
+                    }
+                    // A '.' ends the statement unless it is inside a name
+                    if next == '.' {
+                        let mut lookahead = chars.clone();
+                        lookahead.next();
+                        match lookahead.peek() {
+                            Some(&after) if !after.is_whitespace() && after != '#' => {}
+                            _ => break,
+                        }
+                    }
+                    name.push(next);
+                    chars.next();
+                }
+
+                tokens.push(Token::Name(name));
+            }
+        }
+    }
+
+    tokens
+}
@@ -0,0 +1,151 @@
+mod clap;
+mod lv2;
+
+use std::env;
+use std::path::{Path, PathBuf};
+
+use log::warn;
+use nodio_core::{PluginConfig, PluginFormat, Result};
+
+use crate::Effect;
+
+/// Plugin found in the plugin search paths.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PluginDescriptor {
+    pub format: PluginFormat,
+    /// Path of the plugin library (CLAP) or bundle (LV2)
+    pub path: PathBuf,
+    /// Plugin id (CLAP) or URI (LV2)
+    pub id: String,
+    pub name: String,
+    pub vendor: String,
+}
+
+/// Standard search paths of the plugin format, with the paths in `CLAP_PATH` or `LV2_PATH` first.
+pub fn search_paths(format: PluginFormat) -> Vec<PathBuf> {
+    let env_var = match format {
+        PluginFormat::Clap => "CLAP_PATH",
+        PluginFormat::Lv2 => "LV2_PATH",
+    };
+
+    let mut paths: Vec<PathBuf> = env::var_os(env_var)
+        .map(|value| env::split_paths(&value).collect())
+        .unwrap_or_default();
+
+    let dir_name = match format {
+        PluginFormat::Clap => "CLAP",
+        PluginFormat::Lv2 => "LV2",
+    };
+
+    if cfg!(target_os = "windows") {
+        if let Some(dir) = env::var_os("COMMONPROGRAMFILES") {
+            paths.push(Path::new(&dir).join(dir_name));
+        }
+
+        match format {
+            PluginFormat::Clap => {
+                if let Some(dir) = env::var_os("LOCALAPPDATA") {
+                    paths.push(Path::new(&dir).join("Programs\\Common\\CLAP"));
+                }
+            }
+            PluginFormat::Lv2 => {
+                if let Some(dir) = env::var_os("APPDATA") {
+                    paths.push(Path::new(&dir).join("LV2"));
+                }
+            }
+        }
+    } else if cfg!(target_os = "macos") {
+        if let Some(home) = env::var_os("HOME") {
+            paths.push(
+                Path::new(&home)
+                    .join("Library/Audio/Plug-Ins")
+                    .join(dir_name),
+            );
+        }
+        paths.push(Path::new("/Library/Audio/Plug-Ins").join(dir_name));
+    } else {
+        let dir_name = match format {
+            PluginFormat::Clap => "clap",
+            PluginFormat::Lv2 => "lv2",
+        };
+
+        if let Some(home) = env::var_os("HOME") {
+            paths.push(Path::new(&home).join(format!(".{}", dir_name)));
+        }
+        paths.push(Path::new("/usr/local/lib").join(dir_name));
+        paths.push(Path::new("/usr/lib").join(dir_name));
+    }
+
+    paths
+}
+
+/// Finds the plugins of all formats in the standard search paths.
+pub fn discover_plugins() -> Vec<PluginDescriptor> {
+    [PluginFormat::Clap, PluginFormat::Lv2]
+        .into_iter()
+        .flat_map(|format| discover_plugins_in(format, &search_paths(format)))
+        .collect()
+}
+
+/// Finds the plugins of the given format in the given directories.
+pub fn discover_plugins_in(format: PluginFormat, paths: &[PathBuf]) -> Vec<PluginDescriptor> {
+    let mut plugins = Vec::new();
+
+    for path in paths.iter().filter(|path| path.is_dir()) {
+        match format {
+            PluginFormat::Clap => clap::discover(path, &mut plugins),
+            PluginFormat::Lv2 => lv2::discover(path, &mut plugins),
+        }
+    }
+
+    plugins
+}
+
+/// Loads the plugin to find out its parameters, and creates a configuration for
+/// adding it into an effects chain.
+pub fn plugin_config(descriptor: &PluginDescriptor) -> Result<PluginConfig> {
+    match descriptor.format {
+        PluginFormat::Clap => clap::plugin_config(descriptor),
+        PluginFormat::Lv2 => lv2::plugin_config(descriptor),
+    }
+}
+
+pub(crate) fn load_plugin(config: &PluginConfig, sample_rate: f32) -> Box<dyn Effect> {
+    let plugin: Result<Box<dyn Effect>> = match config.format {
+        PluginFormat::Clap => {
+            clap::ClapEffect::new(config, sample_rate).map(|effect| Box::new(effect) as _)
+        }
+        PluginFormat::Lv2 => {
+            lv2::Lv2Effect::new(config, sample_rate).map(|effect| Box::new(effect) as _)
+        }
+    };
+
+    plugin.unwrap_or_else(|err| {
+        warn!("Could not load plugin {}: {:?}", config.name, err);
+
+        Box::new(MissingPlugin {
+            params: config.params.iter().map(|param| param.default).collect(),
+        })
+    })
+}
+
+/// Stands in for a plugin that could not be loaded, passing the audio through as is.
+struct MissingPlugin {
+    params: Vec<f32>,
+}
+
+impl Effect for MissingPlugin {
+    fn set_param(&mut self, idx: usize, value: f32) {
+        if let Some(param) = self.params.get_mut(idx) {
+            *param = value;
+        }
+    }
+
+    fn param(&self, idx: usize) -> f32 {
+        self.params.get(idx).copied().unwrap_or_default()
+    }
+
+    fn process(&mut self, _block: &mut [f32], _channels: usize) {}
+
+    fn reset(&mut self) {}
+}
@@ -0,0 +1,314 @@
+//! Minimal FLAC encoder using the fixed predictors of the format.
+
+use std::io::{self, Seek, SeekFrom, Write};
+
+const BLOCK_SIZE: usize = 4096;
+const MAX_RICE_PARAM: u32 = 30;
+
+/// Writes interleaved integer samples into a FLAC stream.
+pub struct FlacWriter<W: Write + Seek> {
+    writer: W,
+    sample_rate: u32,
+    channels: usize,
+    bits: u32,
+    /// Samples of the current block per channel
+    block: Vec<Vec<i32>>,
+    frame_number: u64,
+    total_samples: u64,
+}
+
+impl<W: Write + Seek> FlacWriter<W> {
+    pub fn new(mut writer: W, sample_rate: u32, channels: usize, bits: u32) -> io::Result<Self> {
+        if !(1..=8).contains(&channels) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "FLAC supports 1 to 8 channels",
+            ));
+        }
+
+        writer.write_all(b"fLaC")?;
+        // Last metadata block, STREAMINFO, 34 bytes
+        writer.write_all(&[0x80, 0, 0, 34])?;
+        writer.write_all(&streaminfo(sample_rate, channels, bits, 0))?;
+
+        Ok(Self {
+            writer,
+            sample_rate,
+            channels,
+            bits,
+            block: vec![Vec::with_capacity(BLOCK_SIZE); channels],
+            frame_number: 0,
+            total_samples: 0,
+        })
+    }
+
+    pub fn write_samples(&mut self, samples: &[i32]) -> io::Result<()> {
+        for frame in samples.chunks_exact(self.channels) {
+            for (channel, &sample) in self.block.iter_mut().zip(frame) {
+                channel.push(sample);
+            }
+
+            if self.block[0].len() == BLOCK_SIZE {
+                self.write_frame()?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Writes the last block and the total number of samples.
+    pub fn finalize(mut self) -> io::Result<()> {
+        if !self.block[0].is_empty() {
+            self.write_frame()?;
+        }
+
+        self.writer.seek(SeekFrom::Start(8))?;
+        self.writer.write_all(&streaminfo(
+            self.sample_rate,
+            self.channels,
+            self.bits,
+            self.total_samples,
+        ))?;
+        self.writer.seek(SeekFrom::End(0))?;
+        self.writer.flush()
+    }
+
+    fn write_frame(&mut self) -> io::Result<()> {
+        let block_size = self.block[0].len();
+        let mut bits = BitWriter::default();
+
+        // Sync code, fixed block size
+        bits.write(0xfff8, 16);
+        // Block size in 16 bits at the end of the header, sample rate and size from STREAMINFO
+        bits.write(0b0111, 4);
+        bits.write(0b0000, 4);
+        bits.write(self.channels as u64 - 1, 4);
+        bits.write(sample_size_code(self.bits) << 1, 4);
+        write_utf8(&mut bits, self.frame_number);
+        bits.write(block_size as u64 - 1, 16);
+
+        let crc = crc8(&bits.bytes);
+        bits.write(crc as u64, 8);
+
+        for channel in &self.block {
+            write_subframe(&mut bits, channel, self.bits);
+        }
+
+        bits.align();
+        let crc = crc16(&bits.bytes);
+        bits.write(crc as u64, 16);
+
+        self.writer.write_all(&bits.bytes)?;
+
+        self.frame_number += 1;
+        self.total_samples += block_size as u64;
+        for channel in &mut self.block {
+            channel.clear();
+        }
+
+        Ok(())
+    }
+}
+
+/// Sample size of the frame header. Decoders may not support taking it from STREAMINFO.
+fn sample_size_code(bits: u32) -> u64 {
+    match bits {
+        8 => 0b001,
+        12 => 0b010,
+        16 => 0b100,
+        20 => 0b101,
+        24 => 0b110,
+        _ => 0b000,
+    }
+}
+
+fn streaminfo(sample_rate: u32, channels: usize, bits: u32, total_samples: u64) -> [u8; 34] {
+    let mut info = [0; 34];
+
+    info[0..2].copy_from_slice(&(BLOCK_SIZE as u16).to_be_bytes());
+    info[2..4].copy_from_slice(&(BLOCK_SIZE as u16).to_be_bytes());
+    // Frame sizes (bytes 4..10) and MD5 signature (bytes 18..34) are left unknown
+    let packed = (sample_rate as u64) << 44
+        | (channels as u64 - 1) << 41
+        | (bits as u64 - 1) << 36
+        | (total_samples & 0xf_ffff_ffff);
+    info[10..18].copy_from_slice(&packed.to_be_bytes());
+
+    info
+}
+
+fn write_subframe(bits: &mut BitWriter, samples: &[i32], sample_bits: u32) {
+    let sample_bits = sample_bits as usize;
+
+    if samples.iter().all(|&sample| sample == samples[0]) {
+        bits.write(0b0000_0000, 8);
+        bits.write_signed(samples[0] as i64, sample_bits);
+        return;
+    }
+
+    // The order with the smallest residual is assumed to compress best
+    let max_order = 4.min(samples.len() - 1);
+    let (order, residual) = (0..=max_order)
+        .map(|order| (order, fixed_residual(samples, order)))
+        .min_by_key(|(_, residual)| residual.iter().map(|r| r.unsigned_abs()).sum::<u64>())
+        .unwrap();
+
+    let (rice_param, rice_bits) = rice_parameter(&residual);
+    let fixed_bits = order * sample_bits + 11 + rice_bits as usize;
+
+    if fixed_bits >= samples.len() * sample_bits {
+        bits.write(0b0000_0010, 8);
+        for &sample in samples {
+            bits.write_signed(sample as i64, sample_bits);
+        }
+        return;
+    }
+
+    bits.write(0b0001_0000 | (order as u64) << 1, 8);
+    for &sample in &samples[..order] {
+        bits.write_signed(sample as i64, sample_bits);
+    }
+
+    // Rice coding with 5 bit parameters in a single partition
+    bits.write(0b01, 2);
+    bits.write(0, 4);
+    bits.write(rice_param as u64, 5);
+    for &r in &residual {
+        let value = zigzag(r);
+        bits.write_unary(value >> rice_param);
+        bits.write(value & ((1 << rice_param) - 1), rice_param as usize);
+    }
+}
+
+fn fixed_residual(samples: &[i32], order: usize) -> Vec<i64> {
+    let s = |idx: usize| samples[idx] as i64;
+
+    (order..samples.len())
+        .map(|i| match order {
+            0 => s(i),
+            1 => s(i) - s(i - 1),
+            2 => s(i) - 2 * s(i - 1) + s(i - 2),
+            3 => s(i) - 3 * s(i - 1) + 3 * s(i - 2) - s(i - 3),
+            _ => s(i) - 4 * s(i - 1) + 6 * s(i - 2) - 4 * s(i - 3) + s(i - 4),
+        })
+        .collect()
+}
+
+/// Chooses the Rice parameter for the residual, returning it with the number of bits needed.
+fn rice_parameter(residual: &[i64]) -> (u32, u64) {
+    let cost = |param: u32| {
+        residual
+            .iter()
+            .map(|&r| (zigzag(r) >> param) + 1 + param as u64)
+            .sum::<u64>()
+    };
+
+    let mean = residual.iter().map(|&r| zigzag(r)).sum::<u64>() / residual.len().max(1) as u64;
+    let estimate = if mean == 0 {
+        0
+    } else {
+        (63 - mean.leading_zeros()).min(MAX_RICE_PARAM)
+    };
+
+    // The estimate is off by at most one
+    (estimate.saturating_sub(1)..=(estimate + 1).min(MAX_RICE_PARAM))
+        .map(|param| (param, cost(param)))
+        .min_by_key(|&(_, bits)| bits)
+        .unwrap()
+}
+
+fn zigzag(value: i64) -> u64 {
+    ((value << 1) ^ (value >> 63)) as u64
+}
+
+fn write_utf8(bits: &mut BitWriter, value: u64) {
+    if value < 0x80 {
+        bits.write(value, 8);
+        return;
+    }
+
+    let continuation_bytes = match value {
+        0..=0x7ff => 1,
+        0x800..=0xffff => 2,
+        0x1_0000..=0x1f_ffff => 3,
+        0x20_0000..=0x3ff_ffff => 4,
+        _ => 5,
+    };
+
+    let prefix = !(0xffu64 >> (continuation_bytes + 1)) & 0xff;
+    bits.write(prefix | value >> (6 * continuation_bytes), 8);
+    for idx in (0..continuation_bytes).rev() {
+        bits.write(0x80 | (value >> (6 * idx)) & 0x3f, 8);
+    }
+}
+
+fn crc8(data: &[u8]) -> u8 {
+    data.iter().fold(0u8, |mut crc, &byte| {
+        crc ^= byte;
+        for _ in 0..8 {
+            crc = if crc & 0x80 != 0 {
+                crc << 1 ^ 0x07
+            } else {
+                crc << 1
+            };
+        }
+        crc
+    })
+}
+
+fn crc16(data: &[u8]) -> u16 {
+    data.iter().fold(0u16, |mut crc, &byte| {
+        crc ^= (byte as u16) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 {
+                crc << 1 ^ 0x8005
+            } else {
+                crc << 1
+            };
+        }
+        crc
+    })
+}
+
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    acc: u64,
+    len: usize,
+}
+
+impl BitWriter {
+    /// Writes the lowest `count` bits of the value, at most 32.
+    fn write(&mut self, value: u64, count: usize) {
+        if count == 0 {
+            return;
+        }
+
+        self.acc = self.acc << count | (value & ((1 << count) - 1));
+        self.len += count;
+
+        while self.len >= 8 {
+            self.len -= 8;
+            self.bytes.push((self.acc >> self.len) as u8);
+        }
+    }
+
+    fn write_signed(&mut self, value: i64, count: usize) {
+        self.write(value as u64, count);
+    }
+
+    fn write_unary(&mut self, zeros: u64) {
+        let mut zeros = zeros;
+        while zeros >= 32 {
+            self.write(0, 32);
+            zeros -= 32;
+        }
+        self.write(1, zeros as usize + 1);
+    }
+
+    fn align(&mut self) {
+        if self.len > 0 {
+            self.write(0, 8 - self.len);
+        }
+    }
+}
@@ -0,0 +1,392 @@
+mod flac;
+
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use hound::{SampleFormat, WavSpec, WavWriter};
+use log::{info, warn};
+use nodio_core::{
+    Error, RecorderConfig, RecorderState, RecorderStatus, RecordingFormat, Result, Rotation,
+};
+
+use flac::FlacWriter;
+
+/// Writes the audio of a recorder node into files.
+pub struct Recorder {
+    config: RecorderConfig,
+    /// Used as the start of the file names
+    name: String,
+    sample_rate: u32,
+    channels: u16,
+    state: RecorderState,
+    file: Option<RecordingFile>,
+    /// Frames recorded in the current session
+    frames: u64,
+    /// Bytes of the files already finished in the current session
+    finished_bytes: u64,
+}
+
+impl Recorder {
+    pub fn new(config: RecorderConfig, name: &str, sample_rate: u32, channels: u16) -> Self {
+        Self {
+            config,
+            name: name.to_string(),
+            sample_rate,
+            channels,
+            state: RecorderState::Stopped,
+            file: None,
+            frames: 0,
+            finished_bytes: 0,
+        }
+    }
+
+    /// Changes the name that the following files are named after.
+    pub fn set_name(&mut self, name: &str) {
+        self.name = name.to_string();
+    }
+
+    pub fn state(&self) -> RecorderState {
+        self.state
+    }
+
+    /// Changes the configuration. A running recording continues in a new file.
+    pub fn set_config(&mut self, config: RecorderConfig) -> Result<()> {
+        if config == self.config {
+            return Ok(());
+        }
+
+        self.config = config;
+
+        if self.file.is_some() {
+            self.rotate()?;
+        }
+
+        Ok(())
+    }
+
+    pub fn set_state(&mut self, state: RecorderState) -> Result<()> {
+        match state {
+            RecorderState::Recording => self.start(),
+            RecorderState::Paused => {
+                self.pause();
+                Ok(())
+            }
+            RecorderState::Stopped => self.stop(),
+        }
+    }
+
+    /// Starts a new recording, or resumes a paused one.
+    pub fn start(&mut self) -> Result<()> {
+        match self.state {
+            RecorderState::Recording => {}
+            RecorderState::Paused => self.state = RecorderState::Recording,
+            RecorderState::Stopped => {
+                self.frames = 0;
+                self.finished_bytes = 0;
+                self.file = Some(self.create_file()?);
+                self.state = RecorderState::Recording;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn pause(&mut self) {
+        if self.state == RecorderState::Recording {
+            self.state = RecorderState::Paused;
+        }
+    }
+
+    pub fn stop(&mut self) -> Result<()> {
+        self.state = RecorderState::Stopped;
+
+        if let Some(file) = self.file.take() {
+            self.finished_bytes += file.finish()?;
+        }
+
+        Ok(())
+    }
+
+    /// Records a block of interleaved samples. Nothing is written unless recording.
+    ///
+    /// The recording is stopped if writing fails.
+    pub fn write(&mut self, samples: &[f32]) -> Result<()> {
+        if let Err(err) = self.write_samples(samples) {
+            warn!("Recording of {} failed: {:?}", self.name, err);
+            self.stop().ok();
+            return Err(err);
+        }
+
+        Ok(())
+    }
+
+    fn write_samples(&mut self, mut samples: &[f32]) -> Result<()> {
+        if self.state != RecorderState::Recording {
+            return Ok(());
+        }
+
+        let channels = self.channels as usize;
+
+        while samples.len() >= channels {
+            let file = match &mut self.file {
+                Some(file) => file,
+                None => return Ok(()),
+            };
+
+            let mut frames = samples.len() / channels;
+            if let Rotation::Time { minutes } = self.config.rotation {
+                let limit = minutes.max(1) as u64 * 60 * self.sample_rate as u64;
+                frames = frames.min(limit.saturating_sub(file.frames) as usize);
+            }
+
+            let (block, rest) = samples.split_at(frames * channels);
+            file.write(block)?;
+            file.frames += frames as u64;
+            self.frames += frames as u64;
+            samples = rest;
+
+            let full = match self.config.rotation {
+                Rotation::Never => false,
+                Rotation::Size { megabytes } => {
+                    file.bytes() >= megabytes.max(1) as u64 * 1024 * 1024
+                }
+                Rotation::Time { minutes } => {
+                    file.frames >= minutes.max(1) as u64 * 60 * self.sample_rate as u64
+                }
+            };
+
+            if full {
+                self.rotate()?;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn status(&self) -> RecorderStatus {
+        RecorderStatus {
+            state: self.state,
+            elapsed_secs: self.frames as f64 / self.sample_rate as f64,
+            bytes: self.finished_bytes + self.file.as_ref().map_or(0, |file| file.bytes()),
+            file: self
+                .file
+                .as_ref()
+                .map(|file| file.path.to_string_lossy().into_owned()),
+        }
+    }
+
+    /// Finishes the current file and continues in a new one.
+    fn rotate(&mut self) -> Result<()> {
+        if let Some(file) = self.file.take() {
+            self.finished_bytes += file.finish()?;
+        }
+
+        self.file = Some(self.create_file()?);
+
+        Ok(())
+    }
+
+    fn create_file(&self) -> Result<RecordingFile> {
+        let directory = Path::new(&self.config.directory);
+        fs::create_dir_all(directory).map_err(io_error)?;
+
+        let path = file_path(directory, &self.name, self.config.format.extension());
+        info!("Recording {} to {}", self.name, path.display());
+
+        RecordingFile::create(path, self.config.format, self.sample_rate, self.channels)
+    }
+}
+
+impl Drop for Recorder {
+    fn drop(&mut self) {
+        if let Err(err) = self.stop() {
+            warn!("Could not finish recording: {:?}", err);
+        }
+    }
+}
+
+/// New file named after the recorded node and the current time.
+fn file_path(directory: &Path, name: &str, extension: &str) -> PathBuf {
+    let name: String = name
+        .chars()
+        .map(|c| match c {
+            '<' | '>' | ':' | '"' | '/' | '\\' | '|' | '?' | '*' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+    let stem = format!(
+        "{} {}",
+        name.trim(),
+        chrono::Local::now().format("%Y-%m-%d %H-%M-%S")
+    );
+
+    let mut path = directory.join(format!("{}.{}", stem, extension));
+    let mut idx = 2;
+    while path.exists() {
+        path = directory.join(format!("{} ({}).{}", stem, idx, extension));
+        idx += 1;
+    }
+
+    path
+}
+
+fn io_error(err: io::Error) -> Error {
+    Error::Other(err.to_string())
+}
+
+struct RecordingFile {
+    path: PathBuf,
+    writer: FileWriter,
+    bytes: Arc<AtomicU64>,
+    frames: u64,
+}
+
+enum FileWriter {
+    Wav {
+        writer: WavWriter<CountingWriter>,
+        format: RecordingFormat,
+    },
+    Flac {
+        writer: FlacWriter<CountingWriter>,
+        scale: f32,
+    },
+}
+
+impl RecordingFile {
+    fn create(
+        path: PathBuf,
+        format: RecordingFormat,
+        sample_rate: u32,
+        channels: u16,
+    ) -> Result<Self> {
+        let bytes = Arc::new(AtomicU64::new(0));
+        let file = CountingWriter {
+            inner: BufWriter::new(File::create(&path).map_err(io_error)?),
+            pos: 0,
+            bytes: bytes.clone(),
+        };
+
+        let writer = match format {
+            RecordingFormat::Wav16 | RecordingFormat::Wav24 | RecordingFormat::WavFloat => {
+                let (bits_per_sample, sample_format) = match format {
+                    RecordingFormat::Wav16 => (16, SampleFormat::Int),
+                    RecordingFormat::Wav24 => (24, SampleFormat::Int),
+                    _ => (32, SampleFormat::Float),
+                };
+
+                let spec = WavSpec {
+                    channels,
+                    sample_rate,
+                    bits_per_sample,
+                    sample_format,
+                };
+
+                FileWriter::Wav {
+                    writer: WavWriter::new(file, spec)
+                        .map_err(|err| Error::Other(err.to_string()))?,
+                    format,
+                }
+            }
+            RecordingFormat::Flac16 | RecordingFormat::Flac24 => {
+                let bits = if format == RecordingFormat::Flac16 {
+                    16
+                } else {
+                    24
+                };
+
+                FileWriter::Flac {
+                    writer: FlacWriter::new(file, sample_rate, channels as usize, bits)
+                        .map_err(io_error)?,
+                    scale: int_scale(bits),
+                }
+            }
+        };
+
+        Ok(Self {
+            path,
+            writer,
+            bytes,
+            frames: 0,
+        })
+    }
+
+    fn bytes(&self) -> u64 {
+        self.bytes.load(Ordering::Relaxed)
+    }
+
+    fn write(&mut self, samples: &[f32]) -> Result<()> {
+        match &mut self.writer {
+            FileWriter::Wav { writer, format } => {
+                for &sample in samples {
+                    match format {
+                        RecordingFormat::WavFloat => writer.write_sample(sample),
+                        RecordingFormat::Wav24 => {
+                            writer.write_sample(to_int(sample, int_scale(24)))
+                        }
+                        _ => writer.write_sample(to_int(sample, int_scale(16)) as i16),
+                    }
+                    .map_err(|err| Error::Other(err.to_string()))?;
+                }
+                Ok(())
+            }
+            FileWriter::Flac { writer, scale } => {
+                let samples: Vec<i32> = samples
+                    .iter()
+                    .map(|&sample| to_int(sample, *scale))
+                    .collect();
+                writer.write_samples(&samples).map_err(io_error)
+            }
+        }
+    }
+
+    /// Completes the file, returning its size.
+    fn finish(self) -> Result<u64> {
+        match self.writer {
+            FileWriter::Wav { writer, .. } => writer
+                .finalize()
+                .map_err(|err| Error::Other(err.to_string())),
+            FileWriter::Flac { writer, .. } => writer.finalize().map_err(io_error),
+        }?;
+
+        Ok(self.bytes.load(Ordering::Relaxed))
+    }
+}
+
+fn int_scale(bits: u32) -> f32 {
+    ((1 << (bits - 1)) - 1) as f32
+}
+
+fn to_int(sample: f32, scale: f32) -> i32 {
+    (sample.clamp(-1.0, 1.0) * scale).round() as i32
+}
+
+/// File writer keeping track of the size of the file.
+struct CountingWriter {
+    inner: BufWriter<File>,
+    pos: u64,
+    bytes: Arc<AtomicU64>,
+}
+
+impl Write for CountingWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.pos += written as u64;
+        self.bytes.fetch_max(self.pos, Ordering::Relaxed);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl Seek for CountingWriter {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.pos = self.inner.seek(pos)?;
+        Ok(self.pos)
+    }
+}
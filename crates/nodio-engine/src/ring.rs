@@ -0,0 +1,110 @@
+use std::cell::UnsafeCell;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// Single producer, single consumer ring buffer of samples, for passing audio between a
+/// worker thread and an audio callback without locking.
+pub fn ring_buffer(capacity: usize) -> (Producer, Consumer) {
+    let ring = Arc::new(Ring {
+        buffer: (0..capacity + 1).map(|_| UnsafeCell::new(0.0)).collect(),
+        read: AtomicUsize::new(0),
+        write: AtomicUsize::new(0),
+    });
+
+    (Producer { ring: ring.clone() }, Consumer { ring })
+}
+
+struct Ring {
+    /// One slot is always left empty to tell a full buffer from an empty one
+    buffer: Box<[UnsafeCell<f32>]>,
+    read: AtomicUsize,
+    write: AtomicUsize,
+}
+
+// The producer only writes to the free slots and the consumer only reads the filled ones
+unsafe impl Sync for Ring {}
+
+impl Ring {
+    fn len(&self) -> usize {
+        let read = self.read.load(Ordering::Acquire);
+        let write = self.write.load(Ordering::Acquire);
+
+        (write + self.buffer.len() - read) % self.buffer.len()
+    }
+
+    fn capacity(&self) -> usize {
+        self.buffer.len() - 1
+    }
+}
+
+pub struct Producer {
+    ring: Arc<Ring>,
+}
+
+impl Producer {
+    /// Number of samples that can be pushed without overwriting unread samples.
+    pub fn free(&self) -> usize {
+        self.ring.capacity() - self.ring.len()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.ring.capacity()
+    }
+
+    /// Pushes as many of the samples as fit, returning their count.
+    pub fn push(&mut self, samples: &[f32]) -> usize {
+        let ring = &self.ring;
+        let count = samples.len().min(self.free());
+        let mut write = ring.write.load(Ordering::Relaxed);
+
+        for &sample in &samples[..count] {
+            unsafe {
+                *ring.buffer[write].get() = sample;
+            }
+            write = (write + 1) % ring.buffer.len();
+        }
+
+        ring.write.store(write, Ordering::Release);
+
+        count
+    }
+}
+
+pub struct Consumer {
+    ring: Arc<Ring>,
+}
+
+impl Consumer {
+    /// Number of samples available for reading.
+    pub fn available(&self) -> usize {
+        self.ring.len()
+    }
+
+    /// Pops samples into the buffer, returning their count.
+    pub fn pop(&mut self, buffer: &mut [f32]) -> usize {
+        let ring = &self.ring;
+        let count = buffer.len().min(self.available());
+        let mut read = ring.read.load(Ordering::Relaxed);
+
+        for sample in &mut buffer[..count] {
+            *sample = unsafe { *ring.buffer[read].get() };
+            read = (read + 1) % ring.buffer.len();
+        }
+
+        ring.read.store(read, Ordering::Release);
+
+        count
+    }
+
+    /// Drops up to `count` samples without reading them, returning the number dropped.
+    pub fn skip(&mut self, count: usize) -> usize {
+        let ring = &self.ring;
+        let count = count.min(self.available());
+        let read = ring.read.load(Ordering::Relaxed);
+
+        ring.read
+            .store((read + count) % ring.buffer.len(), Ordering::Release);
+
+        count
+    }
+}
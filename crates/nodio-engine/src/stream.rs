@@ -0,0 +1,118 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use log::warn;
+use parking_lot::Mutex;
+
+use crate::ring::{ring_buffer, Producer};
+use crate::{FilePlayer, Recorder};
+
+/// How long the writer thread of a [`BufferedSink`] waits while the buffer is empty
+const WRITER_WAIT: Duration = Duration::from_millis(5);
+
+/// Audio that is rendered to output devices.
+pub trait AudioSource {
+    /// Fills the buffer with interleaved samples, returning the number of frames that are not
+    /// silence.
+    fn read(&mut self, buffer: &mut [f32]) -> usize;
+}
+
+/// Takes the audio captured from an application or input device.
+pub trait AudioSink {
+    /// Takes a block of interleaved samples.
+    fn write(&mut self, samples: &[f32]);
+}
+
+impl AudioSource for FilePlayer {
+    fn read(&mut self, buffer: &mut [f32]) -> usize {
+        FilePlayer::read(self, buffer)
+    }
+}
+
+impl AudioSink for Recorder {
+    fn write(&mut self, samples: &[f32]) {
+        // Errors are logged and stop the recorder
+        Recorder::write(self, samples).ok();
+    }
+}
+
+/// Passes the audio written to it through a ring buffer to a sink on a writer thread, so that a
+/// capture callback does not wait for the sink, e.g. for a recorder encoding and writing files.
+///
+/// Blocks that do not fit into the buffer are dropped. The buffered audio is written before the
+/// sink is dropped.
+pub struct BufferedSink {
+    producer: Producer,
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+    /// Whether a block was dropped since the buffer last had room
+    overflowed: bool,
+}
+
+impl Drop for BufferedSink {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+
+        if let Some(thread) = self.thread.take() {
+            thread.join().ok();
+        }
+    }
+}
+
+impl BufferedSink {
+    /// Buffers up to `capacity` samples of interleaved audio with the given number of channels.
+    pub fn new<S: AudioSink + Send + 'static>(
+        sink: Arc<Mutex<S>>,
+        channels: usize,
+        capacity: usize,
+    ) -> Self {
+        let (producer, mut consumer) = ring_buffer(capacity - capacity % channels);
+        let stop = Arc::new(AtomicBool::new(false));
+
+        let thread = thread::spawn({
+            let stop = stop.clone();
+            let mut block = vec![0.0; capacity.max(channels)];
+
+            move || loop {
+                // Read before the buffer is emptied, so nothing written before stopping is lost
+                let stopping = stop.load(Ordering::Acquire);
+
+                let available = consumer.available();
+                let len = available.min(block.len()) / channels * channels;
+                if len > 0 {
+                    consumer.pop(&mut block[..len]);
+                    sink.lock().write(&block[..len]);
+                } else if stopping {
+                    break;
+                } else {
+                    thread::sleep(WRITER_WAIT);
+                }
+            }
+        });
+
+        Self {
+            producer,
+            stop,
+            thread: Some(thread),
+            overflowed: false,
+        }
+    }
+}
+
+impl AudioSink for BufferedSink {
+    fn write(&mut self, samples: &[f32]) {
+        // Whole blocks only, so that the writer never takes part of a frame
+        if samples.len() > self.producer.free() {
+            if !self.overflowed {
+                warn!("Writer thread falls behind, dropping audio");
+                self.overflowed = true;
+            }
+            return;
+        }
+
+        self.producer.push(samples);
+        self.overflowed = false;
+    }
+}
@@ -0,0 +1,57 @@
+use nodio_core::CalibrationStatus;
+use nodio_engine::{delay_frames, Calibrator, DelayLine};
+
+const SAMPLE_RATE: u32 = 48000;
+const CHANNELS: usize = 2;
+
+/// Runs a calibration through a path with the given latency and gain, in blocks of 10 ms.
+fn calibrate(latency_ms: f32, gain: f32, secs: f32) -> CalibrationStatus {
+    let mut calibrator = Calibrator::new(SAMPLE_RATE, CHANNELS);
+    let mut path = DelayLine::with_delay_ms(CHANNELS, SAMPLE_RATE, latency_ms);
+    let mut block = vec![0.0; 480 * CHANNELS];
+
+    for _ in 0..(secs * 100.0) as usize {
+        calibrator.read(&mut block);
+        path.process(&mut block);
+        block.iter_mut().for_each(|sample| *sample *= gain);
+        calibrator.write(&block);
+    }
+
+    calibrator.status()
+}
+
+#[test]
+fn delay_line_delays_by_frames() {
+    let mut delay_line = DelayLine::new(CHANNELS, 2);
+    let mut buffer = [1.0, 2.0, 3.0, 4.0, 5.0, 6.0];
+    delay_line.process(&mut buffer);
+    assert_eq!(buffer, [0.0, 0.0, 0.0, 0.0, 1.0, 2.0]);
+
+    // A shorter delay skips the oldest audio
+    delay_line.set_delay(1);
+    let mut buffer = [7.0, 8.0];
+    delay_line.process(&mut buffer);
+    assert_eq!(buffer, [5.0, 6.0]);
+
+    assert_eq!(delay_frames(10.0, SAMPLE_RATE), 480);
+}
+
+#[test]
+fn measures_latency() {
+    match calibrate(120.0, 0.1, 1.0) {
+        CalibrationStatus::Measured(latency) => {
+            assert!((latency - 120.0).abs() < 0.1, "latency {}", latency)
+        }
+        status => panic!("{:?}", status),
+    }
+}
+
+#[test]
+fn runs_until_the_click_is_heard() {
+    assert_eq!(calibrate(120.0, 0.1, 0.5), CalibrationStatus::Running);
+}
+
+#[test]
+fn fails_when_the_click_is_not_heard() {
+    assert_eq!(calibrate(120.0, 0.0, 5.0), CalibrationStatus::Failed);
+}
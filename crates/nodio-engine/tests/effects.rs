@@ -0,0 +1,198 @@
+use std::f32::consts::PI;
+
+use nodio_core::{db_to_gain, gain_to_db, EffectConfig, EffectKind};
+use nodio_engine::{create_effect, EffectChain};
+
+const SAMPLE_RATE: f32 = 48000.0;
+
+fn sine(freq: f32, amplitude: f32, frames: usize, channels: usize) -> Vec<f32> {
+    (0..frames)
+        .flat_map(|i| {
+            let sample = amplitude * (2.0 * PI * freq * i as f32 / SAMPLE_RATE).sin();
+            std::iter::repeat(sample).take(channels)
+        })
+        .collect()
+}
+
+/// Square wave, so that the level stays constant for the envelope followers.
+fn square(amplitude: f32, frames: usize) -> Vec<f32> {
+    (0..frames)
+        .map(|i| if (i / 24) % 2 == 0 { amplitude } else { -amplitude })
+        .collect()
+}
+
+fn peak(block: &[f32]) -> f32 {
+    block.iter().fold(0.0, |peak, sample| peak.max(sample.abs()))
+}
+
+fn config(kind: EffectKind, params: &[(usize, f32)]) -> EffectConfig {
+    let mut config = EffectConfig::new(kind);
+    for &(idx, value) in params {
+        config.params[idx] = value;
+    }
+    config
+}
+
+#[test]
+fn flat_eq_is_transparent() {
+    let mut eq = create_effect(&EffectConfig::new(EffectKind::ParametricEq), SAMPLE_RATE);
+
+    let input = sine(440.0, 0.5, 4800, 2);
+    let mut block = input.clone();
+    eq.process(&mut block, 2);
+
+    for (a, b) in input.iter().zip(block.iter()) {
+        assert!((a - b).abs() < 1e-4);
+    }
+}
+
+#[test]
+fn eq_mid_band_boosts_center_frequency() {
+    let mut eq = create_effect(
+        &config(EffectKind::ParametricEq, &[(2, 1000.0), (3, 12.0)]),
+        SAMPLE_RATE,
+    );
+
+    let mut block = sine(1000.0, 0.1, 9600, 1);
+    eq.process(&mut block, 1);
+
+    // Skip the transient at the start
+    let gain_db = gain_to_db(peak(&block[4800..]) / 0.1);
+    assert!((gain_db - 12.0).abs() < 0.5, "gain was {} dB", gain_db);
+}
+
+#[test]
+fn compressor_reduces_level_above_threshold() {
+    let mut compressor = create_effect(
+        &config(EffectKind::Compressor, &[(0, -20.0), (1, 4.0)]),
+        SAMPLE_RATE,
+    );
+
+    let mut block = square(0.5, 48000);
+    compressor.process(&mut block, 1);
+
+    // -6 dB input is 14 dB above the threshold, which becomes 3.5 dB at 4:1
+    let level_db = gain_to_db(peak(&block[24000..]));
+    assert!((level_db - -16.5).abs() < 1.0, "level was {} dB", level_db);
+}
+
+#[test]
+fn compressor_leaves_signal_below_threshold() {
+    let mut compressor = create_effect(
+        &config(EffectKind::Compressor, &[(0, -20.0), (1, 4.0)]),
+        SAMPLE_RATE,
+    );
+
+    let mut block = square(0.05, 4800);
+    compressor.process(&mut block, 1);
+
+    assert!((peak(&block) - 0.05).abs() < 1e-6);
+}
+
+#[test]
+fn limiter_never_exceeds_ceiling() {
+    let mut limiter = create_effect(&config(EffectKind::Limiter, &[(0, -6.0)]), SAMPLE_RATE);
+
+    let mut block: Vec<f32> = sine(100.0, 0.2, 4800, 2);
+    block.extend(sine(3000.0, 1.0, 4800, 2));
+    block.extend(sine(50.0, 0.9, 4800, 2));
+    limiter.process(&mut block, 2);
+
+    assert!(peak(&block) <= db_to_gain(-6.0) + 1e-6);
+}
+
+#[test]
+fn noise_gate_mutes_quiet_signal() {
+    let mut gate = create_effect(&config(EffectKind::NoiseGate, &[(0, -40.0)]), SAMPLE_RATE);
+
+    let mut quiet = sine(440.0, 0.001, 9600, 1);
+    gate.process(&mut quiet, 1);
+    assert!(peak(&quiet) < 1e-6);
+
+    let mut loud = sine(440.0, 0.5, 9600, 1);
+    gate.process(&mut loud, 1);
+    assert!((peak(&loud[4800..]) - 0.5).abs() < 1e-3);
+}
+
+#[test]
+fn gain_pan() {
+    let mut gain_pan = create_effect(&config(EffectKind::GainPan, &[(0, -6.0)]), SAMPLE_RATE);
+
+    let mut block = vec![1.0; 8];
+    gain_pan.process(&mut block, 2);
+    assert!(block.iter().all(|s| (s - 0.5).abs() < 0.01));
+
+    gain_pan.set_param(0, 0.0);
+    gain_pan.set_param(1, -1.0);
+
+    let mut block = vec![1.0; 8];
+    gain_pan.process(&mut block, 2);
+    assert_eq!(block, vec![1.0, 0.0, 1.0, 0.0, 1.0, 0.0, 1.0, 0.0]);
+}
+
+#[test]
+fn reset_clears_state() {
+    let mut eq = create_effect(
+        &config(EffectKind::ParametricEq, &[(1, 12.0), (3, 12.0)]),
+        SAMPLE_RATE,
+    );
+
+    let mut block = sine(100.0, 0.5, 480, 1);
+    eq.process(&mut block, 1);
+    eq.reset();
+
+    let mut silence = vec![0.0; 16];
+    eq.process(&mut silence, 1);
+    assert!(silence.iter().all(|s| *s == 0.0));
+}
+
+#[test]
+fn chain_skips_disabled_effects() {
+    let mut gain = config(EffectKind::GainPan, &[(0, -60.0)]);
+    gain.enabled = false;
+
+    let mut chain = EffectChain::new(SAMPLE_RATE);
+    chain.set_effects(&[gain.clone()]);
+    assert!(chain.is_empty());
+
+    let mut block = vec![0.5; 4];
+    chain.process(&mut block, 2);
+    assert_eq!(block, vec![0.5; 4]);
+
+    gain.enabled = true;
+    chain.set_effects(&[gain]);
+    assert!(!chain.is_empty());
+
+    chain.process(&mut block, 2);
+    assert!(peak(&block) < 0.001);
+}
+
+#[test]
+fn chain_keeps_state_when_params_change() {
+    let mut compressor = config(EffectKind::Compressor, &[(0, -20.0), (3, 2000.0)]);
+
+    let mut chain = EffectChain::new(SAMPLE_RATE);
+    chain.set_effects(&[compressor.clone()]);
+
+    let mut block = square(0.5, 48000);
+    chain.process(&mut block, 1);
+
+    // Changing the makeup gain must not reset the gain reduction
+    compressor.params[4] = 1.0;
+    chain.set_effects(&[compressor]);
+
+    let mut block = square(0.5, 480);
+    chain.process(&mut block, 1);
+    assert!(gain_to_db(peak(&block)) < -14.0);
+}
+
+#[test]
+fn unknown_param_reads_zero() {
+    for kind in EffectKind::ALL {
+        let mut effect = create_effect(&EffectConfig::new(kind), SAMPLE_RATE);
+        let count = EffectConfig::new(kind).params.len();
+
+        effect.set_param(count, 1.0);
+        assert_eq!(effect.param(count), 0.0);
+    }
+}
@@ -0,0 +1,150 @@
+use nodio_core::{GeneratorConfig, Waveform};
+use nodio_engine::SignalGenerator;
+
+const SAMPLE_RATE: u32 = 48000;
+
+fn generate(config: GeneratorConfig, channels: usize, secs: f64) -> Vec<f32> {
+    let mut generator = SignalGenerator::new(config, SAMPLE_RATE, channels);
+    let mut output = vec![0.0; (SAMPLE_RATE as f64 * secs) as usize * channels];
+
+    // Read in blocks like an audio thread
+    for block in output.chunks_mut(480 * channels) {
+        generator.read(block);
+    }
+
+    output
+}
+
+fn config(waveform: Waveform) -> GeneratorConfig {
+    GeneratorConfig {
+        waveform,
+        ..GeneratorConfig::default()
+    }
+}
+
+fn zero_crossings(samples: &[f32]) -> usize {
+    samples
+        .windows(2)
+        .filter(|pair| (pair[0] < 0.0) != (pair[1] < 0.0))
+        .count()
+}
+
+fn rms(samples: &[f32]) -> f32 {
+    (samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32).sqrt()
+}
+
+fn peak(samples: &[f32]) -> f32 {
+    samples.iter().fold(0.0, |peak, s| s.abs().max(peak))
+}
+
+/// Channel of interleaved samples.
+fn channel(samples: &[f32], channels: usize, channel: usize) -> Vec<f32> {
+    samples
+        .iter()
+        .skip(channel)
+        .step_by(channels)
+        .copied()
+        .collect()
+}
+
+#[test]
+fn generates_sine() {
+    let output = generate(
+        GeneratorConfig {
+            frequency_hz: 440.0,
+            level_db: -6.0,
+            ..config(Waveform::Sine)
+        },
+        2,
+        1.0,
+    );
+
+    let left = channel(&output, 2, 0);
+    assert_eq!(left, channel(&output, 2, 1));
+
+    // Two zero crossings per cycle
+    let crossings = zero_crossings(&left);
+    assert!((878..=882).contains(&crossings), "{} crossings", crossings);
+
+    let peak = peak(&left);
+    assert!((peak - 0.501).abs() < 0.01, "peak {}", peak);
+}
+
+#[test]
+fn disabled_generator_is_silent() {
+    let mut generator = SignalGenerator::new(
+        GeneratorConfig {
+            enabled: false,
+            ..GeneratorConfig::default()
+        },
+        SAMPLE_RATE,
+        2,
+    );
+
+    let mut buffer = [1.0; 64];
+    assert_eq!(generator.read(&mut buffer), 0);
+    assert!(buffer.iter().all(|&s| s == 0.0));
+}
+
+#[test]
+fn noise_is_deterministic() {
+    for waveform in [Waveform::WhiteNoise, Waveform::PinkNoise] {
+        let output = generate(config(waveform), 1, 1.0);
+        assert_eq!(output, generate(config(waveform), 1, 1.0));
+
+        // -20 dB RMS
+        let rms = rms(&output);
+        assert!((rms - 0.1).abs() < 0.02, "{:?} RMS {}", waveform, rms);
+    }
+}
+
+#[test]
+fn pink_noise_has_less_treble() {
+    // The difference of consecutive samples is dominated by high frequencies
+    let treble = |samples: &[f32]| {
+        let diff: Vec<f32> = samples.windows(2).map(|pair| pair[1] - pair[0]).collect();
+        rms(&diff) / rms(samples)
+    };
+
+    let white = treble(&generate(config(Waveform::WhiteNoise), 1, 1.0));
+    let pink = treble(&generate(config(Waveform::PinkNoise), 1, 1.0));
+
+    assert!(pink < white / 2.0, "pink {} white {}", pink, white);
+}
+
+#[test]
+fn sweeps_up() {
+    let output = generate(
+        GeneratorConfig {
+            frequency_hz: 100.0,
+            sweep_end_hz: 10000.0,
+            sweep_secs: 2.0,
+            ..config(Waveform::Sweep)
+        },
+        1,
+        2.0,
+    );
+
+    let rate = SAMPLE_RATE as usize;
+    let start = zero_crossings(&output[..rate / 10]);
+    let end = zero_crossings(&output[rate * 19 / 10..]);
+
+    // About 100 Hz at the start and 10 kHz at the end
+    assert!((18..=30).contains(&start), "{} crossings", start);
+    assert!((1500..=2000).contains(&end), "{} crossings", end);
+}
+
+#[test]
+fn identifies_channels() {
+    let output = generate(config(Waveform::ChannelId), 2, 2.0);
+    let rate = SAMPLE_RATE as usize;
+    let left = channel(&output, 2, 0);
+    let right = channel(&output, 2, 1);
+
+    // Left, pause, right, pause
+    assert!(peak(&left[..rate / 2]) > 0.09);
+    assert_eq!(peak(&right[..rate / 2]), 0.0);
+    assert_eq!(peak(&left[rate / 2..rate * 3 / 2]), 0.0);
+    assert!(peak(&right[rate..rate * 3 / 2]) > 0.09);
+    assert_eq!(peak(&right[rate * 3 / 2..]), 0.0);
+}
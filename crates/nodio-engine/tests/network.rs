@@ -0,0 +1,261 @@
+use std::net::{SocketAddr, UdpSocket};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use nodio_core::{GeneratorConfig, NetworkConfig, RtpPayload};
+use nodio_engine::{RtpReceiver, RtpSender, SessionDescription, SignalGenerator};
+
+const SAMPLE_RATE: u32 = 48000;
+const CHANNELS: usize = 2;
+
+fn config(address: &str, payload: RtpPayload, latency_ms: u32) -> NetworkConfig {
+    NetworkConfig {
+        address: address.to_string(),
+        payload,
+        payload_type: None,
+        latency_ms,
+        announce: false,
+    }
+}
+
+/// Receiver on a free port of the loopback interface.
+fn loopback_receiver(payload: RtpPayload, latency_ms: u32) -> (RtpReceiver, SocketAddr) {
+    let receiver = RtpReceiver::new(
+        config("127.0.0.1:0", payload, latency_ms),
+        SAMPLE_RATE,
+        CHANNELS,
+    );
+    let address = receiver
+        .local_addr()
+        .expect("Could not bind loopback address");
+    (receiver, address)
+}
+
+/// Waits until the receiver has buffered the given duration.
+fn wait_for_buffered(receiver: &RtpReceiver, ms: f32) {
+    let start = Instant::now();
+
+    while receiver.status().buffered_ms < ms {
+        assert!(
+            start.elapsed() < Duration::from_secs(5),
+            "Timed out with {:?}",
+            receiver.status()
+        );
+        thread::sleep(Duration::from_millis(1));
+    }
+}
+
+fn sine(secs: f64) -> Vec<f32> {
+    let mut generator = SignalGenerator::new(GeneratorConfig::default(), SAMPLE_RATE, CHANNELS);
+    let mut samples = vec![0.0; (SAMPLE_RATE as f64 * secs) as usize * CHANNELS];
+    generator.read(&mut samples);
+    samples
+}
+
+/// RTP packet with 16 bit samples.
+fn l16_packet(sequence: u16, timestamp: u32, samples: &[f32]) -> Vec<u8> {
+    let mut packet = vec![0x80, RtpPayload::L16.payload_type()];
+    packet.extend_from_slice(&sequence.to_be_bytes());
+    packet.extend_from_slice(&timestamp.to_be_bytes());
+    packet.extend_from_slice(&1234u32.to_be_bytes());
+
+    for &sample in samples {
+        packet.extend_from_slice(&((sample * 32767.0) as i16).to_be_bytes());
+    }
+
+    packet
+}
+
+#[test]
+fn streams_over_loopback() {
+    for (payload, tolerance) in [(RtpPayload::L16, 1e-4), (RtpPayload::L24, 1e-6)] {
+        let (mut receiver, address) = loopback_receiver(payload, 50);
+        let mut sender = RtpSender::new(
+            config(&address.to_string(), payload, 0),
+            "Test",
+            SAMPLE_RATE,
+            CHANNELS,
+        );
+
+        // 100 ms sent in blocks of 10 ms, as captured
+        let input = sine(0.1);
+        for block in input.chunks(480 * CHANNELS) {
+            sender.write(block);
+        }
+        assert_eq!(sender.status().packets, 100);
+
+        wait_for_buffered(&receiver, 100.0);
+
+        let mut output = vec![0.0; input.len()];
+        assert_eq!(receiver.read(&mut output), 4800);
+
+        for (idx, (a, b)) in input.iter().zip(&output).enumerate() {
+            assert!((a - b).abs() < tolerance, "{:?} sample {}", payload, idx);
+        }
+
+        let status = receiver.status();
+        assert_eq!(status.packets, 100);
+        assert_eq!(status.lost, 0);
+    }
+}
+
+#[test]
+fn waits_for_latency() {
+    let (mut receiver, address) = loopback_receiver(RtpPayload::L24, 20);
+    let mut sender = RtpSender::new(
+        config(&address.to_string(), RtpPayload::L24, 0),
+        "Test",
+        SAMPLE_RATE,
+        CHANNELS,
+    );
+
+    let input = sine(0.03);
+    sender.write(&input[..480 * CHANNELS]);
+    wait_for_buffered(&receiver, 10.0);
+
+    let mut output = vec![0.0; 96 * CHANNELS];
+    assert_eq!(receiver.read(&mut output), 0);
+    assert!(output.iter().all(|&s| s == 0.0));
+
+    sender.write(&input[480 * CHANNELS..]);
+    wait_for_buffered(&receiver, 30.0);
+    assert_eq!(receiver.read(&mut output), 96);
+    assert!(output.iter().any(|&s| s != 0.0));
+}
+
+#[test]
+fn reorders_packets_and_skips_lost_ones() {
+    let (mut receiver, address) = loopback_receiver(RtpPayload::L16, 3);
+    let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+
+    let packet =
+        |idx: u16, value: f32| l16_packet(idx, idx as u32 * 48, &vec![value; 48 * CHANNELS]);
+
+    // The third packet is lost, the second arrives late
+    for (idx, value) in [(0, 0.1), (3, 0.4), (1, 0.2), (4, 0.5)] {
+        socket.send_to(&packet(idx, value), address).unwrap();
+    }
+    wait_for_buffered(&receiver, 5.0);
+
+    let mut output = vec![0.0; 5 * 48 * CHANNELS];
+    assert_eq!(receiver.read(&mut output), 4 * 48);
+
+    let levels: Vec<f32> = output
+        .chunks(48 * CHANNELS)
+        .map(|packet| (packet[0] * 10.0).round() / 10.0)
+        .collect();
+    assert_eq!(levels, [0.1, 0.2, 0.0, 0.4, 0.5]);
+    assert_eq!(receiver.status().lost, 1);
+
+    // Arrives after it was due
+    socket.send_to(&packet(2, 0.3), address).unwrap();
+    thread::sleep(Duration::from_millis(100));
+    assert_eq!(receiver.status().lost, 2);
+}
+
+#[test]
+fn drops_packets_of_another_payload_type() {
+    let (mut receiver, address) = loopback_receiver(RtpPayload::L16, 0);
+    let mut sender = RtpSender::new(
+        config(&address.to_string(), RtpPayload::L24, 0),
+        "Test",
+        SAMPLE_RATE,
+        CHANNELS,
+    );
+    sender.write(&sine(0.01));
+
+    let start = Instant::now();
+    while receiver.status().error.is_none() {
+        assert!(
+            start.elapsed() < Duration::from_secs(5),
+            "No error reported"
+        );
+        thread::sleep(Duration::from_millis(1));
+    }
+
+    let status = receiver.status();
+    assert_eq!(status.packets, 0);
+    assert_eq!(status.buffered_ms, 0.0);
+    let mut output = vec![0.0; 480 * CHANNELS];
+    assert_eq!(receiver.read(&mut output), 0);
+
+    // Matching packets are received again
+    let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    socket
+        .send_to(&l16_packet(0, 0, &sine(0.001)), address)
+        .unwrap();
+    wait_for_buffered(&receiver, 1.0);
+    assert_eq!(receiver.status().error, None);
+}
+
+#[test]
+fn reports_invalid_addresses() {
+    let sender = RtpSender::new(
+        config("not an address", RtpPayload::L24, 0),
+        "Test",
+        SAMPLE_RATE,
+        CHANNELS,
+    );
+    assert!(sender.status().error.is_some());
+    assert!(sender.status().sdp.is_none());
+
+    let receiver = RtpReceiver::new(
+        config("not an address", RtpPayload::L24, 20),
+        SAMPLE_RATE,
+        CHANNELS,
+    );
+    assert!(receiver.status().error.is_some());
+}
+
+#[test]
+fn describes_stream() {
+    let sender = RtpSender::new(
+        config("239.69.1.10:5004", RtpPayload::L24, 0),
+        "Gaming PC",
+        SAMPLE_RATE,
+        CHANNELS,
+    );
+    let sdp = sender.status().sdp.unwrap();
+    assert!(sdp.contains("c=IN IP4 239.69.1.10/16\r\n"), "{}", sdp);
+    assert!(sdp.contains("a=rtpmap:96 L24/48000/2\r\n"), "{}", sdp);
+
+    let description = SessionDescription::parse(&sdp).unwrap();
+    assert_eq!(description.name, "Gaming PC");
+    assert_eq!(description.address, "239.69.1.10:5004".parse().unwrap());
+    assert_eq!(description.payload, RtpPayload::L24);
+    assert_eq!(description.sample_rate, SAMPLE_RATE);
+    assert_eq!(description.channels, CHANNELS);
+    assert_eq!(description.packet_ms, 1.0);
+}
+
+#[test]
+fn parses_aes67_description() {
+    let sdp = "v=0\r\n\
+               o=- 1311738121 1311738121 IN IP4 192.168.1.34\r\n\
+               s=Stage box 1-8\r\n\
+               c=IN IP4 239.69.83.134/32\r\n\
+               t=0 0\r\n\
+               a=clock-domain:PTPv2 0\r\n\
+               m=audio 5004 RTP/AVP 97\r\n\
+               i=Channels 1-8\r\n\
+               a=rtpmap:97 L16/48000/8\r\n\
+               a=recvonly\r\n\
+               a=ptime:0.25\r\n\
+               a=ts-refclk:ptp=IEEE1588-2008:00-1D-C1-FF-FE-12-34-56:0\r\n\
+               a=mediaclk:direct=963214424\r\n";
+
+    let description = SessionDescription::parse(sdp).unwrap();
+    assert_eq!(description.name, "Stage box 1-8");
+    assert_eq!(
+        description.origin,
+        "192.168.1.34".parse::<std::net::IpAddr>().unwrap()
+    );
+    assert_eq!(description.address, "239.69.83.134:5004".parse().unwrap());
+    assert_eq!(description.payload, RtpPayload::L16);
+    assert_eq!(description.payload_type, 97);
+    assert_eq!(description.channels, 8);
+    assert_eq!(description.packet_ms, 0.25);
+
+    assert!(SessionDescription::parse(&sdp.replace("L16", "opus")).is_err());
+    assert!(SessionDescription::parse("v=0\r\n").is_err());
+}
@@ -0,0 +1,357 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use hound::{SampleFormat, WavSpec, WavWriter};
+use nodio_core::{
+    Clip, LoopMode, PlayerCommand, PlayerConfig, PlayerState, RecorderConfig, RecordingFormat,
+    Rotation,
+};
+use nodio_engine::{decode_file, FilePlayer, Recorder};
+
+const SAMPLE_RATE: u32 = 8000;
+
+/// Empty directory for the files of a test.
+fn test_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("nodio-player-{}-{}", name, std::process::id()));
+    fs::remove_dir_all(&dir).ok();
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn write_wav(path: &Path, sample_rate: u32, channels: u16, samples: &[f32]) {
+    let spec = WavSpec {
+        channels,
+        sample_rate,
+        bits_per_sample: 32,
+        sample_format: SampleFormat::Float,
+    };
+
+    let mut writer = WavWriter::create(path, spec).unwrap();
+    for &sample in samples {
+        writer.write_sample(sample).unwrap();
+    }
+    writer.finalize().unwrap();
+}
+
+/// Mono file of the given length with a constant value.
+fn constant_file(dir: &Path, name: &str, frames: usize, value: f32) -> String {
+    let path = dir.join(name);
+    write_wav(&path, SAMPLE_RATE, 1, &vec![value; frames]);
+    path.to_string_lossy().into_owned()
+}
+
+fn player(playlist: Vec<String>, loop_mode: LoopMode) -> FilePlayer {
+    let config = PlayerConfig {
+        playlist,
+        loop_mode,
+        gain_db: 0.0,
+    };
+
+    FilePlayer::new(config, SAMPLE_RATE, 1)
+}
+
+/// Reads from the player until `done` returns true for the audio read so far.
+fn read_until(player: &mut FilePlayer, done: impl Fn(&FilePlayer, &[f32]) -> bool) -> Vec<f32> {
+    let start = Instant::now();
+    let mut output = Vec::new();
+    let mut buffer = [0.0; 256];
+
+    while !done(player, &output) {
+        assert!(start.elapsed() < Duration::from_secs(10), "Timed out");
+
+        let frames = player.read(&mut buffer);
+        output.extend_from_slice(&buffer[..frames]);
+
+        if frames == 0 {
+            std::thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    output
+}
+
+fn wait_for(player: &mut FilePlayer, condition: impl Fn(&mut FilePlayer) -> bool) {
+    let start = Instant::now();
+
+    while !condition(player) {
+        assert!(start.elapsed() < Duration::from_secs(10), "Timed out");
+        std::thread::sleep(Duration::from_millis(1));
+    }
+}
+
+fn stopped(player: &FilePlayer, _: &[f32]) -> bool {
+    player.status().state == PlayerState::Stopped
+}
+
+#[test]
+fn decodes_wav_files() {
+    let dir = test_dir("decode-wav");
+    let path = dir.join("stereo.wav");
+    let input: Vec<f32> = (0..2000).map(|i| (i as f32 * 0.01).sin() * 0.5).collect();
+    write_wav(&path, SAMPLE_RATE, 2, &input);
+
+    let output = decode_file(&path, SAMPLE_RATE, 2).unwrap();
+    assert_eq!(output, input);
+}
+
+#[test]
+fn converts_channels() {
+    let dir = test_dir("channels");
+    let path = dir.join("mono.wav");
+    let input: Vec<f32> = (0..1000).map(|i| i as f32 / 1000.0).collect();
+    write_wav(&path, SAMPLE_RATE, 1, &input);
+
+    let output = decode_file(&path, SAMPLE_RATE, 2).unwrap();
+    assert_eq!(output.len(), 2000);
+    for (frame, &sample) in output.chunks(2).zip(&input) {
+        assert_eq!(frame, [sample, sample]);
+    }
+
+    let stereo = dir.join("stereo.wav");
+    write_wav(&stereo, SAMPLE_RATE, 2, &[0.2, 0.4, 0.6, 0.8]);
+    let output = decode_file(&stereo, SAMPLE_RATE, 1).unwrap();
+    assert_eq!(output.len(), 2);
+    assert!((output[0] - 0.3).abs() < 1e-6);
+    assert!((output[1] - 0.7).abs() < 1e-6);
+}
+
+#[test]
+fn resamples() {
+    let dir = test_dir("resample");
+    let path = dir.join("44k.wav");
+    let frequency = 440.0;
+    let input: Vec<f32> = (0..44100)
+        .map(|i| (2.0 * std::f32::consts::PI * frequency * i as f32 / 44100.0).sin())
+        .collect();
+    write_wav(&path, 44100, 1, &input);
+
+    let output = decode_file(&path, 48000, 1).unwrap();
+    assert!((output.len() as i64 - 48000).abs() <= 2, "{}", output.len());
+
+    for (i, &sample) in output.iter().enumerate().take(47000) {
+        let expected = (2.0 * std::f32::consts::PI * frequency * i as f32 / 48000.0).sin();
+        assert!((sample - expected).abs() < 0.01, "{}: {}", i, sample);
+    }
+}
+
+#[test]
+fn decodes_flac_files() {
+    let dir = test_dir("decode-flac");
+    let input: Vec<f32> = (0..5000)
+        .flat_map(|i| {
+            let t = i as f32 / SAMPLE_RATE as f32;
+            [0.5 * (1000.0 * t).sin(), 0.25 * (300.0 * t).cos()]
+        })
+        .collect();
+
+    let config = RecorderConfig {
+        directory: dir.to_string_lossy().into_owned(),
+        format: RecordingFormat::Flac16,
+        rotation: Rotation::Never,
+    };
+    let mut recorder = Recorder::new(config, "flac", SAMPLE_RATE, 2);
+    recorder.start().unwrap();
+    recorder.write(&input).unwrap();
+    recorder.stop().unwrap();
+
+    let path = fs::read_dir(&dir).unwrap().next().unwrap().unwrap().path();
+    let output = decode_file(&path, SAMPLE_RATE, 2).unwrap();
+
+    assert_eq!(output.len(), input.len());
+    for (a, b) in output.iter().zip(&input) {
+        assert!((a - b).abs() < 1.0 / 16384.0);
+    }
+}
+
+#[test]
+fn plays_playlist_once() {
+    let dir = test_dir("once");
+    let a = constant_file(&dir, "a.wav", 800, 0.25);
+    let b = constant_file(&dir, "b.wav", 1200, 0.5);
+
+    let mut player = player(vec![a, b], LoopMode::Off);
+    assert_eq!(player.status().state, PlayerState::Stopped);
+
+    player.command(PlayerCommand::Play);
+    wait_for(&mut player, |player| player.status().track == Some(0));
+    assert_eq!(player.status().duration_secs, Some(0.1));
+
+    let output = read_until(&mut player, stopped);
+    assert_eq!(output.len(), 2000);
+    assert!(output[..800].iter().all(|&sample| sample == 0.25));
+    assert!(output[800..].iter().all(|&sample| sample == 0.5));
+}
+
+#[test]
+fn loops_track() {
+    let dir = test_dir("loop-track");
+    let a = constant_file(&dir, "a.wav", 800, 0.25);
+    let b = constant_file(&dir, "b.wav", 800, 0.5);
+
+    let mut player = player(vec![a, b], LoopMode::Track);
+    player.command(PlayerCommand::Play);
+
+    let output = read_until(&mut player, |_, output| output.len() >= 3000);
+    assert!(output.iter().all(|&sample| sample == 0.25));
+    assert_eq!(player.status().track, Some(0));
+
+    player.command(PlayerCommand::Next);
+    wait_for(&mut player, |player| player.status().track == Some(1));
+    let output = read_until(&mut player, |_, output| output.len() >= 3000);
+    assert!(output.iter().all(|&sample| sample == 0.5));
+}
+
+#[test]
+fn loops_playlist() {
+    let dir = test_dir("loop-playlist");
+    let a = constant_file(&dir, "a.wav", 800, 0.25);
+    let b = constant_file(&dir, "b.wav", 400, 0.5);
+
+    let mut player = player(vec![a, b], LoopMode::Playlist);
+    player.command(PlayerCommand::Play);
+
+    let output = read_until(&mut player, |_, output| output.len() >= 2600);
+    assert!(output[..800].iter().all(|&sample| sample == 0.25));
+    assert!(output[800..1200].iter().all(|&sample| sample == 0.5));
+    assert!(output[1200..2000].iter().all(|&sample| sample == 0.25));
+    assert!(output[2000..2400].iter().all(|&sample| sample == 0.5));
+}
+
+#[test]
+fn applies_gain() {
+    let dir = test_dir("gain");
+    let config = PlayerConfig {
+        playlist: vec![constant_file(&dir, "a.wav", 800, 0.5)],
+        loop_mode: LoopMode::Off,
+        gain_db: 0.0,
+    };
+
+    let mut player = FilePlayer::new(config.clone(), SAMPLE_RATE, 1);
+    player.set_config(PlayerConfig {
+        gain_db: -6.0,
+        ..config
+    });
+    player.command(PlayerCommand::Play);
+    wait_for(&mut player, |player| player.status().track == Some(0));
+
+    let output = read_until(&mut player, stopped);
+    assert_eq!(output.len(), 800);
+    assert!(output.iter().all(|&sample| (sample - 0.2506).abs() < 0.001));
+}
+
+#[test]
+fn pauses() {
+    let dir = test_dir("pause");
+    let a = constant_file(&dir, "a.wav", 800, 0.25);
+
+    let mut player = player(vec![a], LoopMode::Off);
+    player.command(PlayerCommand::Play);
+    let output = read_until(&mut player, |_, output| output.len() >= 300);
+
+    player.command(PlayerCommand::Pause);
+    assert_eq!(player.status().state, PlayerState::Paused);
+
+    let mut buffer = [1.0; 256];
+    assert_eq!(player.read(&mut buffer), 0);
+    assert!(buffer.iter().all(|&sample| sample == 0.0));
+
+    player.command(PlayerCommand::Play);
+    let rest = read_until(&mut player, stopped);
+    assert_eq!(output.len() + rest.len(), 800);
+}
+
+#[test]
+fn seeks() {
+    let dir = test_dir("seek");
+    let path = dir.join("ramp.wav");
+    let ramp: Vec<f32> = (0..800).map(|i| i as f32 / 800.0).collect();
+    write_wav(&path, SAMPLE_RATE, 1, &ramp);
+
+    let mut player = player(vec![path.to_string_lossy().into_owned()], LoopMode::Off);
+    player.command(PlayerCommand::Play);
+    player.command(PlayerCommand::Seek(0.05));
+    wait_for(&mut player, |player| player.status().position_secs >= 0.05);
+
+    let output = read_until(&mut player, stopped);
+    assert_eq!(output, ramp[400..]);
+}
+
+fn clip(path: String, gain_db: f32, choke_group: Option<u32>) -> Clip {
+    Clip {
+        path,
+        gain_db,
+        choke_group,
+        ..Clip::default()
+    }
+}
+
+#[test]
+fn overlaps_clips() {
+    let dir = test_dir("overlap");
+    let a = clip(constant_file(&dir, "a.wav", 400, 0.25), 0.0, None);
+    let b = clip(constant_file(&dir, "b.wav", 200, 0.5), 0.0, None);
+
+    let mut player = player(Vec::new(), LoopMode::Off);
+    player.play_clip(&a);
+    player.play_clip(&b);
+    wait_for(&mut player, |player| player.playing_clips().len() == 2);
+
+    let mut buffer = [0.0; 1000];
+    assert_eq!(player.read(&mut buffer), 400);
+    assert!(buffer[..200].iter().all(|&sample| sample == 0.75));
+    assert!(buffer[200..400].iter().all(|&sample| sample == 0.25));
+    assert!(buffer[400..].iter().all(|&sample| sample == 0.0));
+    assert!(player.playing_clips().is_empty());
+
+    // The same clip can overlap itself
+    player.play_clip(&a);
+    player.play_clip(&a);
+    wait_for(&mut player, |player| player.playing_clips() == [a.id]);
+    assert_eq!(player.read(&mut buffer), 400);
+    assert_eq!(buffer[0], 0.5);
+}
+
+#[test]
+fn chokes_clips() {
+    let dir = test_dir("choke");
+    let a = clip(constant_file(&dir, "a.wav", 400, 0.25), 0.0, Some(1));
+    let b = clip(constant_file(&dir, "b.wav", 400, 0.5), -6.0, Some(1));
+    let c = clip(constant_file(&dir, "c.wav", 400, 0.125), 0.0, Some(2));
+
+    let mut player = player(Vec::new(), LoopMode::Off);
+    player.play_clip(&a);
+    player.play_clip(&c);
+    wait_for(&mut player, |player| player.playing_clips().len() == 2);
+
+    let mut buffer = [0.0; 100];
+    player.read(&mut buffer);
+    assert_eq!(buffer[0], 0.375);
+
+    player.play_clip(&b);
+    wait_for(&mut player, |player| player.playing_clips().contains(&b.id));
+    assert_eq!(player.playing_clips(), [c.id, b.id]);
+
+    player.read(&mut buffer);
+    assert!((buffer[0] - (0.125 + 0.2506)).abs() < 0.001);
+
+    player.stop_clips();
+    assert!(player.playing_clips().is_empty());
+    assert_eq!(player.read(&mut buffer), 0);
+}
+
+#[test]
+fn mixes_clips_with_playlist() {
+    let dir = test_dir("mix");
+    let track = constant_file(&dir, "track.wav", 800, 0.25);
+    let a = clip(constant_file(&dir, "a.wav", 100, 0.5), 0.0, None);
+
+    let mut player = player(vec![track], LoopMode::Off);
+    player.command(PlayerCommand::Play);
+    wait_for(&mut player, |player| player.status().track == Some(0));
+    player.play_clip(&a);
+    wait_for(&mut player, |player| !player.playing_clips().is_empty());
+
+    let output = read_until(&mut player, |_, output| output.len() >= 100);
+    assert!(output[..100].iter().all(|&sample| sample == 0.75));
+}
@@ -0,0 +1,234 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use nodio_core::{EffectConfig, PluginConfig, PluginFormat, PluginParamInfo};
+use nodio_engine::{create_effect, discover_plugins_in, plugin_config};
+
+/// Empty directory for the plugins of a test.
+fn plugin_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("nodio-plugins-{}-{}", name, std::process::id()));
+    fs::remove_dir_all(&dir).ok();
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Source of a test plugin in `tests/plugins`.
+fn fixture(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("plugins")
+        .join(name)
+}
+
+/// Builds a test plugin with the C compiler of the system.
+fn compile_plugin(source: &Path, library: &Path) {
+    let status = Command::new("cc")
+        .args(["-shared", "-fPIC", "-O2", "-o"])
+        .arg(library)
+        .arg(source)
+        .arg("-lm")
+        .status()
+        .expect("No C compiler found");
+    assert!(status.success(), "Could not compile {}", source.display());
+}
+
+/// Stereo block with the same signal in both channels.
+fn block(frames: usize) -> Vec<f32> {
+    (0..frames)
+        .flat_map(|i| {
+            let sample = 0.5 * (i as f32 * 0.05).sin();
+            [sample, sample]
+        })
+        .collect()
+}
+
+fn assert_scaled(output: &[f32], input: &[f32], gain: f32) {
+    assert_eq!(output.len(), input.len());
+    for (out, sample) in output.iter().zip(input) {
+        assert!(
+            (out - sample * gain).abs() < 1e-4,
+            "{} != {}",
+            out,
+            sample * gain
+        );
+    }
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn hosts_clap_plugins() {
+    let dir = plugin_dir("clap-host");
+    compile_plugin(&fixture("gain.c"), &dir.join("gain.clap"));
+
+    let plugins = discover_plugins_in(PluginFormat::Clap, std::slice::from_ref(&dir));
+    assert_eq!(plugins.len(), 1);
+    assert_eq!(plugins[0].id, "org.nodio.test.gain");
+    assert_eq!(plugins[0].name, "Test Gain");
+    assert_eq!(plugins[0].vendor, "Nodio");
+
+    let config = plugin_config(&plugins[0]).unwrap();
+    assert_eq!(config.params.len(), 1);
+    assert_eq!(config.params[0].name, "Gain");
+    assert_eq!(config.params[0].default, 1.0);
+
+    let mut effect = create_effect(&EffectConfig::plugin(config), 48000.0);
+    let input = block(512);
+
+    let mut output = input.clone();
+    effect.process(&mut output, 2);
+    assert_scaled(&output, &input, 1.0);
+
+    effect.set_param(0, 0.5);
+    assert_eq!(effect.param(0), 0.5);
+    assert_eq!(effect.param(1), 0.0);
+    let mut output = input.clone();
+    effect.process(&mut output, 2);
+    assert_scaled(&output, &input, 0.5);
+
+    fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn hosts_lv2_plugins() {
+    let dir = plugin_dir("lv2-host");
+    let bundle = dir.join("amp.lv2");
+    fs::create_dir_all(&bundle).unwrap();
+    for file in ["manifest.ttl", "amp.ttl"] {
+        fs::copy(fixture("amp.lv2").join(file), bundle.join(file)).unwrap();
+    }
+    compile_plugin(&fixture("amp.lv2").join("amp.c"), &bundle.join("amp.so"));
+
+    let plugins = discover_plugins_in(PluginFormat::Lv2, std::slice::from_ref(&dir));
+    assert_eq!(plugins.len(), 1);
+    assert_eq!(plugins[0].name, "Test Amp");
+
+    let config = plugin_config(&plugins[0]).unwrap();
+    assert_eq!(
+        config.params,
+        vec![PluginParamInfo {
+            id: 0,
+            name: "Gain".to_string(),
+            min: -90.0,
+            max: 24.0,
+            default: 0.0,
+        }]
+    );
+
+    // The mono plugin takes the left channel, and its output is played on both
+    let mut effect = create_effect(&EffectConfig::plugin(config), 48000.0);
+    let input = block(5000);
+
+    let mut output = input.clone();
+    effect.process(&mut output, 2);
+    assert_scaled(&output, &input, 1.0);
+
+    effect.set_param(0, -20.0);
+    assert_eq!(effect.param(0), -20.0);
+    let mut output = input.clone();
+    effect.process(&mut output, 2);
+    assert_scaled(&output, &input, 0.1);
+
+    fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn discovers_lv2_plugins_from_bundles() {
+    let dir = plugin_dir("lv2");
+    let bundle = dir.join("amp.lv2");
+    fs::create_dir_all(&bundle).unwrap();
+
+    fs::write(
+        bundle.join("manifest.ttl"),
+        r#"
+        @prefix lv2:  <http://lv2plug.in/ns/lv2core#> .
+        @prefix rdfs: <http://www.w3.org/2000/01/rdf-schema#> .
+
+        <http://example.org/plugins/amp>
+            a lv2:Plugin ;
+            lv2:binary <amp.so> ;
+            rdfs:seeAlso <amp.ttl> .
+        "#,
+    )
+    .unwrap();
+
+    fs::write(
+        bundle.join("amp.ttl"),
+        r#"
+        @prefix doap: <http://usefulinc.com/ns/doap#> .
+        @prefix foaf: <http://xmlns.com/foaf/0.1/> .
+        @prefix lv2:  <http://lv2plug.in/ns/lv2core#> .
+
+        <http://example.org/plugins/amp>
+            a lv2:Plugin, lv2:AmplifierPlugin ;
+            doap:name "Simple Amp"@en ;
+            doap:maintainer [ foaf:name "Example Audio" ] ;
+            lv2:port [
+                a lv2:InputPort, lv2:ControlPort ;
+                lv2:index 0 ;
+                lv2:default 0.0 ;
+                lv2:minimum -90.0 ;
+                lv2:maximum 24.0
+            ] .
+        "#,
+    )
+    .unwrap();
+
+    // Not a bundle
+    fs::create_dir_all(dir.join("other")).unwrap();
+
+    let plugins = discover_plugins_in(PluginFormat::Lv2, std::slice::from_ref(&dir));
+
+    assert_eq!(plugins.len(), 1);
+    assert_eq!(plugins[0].format, PluginFormat::Lv2);
+    assert_eq!(plugins[0].id, "http://example.org/plugins/amp");
+    assert_eq!(plugins[0].name, "Simple Amp");
+    assert_eq!(plugins[0].vendor, "Example Audio");
+    assert_eq!(plugins[0].path, bundle);
+    // The binary is missing
+    assert!(plugin_config(&plugins[0]).is_err());
+
+    fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn skips_invalid_clap_files() {
+    let dir = plugin_dir("clap");
+    fs::create_dir_all(dir.join("vendor")).unwrap();
+    fs::write(dir.join("vendor").join("broken.clap"), b"not a library").unwrap();
+
+    assert!(discover_plugins_in(PluginFormat::Clap, std::slice::from_ref(&dir)).is_empty());
+
+    fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn missing_plugin_passes_audio_through() {
+    let config = EffectConfig::plugin(PluginConfig {
+        format: PluginFormat::Clap,
+        path: "/nonexistent/plugin.clap".to_string(),
+        plugin_id: "org.example.missing".to_string(),
+        name: "Missing".to_string(),
+        params: vec![PluginParamInfo {
+            id: 7,
+            name: "Gain".to_string(),
+            min: -1.0,
+            max: 1.0,
+            default: 0.5,
+        }],
+        state: Vec::new(),
+    });
+
+    let mut effect = create_effect(&config, 48000.0);
+    assert_eq!(effect.param(0), 0.5);
+    assert_eq!(effect.param(1), 0.0);
+
+    effect.set_param(0, -0.25);
+    assert_eq!(effect.param(0), -0.25);
+    assert_eq!(effect.param(1), 0.0);
+
+    let mut block = vec![0.1, -0.2, 0.3, -0.4];
+    effect.process(&mut block, 2);
+    assert_eq!(block, vec![0.1, -0.2, 0.3, -0.4]);
+}
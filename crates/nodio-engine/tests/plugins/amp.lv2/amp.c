@@ -0,0 +1,118 @@
+/*
+ * Mono amplifier like the eg-amp example of LV2 (https://lv2plug.in), which the plugin tests
+ * load. It needs the URID map and an atom sequence input, and plays silence without them. The
+ * structs are declared here as in the LV2 headers, so that no headers need to be installed.
+ */
+#include <math.h>
+#include <stdint.h>
+#include <stdlib.h>
+#include <string.h>
+
+typedef struct {
+    const char *URI;
+    void *data;
+} LV2_Feature;
+
+typedef struct LV2_Descriptor {
+    const char *URI;
+    void *(*instantiate)(const struct LV2_Descriptor *descriptor, double sample_rate,
+                         const char *bundle_path, const LV2_Feature *const *features);
+    void (*connect_port)(void *instance, uint32_t port, void *data_location);
+    void (*activate)(void *instance);
+    void (*run)(void *instance, uint32_t sample_count);
+    void (*deactivate)(void *instance);
+    void (*cleanup)(void *instance);
+    const void *(*extension_data)(const char *uri);
+} LV2_Descriptor;
+
+typedef struct {
+    void *handle;
+    uint32_t (*map)(void *handle, const char *uri);
+} LV2_URID_Map;
+
+typedef struct {
+    uint32_t size;
+    uint32_t type;
+} LV2_Atom;
+
+enum { AMP_GAIN = 0, AMP_INPUT = 1, AMP_OUTPUT = 2, AMP_EVENTS = 3 };
+
+typedef struct {
+    const float *gain;
+    const float *input;
+    float *output;
+    const LV2_Atom *events;
+    uint32_t sequence_type;
+} Amp;
+
+static void *instantiate(const LV2_Descriptor *descriptor, double rate, const char *bundle_path,
+                         const LV2_Feature *const *features) {
+    const LV2_URID_Map *map = NULL;
+    for (int i = 0; features[i]; i++) {
+        if (strcmp(features[i]->URI, "http://lv2plug.in/ns/ext/urid#map") == 0) {
+            map = features[i]->data;
+        }
+    }
+    if (!map) {
+        return NULL;
+    }
+
+    Amp *amp = calloc(1, sizeof(Amp));
+    amp->sequence_type = map->map(map->handle, "http://lv2plug.in/ns/ext/atom#Sequence");
+    return amp;
+}
+
+static void connect_port(void *instance, uint32_t port, void *data) {
+    Amp *amp = instance;
+
+    switch (port) {
+    case AMP_GAIN:
+        amp->gain = data;
+        break;
+    case AMP_INPUT:
+        amp->input = data;
+        break;
+    case AMP_OUTPUT:
+        amp->output = data;
+        break;
+    case AMP_EVENTS:
+        amp->events = data;
+        break;
+    }
+}
+
+static void activate(void *instance) {}
+
+static void run(void *instance, uint32_t n_samples) {
+    const Amp *amp = instance;
+
+    float coef = powf(10.0f, *amp->gain * 0.05f);
+    if (*amp->gain <= -90.0f || amp->events->type != amp->sequence_type) {
+        coef = 0.0f;
+    }
+
+    for (uint32_t pos = 0; pos < n_samples; pos++) {
+        amp->output[pos] = amp->input[pos] * coef;
+    }
+}
+
+static void deactivate(void *instance) {}
+
+static void cleanup(void *instance) { free(instance); }
+
+static const void *extension_data(const char *uri) { return NULL; }
+
+static const LV2_Descriptor descriptor = {
+    "http://nodio.test/plugins/amp",
+    instantiate,
+    connect_port,
+    activate,
+    run,
+    deactivate,
+    cleanup,
+    extension_data,
+};
+
+__attribute__((visibility("default"))) const LV2_Descriptor *lv2_descriptor(uint32_t index) {
+    return index == 0 ? &descriptor : NULL;
+}
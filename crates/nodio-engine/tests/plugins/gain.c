@@ -0,0 +1,287 @@
+/*
+ * Minimal CLAP gain plugin that the plugin tests load. The structs are declared here as in the
+ * CLAP headers (https://github.com/free-audio/clap), so that no headers need to be installed.
+ */
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+#include <string.h>
+
+typedef struct clap_version {
+    uint32_t major;
+    uint32_t minor;
+    uint32_t revision;
+} clap_version_t;
+
+typedef struct clap_host clap_host_t;
+
+typedef struct clap_plugin_descriptor {
+    clap_version_t clap_version;
+    const char *id;
+    const char *name;
+    const char *vendor;
+    const char *url;
+    const char *manual_url;
+    const char *support_url;
+    const char *version;
+    const char *description;
+    const char *const *features;
+} clap_plugin_descriptor_t;
+
+typedef struct clap_event_header {
+    uint32_t size;
+    uint32_t time;
+    uint16_t space_id;
+    uint16_t type;
+    uint32_t flags;
+} clap_event_header_t;
+
+typedef struct clap_event_param_value {
+    clap_event_header_t header;
+    uint32_t param_id;
+    void *cookie;
+    int32_t note_id;
+    int16_t port_index;
+    int16_t channel;
+    int16_t key;
+    double value;
+} clap_event_param_value_t;
+
+typedef struct clap_input_events {
+    void *ctx;
+    uint32_t (*size)(const struct clap_input_events *list);
+    const clap_event_header_t *(*get)(const struct clap_input_events *list, uint32_t index);
+} clap_input_events_t;
+
+typedef struct clap_audio_buffer {
+    float **data32;
+    double **data64;
+    uint32_t channel_count;
+    uint32_t latency;
+    uint64_t constant_mask;
+} clap_audio_buffer_t;
+
+typedef struct clap_process {
+    int64_t steady_time;
+    uint32_t frames_count;
+    const void *transport;
+    const clap_audio_buffer_t *audio_inputs;
+    clap_audio_buffer_t *audio_outputs;
+    uint32_t audio_inputs_count;
+    uint32_t audio_outputs_count;
+    const clap_input_events_t *in_events;
+    const void *out_events;
+} clap_process_t;
+
+typedef struct clap_plugin {
+    const clap_plugin_descriptor_t *desc;
+    void *plugin_data;
+    bool (*init)(const struct clap_plugin *plugin);
+    void (*destroy)(const struct clap_plugin *plugin);
+    bool (*activate)(const struct clap_plugin *plugin, double sample_rate, uint32_t min_frames,
+                     uint32_t max_frames);
+    void (*deactivate)(const struct clap_plugin *plugin);
+    bool (*start_processing)(const struct clap_plugin *plugin);
+    void (*stop_processing)(const struct clap_plugin *plugin);
+    void (*reset)(const struct clap_plugin *plugin);
+    int32_t (*process)(const struct clap_plugin *plugin, const clap_process_t *process);
+    const void *(*get_extension)(const struct clap_plugin *plugin, const char *id);
+    void (*on_main_thread)(const struct clap_plugin *plugin);
+} clap_plugin_t;
+
+typedef struct clap_plugin_factory {
+    uint32_t (*get_plugin_count)(const struct clap_plugin_factory *factory);
+    const clap_plugin_descriptor_t *(*get_plugin_descriptor)(
+        const struct clap_plugin_factory *factory, uint32_t index);
+    const clap_plugin_t *(*create_plugin)(const struct clap_plugin_factory *factory,
+                                          const clap_host_t *host, const char *plugin_id);
+} clap_plugin_factory_t;
+
+typedef struct clap_plugin_entry {
+    clap_version_t clap_version;
+    bool (*init)(const char *plugin_path);
+    void (*deinit)(void);
+    const void *(*get_factory)(const char *factory_id);
+} clap_plugin_entry_t;
+
+typedef struct clap_param_info {
+    uint32_t id;
+    uint32_t flags;
+    void *cookie;
+    char name[256];
+    char module[1024];
+    double min_value;
+    double max_value;
+    double default_value;
+} clap_param_info_t;
+
+typedef struct clap_plugin_params {
+    uint32_t (*count)(const clap_plugin_t *plugin);
+    bool (*get_info)(const clap_plugin_t *plugin, uint32_t param_index,
+                     clap_param_info_t *param_info);
+    bool (*get_value)(const clap_plugin_t *plugin, uint32_t param_id, double *out_value);
+    bool (*value_to_text)(const clap_plugin_t *plugin, uint32_t param_id, double value,
+                          char *out_buffer, uint32_t out_buffer_capacity);
+    bool (*text_to_value)(const clap_plugin_t *plugin, uint32_t param_id,
+                          const char *param_value_text, double *out_value);
+    void (*flush)(const clap_plugin_t *plugin, const clap_input_events_t *in,
+                  const void *out);
+} clap_plugin_params_t;
+
+#define CLAP_EVENT_PARAM_VALUE 5
+#define CLAP_PROCESS_ERROR 0
+#define CLAP_PROCESS_CONTINUE 1
+
+static const char *const features[] = {"audio-effect", NULL};
+
+static const clap_plugin_descriptor_t descriptor = {
+    .clap_version = {1, 1, 0},
+    .id = "org.nodio.test.gain",
+    .name = "Test Gain",
+    .vendor = "Nodio",
+    .url = "",
+    .manual_url = "",
+    .support_url = "",
+    .version = "1.0.0",
+    .description = "Multiplies the audio by the gain",
+    .features = features,
+};
+
+typedef struct {
+    clap_plugin_t plugin;
+    double gain;
+} gain_t;
+
+static bool gain_init(const clap_plugin_t *plugin) { return true; }
+
+static void gain_destroy(const clap_plugin_t *plugin) { free(plugin->plugin_data); }
+
+static bool gain_activate(const clap_plugin_t *plugin, double sample_rate, uint32_t min_frames,
+                          uint32_t max_frames) {
+    return true;
+}
+
+static void gain_deactivate(const clap_plugin_t *plugin) {}
+
+static bool gain_start_processing(const clap_plugin_t *plugin) { return true; }
+
+static void gain_stop_processing(const clap_plugin_t *plugin) {}
+
+static void gain_reset(const clap_plugin_t *plugin) {}
+
+static int32_t gain_process(const clap_plugin_t *plugin, const clap_process_t *process) {
+    gain_t *gain = plugin->plugin_data;
+
+    uint32_t event_count = process->in_events->size(process->in_events);
+    for (uint32_t i = 0; i < event_count; i++) {
+        const clap_event_header_t *header = process->in_events->get(process->in_events, i);
+        if (header->space_id == 0 && header->type == CLAP_EVENT_PARAM_VALUE) {
+            gain->gain = ((const clap_event_param_value_t *)header)->value;
+        }
+    }
+
+    if (process->audio_inputs_count != 1 || process->audio_outputs_count != 1) {
+        return CLAP_PROCESS_ERROR;
+    }
+
+    const clap_audio_buffer_t *input = &process->audio_inputs[0];
+    clap_audio_buffer_t *output = &process->audio_outputs[0];
+    for (uint32_t channel = 0; channel < output->channel_count; channel++) {
+        const float *in = input->data32[channel < input->channel_count ? channel : 0];
+        float *out = output->data32[channel];
+
+        for (uint32_t i = 0; i < process->frames_count; i++) {
+            out[i] = (float)(in[i] * gain->gain);
+        }
+    }
+
+    return CLAP_PROCESS_CONTINUE;
+}
+
+static uint32_t params_count(const clap_plugin_t *plugin) { return 1; }
+
+static bool params_get_info(const clap_plugin_t *plugin, uint32_t param_index,
+                            clap_param_info_t *info) {
+    if (param_index != 0) {
+        return false;
+    }
+
+    memset(info, 0, sizeof(*info));
+    info->id = 0;
+    strcpy(info->name, "Gain");
+    info->min_value = 0.0;
+    info->max_value = 2.0;
+    info->default_value = 1.0;
+    return true;
+}
+
+static bool params_get_value(const clap_plugin_t *plugin, uint32_t param_id, double *value) {
+    *value = ((gain_t *)plugin->plugin_data)->gain;
+    return param_id == 0;
+}
+
+static const clap_plugin_params_t params = {
+    .count = params_count,
+    .get_info = params_get_info,
+    .get_value = params_get_value,
+};
+
+static const void *gain_get_extension(const clap_plugin_t *plugin, const char *id) {
+    return strcmp(id, "clap.params") == 0 ? &params : NULL;
+}
+
+static void gain_on_main_thread(const clap_plugin_t *plugin) {}
+
+static uint32_t factory_get_plugin_count(const clap_plugin_factory_t *factory) { return 1; }
+
+static const clap_plugin_descriptor_t *factory_get_plugin_descriptor(
+    const clap_plugin_factory_t *factory, uint32_t index) {
+    return index == 0 ? &descriptor : NULL;
+}
+
+static const clap_plugin_t *factory_create_plugin(const clap_plugin_factory_t *factory,
+                                                  const clap_host_t *host,
+                                                  const char *plugin_id) {
+    if (strcmp(plugin_id, descriptor.id) != 0) {
+        return NULL;
+    }
+
+    gain_t *gain = calloc(1, sizeof(gain_t));
+    gain->gain = 1.0;
+    gain->plugin = (clap_plugin_t){
+        .desc = &descriptor,
+        .plugin_data = gain,
+        .init = gain_init,
+        .destroy = gain_destroy,
+        .activate = gain_activate,
+        .deactivate = gain_deactivate,
+        .start_processing = gain_start_processing,
+        .stop_processing = gain_stop_processing,
+        .reset = gain_reset,
+        .process = gain_process,
+        .get_extension = gain_get_extension,
+        .on_main_thread = gain_on_main_thread,
+    };
+    return &gain->plugin;
+}
+
+static const clap_plugin_factory_t factory = {
+    .get_plugin_count = factory_get_plugin_count,
+    .get_plugin_descriptor = factory_get_plugin_descriptor,
+    .create_plugin = factory_create_plugin,
+};
+
+static bool entry_init(const char *plugin_path) { return true; }
+
+static void entry_deinit(void) {}
+
+static const void *entry_get_factory(const char *factory_id) {
+    return strcmp(factory_id, "clap.plugin-factory") == 0 ? &factory : NULL;
+}
+
+__attribute__((visibility("default"))) const clap_plugin_entry_t clap_entry = {
+    .clap_version = {1, 1, 0},
+    .init = entry_init,
+    .deinit = entry_deinit,
+    .get_factory = entry_get_factory,
+};
@@ -0,0 +1,280 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use nodio_core::{RecorderConfig, RecorderState, RecordingFormat, Rotation};
+use nodio_engine::{AudioSink, BufferedSink, Recorder};
+use parking_lot::Mutex;
+
+const SAMPLE_RATE: u32 = 48000;
+
+/// Empty directory for the recordings of a test.
+fn recording_dir(name: &str) -> PathBuf {
+    let dir =
+        std::env::temp_dir().join(format!("nodio-recordings-{}-{}", name, std::process::id()));
+    fs::remove_dir_all(&dir).ok();
+    dir
+}
+
+fn recordings(dir: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .collect();
+    files.sort();
+    files
+}
+
+/// Stereo test signal with different content in both channels.
+fn signal(frames: usize) -> Vec<f32> {
+    (0..frames)
+        .flat_map(|i| {
+            let t = i as f32 / SAMPLE_RATE as f32;
+            [
+                0.5 * (2.0 * std::f32::consts::PI * 440.0 * t).sin(),
+                0.25 * (2.0 * std::f32::consts::PI * 1000.0 * t).cos(),
+            ]
+        })
+        .collect()
+}
+
+fn recorder(dir: &Path, format: RecordingFormat, rotation: Rotation) -> Recorder {
+    let config = RecorderConfig {
+        directory: dir.to_string_lossy().into_owned(),
+        format,
+        rotation,
+    };
+
+    Recorder::new(config, "Call: test", SAMPLE_RATE, 2)
+}
+
+#[test]
+fn writes_wav_files() {
+    for format in [
+        RecordingFormat::Wav16,
+        RecordingFormat::Wav24,
+        RecordingFormat::WavFloat,
+    ] {
+        let dir = recording_dir(&format!("{:?}", format));
+        let input = signal(10000);
+
+        let mut recorder = recorder(&dir, format, Rotation::Never);
+        recorder.start().unwrap();
+        recorder.write(&input).unwrap();
+        recorder.stop().unwrap();
+
+        let files = recordings(&dir);
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].extension().unwrap(), "wav");
+        // Characters not allowed in file names are replaced
+        assert!(files[0]
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .starts_with("Call_ test "));
+
+        let mut reader = hound::WavReader::open(&files[0]).unwrap();
+        let spec = reader.spec();
+        assert_eq!(spec.channels, 2);
+        assert_eq!(spec.sample_rate, SAMPLE_RATE);
+
+        let output: Vec<f32> = match format {
+            RecordingFormat::WavFloat => reader.samples::<f32>().map(Result::unwrap).collect(),
+            _ => {
+                let scale = (1 << (spec.bits_per_sample - 1)) as f32;
+                reader
+                    .samples::<i32>()
+                    .map(|sample| sample.unwrap() as f32 / scale)
+                    .collect()
+            }
+        };
+
+        assert_eq!(output.len(), input.len());
+        for (a, b) in input.iter().zip(output) {
+            assert!((a - b).abs() < 1e-4, "{:?}: {} != {}", format, a, b);
+        }
+
+        fs::remove_dir_all(&dir).ok();
+    }
+}
+
+#[test]
+fn writes_lossless_flac_files() {
+    for (format, bits) in [(RecordingFormat::Flac16, 16), (RecordingFormat::Flac24, 24)] {
+        let dir = recording_dir(&format!("{:?}", format));
+        // Silence and a partial last block in addition to the signal
+        let mut input = vec![0.0; 2 * 5000];
+        input.extend(signal(20000));
+
+        let mut recorder = recorder(&dir, format, Rotation::Never);
+        recorder.start().unwrap();
+        for block in input.chunks(960) {
+            recorder.write(block).unwrap();
+        }
+        recorder.stop().unwrap();
+
+        let files = recordings(&dir);
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].extension().unwrap(), "flac");
+
+        let mut reader = claxon::FlacReader::open(&files[0]).unwrap();
+        let info = reader.streaminfo();
+        assert_eq!(info.channels, 2);
+        assert_eq!(info.sample_rate, SAMPLE_RATE);
+        assert_eq!(info.bits_per_sample, bits);
+        assert_eq!(info.samples, Some(25000));
+
+        let scale = ((1 << (bits - 1)) - 1) as f32;
+        let expected: Vec<i32> = input
+            .iter()
+            .map(|sample| (sample * scale).round() as i32)
+            .collect();
+        let output: Vec<i32> = reader.samples().map(Result::unwrap).collect();
+        assert_eq!(output, expected);
+
+        // Compressed, as the signal is very predictable
+        let size = fs::metadata(&files[0]).unwrap().len();
+        assert!(size < (input.len() * bits as usize / 8) as u64 / 2);
+
+        fs::remove_dir_all(&dir).ok();
+    }
+}
+
+#[test]
+fn rotates_files_by_time() {
+    let dir = recording_dir("time");
+
+    let mut recorder = recorder(&dir, RecordingFormat::Wav16, Rotation::Time { minutes: 1 });
+    recorder.start().unwrap();
+    // 2.5 minutes in blocks that do not line up with the rotation
+    let block = vec![0.1; 2 * 7000];
+    let mut frames = 0;
+    while frames < SAMPLE_RATE as usize * 150 {
+        recorder.write(&block).unwrap();
+        frames += 7000;
+    }
+    recorder.stop().unwrap();
+
+    let lengths: Vec<u32> = recordings(&dir)
+        .iter()
+        .map(|file| hound::WavReader::open(file).unwrap().duration())
+        .collect();
+
+    let minute = SAMPLE_RATE * 60;
+    assert_eq!(lengths.len(), 3);
+    assert!(lengths.contains(&minute));
+    assert_eq!(lengths.iter().sum::<u32>(), frames as u32);
+    assert_eq!(lengths.iter().filter(|&&len| len == minute).count(), 2);
+
+    fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn rotates_files_by_size() {
+    let dir = recording_dir("size");
+
+    let mut recorder = recorder(
+        &dir,
+        RecordingFormat::Wav16,
+        Rotation::Size { megabytes: 1 },
+    );
+    recorder.start().unwrap();
+    // 2.5 MB of 16 bit stereo samples
+    let block = vec![0.1; 2 * 1000];
+    for _ in 0..(5 * 1024 * 1024 / 2 / 4000) {
+        recorder.write(&block).unwrap();
+    }
+
+    let status = recorder.status();
+    recorder.stop().unwrap();
+
+    let files = recordings(&dir);
+    assert_eq!(files.len(), 3);
+
+    let mut sizes: Vec<u64> = files
+        .iter()
+        .map(|file| fs::metadata(file).unwrap().len())
+        .collect();
+    sizes.sort_unstable();
+    // Files are full after the block that crosses the limit
+    for &size in &sizes[1..] {
+        assert!((1024 * 1024..1024 * 1024 + 4000 + 100).contains(&size));
+    }
+    assert!(status.bytes > 0 && status.bytes <= sizes.iter().sum::<u64>());
+
+    fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn pausing_keeps_the_file_and_elapsed_time() {
+    let dir = recording_dir("pause");
+    let block = signal(SAMPLE_RATE as usize);
+
+    let mut recorder = recorder(&dir, RecordingFormat::Flac16, Rotation::Never);
+    assert_eq!(recorder.status().state, RecorderState::Stopped);
+
+    // Audio is only written while recording
+    recorder.write(&block).unwrap();
+    recorder.start().unwrap();
+    recorder.write(&block).unwrap();
+
+    recorder.set_state(RecorderState::Paused).unwrap();
+    recorder.write(&block).unwrap();
+    let status = recorder.status();
+    assert_eq!(status.state, RecorderState::Paused);
+    assert_eq!(status.elapsed_secs, 1.0);
+
+    recorder.set_state(RecorderState::Recording).unwrap();
+    recorder.write(&block).unwrap();
+    assert_eq!(recorder.status().elapsed_secs, 2.0);
+    assert!(recorder.status().file.is_some());
+
+    recorder.set_state(RecorderState::Stopped).unwrap();
+    let status = recorder.status();
+    assert_eq!(status.state, RecorderState::Stopped);
+    assert!(status.file.is_none());
+
+    let files = recordings(&dir);
+    assert_eq!(files.len(), 1);
+    assert_eq!(
+        claxon::FlacReader::open(&files[0])
+            .unwrap()
+            .streaminfo()
+            .samples,
+        Some(2 * SAMPLE_RATE as u64)
+    );
+    assert_eq!(status.bytes, fs::metadata(&files[0]).unwrap().len());
+
+    fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn buffered_sink_records_everything_written_to_it() {
+    let dir = recording_dir("buffered");
+    let input = signal(20000);
+
+    let recorder = Arc::new(Mutex::new(recorder(
+        &dir,
+        RecordingFormat::WavFloat,
+        Rotation::Never,
+    )));
+    recorder.lock().start().unwrap();
+
+    let mut sink = BufferedSink::new(recorder.clone(), 2, input.len());
+    for block in input.chunks(960) {
+        sink.write(block);
+    }
+    // Waits for the writer thread to write the buffered audio
+    drop(sink);
+
+    let mut recorder = recorder.lock();
+    assert_eq!(recorder.status().elapsed_secs, 20000.0 / SAMPLE_RATE as f64);
+    recorder.stop().unwrap();
+
+    let files = recordings(&dir);
+    let mut reader = hound::WavReader::open(&files[0]).unwrap();
+    let output: Vec<f32> = reader.samples::<f32>().map(Result::unwrap).collect();
+    assert_eq!(output, input);
+
+    fs::remove_dir_all(&dir).ok();
+}
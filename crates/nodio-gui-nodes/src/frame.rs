@@ -0,0 +1,302 @@
+use derivative::Derivative;
+
+use super::*;
+
+/// The title and color of a frame. If color is None then the Context style is used
+#[derive(Default, Debug)]
+pub struct FrameArgs {
+    pub title: String,
+    pub color: Option<egui::Color32>,
+    /// Show only the header of the frame, with the links of its nodes attached to its sides
+    pub collapsed: bool,
+}
+
+#[derive(Derivative)]
+#[derivative(Debug)]
+pub(crate) struct FrameData {
+    pub in_use: bool,
+    pub title: String,
+    pub color: egui::Color32,
+    pub node_ids: Vec<Uuid>,
+    pub collapsed: bool,
+    /// The whole frame, which is only the header when collapsed
+    pub rect: Rect,
+    pub header_rect: Rect,
+    #[derivative(Debug = "ignore")]
+    pub shape: Option<egui::layers::ShapeIdx>,
+}
+
+impl FrameData {
+    pub fn new() -> Self {
+        Self {
+            in_use: true,
+            title: String::new(),
+            color: egui::Color32::TRANSPARENT,
+            node_ids: Vec::new(),
+            collapsed: false,
+            rect: Rect::NOTHING,
+            header_rect: Rect::NOTHING,
+            shape: None,
+        }
+    }
+}
+
+impl Default for FrameData {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Context {
+    /// Add a titled frame around a group of nodes. Dragging the header of the frame moves the
+    /// nodes in it, and a collapsed frame is shown as only its header.
+    /// Frames are drawn behind the nodes, so they must be added before the nodes.
+    pub fn add_frame(&mut self, id: Uuid, node_ids: &[Uuid], args: FrameArgs, ui: &mut Ui) {
+        let frame = self.frames.entry(id).or_default();
+        frame.in_use = true;
+        frame.title = args.title;
+        frame.color = args
+            .color
+            .unwrap_or(self.scaled_style.colors[ColorStyle::Frame as usize]);
+        frame.node_ids = node_ids.to_vec();
+        frame.collapsed = args.collapsed;
+        frame.shape.replace(ui.painter().add(egui::Shape::Noop));
+
+        if args.collapsed {
+            self.hidden_node_ids.extend_from_slice(node_ids);
+        }
+    }
+
+    /// Check if there is a frame whose header is hovered by the pointer
+    pub fn hovered_frame(&self) -> Option<Uuid> {
+        self.hovered_frame_id
+    }
+
+    /// The frame whose header was double-clicked, to be collapsed or expanded
+    pub fn toggled_frame(&self) -> Option<Uuid> {
+        self.toggled_frame_id
+    }
+
+    pub fn get_selected_frames(&self) -> &[Uuid] {
+        &self.selected_frame_ids
+    }
+
+    pub(crate) fn is_node_hidden(&self, node_id: Uuid) -> bool {
+        self.hidden_node_ids.contains(&node_id)
+    }
+
+    /// Links between two nodes of the same collapsed frame are not shown
+    pub(crate) fn is_link_hidden(&self, link: &LinkData) -> bool {
+        let parent_node_id = |pin_id| self.pins.get(pin_id).map(|pin| pin.parent_node_id);
+
+        match (
+            parent_node_id(&link.start_pin_id),
+            parent_node_id(&link.end_pin_id),
+        ) {
+            (Some(start_node_id), Some(end_node_id)) => self.frames.values().any(|frame| {
+                frame.in_use
+                    && frame.collapsed
+                    && frame.node_ids.contains(&start_node_id)
+                    && frame.node_ids.contains(&end_node_id)
+            }),
+            _ => false,
+        }
+    }
+
+    /// The pins of the nodes in a collapsed frame are gathered on the sides of its header
+    pub(crate) fn collapsed_frame_pin_pos(
+        &self,
+        node_id: Uuid,
+        kind: AttributeKind,
+    ) -> Option<Pos2> {
+        let frame = self.frames.values().find(|frame| {
+            frame.in_use
+                && frame.collapsed
+                && frame.rect.is_positive()
+                && frame.node_ids.contains(&node_id)
+        })?;
+
+        let x = match kind {
+            AttributeKind::Input => frame.rect.min.x - self.scaled_style.pin_offset,
+            _ => frame.rect.max.x + self.scaled_style.pin_offset,
+        };
+        Some(pos2(x, frame.rect.center().y))
+    }
+
+    /// Place the frames around their nodes, which have been laid out in this frame
+    pub(crate) fn update_frame_rects(&mut self, ui: &Ui) {
+        let fonts = ui.fonts();
+        let font_id = egui::TextStyle::Body.resolve(&self.node_ui_style);
+        let padding = Vec2::new(
+            self.scaled_style.node_padding_horizontal,
+            self.scaled_style.node_padding_vertical,
+        );
+        let header_height = fonts.row_height(&font_id) + 2.0 * padding.y;
+
+        for frame in self.frames.values_mut().filter(|frame| frame.in_use) {
+            let bounds = frame
+                .node_ids
+                .iter()
+                .filter_map(|node_id| self.nodes.get(node_id))
+                .filter(|node| node.in_use)
+                .map(|node| node.rect)
+                .reduce(Rect::union);
+
+            let bounds = match bounds {
+                Some(bounds) => bounds,
+                None => {
+                    frame.rect = Rect::NOTHING;
+                    frame.header_rect = Rect::NOTHING;
+                    continue;
+                }
+            };
+
+            if frame.collapsed {
+                let title_width = fonts
+                    .layout_no_wrap(frame.title.clone(), font_id.clone(), egui::Color32::WHITE)
+                    .size()
+                    .x;
+                let width = self
+                    .scaled_style
+                    .frame_collapsed_width
+                    .max(title_width + 2.0 * padding.x);
+
+                frame.header_rect =
+                    Rect::from_min_size(bounds.min, Vec2::new(width, header_height));
+                frame.rect = frame.header_rect;
+            } else {
+                let body_rect = bounds.expand(self.scaled_style.frame_padding);
+
+                frame.header_rect = Rect::from_min_max(
+                    body_rect.min - Vec2::new(0.0, header_height),
+                    pos2(body_rect.max.x, body_rect.min.y),
+                );
+                frame.rect = Rect::from_min_max(frame.header_rect.min, body_rect.max);
+            }
+        }
+    }
+
+    pub(crate) fn resolve_hovered_frame(&mut self) {
+        self.hovered_frame_id = self
+            .frames
+            .iter()
+            .rev()
+            .find(|(_, frame)| frame.in_use && frame.header_rect.contains(self.mouse_pos))
+            .map(|(&frame_id, _)| frame_id);
+    }
+
+    pub(crate) fn draw_frame(&mut self, frame_id: Uuid, ui: &mut Ui) {
+        let frame = self.frames.get_mut(&frame_id).unwrap();
+        let shape = match frame.shape.take() {
+            Some(shape) if frame.in_use && frame.rect.is_positive() => shape,
+            _ => return,
+        };
+
+        let frame = self.frames.get(&frame_id).unwrap();
+        let style = &self.scaled_style;
+        let rounding = style.node_corner_rounding;
+        let mut shapes = Vec::new();
+
+        if frame.collapsed {
+            shapes.push(egui::Shape::rect_filled(
+                frame.header_rect,
+                rounding,
+                frame.color,
+            ));
+
+            for kind in [AttributeKind::Input, AttributeKind::Output] {
+                let has_pins = self.pins.values().any(|pin| {
+                    pin.in_use && pin.kind == kind && frame.node_ids.contains(&pin.parent_node_id)
+                });
+                if let (true, Some(pin_pos)) = (
+                    has_pins,
+                    frame
+                        .node_ids
+                        .first()
+                        .and_then(|node_id| self.collapsed_frame_pin_pos(*node_id, kind)),
+                ) {
+                    shapes.push(egui::Shape::circle_filled(
+                        pin_pos,
+                        style.pin_circle_radius,
+                        style.colors[ColorStyle::Pin as usize],
+                    ));
+                }
+            }
+        } else {
+            shapes.push(egui::Shape::rect_filled(
+                frame.rect,
+                rounding,
+                frame.color.linear_multiply(0.15),
+            ));
+            shapes.push(egui::Shape::rect_filled(
+                frame.header_rect,
+                egui::epaint::Rounding {
+                    nw: rounding,
+                    ne: rounding,
+                    sw: 0.0,
+                    se: 0.0,
+                },
+                frame.color,
+            ));
+        }
+
+        if self.selected_frame_ids.contains(&frame_id) {
+            shapes.push(egui::Shape::rect_stroke(
+                frame.rect,
+                rounding,
+                (
+                    style.node_border_thickness.max(1.0),
+                    style.colors[ColorStyle::FrameOutlineSelected as usize],
+                ),
+            ));
+        }
+
+        shapes.push(egui::Shape::text(
+            &ui.fonts(),
+            pos2(
+                frame.header_rect.min.x + style.node_padding_horizontal,
+                frame.header_rect.center().y,
+            ),
+            egui::Align2::LEFT_CENTER,
+            &frame.title,
+            egui::TextStyle::Body.resolve(&self.node_ui_style),
+            style.colors[ColorStyle::FrameTitle as usize],
+        ));
+
+        ui.painter().set(shape, egui::Shape::Vec(shapes));
+
+        let frame_hovered = self.hovered_frame_id == Some(frame_id)
+            && self.click_interaction_type != ClickInteractionType::BoxSelection;
+        if frame_hovered && self.left_mouse_pressed && self.hovered_link_id.is_none() {
+            self.begin_frame_selection(frame_id);
+        }
+    }
+
+    /// Select the nodes of the frame, so dragging the header moves them
+    fn begin_frame_selection(&mut self, frame_id: Uuid) {
+        if self.click_interaction_type != ClickInteractionType::None {
+            return;
+        }
+        self.click_interaction_type = ClickInteractionType::Node;
+
+        if !self.selected_frame_ids.contains(&frame_id) {
+            let node_ids = self.frames.get(&frame_id).unwrap().node_ids.clone();
+
+            self.selected_frame_ids.clear();
+            self.selected_frame_ids.push(frame_id);
+            self.selected_link_ids.clear();
+            self.selected_node_ids = node_ids
+                .iter()
+                .filter(|&node_id| self.nodes.contains_key(node_id))
+                .copied()
+                .collect();
+
+            self.node_depth_order
+                .retain(|depth_id| !node_ids.contains(depth_id));
+            self.node_depth_order
+                .extend(self.selected_node_ids.iter().copied());
+        }
+
+        self.begin_node_drag();
+    }
+}
@@ -4,6 +4,7 @@
 ///!
 use std::cmp::Ordering;
 use std::collections::HashMap;
+use std::sync::Arc;
 
 use derivative::Derivative;
 use egui::{pos2, Pos2, Rect, Sense, Ui, Vec2};
@@ -11,21 +12,40 @@ use indexmap::IndexMap;
 use log::debug;
 use uuid::Uuid;
 
+use frame::*;
 use link::*;
 use node::*;
 use pin::*;
+use style::scaled_ui_style;
+use validation::LinkValidator;
 
 pub use {
+    frame::FrameArgs,
     link::LinkArgs,
     node::NodeBuilder,
+    note::{NoteArgs, MIN_NOTE_SIZE},
     pin::{AttributeFlags, PinArgs, PinShape},
-    style::{ColorStyle, Style, StyleFlags, StyleVar},
+    style::{ColorStyle, LinkStyle, MinimapPosition, Style, StyleFlags, StyleVar},
 };
 
+mod frame;
 mod link;
+mod minimap;
 mod node;
+mod note;
 mod pin;
+mod reroute;
 mod style;
+mod validation;
+
+/// Zoom limits of the editor
+pub const MIN_ZOOM: f32 = 0.25;
+pub const MAX_ZOOM: f32 = 2.0;
+
+/// Zoom factor per point of mouse wheel scrolling
+const WHEEL_ZOOM_SPEED: f32 = 0.002;
+/// Space around the nodes when zooming to fit them
+const ZOOM_TO_FIT_MARGIN: f32 = 40.0;
 
 /// The Context that tracks the state of the node editor
 #[derive(Derivative)]
@@ -35,6 +55,12 @@ pub struct Context {
     io: IO,
     #[derivative(Debug = "ignore")]
     style: Style,
+    /// The style scaled by the zoom, which the editor is drawn and hit tested with
+    #[derivative(Debug = "ignore")]
+    scaled_style: Style,
+    /// Style of the contents of the nodes, scaled by the zoom
+    #[derivative(Debug = "ignore")]
+    node_ui_style: Arc<egui::Style>,
 
     node_ids_overlapping_with_mouse: Vec<Uuid>,
     occluded_pin_ids: Vec<Uuid>,
@@ -46,10 +72,13 @@ pub struct Context {
     hovered_node_id: Option<Uuid>,
     interactive_node_id: Option<Uuid>,
     hovered_link_id: Option<Uuid>,
+    context_menu_link_id: Option<Uuid>,
     hovered_pin_id: Option<Uuid>,
     detached_link_id: Option<Uuid>,
     dropped_link_id: Option<Uuid>,
     snap_link_id: Option<Uuid>,
+    /// Nodes dragged to a new position in this frame, as (node, from, to)
+    moved_nodes: Vec<(Uuid, Pos2, Pos2)>,
 
     hovered_pin_flags: usize,
     ui_element_hovered: bool,
@@ -73,13 +102,32 @@ pub struct Context {
     nodes: IndexMap<Uuid, Node>,
     pins: IndexMap<Uuid, PinData>,
     links: IndexMap<Uuid, LinkData>,
+    frames: IndexMap<Uuid, FrameData>,
+    /// Nodes in collapsed frames, which are laid out but not shown
+    hidden_node_ids: Vec<Uuid>,
+    hovered_frame_id: Option<Uuid>,
+    toggled_frame_id: Option<Uuid>,
+    /// Reroute points of the links in grid space, ordered from the start pin to the end pin
+    link_reroutes: HashMap<Uuid, Vec<Pos2>>,
+    /// The hovered reroute point as (link, index)
+    hovered_reroute: Option<(Uuid, usize)>,
+    rerouted_link_id: Option<Uuid>,
+    #[derivative(Debug = "ignore")]
+    link_validator: Option<Box<LinkValidator>>,
+    /// Pins the dragged link cannot be snapped to, with the reason
+    refused_pins: HashMap<Uuid, String>,
 
     end_pin_link_mapping: HashMap<Uuid, Vec<Uuid>>,
 
     panning: Vec2,
+    #[derivative(Default(value = "1.0"))]
+    zoom: f32,
+    /// Area of the editor shown by the minimap while it is being dragged
+    minimap_drag_bounds: Option<Rect>,
 
     selected_node_ids: Vec<Uuid>,
     selected_link_ids: Vec<Uuid>,
+    selected_frame_ids: Vec<Uuid>,
 
     node_depth_order: Vec<Uuid>,
 
@@ -99,6 +147,12 @@ impl Context {
         self.detached_link_id.take();
         self.dropped_link_id.take();
         self.snap_link_id.take();
+        self.hovered_frame_id.take();
+        self.toggled_frame_id.take();
+        self.hovered_reroute.take();
+        self.rerouted_link_id.take();
+        self.moved_nodes.clear();
+        self.hidden_node_ids.clear();
         self.partial_link.take();
         self.end_pin_link_mapping.clear();
         self.node_ids_overlapping_with_mouse.clear();
@@ -107,6 +161,21 @@ impl Context {
         self.canvas_rect_screen_space = ui.available_rect_before_wrap();
         self.canvas_origin_screen_space = self.canvas_rect_screen_space.min.to_vec2();
 
+        // Zoomed before laying out the nodes, so they are placed with the zoom of this frame
+        if self.mouse_in_canvas {
+            let input = ui.input();
+            let zoom_delta = input.zoom_delta() * (input.scroll_delta.y * WHEEL_ZOOM_SPEED).exp();
+            let mouse_pos = input.pointer.hover_pos();
+            drop(input);
+
+            if let (Some(mouse_pos), true) = (mouse_pos, zoom_delta != 1.0) {
+                self.zoom_around(mouse_pos, self.zoom * zoom_delta);
+            }
+        }
+
+        self.scaled_style = self.style.scaled(self.zoom);
+        self.node_ui_style = Arc::new(scaled_ui_style(ui.style(), self.zoom));
+
         for node in self.nodes.values_mut() {
             node.in_use = false;
         }
@@ -116,6 +185,9 @@ impl Context {
         for link in self.links.values_mut() {
             link.in_use = false;
         }
+        for frame in self.frames.values_mut() {
+            frame.in_use = false;
+        }
 
         ui.set_min_size(self.canvas_rect_screen_space.size());
 
@@ -130,10 +202,10 @@ impl Context {
         ui.painter().rect_filled(
             self.canvas_rect_screen_space,
             0.0,
-            self.style.colors[ColorStyle::GridBackground as usize],
+            self.scaled_style.colors[ColorStyle::GridBackground as usize],
         );
 
-        if (self.style.flags & StyleFlags::GridLines as usize) != 0 {
+        if (self.scaled_style.flags & StyleFlags::GridLines as usize) != 0 {
             self.draw_grid(self.canvas_rect_screen_space.size(), &mut ui);
         }
     }
@@ -184,6 +256,8 @@ impl Context {
             .link_detach_with_modifier_click
             .is_active(&ui.ctx().input().modifiers);
 
+        self.update_frame_rects(ui);
+
         if self.mouse_in_canvas {
             self.resolve_occluded_pins();
             self.resolve_hovered_pin();
@@ -191,12 +265,37 @@ impl Context {
             if self.hovered_pin_id.is_none() {
                 self.resolve_hovered_node();
             }
+            if self.hovered_pin_id.is_none() && self.hovered_node_id.is_none() {
+                self.resolve_hovered_frame();
+            }
         }
 
+        self.update_refused_pins();
         self.click_interaction_update(ui);
 
         if self.mouse_in_canvas && self.hovered_node_id.is_none() {
-            self.resolve_hovered_link();
+            self.resolve_hovered_reroute();
+            if self.hovered_reroute.is_none() {
+                self.resolve_hovered_link();
+            }
+        }
+
+        if response.secondary_clicked() {
+            self.context_menu_link_id = self.hovered_link_id;
+        }
+        if response.double_clicked() {
+            if let Some((link_id, idx)) = self.hovered_reroute {
+                self.remove_reroute(link_id, idx);
+            } else if let Some(link_id) = self.hovered_link_id {
+                self.insert_reroute(link_id);
+            } else {
+                self.toggled_frame_id = self.hovered_frame_id;
+            }
+        }
+
+        let frame_ids = self.frames.keys().cloned().collect::<Vec<_>>();
+        for frame_id in frame_ids {
+            self.draw_frame(frame_id, ui);
         }
 
         for node_id in self.node_depth_order.clone() {
@@ -224,16 +323,27 @@ impl Context {
 
         self.pins.retain(|_, pin| pin.in_use);
         self.links.retain(|_, link| link.in_use);
+        self.link_reroutes
+            .retain(|link_id, _| self.links.contains_key(link_id));
+        self.frames.retain(|_, frame| frame.in_use);
 
         ui.painter().rect_stroke(
             self.canvas_rect_screen_space,
             0.0,
-            (1.0, self.style.colors[ColorStyle::GridLine as usize]),
+            (1.0, self.scaled_style.colors[ColorStyle::GridLine as usize]),
         );
 
+        if (self.style.flags & StyleFlags::Minimap as usize) != 0 {
+            self.show_minimap(ui);
+        }
+
         response
     }
 
+    pub fn style(&self) -> &Style {
+        &self.style
+    }
+
     pub fn style_mut(&mut self) -> &mut Style {
         &mut self.style
     }
@@ -242,6 +352,20 @@ impl Context {
         self.nodes.get(&node_id).map(|node| node.origin)
     }
 
+    /// Move a node that has already been shown. Nodes shown for the first time are placed by
+    /// [NodeBuilder::with_origin].
+    pub fn set_node_pos(&mut self, node_id: Uuid, pos: Pos2) {
+        if let Some(node) = self.nodes.get_mut(&node_id) {
+            node.origin = pos;
+        }
+    }
+
+    /// Nodes that the user has dragged to a new position, as (node, from, to). Reported once
+    /// when the drag ends.
+    pub fn moved_nodes(&self) -> &[(Uuid, Pos2, Pos2)] {
+        &self.moved_nodes
+    }
+
     /// Check if there is a node that is hovered by the pointer
     pub fn hovered_node(&self) -> Option<Uuid> {
         self.hovered_node_id
@@ -252,6 +376,13 @@ impl Context {
         self.hovered_link_id
     }
 
+    /// The link that was hovered when the editor was last right-clicked, for showing a context
+    /// menu of the link instead of the editor
+    pub fn context_menu_link(&self) -> Option<Uuid> {
+        self.context_menu_link_id
+            .filter(|link_id| self.links.contains_key(link_id))
+    }
+
     /// Check if there is a pin that is hovered by the pointer
     pub fn hovered_pin(&self) -> Option<Uuid> {
         self.hovered_pin_id
@@ -270,7 +401,15 @@ impl Context {
     }
 
     pub fn clear_node_selection(&mut self) {
-        self.selected_node_ids.clear()
+        self.selected_node_ids.clear();
+        self.selected_frame_ids.clear();
+    }
+
+    /// Replace the selection with the given nodes, e.g. ones that were just pasted
+    pub fn select_nodes(&mut self, node_ids: &[Uuid]) {
+        self.selected_node_ids = node_ids.to_vec();
+        self.selected_link_ids.clear();
+        self.selected_frame_ids.clear();
     }
 
     pub fn clear_link_selection(&mut self) {
@@ -333,6 +472,31 @@ impl Context {
         self.panning = panning;
     }
 
+    pub fn zoom(&self) -> f32 {
+        self.zoom
+    }
+
+    /// Set the zoom, keeping the center of the editor in place
+    pub fn set_zoom(&mut self, zoom: f32) {
+        self.zoom_around(self.canvas_rect_screen_space.center(), zoom);
+    }
+
+    /// Zoom and pan so all nodes are visible
+    pub fn zoom_to_fit(&mut self) {
+        let node_ids = self.nodes.keys().cloned().collect::<Vec<_>>();
+        self.zoom_to_nodes(&node_ids);
+    }
+
+    /// Zoom and pan so the selected nodes are visible
+    pub fn zoom_to_selection(&mut self) {
+        self.zoom_to_nodes(&self.selected_node_ids.clone());
+    }
+
+    /// Convert a position on the screen, e.g. of the pointer, to the position of a node there
+    pub fn screen_space_to_grid_space(&self, v: Pos2) -> Pos2 {
+        ((v - self.canvas_origin_screen_space - self.panning).to_vec2() / self.zoom).to_pos2()
+    }
+
     pub fn node_dimensions(&self, id: Uuid) -> Option<Vec2> {
         self.nodes.iter().find_map(|(&node_id, node)| {
             if node_id == id {
@@ -377,12 +541,12 @@ impl Context {
         });
         node.in_use = true;
 
-        self.style.format_node(node);
+        self.scaled_style.format_node(node);
         node.background_shape
             .replace(ui.painter().add(egui::Shape::Noop));
 
         let node_origin = node.origin;
-        let node_size = node.size;
+        let node_size = node.size * self.zoom;
         let title_space = node.layout_style.padding.y;
 
         node.header_shapes.push(ui.painter().add(egui::Shape::Noop));
@@ -391,8 +555,17 @@ impl Context {
 
         let padding = node.layout_style.padding;
         let node_pos = self.grid_space_to_screen_space(node_origin);
+        let node_ui_style = self.node_ui_style.clone();
+        let zoom = self.zoom;
+        let hidden = self.is_node_hidden(node_id);
 
         let response = ui.allocate_ui_at_rect(Rect::from_min_size(node_pos, node_size), |ui| {
+            ui.set_style(node_ui_style);
+            if hidden {
+                // Still laid out, so the node keeps its size while its frame is collapsed
+                ui.set_visible(false);
+            }
+
             if let Some(header_contents) = header_contents {
                 let response = ui.allocate_ui(ui.available_size(), header_contents);
                 header_content_rect = response.response.rect;
@@ -400,7 +573,7 @@ impl Context {
                 ui.add_space(title_space);
             }
 
-            ui.allocate_space(Vec2::splat(4.0));
+            ui.allocate_space(Vec2::splat(4.0 * zoom));
 
             for NodeAttribute {
                 id: attr_id,
@@ -424,7 +597,7 @@ impl Context {
 
         node.header_content_rect.max.x = node.rect.max.x;
 
-        let hovered = response.inner;
+        let hovered = response.inner && !hidden;
         if hovered {
             self.node_ids_overlapping_with_mouse.push(node_id);
         }
@@ -445,7 +618,7 @@ impl Context {
             pin.kind = kind;
             pin.attribute_rect = response.rect;
 
-            self.style.format_pin(pin, args);
+            self.scaled_style.format_pin(pin, args);
             self.nodes.get_mut(&node_id).unwrap().add_pin(pin_id);
         }
 
@@ -475,7 +648,7 @@ impl Context {
         link.end_pin_id = end_pin_id;
 
         link.shape.replace(ui.painter().add(egui::Shape::Noop));
-        self.style.format_link(link, args);
+        self.scaled_style.format_link(link, args);
 
         if (self.click_interaction_type == ClickInteractionType::LinkCreation
             && self
@@ -493,24 +666,67 @@ impl Context {
     }
 
     fn draw_grid(&self, canvas_size: Vec2, ui: &mut Ui) {
-        let mut y = self.panning.y.rem_euclid(self.style.grid_spacing);
+        // Every other dot is left out when zoomed out far
+        let mut grid_spacing = self.scaled_style.grid_spacing;
+        while grid_spacing < 12.0 {
+            grid_spacing *= 2.0;
+        }
+
+        let mut y = self.panning.y.rem_euclid(grid_spacing);
         while y < canvas_size.y {
-            let mut x = self.panning.x.rem_euclid(self.style.grid_spacing);
+            let mut x = self.panning.x.rem_euclid(grid_spacing);
             while x < canvas_size.x {
                 ui.painter().circle_filled(
                     self.editor_space_to_screen_space([x, y].into()),
-                    2.0,
-                    self.style.colors[ColorStyle::GridLine as usize],
+                    2.0 * self.zoom,
+                    self.scaled_style.colors[ColorStyle::GridLine as usize],
                 );
-                x += self.style.grid_spacing;
+                x += grid_spacing;
             }
 
-            y += self.style.grid_spacing;
+            y += grid_spacing;
         }
     }
 
     fn grid_space_to_screen_space(&self, v: Pos2) -> Pos2 {
-        v + self.canvas_origin_screen_space + self.panning
+        (v.to_vec2() * self.zoom + self.canvas_origin_screen_space + self.panning).to_pos2()
+    }
+
+    fn screen_space_rect_to_grid_space(&self, rect: Rect) -> Rect {
+        Rect::from_min_max(
+            self.screen_space_to_grid_space(rect.min),
+            self.screen_space_to_grid_space(rect.max),
+        )
+    }
+
+    /// Change the zoom, keeping the grid position at a screen position in place
+    fn zoom_around(&mut self, screen_pos: Pos2, zoom: f32) {
+        let grid_pos = self.screen_space_to_grid_space(screen_pos);
+
+        self.zoom = zoom.clamp(MIN_ZOOM, MAX_ZOOM);
+        self.panning =
+            screen_pos.to_vec2() - self.canvas_origin_screen_space - grid_pos.to_vec2() * self.zoom;
+    }
+
+    fn zoom_to_nodes(&mut self, node_ids: &[Uuid]) {
+        let bounds = node_ids
+            .iter()
+            .filter_map(|node_id| self.nodes.get(node_id))
+            .map(|node| self.screen_space_rect_to_grid_space(node.rect))
+            .reduce(|bounds, rect| bounds.union(rect));
+
+        let canvas_rect = self.canvas_rect_screen_space.shrink(ZOOM_TO_FIT_MARGIN);
+        let bounds = match bounds {
+            Some(bounds) if bounds.is_positive() && canvas_rect.is_positive() => bounds,
+            _ => return,
+        };
+
+        self.zoom = (canvas_rect.width() / bounds.width())
+            .min(canvas_rect.height() / bounds.height())
+            .clamp(MIN_ZOOM, MAX_ZOOM);
+        self.panning = canvas_rect.center().to_vec2()
+            - self.canvas_origin_screen_space
+            - bounds.center().to_vec2() * self.zoom;
     }
 
     fn editor_space_to_screen_space(&self, v: Pos2) -> Pos2 {
@@ -518,8 +734,12 @@ impl Context {
     }
 
     fn get_screen_space_pin_coordinates(&self, pin: &PinData) -> Pos2 {
+        if let Some(pos) = self.collapsed_frame_pin_pos(pin.parent_node_id, pin.kind) {
+            return pos;
+        }
+
         let parent_node_rect = self.nodes.get(&pin.parent_node_id).unwrap().rect;
-        self.style.get_screen_space_pin_coordinates(
+        self.scaled_style.get_screen_space_pin_coordinates(
             &parent_node_rect,
             &pin.attribute_rect,
             pin.kind,
@@ -552,10 +772,10 @@ impl Context {
         let mut smallest_distance = f32::MAX;
         self.hovered_pin_id.take();
 
-        let hover_radius_sqr = self.style.pin_hover_radius.powi(2);
+        let hover_radius_sqr = self.scaled_style.pin_hover_radius.powi(2);
 
         for (pin_id, pin) in self.pins.iter() {
-            if self.occluded_pin_ids.contains(pin_id) {
+            if self.occluded_pin_ids.contains(pin_id) || self.is_node_hidden(pin.parent_node_id) {
                 continue;
             }
 
@@ -596,9 +816,10 @@ impl Context {
         self.hovered_link_id.take();
 
         let links_clone = self.links.clone();
-        for (&link_id, link) in self.links.iter_mut() {
+        for (&link_id, link) in self.links.iter() {
             if !self.pins.contains_key(&link.start_pin_id)
                 || !self.pins.contains_key(&link.end_pin_id)
+                || self.is_link_hidden(link)
             {
                 continue;
             }
@@ -623,22 +844,21 @@ impl Context {
             .unwrap_or(0);
 
             let end_pos = if self.hovered_pin_id == Some(link.end_pin_id) && pin_link_count > 1 {
-                self.style
-                    .calculate_link_end_pos(end_pin.pos, self.mouse_pos, pin_link_count, idx)
+                self.scaled_style.calculate_link_end_pos(
+                    end_pin.pos,
+                    self.mouse_pos,
+                    pin_link_count,
+                    idx,
+                )
             } else {
                 end_pin.pos
             };
 
-            let link_data = LinkBezierData::build(
-                start_pin.pos,
-                end_pos,
-                start_pin.kind,
-                self.style.link_line_segments_per_length,
-            );
-
-            let distance = link_data.get_distance_to_cubic_bezier(&self.mouse_pos);
+            let distance = self
+                .link_path(link_id, start_pin.pos, end_pos, start_pin.kind)
+                .get_distance(&self.mouse_pos);
 
-            if distance < self.style.link_hover_distance && distance < smallest_distance {
+            if distance < self.scaled_style.link_hover_distance && distance < smallest_distance {
                 smallest_distance = distance;
                 self.hovered_link_id.replace(link_id);
             }
@@ -709,14 +929,16 @@ impl Context {
 
     fn draw_link(&mut self, link_id: Uuid, ui: &mut Ui) {
         let links_clone = self.links.clone();
-        let link = self.links.get_mut(&link_id).unwrap();
+        let link = self.links.get(&link_id).unwrap();
 
         if !link.in_use
             || !self.pins.contains_key(&link.start_pin_id)
             || !self.pins.contains_key(&link.end_pin_id)
+            || self.is_link_hidden(link)
         {
             return;
         }
+        let link = self.links.get_mut(&link_id).unwrap();
 
         let same_pin_link_count = Self::link_count_for_end_pin(
             &self.end_pin_link_mapping,
@@ -739,25 +961,31 @@ impl Context {
         let hovered_pin_id = self.hovered_pin_id;
 
         let end_pos = if hovered_pin_id == Some(link.end_pin_id) && same_pin_link_count > 1 {
-            self.style
-                .calculate_link_end_pos(end_pin.pos, self.mouse_pos, same_pin_link_count, idx)
+            self.scaled_style.calculate_link_end_pos(
+                end_pin.pos,
+                self.mouse_pos,
+                same_pin_link_count,
+                idx,
+            )
         } else {
             end_pin.pos
         };
 
-        let link_bezier_data = LinkBezierData::build(
-            start_pin.pos,
-            end_pos,
-            start_pin.kind,
-            self.style.link_line_segments_per_length,
-        );
         let link_shape = link.shape.take().unwrap();
+        let link_path = self.link_path(link_id, start_pin.pos, end_pos, start_pin.kind);
         let link_hovered = self.hovered_link_id == Some(link_id)
             && self.click_interaction_type != ClickInteractionType::BoxSelection;
 
         if link_hovered && self.left_mouse_pressed {
             self.begin_link_interaction(link_id);
         }
+        if let (Some((reroute_link_id, reroute_idx)), true) =
+            (self.hovered_reroute, self.left_mouse_pressed)
+        {
+            if reroute_link_id == link_id {
+                self.begin_reroute_drag(link_id, reroute_idx);
+            }
+        }
 
         if self.detached_link_id == Some(link_id) {
             return;
@@ -773,18 +1001,66 @@ impl Context {
             }
         }
 
+        // Dashes flow faster and brighter the louder the link is
+        let flow_shape = match link.flow {
+            Some(level) => {
+                let level = level.clamp(0.0, 1.0);
+                let dt = ui.input().unstable_dt.min(1.0 / 30.0);
+                let link = self.links.get_mut(&link_id).unwrap();
+                link.flow_offset += dt * level * self.scaled_style.link_flow_speed;
+                if level > 0.0 {
+                    ui.ctx().request_repaint();
+                }
+
+                let flow_color = self.scaled_style.colors[ColorStyle::LinkFlow as usize]
+                    .linear_multiply(0.2 + 0.8 * level);
+                link_path.draw_flow(
+                    link.flow_offset,
+                    self.scaled_style.link_flow_dash_length,
+                    (self.scaled_style.link_thickness, flow_color),
+                )
+            }
+            None => egui::Shape::Noop,
+        };
+
         ui.painter().set(
             link_shape,
-            link_bezier_data.draw((self.style.link_thickness, link_color)),
+            egui::Shape::Vec(vec![
+                link_path.draw((self.scaled_style.link_thickness, link_color)),
+                flow_shape,
+                egui::Shape::Vec(self.draw_reroutes(link_id, link_color)),
+            ]),
         );
     }
 
     fn draw_node(&mut self, node_id: Uuid, ui: &mut Ui) {
+        let mut note_decorations = self
+            .nodes
+            .get(&node_id)
+            .filter(|node| node.note)
+            .map(|node| {
+                self.draw_note_decorations(node, self.selected_node_ids.contains(&node_id))
+            });
+        let note_grip_hovered = self.nodes.get(&node_id).is_some_and(|node| {
+            node.note && self.note_grip_rect(node.rect).contains(self.mouse_pos)
+        });
+
         let node: &mut Node = self.nodes.get_mut(&node_id).unwrap();
         if !node.in_use {
             return;
         }
 
+        if self.hidden_node_ids.contains(&node_id) {
+            node.header_shapes.clear();
+            node.background_shape.take();
+
+            // Pins are still placed, for the links to the header of the collapsed frame
+            for pin_id in node.pin_ids.iter().cloned().collect::<Vec<_>>() {
+                self.draw_pin(pin_id, ui);
+            }
+            return;
+        }
+
         let node_hovered = self.hovered_node_id == Some(node_id)
             && self.click_interaction_type != ClickInteractionType::BoxSelection;
 
@@ -805,14 +1081,17 @@ impl Context {
         let painter = ui.painter();
 
         if let Some(bg_shape) = node.background_shape.take() {
-            painter.set(
-                bg_shape,
-                egui::Shape::rect_filled(
-                    node.rect,
-                    node.layout_style.corner_rounding,
-                    node_bg_color,
-                ),
+            let background = egui::Shape::rect_filled(
+                node.rect,
+                node.layout_style.corner_rounding,
+                node_bg_color,
             );
+            match note_decorations.take() {
+                Some(decorations) => {
+                    painter.set(bg_shape, egui::Shape::Vec(vec![background, decorations]))
+                }
+                None => painter.set(bg_shape, background),
+            }
         }
 
         if node.header_content_rect.height() > 0.0 {
@@ -854,20 +1133,28 @@ impl Context {
             self.draw_pin(pin_id, ui);
         }
 
+        let note_grip_hovered = node_hovered && note_grip_hovered;
+        if note_grip_hovered {
+            ui.output().cursor_icon = egui::CursorIcon::ResizeSouthEast;
+        }
+
         if node_hovered && self.left_mouse_pressed && self.interactive_node_id != Some(node_id) {
-            self.begin_node_selection(node_id);
+            if note_grip_hovered {
+                self.begin_note_resize(node_id);
+            } else {
+                self.begin_node_selection(node_id);
+            }
         }
     }
 
     fn draw_pin(&mut self, pin_id: Uuid, ui: &mut Ui) {
+        let pin_pos = self.get_screen_space_pin_coordinates(self.pins.get(&pin_id).unwrap());
         let pin: &mut PinData = self.pins.get_mut(&pin_id).unwrap();
-        let parent_node_rect = self.nodes.get(&pin.parent_node_id).unwrap().rect;
+        pin.pos = pin_pos;
 
-        pin.pos = self.style.get_screen_space_pin_coordinates(
-            &parent_node_rect,
-            &pin.attribute_rect,
-            pin.kind,
-        );
+        if self.hidden_node_ids.contains(&pin.parent_node_id) {
+            return;
+        }
 
         let mut pin_color = pin.color_style.background;
 
@@ -886,10 +1173,13 @@ impl Context {
             if self.left_mouse_pressed && (pin.is_output() || self.hovered_link_id.is_some()) {
                 self.begin_link_creation(pin_id);
             }
+
+            self.show_link_refusal(pin_id, ui);
         }
+        let pin_color = self.refused_pin_color(pin_id, pin_color);
 
         if pin_hovered && attached_link_count > 1 {
-            self.style.draw_hovered_pin(
+            self.scaled_style.draw_hovered_pin(
                 attached_link_count,
                 pin_pos,
                 self.mouse_pos,
@@ -898,11 +1188,11 @@ impl Context {
                 ui,
             );
         } else {
-            self.style.draw_pin(
+            self.scaled_style.draw_pin(
                 pin_pos,
                 pin_shape,
                 pin_color,
-                self.style.pin_circle_radius,
+                self.scaled_style.pin_circle_radius,
                 ui,
             );
         }
@@ -910,7 +1200,9 @@ impl Context {
 
     fn begin_canvas_interaction(&mut self) {
         let any_ui_element_hovered = self.hovered_node_id.is_some()
+            || self.hovered_frame_id.is_some()
             || self.hovered_link_id.is_some()
+            || self.hovered_reroute.is_some()
             || self.hovered_pin_id.is_some();
 
         let mouse_not_in_canvas = !self.mouse_in_canvas;
@@ -932,39 +1224,18 @@ impl Context {
 
     fn translate_selected_nodes(&mut self) {
         if self.left_mouse_dragging {
-            let delta = self.mouse_delta;
+            let delta = self.mouse_delta / self.zoom;
+            // Selected nodes may not have been shown yet
             for node_id in self.selected_node_ids.iter() {
-                let node = self.nodes.get_mut(node_id).unwrap();
-                if node.draggable {
+                if let Some(node) = self.nodes.get_mut(node_id).filter(|node| node.draggable) {
                     node.origin += delta;
                 }
             }
         }
     }
 
-    fn should_link_snap_to_pin(
-        &self,
-        start_pin: &PinData,
-        hovered_pin_id: Uuid,
-        duplicate_link: Option<Uuid>,
-    ) -> bool {
-        let end_pin = self.pins.get(&hovered_pin_id).unwrap();
-
-        if start_pin.parent_node_id == end_pin.parent_node_id {
-            return false;
-        }
-
-        if start_pin.kind == end_pin.kind {
-            return false;
-        }
-
-        if duplicate_link.map_or(false, |duplicate_id| {
-            Some(duplicate_id) != self.snap_link_id
-        }) {
-            return false;
-        }
-
-        true
+    fn should_link_snap_to_pin(&self, start_pin_id: Uuid, hovered_pin_id: Uuid) -> bool {
+        hovered_pin_id != start_pin_id && !self.refused_pins.contains_key(&hovered_pin_id)
     }
 
     fn box_selector_update_selection(&mut self) -> Rect {
@@ -979,35 +1250,41 @@ impl Context {
 
         self.selected_node_ids.clear();
         for (node_id, node) in self.nodes.iter() {
-            if node.in_use && box_rect.intersects(node.rect) {
+            if node.in_use && !self.is_node_hidden(*node_id) && box_rect.intersects(node.rect) {
                 self.selected_node_ids.push(*node_id);
             }
         }
 
+        // Touching the header of a frame selects the whole frame
+        self.selected_frame_ids.clear();
+        for (&frame_id, frame) in self.frames.iter() {
+            if !frame.in_use || !box_rect.intersects(frame.header_rect) {
+                continue;
+            }
+
+            self.selected_frame_ids.push(frame_id);
+            for node_id in frame.node_ids.iter() {
+                if self.nodes.contains_key(node_id) && !self.selected_node_ids.contains(node_id) {
+                    self.selected_node_ids.push(*node_id);
+                }
+            }
+        }
+
         self.selected_link_ids.clear();
         for (&link_id, link) in self.links.iter().filter(|(_, link)| link.in_use) {
             if !self.pins.contains_key(&link.start_pin_id)
                 || !self.pins.contains_key(&link.end_pin_id)
+                || self.is_link_hidden(link)
             {
                 continue;
             }
 
             let pin_start = self.pins.get(&link.start_pin_id).unwrap();
             let pin_end = self.pins.get(&link.end_pin_id).unwrap();
-            let node_start_rect = self.nodes.get(&pin_start.parent_node_id).unwrap().rect;
-            let node_end_rect = self.nodes.get(&pin_end.parent_node_id).unwrap().rect;
-            let start = self.style.get_screen_space_pin_coordinates(
-                &node_start_rect,
-                &pin_start.attribute_rect,
-                pin_start.kind,
-            );
-            let end = self.style.get_screen_space_pin_coordinates(
-                &node_end_rect,
-                &pin_end.attribute_rect,
-                pin_end.kind,
-            );
+            let start = self.get_screen_space_pin_coordinates(pin_start);
+            let end = self.get_screen_space_pin_coordinates(pin_end);
 
-            if self.rectangle_overlaps_link(&box_rect, &start, &end, pin_start.kind) {
+            if self.rectangle_overlaps_link(&box_rect, link_id, &start, &end, pin_start.kind) {
                 self.selected_link_ids.push(link_id);
             }
         }
@@ -1018,33 +1295,13 @@ impl Context {
     fn rectangle_overlaps_link(
         &self,
         rect: &Rect,
+        link_id: Uuid,
         start: &Pos2,
         end: &Pos2,
         start_type: AttributeKind,
     ) -> bool {
-        let mut lrect = Rect::from_min_max(*start, *end);
-        if lrect.min.x > lrect.max.x {
-            std::mem::swap(&mut lrect.min.x, &mut lrect.max.x);
-        }
-
-        if lrect.min.y > lrect.max.y {
-            std::mem::swap(&mut lrect.min.y, &mut lrect.max.y);
-        }
-
-        if rect.intersects(lrect) {
-            if rect.contains(*start) || rect.contains(*end) {
-                return true;
-            }
-
-            let link_data = LinkBezierData::build(
-                *start,
-                *end,
-                start_type,
-                self.style.link_line_segments_per_length,
-            );
-            return link_data.rectangle_overlaps_bezier(rect);
-        }
-        false
+        let link_path = self.link_path(link_id, *start, *end, start_type);
+        rect.intersects(link_path.bounding_rect()) && link_path.rectangle_overlaps(rect)
     }
 
     fn click_interaction_update(&mut self, ui: &mut Ui) {
@@ -1053,9 +1310,9 @@ impl Context {
                 self.click_interaction_state.box_selection.max = self.mouse_pos;
                 let rect = self.box_selector_update_selection();
 
-                let box_selector_color = self.style.colors[ColorStyle::BoxSelector as usize];
+                let box_selector_color = self.scaled_style.colors[ColorStyle::BoxSelector as usize];
                 let box_selector_outline =
-                    self.style.colors[ColorStyle::BoxSelectorOutline as usize];
+                    self.scaled_style.colors[ColorStyle::BoxSelectorOutline as usize];
                 ui.painter()
                     .rect(rect, 0.0, box_selector_color, (1.0, box_selector_outline));
 
@@ -1079,6 +1336,29 @@ impl Context {
                 self.translate_selected_nodes();
                 if self.left_mouse_released {
                     self.click_interaction_type = ClickInteractionType::None;
+
+                    let drag_origins =
+                        std::mem::take(&mut self.click_interaction_state.drag_origins);
+                    self.moved_nodes = drag_origins
+                        .into_iter()
+                        .filter_map(|(node_id, from)| {
+                            let to = self.nodes.get(&node_id)?.origin;
+                            (to != from).then_some((node_id, from, to))
+                        })
+                        .collect();
+                }
+            }
+            ClickInteractionType::NoteResize => {
+                self.resize_note();
+                if self.left_mouse_released {
+                    self.click_interaction_type = ClickInteractionType::None;
+                }
+            }
+            ClickInteractionType::RerouteDrag => {
+                self.drag_reroute();
+                if self.left_mouse_released {
+                    self.click_interaction_type = ClickInteractionType::None;
+                    self.rerouted_link_id = Some(self.click_interaction_state.dragged_reroute.0);
                 }
             }
             ClickInteractionType::Link => {
@@ -1095,11 +1375,10 @@ impl Context {
                 });
 
                 let should_snap = self.hovered_pin_id.map_or(false, |hovered_pin_id| {
-                    let start_pin = self
-                        .pins
-                        .get(&self.click_interaction_state.link_creation.start_pin_id)
-                        .unwrap();
-                    self.should_link_snap_to_pin(start_pin, hovered_pin_id, maybe_duplicate_link_id)
+                    self.should_link_snap_to_pin(
+                        self.click_interaction_state.link_creation.start_pin_id,
+                        hovered_pin_id,
+                    )
                 });
 
                 let snapping_pin_changed = self
@@ -1153,7 +1432,7 @@ impl Context {
                     .unwrap_or(0);
 
                     if same_pin_link_count > 1 {
-                        self.style.calculate_link_end_pos(
+                        self.scaled_style.calculate_link_end_pos(
                             pin_pos,
                             self.mouse_pos,
                             same_pin_link_count,
@@ -1166,15 +1445,17 @@ impl Context {
                     self.mouse_pos
                 };
 
-                let link_data = LinkBezierData::build(
+                let link_data = LinkPathData::build(
                     start_pos,
                     end_pos,
+                    &[],
                     start_pin.kind,
-                    self.style.link_line_segments_per_length,
+                    self.scaled_style.link_style,
+                    self.scaled_style.link_line_segments_per_length,
                 );
                 ui.painter().add(link_data.draw((
-                    self.style.link_thickness,
-                    self.style.colors[ColorStyle::Link as usize],
+                    self.scaled_style.link_thickness,
+                    self.scaled_style.colors[ColorStyle::Link as usize],
                 )));
 
                 let link_creation_on_snap = self.hovered_pin_id.map_or(false, |hovered_pin_id| {
@@ -1285,6 +1566,7 @@ impl Context {
     fn begin_link_selection(&mut self, link_id: Uuid) {
         self.click_interaction_type = ClickInteractionType::Link;
         self.selected_node_ids.clear();
+        self.selected_frame_ids.clear();
         self.selected_link_ids.clear();
         self.selected_link_ids.push(link_id);
     }
@@ -1306,12 +1588,24 @@ impl Context {
         self.click_interaction_type = ClickInteractionType::Node;
         if !self.selected_node_ids.contains(&id) {
             self.selected_node_ids.clear();
+            self.selected_frame_ids.clear();
             self.selected_link_ids.clear();
             self.selected_node_ids.push(id);
 
             self.node_depth_order.retain(|depth_id| *depth_id != id);
             self.node_depth_order.push(id);
         }
+
+        self.begin_node_drag();
+    }
+
+    /// Remember where the selected nodes were, to report them as moved when the drag ends
+    fn begin_node_drag(&mut self) {
+        self.click_interaction_state.drag_origins = self
+            .selected_node_ids
+            .iter()
+            .filter_map(|node_id| Some((*node_id, self.nodes.get(node_id)?.origin)))
+            .collect();
     }
 }
 
@@ -1326,6 +1620,8 @@ enum ElementStateChange {
 #[derive(PartialEq, Debug, Copy, Clone)]
 enum ClickInteractionType {
     Node,
+    NoteResize,
+    RerouteDrag,
     Link,
     LinkCreation,
     Panning,
@@ -1355,6 +1651,11 @@ struct ClickInteractionState {
     link_creation: ClickInteractionStateLinkCreation,
     #[derivative(Default(value = "[[0.0; 2].into(); 2].into()"))]
     box_selection: Rect,
+    /// Positions of the dragged nodes when the drag started
+    drag_origins: Vec<(Uuid, Pos2)>,
+    resized_note_id: Uuid,
+    /// The dragged reroute point as (link, index)
+    dragged_reroute: (Uuid, usize),
 }
 
 /// This controls the modifiers needed for certain mouse interactions
@@ -9,6 +9,10 @@ pub struct LinkArgs {
     pub base: Option<egui::Color32>,
     pub hovered: Option<egui::Color32>,
     pub selected: Option<egui::Color32>,
+    pub style: Option<LinkStyle>,
+    /// Level of the signal on the link from 0 to 1, which animates dashes flowing along it.
+    /// None draws no dashes.
+    pub flow: Option<f32>,
 }
 
 impl LinkArgs {
@@ -17,6 +21,8 @@ impl LinkArgs {
             base: None,
             hovered: None,
             selected: None,
+            style: None,
+            flow: None,
         }
     }
 }
@@ -36,6 +42,10 @@ pub struct LinkData {
     pub end_pin_id: Uuid,
     #[derivative(Debug = "ignore")]
     pub color_style: LinkDataColorStyle,
+    pub style: LinkStyle,
+    pub flow: Option<f32>,
+    /// Distance the flow dashes have moved along the link
+    pub flow_offset: f32,
     #[derivative(Debug = "ignore")]
     pub shape: Option<egui::layers::ShapeIdx>,
 }
@@ -47,6 +57,9 @@ impl LinkData {
             start_pin_id: Uuid::new_v4(),
             end_pin_id: Uuid::new_v4(),
             color_style: Default::default(),
+            style: LinkStyle::Bezier,
+            flow: None,
+            flow_offset: 0.0,
             shape: None,
         }
     }
@@ -104,89 +117,196 @@ impl BezierCurve {
     }
 }
 
+/// A link split into segments by its reroute points. Each segment is flattened to a polyline
+/// in the style of the link.
 #[derive(Debug)]
-pub(crate) struct LinkBezierData {
-    pub bezier: BezierCurve,
-    pub num_segments: usize,
+pub(crate) struct LinkPathData {
+    /// From the start pin of the link to its end pin
+    pub segments: Vec<Vec<Pos2>>,
 }
 
-impl LinkBezierData {
-    #[inline]
+impl LinkPathData {
+    /// The reroute points are ordered from the start pin to the end pin.
     pub(crate) fn build(
         start: Pos2,
         end: Pos2,
+        reroutes: &[Pos2],
         start_type: AttributeKind,
+        link_style: LinkStyle,
         line_segments_per_length: f32,
     ) -> Self {
-        let (mut start, mut end) = (start, end);
-        if start_type == AttributeKind::Input {
-            std::mem::swap(&mut start, &mut end);
-        }
+        let points = std::iter::once(start)
+            .chain(reroutes.iter().copied())
+            .chain(std::iter::once(end))
+            .collect::<Vec<_>>();
 
-        let link_length = end.distance(start);
-        let offset = egui::vec2(0.25 * link_length, 0.0);
         Self {
-            bezier: BezierCurve(start, start + offset, end - offset, end),
-            num_segments: 1.max((link_length * line_segments_per_length) as usize),
+            segments: points
+                .windows(2)
+                .map(|points| {
+                    // Segments are routed from the output side, but keep the direction of the link
+                    if start_type == AttributeKind::Input {
+                        let mut segment = segment_points(
+                            points[1],
+                            points[0],
+                            link_style,
+                            line_segments_per_length,
+                        );
+                        segment.reverse();
+                        segment
+                    } else {
+                        segment_points(points[0], points[1], link_style, line_segments_per_length)
+                    }
+                })
+                .collect(),
         }
     }
 
-    pub(crate) fn get_closest_point_on_cubic_bezier(&self, p: &Pos2) -> Pos2 {
-        let mut p_last = self.bezier.0;
-        let mut p_closest = self.bezier.0;
-        let mut p_closest_dist = f32::MAX;
-        let t_step = 1.0 / self.num_segments as f32;
-        for i in 1..=self.num_segments {
-            let p_current = self.bezier.eval(t_step * i as f32);
-            let p_line = line_closest_point(&p_last, &p_current, p);
-            let dist = p.distance_sq(p_line);
-            if dist < p_closest_dist {
-                p_closest = p_line;
-                p_closest_dist = dist;
-            }
-            p_last = p_current;
+    fn lines(&self) -> impl Iterator<Item = (Pos2, Pos2)> + '_ {
+        self.segments
+            .iter()
+            .flat_map(|segment| segment.windows(2).map(|line| (line[0], line[1])))
+    }
+
+    pub(crate) fn get_distance(&self, pos: &Pos2) -> f32 {
+        self.segments
+            .iter()
+            .map(|segment| segment_distance(segment, pos))
+            .fold(f32::MAX, f32::min)
+    }
+
+    /// Index of the segment closest to the position, which is where a reroute point at the
+    /// position is inserted
+    pub(crate) fn closest_segment(&self, pos: &Pos2) -> usize {
+        self.segments
+            .iter()
+            .map(|segment| segment_distance(segment, pos))
+            .enumerate()
+            .min_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap_or(Ordering::Equal))
+            .map_or(0, |(idx, _)| idx)
+    }
+
+    pub(crate) fn bounding_rect(&self) -> Rect {
+        let mut rect = Rect::NOTHING;
+        for point in self.segments.iter().flatten() {
+            rect.extend_with(*point);
         }
-        p_closest
+        rect
     }
 
-    #[inline]
-    pub(crate) fn get_distance_to_cubic_bezier(&self, pos: &Pos2) -> f32 {
-        let point_on_curve = self.get_closest_point_on_cubic_bezier(pos);
-        pos.distance(point_on_curve)
+    pub(crate) fn rectangle_overlaps(&self, rect: &Rect) -> bool {
+        self.lines()
+            .any(|(a, b)| rectangle_overlaps_line_segment(rect, &a, &b))
     }
 
-    #[inline]
-    pub(crate) fn rectangle_overlaps_bezier(&self, rect: &Rect) -> bool {
-        let mut current = self.bezier.eval(0.0);
-        let dt = 1.0 / self.num_segments as f32;
-        for i in 0..self.num_segments {
-            let next = self.bezier.eval((i + 1) as f32 * dt);
-            if rectangle_overlaps_line_segment(rect, &current, &next) {
-                return true;
+    pub(crate) fn draw(&self, stroke: impl Into<egui::Stroke>) -> egui::Shape {
+        let stroke = stroke.into();
+        egui::Shape::Vec(
+            self.segments
+                .iter()
+                .map(|segment| {
+                    egui::Shape::Path(PathShape {
+                        points: segment.clone(),
+                        closed: false,
+                        fill: egui::Color32::TRANSPARENT,
+                        stroke,
+                    })
+                })
+                .collect(),
+        )
+    }
+
+    /// Dashes along the link with gaps as long as the dashes. The dashes are moved towards the
+    /// end of the link by the offset.
+    pub(crate) fn draw_flow(
+        &self,
+        offset: f32,
+        dash_length: f32,
+        stroke: impl Into<egui::Stroke>,
+    ) -> egui::Shape {
+        let stroke = stroke.into();
+        let period = 2.0 * dash_length;
+        if period <= 0.0 {
+            return egui::Shape::Noop;
+        }
+        let phase = offset.rem_euclid(period);
+
+        let mut shapes = Vec::new();
+        // Distance along the link to the start of the current line
+        let mut travelled = 0.0;
+        for (a, b) in self.lines() {
+            let length = a.distance(b);
+            if length <= 0.0 {
+                continue;
             }
-            current = next;
+            let dir = (b - a) / length;
+
+            let mut dash_start = phase + ((travelled - phase) / period).floor() * period;
+            while dash_start < travelled + length {
+                let from = dash_start.max(travelled) - travelled;
+                let to = (dash_start + dash_length).min(travelled + length) - travelled;
+                if to > from {
+                    shapes.push(egui::Shape::line_segment(
+                        [a + dir * from, a + dir * to],
+                        stroke,
+                    ));
+                }
+                dash_start += period;
+            }
+            travelled += length;
         }
-        false
+
+        egui::Shape::Vec(shapes)
     }
+}
 
-    pub(crate) fn draw(&self, stroke: impl Into<egui::Stroke>) -> egui::Shape {
-        let points = std::iter::once(self.bezier.0)
-            .chain(
-                (1..self.num_segments)
-                    .map(|x| self.bezier.eval(x as f32 / self.num_segments as f32)),
-            )
-            .chain(std::iter::once(self.bezier.3))
-            .collect();
-        let path_shape = PathShape {
-            points,
-            closed: false,
-            fill: egui::Color32::TRANSPARENT,
-            stroke: stroke.into(),
-        };
-        egui::Shape::Path(path_shape)
+/// The polyline of a link segment from an output side start to an input side end
+fn segment_points(
+    start: Pos2,
+    end: Pos2,
+    link_style: LinkStyle,
+    line_segments_per_length: f32,
+) -> Vec<Pos2> {
+    let link_length = end.distance(start);
+
+    match link_style {
+        LinkStyle::Bezier => {
+            let offset = egui::vec2(0.25 * link_length, 0.0);
+            let bezier = BezierCurve(start, start + offset, end - offset, end);
+            let num_segments = 1.max((link_length * line_segments_per_length) as usize);
+            (0..=num_segments)
+                .map(|i| bezier.eval(i as f32 / num_segments as f32))
+                .collect()
+        }
+        LinkStyle::Straight => vec![start, end],
+        LinkStyle::Orthogonal if start.x <= end.x => {
+            let mid_x = 0.5 * (start.x + end.x);
+            vec![start, pos2(mid_x, start.y), pos2(mid_x, end.y), end]
+        }
+        LinkStyle::Orthogonal => {
+            // Leaves the output to the right and enters the input from the left, so it goes
+            // around between them
+            let stub = 0.25 * link_length;
+            let mid_y = 0.5 * (start.y + end.y);
+            vec![
+                start,
+                pos2(start.x + stub, start.y),
+                pos2(start.x + stub, mid_y),
+                pos2(end.x - stub, mid_y),
+                pos2(end.x - stub, end.y),
+                end,
+            ]
+        }
     }
 }
 
+fn segment_distance(segment: &[Pos2], pos: &Pos2) -> f32 {
+    segment
+        .windows(2)
+        .map(|line| pos.distance(line_closest_point(&line[0], &line[1], pos)))
+        .fold(f32::MAX, f32::min)
+}
+
 #[inline]
 pub fn line_closest_point(a: &Pos2, b: &Pos2, p: &Pos2) -> Pos2 {
     let ap = *p - *a;
@@ -204,7 +324,7 @@ pub fn line_closest_point(a: &Pos2, b: &Pos2, p: &Pos2) -> Pos2 {
 
 #[inline]
 fn eval_inplicit_line_eq(p1: &Pos2, p2: &Pos2, p: &Pos2) -> f32 {
-    (p2.y * p1.y) * p.x + (p1.x * p2.x) * p.y * (p2.x * p1.y - p1.x * p2.y)
+    (p2.y - p1.y) * p.x + (p1.x - p2.x) * p.y + (p2.x * p1.y - p1.x * p2.y)
 }
 
 #[inline]
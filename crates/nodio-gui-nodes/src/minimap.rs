@@ -0,0 +1,121 @@
+use super::*;
+
+impl Context {
+    /// Draw the nodes and links scaled down in a corner of the editor, together with the part
+    /// of the editor that is visible. Clicking or dragging on the minimap pans the editor there.
+    pub(crate) fn show_minimap(&mut self, ui: &Ui) {
+        let area_rect = self
+            .canvas_rect_screen_space
+            .shrink(self.style.minimap_margin);
+        let size = self.style.minimap_size.min(area_rect.size());
+        if size.x <= 0.0 || size.y <= 0.0 {
+            return;
+        }
+
+        let min = match self.style.minimap_position {
+            MinimapPosition::TopLeft => area_rect.left_top(),
+            MinimapPosition::TopRight => pos2(area_rect.right() - size.x, area_rect.top()),
+            MinimapPosition::BottomLeft => pos2(area_rect.left(), area_rect.bottom() - size.y),
+            MinimapPosition::BottomRight => area_rect.right_bottom() - size,
+        };
+        let minimap_rect = Rect::from_min_size(min, size);
+
+        let viewport = self.screen_space_rect_to_grid_space(self.canvas_rect_screen_space);
+        let node_rects = self
+            .node_depth_order
+            .iter()
+            .filter_map(|node_id| self.nodes.get(node_id).map(|node| (node_id, node)))
+            .filter(|(_, node)| node.in_use)
+            .map(|(node_id, node)| {
+                (
+                    self.screen_space_rect_to_grid_space(node.rect),
+                    self.selected_node_ids.contains(node_id),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        // Kept while dragging, so the minimap does not move under the pointer
+        let bounds = self.minimap_drag_bounds.unwrap_or_else(|| {
+            node_rects
+                .iter()
+                .fold(viewport, |bounds, (rect, _)| bounds.union(*rect))
+        });
+        if !bounds.is_positive() {
+            return;
+        }
+
+        let scale = (size.x / bounds.width()).min(size.y / bounds.height());
+        let offset = minimap_rect.center().to_vec2() - bounds.center().to_vec2() * scale;
+        let to_minimap = |pos: Pos2| (pos.to_vec2() * scale + offset).to_pos2();
+        let to_minimap_rect =
+            |rect: Rect| Rect::from_min_max(to_minimap(rect.min), to_minimap(rect.max));
+
+        let links = self
+            .links
+            .iter()
+            .filter(|(_, link)| link.in_use)
+            .filter_map(|(&link_id, link)| {
+                let start = self.pins.get(&link.start_pin_id)?.pos;
+                let end = self.pins.get(&link.end_pin_id)?.pos;
+                let points = std::iter::once(self.screen_space_to_grid_space(start))
+                    .chain(self.link_reroutes(link_id).iter().copied())
+                    .chain(std::iter::once(self.screen_space_to_grid_space(end)))
+                    .map(to_minimap)
+                    .collect::<Vec<_>>();
+                Some(points)
+            })
+            .collect::<Vec<_>>();
+
+        let colors = self.style.colors;
+
+        egui::Area::new(ui.id().with("Minimap"))
+            .fixed_pos(minimap_rect.min)
+            .show(ui.ctx(), |ui| {
+                let response = ui.allocate_rect(minimap_rect, Sense::click_and_drag());
+                let painter = ui.painter_at(minimap_rect);
+
+                painter.rect(
+                    minimap_rect,
+                    0.0,
+                    colors[ColorStyle::MinimapBackground as usize],
+                    (1.0, colors[ColorStyle::MinimapOutline as usize]),
+                );
+
+                for points in links {
+                    painter.add(egui::Shape::line(
+                        points,
+                        (1.0, colors[ColorStyle::MinimapLink as usize]),
+                    ));
+                }
+
+                for (rect, selected) in node_rects {
+                    let color = if selected {
+                        colors[ColorStyle::MinimapNodeSelected as usize]
+                    } else {
+                        colors[ColorStyle::MinimapNode as usize]
+                    };
+                    painter.rect_filled(to_minimap_rect(rect), 1.0, color);
+                }
+
+                painter.rect(
+                    to_minimap_rect(viewport),
+                    0.0,
+                    colors[ColorStyle::MinimapViewport as usize],
+                    (1.0, colors[ColorStyle::MinimapViewportOutline as usize]),
+                );
+
+                match response.interact_pointer_pos() {
+                    Some(pointer_pos) if response.is_pointer_button_down_on() => {
+                        let grid_pos = (pointer_pos.to_vec2() - offset) / scale;
+
+                        // Center the editor on the position
+                        self.panning = self.canvas_rect_screen_space.center().to_vec2()
+                            - self.canvas_origin_screen_space
+                            - grid_pos * self.zoom;
+                        self.minimap_drag_bounds = Some(bounds);
+                    }
+                    _ => self.minimap_drag_bounds = None,
+                }
+            });
+    }
+}
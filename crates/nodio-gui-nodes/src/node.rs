@@ -32,6 +32,8 @@ pub(crate) struct Node {
     pub layout_style: NodeLayoutStyle,
     pub pin_ids: HashSet<Uuid>,
     pub draggable: bool,
+    /// A sticky note, whose size is set by the user instead of its contents
+    pub note: bool,
 
     #[derivative(Debug = "ignore")]
     pub header_shapes: Vec<egui::layers::ShapeIdx>,
@@ -51,6 +53,7 @@ impl Node {
             layout_style: Default::default(),
             pin_ids: Default::default(),
             draggable: true,
+            note: false,
             header_shapes: Vec::new(),
             background_shape: None,
         }
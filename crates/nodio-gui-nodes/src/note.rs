@@ -0,0 +1,178 @@
+use egui::color::Hsva;
+
+use super::*;
+
+/// Smallest size of a note in grid space
+pub const MIN_NOTE_SIZE: Vec2 = Vec2::new(80.0, 50.0);
+
+/// The color and initial placement of a note. If color is None then the Context style is used
+#[derive(Default, Debug)]
+pub struct NoteArgs {
+    pub color: Option<egui::Color32>,
+    /// Position of the note in grid space when it is first created
+    pub origin: Option<Pos2>,
+    /// Size of the note in grid space when it is first created
+    pub size: Option<Vec2>,
+}
+
+impl Context {
+    /// Add a sticky note with editable text. Notes are selected, dragged and stacked like
+    /// nodes, but have no pins. The note is dragged by the bar at its top, and resized by the
+    /// grip in its bottom right corner.
+    pub fn add_note(
+        &mut self,
+        id: Uuid,
+        text: &mut String,
+        args: NoteArgs,
+        ui: &mut Ui,
+    ) -> egui::Response {
+        let node: &mut Node = self.nodes.entry(id).or_insert_with(|| {
+            let mut node = Node::new();
+            node.note = true;
+            if let Some(origin) = args.origin {
+                node.origin = origin;
+            }
+            node.size = args.size.unwrap_or(Vec2::new(180.0, 120.0));
+            debug!(
+                "New note created at ({}, {}): {}",
+                node.origin.x, node.origin.y, id
+            );
+
+            if !self.node_depth_order.contains(&id) {
+                self.node_depth_order.push(id);
+            }
+            node
+        });
+        node.in_use = true;
+
+        let color = args
+            .color
+            .unwrap_or(self.scaled_style.colors[ColorStyle::Note as usize]);
+        let mut bar_color = Hsva::from(color);
+        bar_color.v *= 0.85;
+
+        self.scaled_style.format_node(node);
+        node.color_style.background = color;
+        node.color_style.background_hovered = color;
+        node.color_style.background_selected = color;
+        node.color_style.header = bar_color.into();
+        node.color_style.header_hovered = bar_color.into();
+        node.color_style.header_selected = bar_color.into();
+        node.background_shape
+            .replace(ui.painter().add(egui::Shape::Noop));
+        node.header_shapes.push(ui.painter().add(egui::Shape::Noop));
+        node.header_shapes.push(ui.painter().add(egui::Shape::Noop));
+
+        let (origin, size) = (node.origin, node.size);
+        let padding = node.layout_style.padding;
+        let rect = Rect::from_min_size(self.grid_space_to_screen_space(origin), size * self.zoom);
+        let bar_rect = Rect::from_min_size(rect.min, Vec2::new(rect.width(), 2.0 * padding.y));
+
+        let node = self.nodes.get_mut(&id).unwrap();
+        node.rect = rect;
+        node.header_content_rect = bar_rect;
+
+        let node_ui_style = self.node_ui_style.clone();
+        let text_color = self.scaled_style.colors[ColorStyle::NoteText as usize];
+        let hidden = self.is_node_hidden(id);
+        let text_rect = Rect::from_min_max(bar_rect.left_bottom(), rect.max).shrink2(padding);
+
+        let response = ui
+            .allocate_ui_at_rect(text_rect, |ui| {
+                ui.set_style(node_ui_style);
+                ui.set_clip_rect(text_rect.intersect(ui.clip_rect()));
+                if hidden {
+                    ui.set_visible(false);
+                }
+
+                ui.add(
+                    egui::TextEdit::multiline(text)
+                        .id_source(id)
+                        .frame(false)
+                        .desired_width(f32::INFINITY)
+                        .desired_rows(1)
+                        .text_color(text_color),
+                )
+            })
+            .inner;
+
+        if response.is_pointer_button_down_on() {
+            self.interactive_node_id.replace(id);
+        }
+        if !hidden && ui.rect_contains_pointer(rect) {
+            self.node_ids_overlapping_with_mouse.push(id);
+        }
+
+        response
+    }
+
+    /// Size of a note in grid space
+    pub fn note_size(&self, id: Uuid) -> Option<Vec2> {
+        self.nodes
+            .get(&id)
+            .filter(|node| node.note)
+            .map(|node| node.size)
+    }
+
+    /// The grip that a note is resized with
+    pub(crate) fn note_grip_rect(&self, note_rect: Rect) -> Rect {
+        let size = 2.0 * self.scaled_style.node_padding_vertical;
+        Rect::from_min_max(note_rect.max - Vec2::splat(size), note_rect.max)
+    }
+
+    pub(crate) fn draw_note_decorations(&self, node: &Node, selected: bool) -> egui::Shape {
+        let grip_rect = self.note_grip_rect(node.rect);
+        let stroke = (
+            self.scaled_style.node_border_thickness.max(1.0),
+            node.color_style.header,
+        );
+
+        let mut shapes = (1..=3)
+            .map(|i| {
+                let offset = grip_rect.width() * i as f32 / 4.0;
+                egui::Shape::line_segment(
+                    [
+                        pos2(grip_rect.max.x - offset, grip_rect.max.y),
+                        pos2(grip_rect.max.x, grip_rect.max.y - offset),
+                    ],
+                    stroke,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        if selected {
+            shapes.push(egui::Shape::rect_stroke(
+                node.rect,
+                node.layout_style.corner_rounding,
+                (
+                    self.scaled_style.node_border_thickness.max(1.0),
+                    self.scaled_style.colors[ColorStyle::NoteOutlineSelected as usize],
+                ),
+            ));
+        }
+
+        egui::Shape::Vec(shapes)
+    }
+
+    pub(crate) fn begin_note_resize(&mut self, id: Uuid) {
+        if self.click_interaction_type != ClickInteractionType::None {
+            return;
+        }
+        self.click_interaction_type = ClickInteractionType::NoteResize;
+        self.click_interaction_state.resized_note_id = id;
+    }
+
+    /// Resize the note so its bottom right corner follows the pointer
+    pub(crate) fn resize_note(&mut self) {
+        let note_id = self.click_interaction_state.resized_note_id;
+        let zoom = self.zoom;
+        let origin = match self.nodes.get(&note_id) {
+            Some(node) => self.grid_space_to_screen_space(node.origin),
+            None => return,
+        };
+
+        if let Some(node) = self.nodes.get_mut(&note_id) {
+            node.size = ((self.mouse_pos - origin) / zoom).max(MIN_NOTE_SIZE);
+        }
+    }
+}
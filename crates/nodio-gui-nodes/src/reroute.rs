@@ -0,0 +1,152 @@
+use super::*;
+
+impl Context {
+    /// Set the reroute points of a link in grid space, ordered from its start pin to its end pin.
+    /// Reroute points only change the path the link is drawn along.
+    pub fn set_link_reroutes(&mut self, link_id: Uuid, points: Vec<Pos2>) {
+        if points.is_empty() {
+            self.link_reroutes.remove(&link_id);
+        } else {
+            self.link_reroutes.insert(link_id, points);
+        }
+    }
+
+    /// Reroute points of a link in grid space, ordered from its start pin to its end pin
+    pub fn link_reroutes(&self, link_id: Uuid) -> &[Pos2] {
+        self.link_reroutes
+            .get(&link_id)
+            .map_or(&[], |points| points.as_slice())
+    }
+
+    /// The link whose reroute points were added, moved or removed by the user in this frame
+    pub fn rerouted_link(&self) -> Option<Uuid> {
+        self.rerouted_link_id
+    }
+
+    /// The path of a link from its start to its end position in screen space, through its reroute
+    /// points and in its link style
+    pub(crate) fn link_path(
+        &self,
+        link_id: Uuid,
+        start: Pos2,
+        end: Pos2,
+        start_type: AttributeKind,
+    ) -> LinkPathData {
+        let reroutes = self
+            .link_reroutes(link_id)
+            .iter()
+            .map(|point| self.grid_space_to_screen_space(*point))
+            .collect::<Vec<_>>();
+
+        LinkPathData::build(
+            start,
+            end,
+            &reroutes,
+            start_type,
+            self.links
+                .get(&link_id)
+                .map_or(self.scaled_style.link_style, |link| link.style),
+            self.scaled_style.link_line_segments_per_length,
+        )
+    }
+
+    pub(crate) fn resolve_hovered_reroute(&mut self) {
+        let hover_radius_sqr = (2.0 * self.scaled_style.reroute_radius).powi(2);
+
+        let ctx = &*self;
+        let hovered_reroute =
+            ctx.link_reroutes
+                .iter()
+                .filter(|(link_id, _)| {
+                    ctx.links
+                        .get(*link_id)
+                        .is_some_and(|link| link.in_use && !ctx.is_link_hidden(link))
+                })
+                .flat_map(|(&link_id, points)| {
+                    points.iter().enumerate().map(move |(idx, point)| {
+                        (link_id, idx, ctx.grid_space_to_screen_space(*point))
+                    })
+                })
+                .map(|(link_id, idx, pos)| (link_id, idx, (pos - ctx.mouse_pos).length_sq()))
+                .filter(|(_, _, distance_sqr)| *distance_sqr < hover_radius_sqr)
+                .min_by(|(_, _, a), (_, _, b)| a.partial_cmp(b).unwrap_or(Ordering::Equal))
+                .map(|(link_id, idx, _)| (link_id, idx));
+        self.hovered_reroute = hovered_reroute;
+    }
+
+    /// Add a reroute point at the pointer, on the segment of the link closest to it
+    pub(crate) fn insert_reroute(&mut self, link_id: Uuid) {
+        let link = match self.links.get(&link_id) {
+            Some(link) => link,
+            None => return,
+        };
+        let (start_pin, end_pin) = match (
+            self.pins.get(&link.start_pin_id),
+            self.pins.get(&link.end_pin_id),
+        ) {
+            (Some(start_pin), Some(end_pin)) => (start_pin, end_pin),
+            _ => return,
+        };
+
+        let idx = self
+            .link_path(link_id, start_pin.pos, end_pin.pos, start_pin.kind)
+            .closest_segment(&self.mouse_pos);
+        let point = self.screen_space_to_grid_space(self.mouse_pos);
+
+        self.link_reroutes
+            .entry(link_id)
+            .or_default()
+            .insert(idx, point);
+        self.rerouted_link_id = Some(link_id);
+    }
+
+    pub(crate) fn remove_reroute(&mut self, link_id: Uuid, idx: usize) {
+        if let Some(points) = self.link_reroutes.get_mut(&link_id) {
+            if idx < points.len() {
+                points.remove(idx);
+            }
+            if points.is_empty() {
+                self.link_reroutes.remove(&link_id);
+            }
+            self.rerouted_link_id = Some(link_id);
+        }
+    }
+
+    pub(crate) fn begin_reroute_drag(&mut self, link_id: Uuid, idx: usize) {
+        if self.click_interaction_type != ClickInteractionType::None {
+            return;
+        }
+        self.click_interaction_type = ClickInteractionType::RerouteDrag;
+        self.click_interaction_state.dragged_reroute = (link_id, idx);
+    }
+
+    pub(crate) fn drag_reroute(&mut self) {
+        let (link_id, idx) = self.click_interaction_state.dragged_reroute;
+        let point = self.screen_space_to_grid_space(self.mouse_pos);
+
+        if let Some(dragged) = self
+            .link_reroutes
+            .get_mut(&link_id)
+            .and_then(|points| points.get_mut(idx))
+        {
+            *dragged = point;
+        }
+    }
+
+    /// The dots of the reroute points of a link
+    pub(crate) fn draw_reroutes(&self, link_id: Uuid, color: egui::Color32) -> Vec<egui::Shape> {
+        self.link_reroutes(link_id)
+            .iter()
+            .enumerate()
+            .map(|(idx, point)| {
+                let radius = if self.hovered_reroute == Some((link_id, idx)) {
+                    1.5 * self.scaled_style.reroute_radius
+                } else {
+                    self.scaled_style.reroute_radius
+                };
+
+                egui::Shape::circle_filled(self.grid_space_to_screen_space(*point), radius, color)
+            })
+            .collect()
+    }
+}
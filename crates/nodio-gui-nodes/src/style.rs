@@ -15,12 +15,26 @@ pub enum ColorStyle {
     Link,
     LinkHovered,
     LinkSelected,
+    LinkFlow,
     Pin,
     PinHovered,
     BoxSelector,
     BoxSelectorOutline,
     GridBackground,
     GridLine,
+    MinimapBackground,
+    MinimapOutline,
+    MinimapNode,
+    MinimapNodeSelected,
+    MinimapLink,
+    MinimapViewport,
+    MinimapViewportOutline,
+    Frame,
+    FrameTitle,
+    FrameOutlineSelected,
+    Note,
+    NoteText,
+    NoteOutlineSelected,
     Count,
 }
 
@@ -35,12 +49,17 @@ pub enum StyleVar {
     LinkThickness,
     LinkLineSegmentsPerLength,
     LinkHoverDistance,
+    RerouteRadius,
+    LinkFlowDashLength,
+    LinkFlowSpeed,
     PinCircleRadius,
     PinQuadSideLength,
     PinTriangleSideLength,
     PinLineThickness,
     PinHoverRadius,
     PinOffset,
+    FramePadding,
+    FrameCollapsedWidth,
 }
 
 /// Controls some style aspects
@@ -48,6 +67,26 @@ pub enum StyleVar {
 pub enum StyleFlags {
     None = 0,
     GridLines = 1 << 2,
+    Minimap = 1 << 3,
+}
+
+/// Corner of the editor that the minimap is shown in
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MinimapPosition {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+/// How links are routed between their pins
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LinkStyle {
+    /// Curves leaving and entering the pins horizontally
+    Bezier,
+    Straight,
+    /// Horizontal and vertical lines with right angle corners
+    Orthogonal,
 }
 
 impl ColorStyle {
@@ -72,6 +111,7 @@ impl ColorStyle {
             egui::Color32::from_rgba_unmultiplied(60, 150, 250, 255);
         colors[ColorStyle::LinkSelected as usize] =
             egui::Color32::from_rgba_unmultiplied(60, 150, 250, 255);
+        colors[ColorStyle::LinkFlow as usize] = egui::Color32::from_rgb(190, 225, 255);
         colors[ColorStyle::Pin as usize] = egui::Color32::from_rgba_unmultiplied(60, 133, 224, 255);
         colors[ColorStyle::PinHovered as usize] =
             egui::Color32::from_rgba_unmultiplied(53, 150, 250, 255);
@@ -81,6 +121,22 @@ impl ColorStyle {
             egui::Color32::from_rgba_unmultiplied(61, 133, 224, 150);
         colors[ColorStyle::GridBackground as usize] = egui::Color32::from_rgb(20, 20, 20);
         colors[ColorStyle::GridLine as usize] = egui::Color32::from_rgb(26, 26, 26);
+        colors[ColorStyle::MinimapBackground as usize] =
+            egui::Color32::from_rgba_unmultiplied(30, 30, 30, 230);
+        colors[ColorStyle::MinimapOutline as usize] = egui::Color32::from_rgb(60, 60, 60);
+        colors[ColorStyle::MinimapNode as usize] = egui::Color32::from_rgb(90, 90, 90);
+        colors[ColorStyle::MinimapNodeSelected as usize] = egui::Color32::from_rgb(140, 140, 140);
+        colors[ColorStyle::MinimapLink as usize] = egui::Color32::from_rgb(60, 133, 224);
+        colors[ColorStyle::MinimapViewport as usize] =
+            egui::Color32::from_rgba_unmultiplied(255, 255, 255, 10);
+        colors[ColorStyle::MinimapViewportOutline as usize] =
+            egui::Color32::from_rgba_unmultiplied(255, 255, 255, 120);
+        colors[ColorStyle::Frame as usize] = egui::Color32::from_rgb(58, 78, 110);
+        colors[ColorStyle::FrameTitle as usize] = egui::Color32::from_rgb(220, 220, 220);
+        colors[ColorStyle::FrameOutlineSelected as usize] = egui::Color32::from_rgb(60, 150, 250);
+        colors[ColorStyle::Note as usize] = egui::Color32::from_rgb(230, 200, 90);
+        colors[ColorStyle::NoteText as usize] = egui::Color32::from_rgb(30, 30, 30);
+        colors[ColorStyle::NoteOutlineSelected as usize] = egui::Color32::from_rgb(60, 150, 250);
         colors
     }
 }
@@ -96,6 +152,14 @@ pub struct Style {
     pub link_thickness: f32,
     pub link_line_segments_per_length: f32,
     pub link_hover_distance: f32,
+    /// Radius of the dots of the reroute points of links
+    pub reroute_radius: f32,
+    /// Routing of the links that do not set their own
+    pub link_style: LinkStyle,
+    /// Length of the dashes that flow along the links, and of the gaps between them
+    pub link_flow_dash_length: f32,
+    /// Distance per second that the dashes flow along a link at full level
+    pub link_flow_speed: f32,
 
     pub pin_circle_radius: f32,
     pub pin_quad_side_length: f32,
@@ -105,6 +169,16 @@ pub struct Style {
     pub pin_hover_shape_radius: f32,
     pub pin_offset: f32,
 
+    /// Space between a frame and the nodes in it
+    pub frame_padding: f32,
+    /// Smallest width of the header of a collapsed frame
+    pub frame_collapsed_width: f32,
+
+    pub minimap_position: MinimapPosition,
+    pub minimap_size: Vec2,
+    /// Distance of the minimap from the edges of the editor
+    pub minimap_margin: f32,
+
     pub flags: usize,
     pub colors: [egui::Color32; ColorStyle::Count as usize],
 }
@@ -120,6 +194,10 @@ impl Default for Style {
             link_thickness: 3.0,
             link_line_segments_per_length: 0.1,
             link_hover_distance: 10.0,
+            reroute_radius: 5.0,
+            link_style: LinkStyle::Bezier,
+            link_flow_dash_length: 8.0,
+            link_flow_speed: 80.0,
             pin_circle_radius: 4.0,
             pin_quad_side_length: 7.0,
             pin_triangle_side_length: 9.5,
@@ -127,6 +205,11 @@ impl Default for Style {
             pin_hover_radius: 25.0,
             pin_hover_shape_radius: 15.0,
             pin_offset: 0.0,
+            frame_padding: 16.0,
+            frame_collapsed_width: 160.0,
+            minimap_position: MinimapPosition::BottomRight,
+            minimap_size: Vec2::new(200.0, 140.0),
+            minimap_margin: 10.0,
             flags: StyleFlags::GridLines as usize,
             colors: ColorStyle::colors_dark(),
         }
@@ -134,6 +217,39 @@ impl Default for Style {
 }
 
 impl Style {
+    /// The style with all sizes multiplied by a zoom level
+    pub(crate) fn scaled(&self, zoom: f32) -> Self {
+        Self {
+            grid_spacing: self.grid_spacing * zoom,
+            node_corner_rounding: self.node_corner_rounding * zoom,
+            node_padding_horizontal: self.node_padding_horizontal * zoom,
+            node_padding_vertical: self.node_padding_vertical * zoom,
+            node_border_thickness: self.node_border_thickness * zoom,
+            link_thickness: self.link_thickness * zoom,
+            link_line_segments_per_length: self.link_line_segments_per_length,
+            link_hover_distance: self.link_hover_distance * zoom,
+            reroute_radius: self.reroute_radius * zoom,
+            link_style: self.link_style,
+            link_flow_dash_length: self.link_flow_dash_length * zoom,
+            link_flow_speed: self.link_flow_speed * zoom,
+            pin_circle_radius: self.pin_circle_radius * zoom,
+            pin_quad_side_length: self.pin_quad_side_length * zoom,
+            pin_triangle_side_length: self.pin_triangle_side_length * zoom,
+            pin_line_thickness: self.pin_line_thickness * zoom,
+            pin_hover_radius: self.pin_hover_radius * zoom,
+            pin_hover_shape_radius: self.pin_hover_shape_radius * zoom,
+            pin_offset: self.pin_offset * zoom,
+            frame_padding: self.frame_padding * zoom,
+            frame_collapsed_width: self.frame_collapsed_width * zoom,
+            // The minimap is not zoomed
+            minimap_position: self.minimap_position,
+            minimap_size: self.minimap_size,
+            minimap_margin: self.minimap_margin,
+            flags: self.flags,
+            colors: self.colors,
+        }
+    }
+
     pub(crate) fn get_screen_space_pin_coordinates(
         &self,
         node_rect: &Rect,
@@ -240,7 +356,7 @@ impl Style {
     pub(crate) fn hovered_pin_radius(&self, pin_pos: Pos2, mouse_pos: Pos2) -> f32 {
         remap(
             self.pin_hover_radius - (pin_pos - mouse_pos).length(),
-            0.0..=(self.pin_hover_radius - self.pin_hover_shape_radius - 5.0),
+            0.0..=(self.pin_hover_radius - self.pin_hover_shape_radius) * 0.5,
             0.0..=self.pin_hover_shape_radius,
         )
         .min(self.pin_hover_shape_radius)
@@ -296,5 +412,30 @@ impl Style {
         link.color_style.selected = args
             .selected
             .unwrap_or(self.colors[ColorStyle::LinkSelected as usize]);
+        link.style = args.style.unwrap_or(self.link_style);
+        link.flow = args.flow;
+    }
+}
+
+/// Style of the contents of the nodes at a zoom level
+pub(crate) fn scaled_ui_style(style: &egui::Style, zoom: f32) -> egui::Style {
+    let mut style = style.clone();
+
+    for font_id in style.text_styles.values_mut() {
+        // Rounded to half points, so zooming does not rasterize the fonts at every size
+        font_id.size = ((font_id.size * zoom * 2.0).round() / 2.0).max(1.0);
     }
+
+    let spacing = &mut style.spacing;
+    spacing.item_spacing *= zoom;
+    spacing.button_padding *= zoom;
+    spacing.indent *= zoom;
+    spacing.interact_size *= zoom;
+    spacing.slider_width *= zoom;
+    spacing.text_edit_width *= zoom;
+    spacing.icon_width *= zoom;
+    spacing.icon_width_inner *= zoom;
+    spacing.icon_spacing *= zoom;
+
+    style
 }
@@ -0,0 +1,94 @@
+use super::*;
+
+/// Decides whether a link from an output pin to an input pin may be made, returning the reason
+/// if it may not
+pub(crate) type LinkValidator = dyn Fn(Uuid, Uuid) -> Result<(), String>;
+
+/// Opacity of the pins that a dragged link cannot be snapped to
+const REFUSED_PIN_OPACITY: f32 = 0.3;
+
+impl Context {
+    /// Set the validator that is asked about every pin while a link is dragged, with the output
+    /// pin and the input pin of the link that would be made. Pins that are refused are dimmed,
+    /// the link does not snap to them, and the reason is shown when one is hovered.
+    pub fn set_link_validator(
+        &mut self,
+        validator: impl Fn(Uuid, Uuid) -> Result<(), String> + 'static,
+    ) {
+        self.link_validator = Some(Box::new(validator));
+    }
+
+    /// Why a link from the start pin cannot be snapped to the end pin
+    pub(crate) fn link_refusal(&self, start_pin_id: Uuid, end_pin_id: Uuid) -> Option<String> {
+        let (start_pin, end_pin) = match (self.pins.get(&start_pin_id), self.pins.get(&end_pin_id))
+        {
+            (Some(start_pin), Some(end_pin)) => (start_pin, end_pin),
+            _ => return None,
+        };
+
+        if start_pin.parent_node_id == end_pin.parent_node_id {
+            return Some("Pins of the same node cannot be linked".to_string());
+        }
+        if start_pin.kind == end_pin.kind {
+            return Some(if start_pin.is_output() {
+                "Outputs link to inputs".to_string()
+            } else {
+                "Inputs link to outputs".to_string()
+            });
+        }
+
+        // The link snapped to the pin was made while dragging, and is not a duplicate of itself
+        match self.find_duplicate_link(start_pin_id, end_pin_id) {
+            Some(duplicate_id) if Some(duplicate_id) == self.snap_link_id => return None,
+            Some(_) => return Some("The pins are linked already".to_string()),
+            None => (),
+        }
+
+        let (output_pin_id, input_pin_id) = if start_pin.is_output() {
+            (start_pin_id, end_pin_id)
+        } else {
+            (end_pin_id, start_pin_id)
+        };
+        self.link_validator
+            .as_ref()
+            .and_then(|validator| validator(output_pin_id, input_pin_id).err())
+    }
+
+    /// Asks about the pins in use while a link is dragged
+    pub(crate) fn update_refused_pins(&mut self) {
+        self.refused_pins.clear();
+        if self.click_interaction_type != ClickInteractionType::LinkCreation {
+            return;
+        }
+
+        let start_pin_id = self.click_interaction_state.link_creation.start_pin_id;
+        let refused_pins = self
+            .pins
+            .iter()
+            .filter(|(&pin_id, pin)| pin.in_use && pin_id != start_pin_id)
+            .filter_map(|(&pin_id, _)| {
+                self.link_refusal(start_pin_id, pin_id)
+                    .map(|reason| (pin_id, reason))
+            })
+            .collect();
+        self.refused_pins = refused_pins;
+    }
+
+    /// The color of a pin, dimmed if the dragged link cannot be snapped to it
+    pub(crate) fn refused_pin_color(&self, pin_id: Uuid, color: egui::Color32) -> egui::Color32 {
+        if self.refused_pins.contains_key(&pin_id) {
+            color.linear_multiply(REFUSED_PIN_OPACITY)
+        } else {
+            color
+        }
+    }
+
+    /// Explains at the pointer why the dragged link cannot be snapped to the hovered pin
+    pub(crate) fn show_link_refusal(&self, pin_id: Uuid, ui: &Ui) {
+        if let Some(reason) = self.refused_pins.get(&pin_id) {
+            egui::show_tooltip_at_pointer(ui.ctx(), ui.id().with("LinkRefusal"), |ui| {
+                ui.label(reason);
+            });
+        }
+    }
+}
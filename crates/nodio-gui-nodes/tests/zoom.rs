@@ -0,0 +1,126 @@
+use egui::{pos2, CentralPanel, Event, Pos2, RawInput, Rect, Vec2};
+use nodio_gui_nodes::{Context, MAX_ZOOM, MIN_ZOOM};
+use uuid::Uuid;
+
+/// Runs a frame of an 800 x 600 editor with the given input events and nodes (id, position).
+fn run_frame(
+    ui_ctx: &egui::Context,
+    ctx: &mut Context,
+    events: Vec<Event>,
+    nodes: &[(Uuid, Pos2)],
+) {
+    let input = RawInput {
+        screen_rect: Some(Rect::from_min_size(Pos2::ZERO, Vec2::new(800.0, 600.0))),
+        events,
+        ..Default::default()
+    };
+
+    let _ = ui_ctx.run(input, |ui_ctx| {
+        CentralPanel::default().show(ui_ctx, |ui| {
+            ctx.begin_frame(ui);
+            for &(id, pos) in nodes {
+                ctx.add_node(id)
+                    .with_origin(pos)
+                    .with_header(|ui| {
+                        ui.label("Node");
+                    })
+                    .show(ui);
+            }
+            ctx.end_frame(ui);
+        });
+    });
+}
+
+fn assert_near(a: Pos2, b: Pos2) {
+    assert!(a.distance(b) < 0.01, "{:?} != {:?}", a, b);
+}
+
+#[test]
+fn zooming_back_returns_to_the_same_view() {
+    let ui_ctx = egui::Context::default();
+    let mut ctx = Context::default();
+    run_frame(&ui_ctx, &mut ctx, Vec::new(), &[]);
+
+    let screen_pos = pos2(123.0, 456.0);
+    let grid_pos = ctx.screen_space_to_grid_space(screen_pos);
+
+    ctx.set_zoom(1.5);
+    assert_eq!(ctx.zoom(), 1.5);
+    ctx.set_zoom(1.0);
+
+    assert_near(ctx.screen_space_to_grid_space(screen_pos), grid_pos);
+}
+
+#[test]
+fn zoom_scales_distances() {
+    let ui_ctx = egui::Context::default();
+    let mut ctx = Context::default();
+    run_frame(&ui_ctx, &mut ctx, Vec::new(), &[]);
+
+    ctx.set_zoom(2.0);
+
+    let start = ctx.screen_space_to_grid_space(pos2(100.0, 100.0));
+    let end = ctx.screen_space_to_grid_space(pos2(300.0, 100.0));
+    assert!((start.distance(end) - 100.0).abs() < 0.01);
+}
+
+#[test]
+fn wheel_zooms_around_the_pointer() {
+    let ui_ctx = egui::Context::default();
+    let mut ctx = Context::default();
+    let pointer = pos2(200.0, 150.0);
+    run_frame(&ui_ctx, &mut ctx, vec![Event::PointerMoved(pointer)], &[]);
+    let grid_pos = ctx.screen_space_to_grid_space(pointer);
+
+    run_frame(
+        &ui_ctx,
+        &mut ctx,
+        vec![Event::Scroll(Vec2::new(0.0, 100.0))],
+        &[],
+    );
+
+    assert!(ctx.zoom() > 1.0);
+    assert_near(ctx.screen_space_to_grid_space(pointer), grid_pos);
+}
+
+#[test]
+fn zoom_is_clamped() {
+    let ui_ctx = egui::Context::default();
+    let mut ctx = Context::default();
+    run_frame(&ui_ctx, &mut ctx, Vec::new(), &[]);
+
+    ctx.set_zoom(10.0);
+    assert_eq!(ctx.zoom(), MAX_ZOOM);
+    ctx.set_zoom(0.0);
+    assert_eq!(ctx.zoom(), MIN_ZOOM);
+
+    let pointer = pos2(200.0, 150.0);
+    run_frame(&ui_ctx, &mut ctx, vec![Event::PointerMoved(pointer)], &[]);
+    for _ in 0..10 {
+        run_frame(
+            &ui_ctx,
+            &mut ctx,
+            vec![Event::Scroll(Vec2::new(0.0, 1000.0))],
+            &[],
+        );
+    }
+    assert_eq!(ctx.zoom(), MAX_ZOOM);
+}
+
+#[test]
+fn zoom_to_fit_is_clamped() {
+    let ui_ctx = egui::Context::default();
+    let mut ctx = Context::default();
+    let nodes = [
+        (Uuid::new_v4(), pos2(0.0, 0.0)),
+        (Uuid::new_v4(), pos2(100_000.0, 0.0)),
+    ];
+    run_frame(&ui_ctx, &mut ctx, Vec::new(), &nodes);
+
+    ctx.zoom_to_fit();
+    assert_eq!(ctx.zoom(), MIN_ZOOM);
+
+    ctx.select_nodes(&[nodes[0].0]);
+    ctx.zoom_to_selection();
+    assert_eq!(ctx.zoom(), MAX_ZOOM);
+}
@@ -0,0 +1,89 @@
+//! Nodes and measurements shared by the tests of the simulated backend.
+
+use nodio_core::{Context, GeneratorConfig, Node, NodeKind, PortDirection, Uuid};
+
+use crate::{SimulatedContext, SIM_CHANNELS, SIM_SAMPLE_RATE};
+
+pub fn add_node(ctx: &mut SimulatedContext, node: Node) -> Uuid {
+    let id = node.id;
+    ctx.add_node(node);
+    id
+}
+
+/// A generator node with the default signal
+pub fn generator_node() -> Node {
+    Node {
+        kind: NodeKind::Generator,
+        display_name: "Generator".to_string(),
+        generator: Some(GeneratorConfig::default()),
+        ..Default::default()
+    }
+}
+
+pub fn add_generator_node(ctx: &mut SimulatedContext) -> Uuid {
+    add_node(ctx, generator_node())
+}
+
+/// Adds an output device, and returns a node for it.
+pub fn output_node(ctx: &mut SimulatedContext, name: &str) -> Node {
+    Node {
+        id: ctx.add_output_device(name),
+        kind: NodeKind::OutputDevice,
+        display_name: name.to_string(),
+        ..Default::default()
+    }
+}
+
+pub fn add_output_node(ctx: &mut SimulatedContext, name: &str) -> Uuid {
+    let node = output_node(ctx, name);
+    add_node(ctx, node)
+}
+
+/// Id of the output port of a node, the monitor port of an output device.
+pub fn output_port(ctx: &SimulatedContext, node_id: Uuid) -> Uuid {
+    ctx.nodes()
+        .iter()
+        .find(|node| node.id == node_id)
+        .and_then(|node| {
+            node.ports
+                .iter()
+                .find(|port| port.direction == PortDirection::Output)
+        })
+        .map(|port| port.id)
+        .unwrap()
+}
+
+/// An application node, matched to its process by the file name if it is not empty
+pub fn app_node(display_name: &str, filename: &str) -> Node {
+    Node {
+        kind: NodeKind::Application,
+        display_name: display_name.to_string(),
+        filename: filename.to_string(),
+        ..Default::default()
+    }
+}
+
+/// Starts a process, and adds an application node for it.
+pub fn add_app_node(ctx: &mut SimulatedContext, display_name: &str) -> Uuid {
+    ctx.start_process(display_name, "");
+    add_node(ctx, app_node(display_name, ""))
+}
+
+/// Peaks of the channels of 100 ms of the audio the device receives.
+pub fn render_peaks(ctx: &mut SimulatedContext, device_id: Uuid) -> Vec<f32> {
+    let mut buffer = vec![0.0; SIM_SAMPLE_RATE as usize / 10 * SIM_CHANNELS];
+    ctx.render(device_id, &mut buffer);
+
+    let mut peaks = vec![0.0f32; SIM_CHANNELS];
+    for frame in buffer.chunks(SIM_CHANNELS) {
+        for (peak, sample) in peaks.iter_mut().zip(frame) {
+            *peak = peak.max(sample.abs());
+        }
+    }
+    peaks
+}
+
+/// Peak of 100 ms of the audio the device receives.
+pub fn render_peak(ctx: &mut SimulatedContext, device_id: Uuid) -> f32 {
+    render_peaks(ctx, device_id).into_iter().fold(0.0, f32::max)
+}
@@ -0,0 +1,1157 @@
+//! Simulated audio backend that keeps devices, processes and routes in memory.
+//! Used for testing backend independent logic, and as the backend on platforms
+//! that do not have a native one yet.
+#![deny(clippy::all)]
+pub mod fixtures;
+
+use std::sync::Arc;
+
+use log::{info, warn};
+use parking_lot::{Mutex, RwLock};
+
+use nodio_core::{
+    apply_effect_states, find_port, is_virtual_cable, port_node_id, CalibrationStatus,
+    ChannelMatrix, Clip, Connection, ConnectionState, Connections, Context, DefaultDevice,
+    DeviceInfo, EffectConfig, Error, GeneratorConfig, NetworkConfig, NetworkStatus, Node, NodeKind,
+    PlayerCommand, PlayerConfig, PlayerStatus, ProcessInfo, RecorderConfig, RecorderState,
+    RecorderStatus, Result, Uuid, VirtualMicConfig,
+};
+use nodio_engine::{
+    delay_frames, Calibrator, DelayLine, EffectChain, FilePlayer, Recorder, RtpReceiver, RtpSender,
+    SignalGenerator,
+};
+
+/// Format of the audio rendered by [`SimulatedContext::render`]
+pub const SIM_SAMPLE_RATE: u32 = 48000;
+pub const SIM_CHANNELS: usize = 2;
+
+struct SimulatedDevice {
+    id: Uuid,
+    name: String,
+    present: bool,
+    /// Time the audio takes through the device (ms)
+    latency_ms: f32,
+}
+
+struct Route {
+    port_id: Uuid,
+    target_port_id: Uuid,
+    /// Nodes of the ports
+    src_id: Uuid,
+    dst_id: Uuid,
+    /// The device the audio is routed to, which differs from `dst_id` for default devices
+    device_id: Uuid,
+    /// Effects of the source node, followed by the effects of the connection
+    effects: Mutex<EffectChain>,
+    delay: DelayLine,
+}
+
+#[derive(Default)]
+pub struct SimulatedContext {
+    nodes: Vec<Node>,
+
+    processes: Vec<ProcessInfo>,
+    input_devices: Vec<SimulatedDevice>,
+    output_devices: Vec<SimulatedDevice>,
+
+    default_output: Option<Uuid>,
+    default_communications: Option<Uuid>,
+
+    connections: Connections,
+    /// Connections that are currently routing audio
+    routes: Vec<Route>,
+    /// Recorders of the recorder nodes
+    recorders: Vec<(Uuid, Recorder)>,
+    /// Players of the file player nodes. Locked because listing the playing clips takes over the
+    /// clips the decoder has handed to the player.
+    players: Vec<(Uuid, Mutex<FilePlayer>)>,
+    /// Signal generators of the generator nodes
+    generators: Vec<(Uuid, SignalGenerator)>,
+    /// Streams of the network sender and receiver nodes
+    network_senders: Vec<(Uuid, RtpSender)>,
+    network_receivers: Vec<(Uuid, RtpReceiver)>,
+    /// Result of the last latency calibration
+    calibration: Option<CalibrationStatus>,
+
+    new_processes: Vec<ProcessInfo>,
+    next_pid: u32,
+}
+
+impl SimulatedContext {
+    pub fn new() -> Arc<RwLock<Self>> {
+        Arc::new(RwLock::new(Self::default()))
+    }
+
+    pub fn add_input_device(&mut self, name: &str) -> Uuid {
+        let id = Uuid::new_v4();
+        self.input_devices.push(SimulatedDevice {
+            id,
+            name: name.to_string(),
+            present: true,
+            latency_ms: 0.0,
+        });
+        self.update();
+        id
+    }
+
+    pub fn add_output_device(&mut self, name: &str) -> Uuid {
+        let id = Uuid::new_v4();
+        self.output_devices.push(SimulatedDevice {
+            id,
+            name: name.to_string(),
+            present: true,
+            latency_ms: 0.0,
+        });
+        self.update();
+        id
+    }
+
+    /// Simulates the time audio takes through a device, which is reported as part of the
+    /// latency of the connections to it and heard by calibrations.
+    pub fn set_device_latency(&mut self, device_id: Uuid, latency_ms: f32) {
+        for device in self
+            .input_devices
+            .iter_mut()
+            .chain(self.output_devices.iter_mut())
+            .filter(|device| device.id == device_id)
+        {
+            device.latency_ms = latency_ms;
+        }
+    }
+
+    /// Simulates plugging in a previously unplugged device.
+    pub fn plug_device(&mut self, device_id: Uuid) {
+        self.set_device_present(device_id, true);
+    }
+
+    /// Simulates unplugging a device.
+    pub fn unplug_device(&mut self, device_id: Uuid) {
+        self.set_device_present(device_id, false);
+    }
+
+    /// Simulates a process that starts playing audio. Returns the process id.
+    pub fn start_process(&mut self, display_name: &str, filename: &str) -> u32 {
+        self.next_pid += 1;
+
+        let process = ProcessInfo {
+            pid: self.next_pid,
+            display_name: display_name.to_string(),
+            filename: filename.to_string(),
+        };
+
+        for node in self.nodes.iter_mut() {
+            if node.process_id.is_none() && process_node_match(node, &process) {
+                node.process_id = Some(process.pid);
+            }
+        }
+
+        self.processes.push(process.clone());
+        self.new_processes.push(process);
+        self.update();
+
+        self.next_pid
+    }
+
+    pub fn stop_process(&mut self, pid: u32) {
+        self.processes.retain(|process| process.pid != pid);
+
+        for node in self
+            .nodes
+            .iter_mut()
+            .filter(|node| node.process_id == Some(pid))
+        {
+            node.process_id = None;
+        }
+
+        self.update();
+    }
+
+    /// Simulates changing the system default device, moving the connections of the
+    /// default device node to the new device.
+    pub fn set_default_device(&mut self, default_device: DefaultDevice, device_id: Uuid) {
+        match default_device {
+            DefaultDevice::Output => self.default_output = Some(device_id),
+            DefaultDevice::Communications => self.default_communications = Some(device_id),
+        }
+
+        let default_device_id = default_device.id();
+
+        for conn in self.node_connections(default_device_id) {
+            if port_node_id(&self.nodes, conn.dst_id) == default_device_id
+                && conn.state == ConnectionState::Active
+            {
+                self.remove_route(conn.src_id, conn.dst_id);
+                self.connections
+                    .set_state(conn.src_id, conn.dst_id, ConnectionState::Pending);
+            }
+        }
+
+        self.update();
+    }
+
+    /// Source and device pairs of the connections that are currently routing audio.
+    pub fn routes(&self) -> Vec<(Uuid, Uuid)> {
+        self.routes
+            .iter()
+            .map(|route| (route.src_id, route.device_id))
+            .collect()
+    }
+
+    /// Renders the audio routed to an output device, like a null sink would receive it. For a
+    /// virtual microphone node without a cable, this is what its capture device delivers.
+    ///
+    /// Only generator, file player and network receiver nodes produce audio. Every call advances
+    /// the sources routed to the device, so a source routed to several devices skips ahead.
+    pub fn render(&mut self, device_id: Uuid, buffer: &mut [f32]) {
+        buffer.iter_mut().for_each(|sample| *sample = 0.0);
+
+        let routes = self
+            .routes
+            .iter()
+            .filter(|route| route.device_id == device_id)
+            .map(|route| (route.port_id, route.target_port_id))
+            .collect::<Vec<_>>();
+
+        let mut block = vec![0.0; buffer.len()];
+
+        for (port_id, target_port_id) in routes {
+            if self.read_route(port_id, target_port_id, &mut block) {
+                for (out, sample) in buffer.iter_mut().zip(&block) {
+                    *out += sample;
+                }
+            }
+        }
+    }
+
+    /// Sends the given number of frames of the sources routed to the network sender nodes.
+    pub fn transmit(&mut self, frames: usize) {
+        let senders = self
+            .network_senders
+            .iter()
+            .map(|(id, _)| *id)
+            .collect::<Vec<_>>();
+
+        for (dst_id, block) in self.read_sinks(&senders, frames) {
+            if let Some((_, sender)) = self
+                .network_senders
+                .iter_mut()
+                .find(|(id, _)| *id == dst_id)
+            {
+                sender.write(&block);
+            }
+        }
+    }
+
+    /// Writes the given number of frames of the sources routed to the recorder nodes, which
+    /// are recorded into files while the recorders are recording.
+    pub fn record(&mut self, frames: usize) {
+        let recorders = self.recorders.iter().map(|(id, _)| *id).collect::<Vec<_>>();
+
+        for (dst_id, block) in self.read_sinks(&recorders, frames) {
+            if let Some((_, recorder)) = self.recorders.iter_mut().find(|(id, _)| *id == dst_id) {
+                // Failures are logged, and stop the recording
+                recorder.write(&block).ok();
+            }
+        }
+    }
+
+    /// Reads the given number of frames of the sources routed to the sink nodes, like recorders
+    /// and network senders. Sources that produce no audio are read as silence.
+    fn read_sinks(&mut self, sink_ids: &[Uuid], frames: usize) -> Vec<(Uuid, Vec<f32>)> {
+        let routes = self
+            .routes
+            .iter()
+            .filter(|route| sink_ids.contains(&route.dst_id))
+            .map(|route| (route.port_id, route.target_port_id, route.dst_id))
+            .collect::<Vec<_>>();
+
+        let mut blocks = Vec::new();
+
+        for (port_id, target_port_id, dst_id) in routes {
+            let mut block = vec![0.0; frames * SIM_CHANNELS];
+            if !self.read_route(port_id, target_port_id, &mut block) {
+                block.iter_mut().for_each(|sample| *sample = 0.0);
+            }
+
+            blocks.push((dst_id, block));
+        }
+
+        blocks
+    }
+
+    /// Reads the audio of a source node through the effects and the channel matrix of its
+    /// connection, fitted to the channels of the source port. Returns false if the node produces
+    /// no audio.
+    fn read_route(&mut self, port_id: Uuid, target_port_id: Uuid, block: &mut [f32]) -> bool {
+        if !self.read_source(port_node_id(&self.nodes, port_id), block) {
+            return false;
+        }
+
+        if let Some(route) = self.route_mut(port_id, target_port_id) {
+            route.effects.get_mut().process(block, SIM_CHANNELS);
+        }
+
+        let matrix = find_port(&self.nodes, port_id).and_then(|(_, port)| {
+            port.link_matrix(self.connections.channel_matrix(port_id, target_port_id))
+        });
+        if let Some(matrix) = matrix {
+            let input = block.to_vec();
+            matrix.apply(&input, SIM_CHANNELS, block, SIM_CHANNELS);
+        }
+
+        if let Some(route) = self.route_mut(port_id, target_port_id) {
+            route.delay.process(block);
+        }
+
+        true
+    }
+
+    fn route(&self, port_id: Uuid, target_port_id: Uuid) -> Option<&Route> {
+        self.routes
+            .iter()
+            .find(|route| route.port_id == port_id && route.target_port_id == target_port_id)
+    }
+
+    fn route_mut(&mut self, port_id: Uuid, target_port_id: Uuid) -> Option<&mut Route> {
+        self.routes
+            .iter_mut()
+            .find(|route| route.port_id == port_id && route.target_port_id == target_port_id)
+    }
+
+    fn player(&mut self, node_id: Uuid) -> Option<&mut FilePlayer> {
+        self.players
+            .iter_mut()
+            .find(|(id, _)| *id == node_id)
+            .map(|(_, player)| player.get_mut())
+    }
+
+    /// Reads the audio of a source node. Returns false if the node produces no audio.
+    fn read_source(&mut self, src_id: Uuid, block: &mut [f32]) -> bool {
+        if let Some((_, generator)) = self.generators.iter_mut().find(|(id, _)| *id == src_id) {
+            generator.read(block);
+            return true;
+        }
+
+        if let Some((_, player)) = self.players.iter_mut().find(|(id, _)| *id == src_id) {
+            player.get_mut().read(block);
+            return true;
+        }
+
+        if let Some((_, receiver)) = self
+            .network_receivers
+            .iter_mut()
+            .find(|(id, _)| *id == src_id)
+        {
+            receiver.read(block);
+            return true;
+        }
+
+        // Output devices are captured with loopback, after what is routed to them
+        let is_output_device = self
+            .nodes
+            .iter()
+            .any(|node| node.id == src_id && node.kind == NodeKind::OutputDevice);
+        if is_output_device {
+            if let Some(device_id) = self.resolve_device(src_id) {
+                self.render(device_id, block);
+                return true;
+            }
+        }
+
+        false
+    }
+
+    fn set_device_present(&mut self, device_id: Uuid, present: bool) {
+        for device in self
+            .input_devices
+            .iter_mut()
+            .chain(self.output_devices.iter_mut())
+            .filter(|device| device.id == device_id)
+        {
+            device.present = present;
+        }
+
+        self.update();
+    }
+
+    /// Maps a default device node to the current default device, and a virtual microphone node
+    /// to its cable.
+    fn resolve_device(&self, id: Uuid) -> Option<Uuid> {
+        match DefaultDevice::from_id(id) {
+            Some(DefaultDevice::Output) => self.default_output,
+            Some(DefaultDevice::Communications) => self.default_communications,
+            None => match self.virtual_mic(id) {
+                Some(node) => Some(
+                    node.virtual_mic
+                        .as_ref()
+                        .and_then(|config| config.cable_id)
+                        .unwrap_or(id),
+                ),
+                None => Some(id),
+            },
+        }
+    }
+
+    fn virtual_mic(&self, id: Uuid) -> Option<&Node> {
+        self.nodes
+            .iter()
+            .find(|node| node.id == id && node.kind == NodeKind::VirtualMicrophone)
+    }
+
+    fn remove_route(&mut self, port_id: Uuid, target_port_id: Uuid) {
+        self.routes
+            .retain(|route| route.port_id != port_id || route.target_port_id != target_port_id);
+    }
+
+    fn device_latency(&self, device_id: Uuid) -> f32 {
+        self.input_devices
+            .iter()
+            .chain(self.output_devices.iter())
+            .find(|device| device.id == device_id)
+            .map_or(0.0, |device| device.latency_ms)
+    }
+
+    fn device_present(&self, device_id: Uuid) -> bool {
+        let device_id = match self.resolve_device(device_id) {
+            Some(device_id) => device_id,
+            None => return false,
+        };
+
+        // A virtual microphone without a cable is a capture device of its own
+        if self.virtual_mic(device_id).is_some() {
+            return true;
+        }
+
+        self.input_devices
+            .iter()
+            .chain(self.output_devices.iter())
+            .any(|device| device.id == device_id && device.present)
+    }
+
+    fn process_running(&self, pid: Option<u32>) -> bool {
+        self.processes
+            .iter()
+            .any(|process| Some(process.pid) == pid)
+    }
+
+    fn endpoint_available(&self, id: Uuid) -> bool {
+        match self.nodes.iter().find(|node| node.id == id) {
+            Some(node) if node.kind == NodeKind::Application => {
+                self.process_running(node.process_id)
+            }
+            Some(node)
+                if matches!(
+                    node.kind,
+                    NodeKind::Recorder
+                        | NodeKind::FilePlayer
+                        | NodeKind::Generator
+                        | NodeKind::NetworkSender
+                        | NodeKind::NetworkReceiver
+                ) =>
+            {
+                true
+            }
+            _ => self.device_present(id),
+        }
+    }
+
+    /// Updates node states and reconciles connections after a change in devices or processes.
+    fn update(&mut self) {
+        let present = self
+            .nodes
+            .iter()
+            .map(|node| match node.kind {
+                NodeKind::Application => self.process_running(node.process_id),
+                NodeKind::Recorder
+                | NodeKind::FilePlayer
+                | NodeKind::Generator
+                | NodeKind::NetworkSender
+                | NodeKind::NetworkReceiver => true,
+                _ => self.device_present(node.id),
+            })
+            .collect::<Vec<_>>();
+
+        for (node, present) in self.nodes.iter_mut().zip(present) {
+            node.present = present;
+        }
+
+        let reconciliation = self
+            .connections
+            .reconcile(|id| self.endpoint_available(port_node_id(&self.nodes, id)));
+
+        for (src_id, dst_id) in reconciliation.suspend {
+            info!("Suspending connection {} => {}", src_id, dst_id);
+
+            self.remove_route(src_id, dst_id);
+            self.connections
+                .set_state(src_id, dst_id, ConnectionState::Pending);
+        }
+
+        for (src_id, dst_id) in reconciliation.apply {
+            match self.apply_connection(src_id, dst_id) {
+                Ok(()) => {
+                    info!("Restored connection {} => {}", src_id, dst_id);
+                    self.connections
+                        .set_state(src_id, dst_id, ConnectionState::Active);
+                }
+                Err(err) => warn!("Could not restore connection: {}", err),
+            }
+        }
+    }
+
+    fn apply_connection(&mut self, port_id: Uuid, target_port_id: Uuid) -> Result<()> {
+        let (src_id, dst_id) = self.link_nodes(port_id, target_port_id);
+        let node = self
+            .nodes
+            .iter()
+            .find(|n| n.id == src_id)
+            .ok_or_else(|| Error::CouldNotConnect("No such node found".to_string()))?;
+
+        if node.kind == NodeKind::Application && !self.process_running(node.process_id) {
+            return Err(Error::CouldNotConnect("No such process".to_string()));
+        }
+
+        let device_id = self.resolve_device(dst_id).ok_or(Error::NoSuchDevice)?;
+
+        // Recordings are named after their source
+        let name = node.display_name.clone();
+        if let Some((_, recorder)) = self.recorders.iter_mut().find(|(id, _)| *id == dst_id) {
+            recorder.set_name(&name);
+        }
+
+        let delay_ms = self.connections.delay(port_id, target_port_id);
+        let mut effects = EffectChain::new(SIM_SAMPLE_RATE as f32);
+        effects.set_effects(&self.route_effects(port_id, target_port_id));
+
+        self.remove_route(port_id, target_port_id);
+        self.routes.push(Route {
+            port_id,
+            target_port_id,
+            src_id,
+            dst_id,
+            device_id,
+            effects: Mutex::new(effects),
+            delay: DelayLine::with_delay_ms(SIM_CHANNELS, SIM_SAMPLE_RATE, delay_ms),
+        });
+
+        Ok(())
+    }
+
+    /// Effects the audio of a connection passes through, those of the source node first.
+    fn route_effects(&self, port_id: Uuid, target_port_id: Uuid) -> Vec<EffectConfig> {
+        let src_id = port_node_id(&self.nodes, port_id);
+        let node_effects = self
+            .nodes
+            .iter()
+            .filter(|n| n.id == src_id && self.applies_node_effects(n.id))
+            .flat_map(|n| n.effects.iter());
+
+        node_effects
+            .chain(self.connections.effects(port_id, target_port_id))
+            .cloned()
+            .collect()
+    }
+
+    /// Updates the effect chains of the routes of a node after its effects or the effects of
+    /// one of its connections changed.
+    fn update_route_effects(&mut self, src_id: Uuid) {
+        let effects = self
+            .routes
+            .iter()
+            .filter(|route| route.src_id == src_id)
+            .map(|route| self.route_effects(route.port_id, route.target_port_id))
+            .collect::<Vec<_>>();
+
+        for (route, effects) in self
+            .routes
+            .iter_mut()
+            .filter(|route| route.src_id == src_id)
+            .zip(effects)
+        {
+            route.effects.get_mut().set_effects(&effects);
+        }
+    }
+
+    /// The nodes of the ports at the ends of a link. Audio is routed between nodes.
+    fn link_nodes(&self, port_id: Uuid, target_port_id: Uuid) -> (Uuid, Uuid) {
+        (
+            port_node_id(&self.nodes, port_id),
+            port_node_id(&self.nodes, target_port_id),
+        )
+    }
+
+    /// Connections from or to any port of the given node.
+    fn node_connections(&self, node_id: Uuid) -> Vec<Connection> {
+        self.connections
+            .iter()
+            .filter(|conn| {
+                port_node_id(&self.nodes, conn.src_id) == node_id
+                    || port_node_id(&self.nodes, conn.dst_id) == node_id
+            })
+            .cloned()
+            .collect()
+    }
+}
+
+impl Context for SimulatedContext {
+    fn add_node(&mut self, mut node: Node) {
+        if self.nodes.iter().any(|other| other.id == node.id) {
+            info!("Node already added: {}", &node.display_name);
+            return;
+        }
+
+        node.add_missing_ports();
+
+        if let Some(process) = self
+            .processes
+            .iter()
+            .find(|&process| process_node_match(&node, process))
+        {
+            node.process_id = Some(process.pid);
+        }
+
+        if node.kind == NodeKind::Recorder {
+            let config = node.recorder.clone().unwrap_or_default();
+            self.recorders.push((
+                node.id,
+                Recorder::new(
+                    config,
+                    &node.display_name,
+                    SIM_SAMPLE_RATE,
+                    SIM_CHANNELS as u16,
+                ),
+            ));
+        }
+
+        if node.kind == NodeKind::FilePlayer {
+            let config = node.player.clone().unwrap_or_default();
+            self.players.push((
+                node.id,
+                Mutex::new(FilePlayer::new(config, SIM_SAMPLE_RATE, SIM_CHANNELS)),
+            ));
+        }
+
+        if node.kind == NodeKind::Generator {
+            let config = node.generator.clone().unwrap_or_default();
+            self.generators.push((
+                node.id,
+                SignalGenerator::new(config, SIM_SAMPLE_RATE, SIM_CHANNELS),
+            ));
+        }
+
+        if node.kind == NodeKind::NetworkSender {
+            let config = node.network.clone().unwrap_or_default();
+            self.network_senders.push((
+                node.id,
+                RtpSender::new(config, &node.display_name, SIM_SAMPLE_RATE, SIM_CHANNELS),
+            ));
+        }
+
+        if node.kind == NodeKind::NetworkReceiver {
+            let config = node.network.clone().unwrap_or_default();
+            self.network_receivers.push((
+                node.id,
+                RtpReceiver::new(config, SIM_SAMPLE_RATE, SIM_CHANNELS),
+            ));
+        }
+
+        self.nodes.push(node);
+        self.update();
+    }
+
+    fn remove_node(&mut self, node_id: Uuid) {
+        for conn in self.node_connections(node_id) {
+            self.disconnect_node(conn.src_id, conn.dst_id);
+        }
+
+        self.nodes.retain(|node| node.id != node_id);
+        self.recorders.retain(|(id, _)| *id != node_id);
+        self.players.retain(|(id, _)| *id != node_id);
+        self.generators.retain(|(id, _)| *id != node_id);
+        self.network_senders.retain(|(id, _)| *id != node_id);
+        self.network_receivers.retain(|(id, _)| *id != node_id);
+    }
+
+    fn nodes(&self) -> &[Node] {
+        self.nodes.as_slice()
+    }
+
+    fn nodes_mut(&mut self) -> &mut [Node] {
+        &mut self.nodes
+    }
+
+    fn connect_node(&mut self, port_id: Uuid, target_port_id: Uuid) -> Result<()> {
+        if let (Some((_, port)), Some((_, target_port))) = (
+            find_port(&self.nodes, port_id),
+            find_port(&self.nodes, target_port_id),
+        ) {
+            port.check_link(target_port)?;
+        }
+        let (node_id, target_id) = self.link_nodes(port_id, target_port_id);
+
+        let node_kind = match self.nodes.iter().find(|n| n.id == node_id) {
+            Some(node) => node.kind,
+            None => return Err(Error::CouldNotConnect("No such node found".to_string())),
+        };
+
+        // Recorders and network senders take the audio of a single source
+        let target_sink = self
+            .nodes
+            .iter()
+            .find(|n| {
+                n.id == target_id && matches!(n.kind, NodeKind::Recorder | NodeKind::NetworkSender)
+            })
+            .map(|n| n.kind);
+
+        if DefaultDevice::from_id(target_id).is_none()
+            && target_sink.is_none()
+            && self.virtual_mic(target_id).is_none()
+            && !self.output_devices.iter().any(|d| d.id == target_id)
+        {
+            return Err(Error::NoSuchDevice);
+        }
+
+        match node_kind {
+            // What an output device plays is only captured into recorders and network senders
+            NodeKind::OutputDevice if target_sink.is_none() => {
+                return Err(Error::CouldNotConnect(
+                    "Output device can only be recorded or sent to the network".to_string(),
+                ))
+            }
+            NodeKind::Recorder => {
+                return Err(Error::CouldNotConnect(
+                    "Recorder cannot be used as an input!".to_string(),
+                ))
+            }
+            NodeKind::NetworkSender => {
+                return Err(Error::CouldNotConnect(
+                    "Network sender cannot be used as an input!".to_string(),
+                ))
+            }
+            NodeKind::VirtualMicrophone => {
+                return Err(Error::CouldNotConnect(
+                    "Virtual microphone cannot be used as an input!".to_string(),
+                ))
+            }
+            _ => {}
+        }
+
+        if self.connections.iter().any(|conn| {
+            (conn.src_id, conn.dst_id) != (port_id, target_port_id)
+                && self.link_nodes(conn.src_id, conn.dst_id) == (node_id, target_id)
+        }) {
+            return Err(Error::CouldNotConnect(
+                "The nodes are linked already through other ports".to_string(),
+            ));
+        }
+
+        if target_sink.is_some()
+            && self.node_connections(target_id).iter().any(|conn| {
+                port_node_id(&self.nodes, conn.dst_id) == target_id
+                    && port_node_id(&self.nodes, conn.src_id) != node_id
+            })
+        {
+            return Err(Error::CouldNotConnect(match target_sink {
+                Some(NodeKind::NetworkSender) => "Network sender already has a source".to_string(),
+                _ => "Recorder already has a source".to_string(),
+            }));
+        }
+
+        if self.endpoint_available(node_id) && self.endpoint_available(target_id) {
+            self.apply_connection(port_id, target_port_id)?;
+            self.connections
+                .insert(port_id, target_port_id, ConnectionState::Active);
+        } else {
+            self.connections
+                .insert(port_id, target_port_id, ConnectionState::Pending);
+        }
+
+        Ok(())
+    }
+
+    fn disconnect_node(&mut self, port_id: Uuid, target_port_id: Uuid) {
+        if self.connections.remove(port_id, target_port_id).is_none() {
+            warn!("No such connection found");
+        }
+
+        self.remove_route(port_id, target_port_id);
+    }
+
+    fn connection_state(&self, port_id: Uuid, target_port_id: Uuid) -> Option<ConnectionState> {
+        self.connections.state(port_id, target_port_id)
+    }
+
+    fn set_channel_matrix(
+        &mut self,
+        port_id: Uuid,
+        target_port_id: Uuid,
+        channel_matrix: Option<ChannelMatrix>,
+    ) {
+        self.connections
+            .set_channel_matrix(port_id, target_port_id, channel_matrix);
+    }
+
+    fn channel_matrix(&self, port_id: Uuid, target_port_id: Uuid) -> Option<ChannelMatrix> {
+        self.connections
+            .channel_matrix(port_id, target_port_id)
+            .cloned()
+    }
+
+    fn set_link_delay(&mut self, port_id: Uuid, target_port_id: Uuid, delay_ms: f32) {
+        self.connections
+            .set_delay(port_id, target_port_id, delay_ms);
+
+        if let Some(route) = self.route_mut(port_id, target_port_id) {
+            route
+                .delay
+                .set_delay(delay_frames(delay_ms, SIM_SAMPLE_RATE));
+        }
+    }
+
+    fn link_delay(&self, port_id: Uuid, target_port_id: Uuid) -> f32 {
+        self.connections.delay(port_id, target_port_id)
+    }
+
+    fn set_link_effects(
+        &mut self,
+        port_id: Uuid,
+        target_port_id: Uuid,
+        effects: Vec<EffectConfig>,
+    ) {
+        self.connections
+            .set_effects(port_id, target_port_id, effects);
+        self.update_route_effects(port_node_id(&self.nodes, port_id));
+    }
+
+    fn link_effects(&self, port_id: Uuid, target_port_id: Uuid) -> Vec<EffectConfig> {
+        self.connections.effects(port_id, target_port_id).to_vec()
+    }
+
+    fn set_link_effect_param(
+        &mut self,
+        port_id: Uuid,
+        target_port_id: Uuid,
+        effect_id: Uuid,
+        param_idx: usize,
+        value: f32,
+    ) {
+        if let Some(effect) = self
+            .connections
+            .effects_mut(port_id, target_port_id)
+            .and_then(|effects| effects.iter_mut().find(|effect| effect.id == effect_id))
+        {
+            effect.set_param(param_idx, value);
+        }
+
+        if let Some(route) = self.route_mut(port_id, target_port_id) {
+            route
+                .effects
+                .get_mut()
+                .set_param(effect_id, param_idx, value);
+        }
+    }
+
+    /// Every active link is routed by the simulation.
+    fn processes_link(&self, port_id: Uuid, target_port_id: Uuid) -> bool {
+        self.route(port_id, target_port_id).is_some()
+    }
+
+    fn link_latency(&self, port_id: Uuid, target_port_id: Uuid) -> Option<f32> {
+        let route = self.route(port_id, target_port_id)?;
+
+        Some(self.device_latency(route.device_id) + self.connections.delay(port_id, target_port_id))
+    }
+
+    /// Simulates a microphone next to the target, which hears the click after the latency of
+    /// both devices. Runs to the end right away.
+    fn start_calibration(&mut self, target_id: Uuid, capture_id: Uuid) -> Result<()> {
+        let device_id = self
+            .resolve_device(target_id)
+            .filter(|&id| self.output_devices.iter().any(|d| d.id == id && d.present))
+            .ok_or(Error::NoSuchDevice)?;
+
+        if !self
+            .input_devices
+            .iter()
+            .any(|d| d.id == capture_id && d.present)
+        {
+            return Err(Error::NoSuchDevice);
+        }
+
+        let latency_ms = self.device_latency(device_id) + self.device_latency(capture_id);
+        let mut calibrator = Calibrator::new(SIM_SAMPLE_RATE, SIM_CHANNELS);
+        let mut path = DelayLine::with_delay_ms(SIM_CHANNELS, SIM_SAMPLE_RATE, latency_ms);
+        let mut block = vec![0.0; SIM_SAMPLE_RATE as usize / 100 * SIM_CHANNELS];
+
+        while calibrator.status() == CalibrationStatus::Running {
+            calibrator.read(&mut block);
+            path.process(&mut block);
+            // Heard through the air
+            block.iter_mut().for_each(|sample| *sample *= 0.1);
+            calibrator.write(&block);
+        }
+
+        self.calibration = Some(calibrator.status());
+
+        Ok(())
+    }
+
+    fn calibration_status(&self) -> Option<CalibrationStatus> {
+        self.calibration
+    }
+
+    fn stop_calibration(&mut self) {
+        self.calibration = None;
+    }
+
+    fn set_volume(&mut self, node_id: Uuid, volume: f32) {
+        if let Some(node) = self.nodes.iter_mut().find(|n| n.id == node_id) {
+            node.volume = volume;
+        }
+    }
+
+    fn set_effects(&mut self, node_id: Uuid, effects: Vec<EffectConfig>) {
+        if let Some(node) = self.nodes.iter_mut().find(|n| n.id == node_id) {
+            node.effects = effects;
+        }
+
+        self.update_route_effects(node_id);
+    }
+
+    /// Sources are processed on every route they take. Sinks take the audio of each route as it
+    /// comes, so their effects would apply nowhere.
+    fn applies_node_effects(&self, node_id: Uuid) -> bool {
+        self.nodes.iter().any(|n| {
+            n.id == node_id
+                && !matches!(
+                    n.kind,
+                    NodeKind::OutputDevice
+                        | NodeKind::Recorder
+                        | NodeKind::NetworkSender
+                        | NodeKind::VirtualMicrophone
+                )
+        })
+    }
+
+    fn set_effect_param(&mut self, node_id: Uuid, effect_id: Uuid, param_idx: usize, value: f32) {
+        if let Some(effect) = self
+            .nodes
+            .iter_mut()
+            .filter(|n| n.id == node_id)
+            .flat_map(|n| n.effects.iter_mut())
+            .find(|effect| effect.id == effect_id)
+        {
+            effect.set_param(param_idx, value);
+        }
+
+        for route in self
+            .routes
+            .iter_mut()
+            .filter(|route| route.src_id == node_id)
+        {
+            route
+                .effects
+                .get_mut()
+                .set_param(effect_id, param_idx, value);
+        }
+    }
+
+    fn store_effect_states(&mut self) {
+        for route in self.routes.iter_mut() {
+            let states = route.effects.get_mut().save_states();
+
+            if let Some(node) = self.nodes.iter_mut().find(|n| n.id == route.src_id) {
+                apply_effect_states(&mut node.effects, &states);
+            }
+            if let Some(effects) = self
+                .connections
+                .effects_mut(route.port_id, route.target_port_id)
+            {
+                apply_effect_states(effects, &states);
+            }
+        }
+    }
+
+    fn set_recorder_config(&mut self, node_id: Uuid, config: RecorderConfig) {
+        if let Some((_, recorder)) = self.recorders.iter_mut().find(|(id, _)| *id == node_id) {
+            if let Err(err) = recorder.set_config(config.clone()) {
+                warn!("Could not continue recording: {}", err);
+            }
+        }
+
+        if let Some(node) = self.nodes.iter_mut().find(|n| n.id == node_id) {
+            node.recorder = Some(config);
+        }
+    }
+
+    fn set_recorder_state(&mut self, node_id: Uuid, state: RecorderState) -> Result<()> {
+        self.recorders
+            .iter_mut()
+            .find(|(id, _)| *id == node_id)
+            .ok_or_else(|| Error::Other("No such recorder".to_string()))?
+            .1
+            .set_state(state)
+    }
+
+    fn recorder_status(&self, node_id: Uuid) -> Option<RecorderStatus> {
+        self.recorders
+            .iter()
+            .find(|(id, _)| *id == node_id)
+            .map(|(_, recorder)| recorder.status())
+    }
+
+    fn set_player_config(&mut self, node_id: Uuid, config: PlayerConfig) {
+        if let Some(player) = self.player(node_id) {
+            player.set_config(config.clone());
+        }
+
+        if let Some(node) = self.nodes.iter_mut().find(|n| n.id == node_id) {
+            node.player = Some(config);
+        }
+    }
+
+    fn player_command(&mut self, node_id: Uuid, command: PlayerCommand) -> Result<()> {
+        self.player(node_id)
+            .ok_or_else(|| Error::Other("No such player".to_string()))?
+            .command(command);
+
+        Ok(())
+    }
+
+    fn player_status(&self, node_id: Uuid) -> Option<PlayerStatus> {
+        self.players
+            .iter()
+            .find(|(id, _)| *id == node_id)
+            .map(|(_, player)| player.lock().status())
+    }
+
+    fn play_clip(&mut self, node_id: Uuid, clip: &Clip) -> Result<()> {
+        if !std::path::Path::new(&clip.path).is_file() {
+            return Err(Error::Other(format!("No such file: {}", clip.path)));
+        }
+
+        self.player(node_id)
+            .ok_or_else(|| Error::Other("No such player".to_string()))?
+            .play_clip(clip);
+
+        Ok(())
+    }
+
+    fn stop_clips(&mut self, node_id: Uuid) {
+        if let Some(player) = self.player(node_id) {
+            player.stop_clips();
+        }
+    }
+
+    fn playing_clips(&self, node_id: Uuid) -> Vec<Uuid> {
+        self.players
+            .iter()
+            .find(|(id, _)| *id == node_id)
+            .map(|(_, player)| player.lock().playing_clips())
+            .unwrap_or_default()
+    }
+
+    fn set_generator_config(&mut self, node_id: Uuid, config: GeneratorConfig) {
+        if let Some((_, generator)) = self.generators.iter_mut().find(|(id, _)| *id == node_id) {
+            generator.set_config(config.clone());
+        }
+
+        if let Some(node) = self.nodes.iter_mut().find(|n| n.id == node_id) {
+            node.generator = Some(config);
+        }
+    }
+
+    fn set_network_config(&mut self, node_id: Uuid, config: NetworkConfig) {
+        if let Some((_, sender)) = self
+            .network_senders
+            .iter_mut()
+            .find(|(id, _)| *id == node_id)
+        {
+            sender.set_config(config.clone());
+        }
+
+        if let Some((_, receiver)) = self
+            .network_receivers
+            .iter_mut()
+            .find(|(id, _)| *id == node_id)
+        {
+            receiver.set_config(config.clone());
+        }
+
+        if let Some(node) = self.nodes.iter_mut().find(|n| n.id == node_id) {
+            node.network = Some(config);
+        }
+    }
+
+    fn network_status(&self, node_id: Uuid) -> Option<NetworkStatus> {
+        let sender = self
+            .network_senders
+            .iter()
+            .find(|(id, _)| *id == node_id)
+            .map(|(_, sender)| sender.status());
+
+        sender.or_else(|| {
+            self.network_receivers
+                .iter()
+                .find(|(id, _)| *id == node_id)
+                .map(|(_, receiver)| receiver.status())
+        })
+    }
+
+    /// Moves the connections of the node to the new cable, like a default device change.
+    fn set_virtual_mic_config(&mut self, node_id: Uuid, config: VirtualMicConfig) {
+        if self.virtual_mic(node_id).is_none() {
+            return;
+        }
+
+        for conn in self.node_connections(node_id) {
+            if port_node_id(&self.nodes, conn.dst_id) == node_id
+                && conn.state == ConnectionState::Active
+            {
+                self.remove_route(conn.src_id, conn.dst_id);
+                self.connections
+                    .set_state(conn.src_id, conn.dst_id, ConnectionState::Pending);
+            }
+        }
+
+        if let Some(node) = self.nodes.iter_mut().find(|n| n.id == node_id) {
+            node.virtual_mic = Some(config);
+        }
+
+        self.update();
+    }
+
+    fn application_processes(&self) -> Vec<ProcessInfo> {
+        self.processes.clone()
+    }
+
+    fn input_devices(&self) -> Vec<DeviceInfo> {
+        device_infos(&self.input_devices)
+    }
+
+    fn output_devices(&self) -> Vec<DeviceInfo> {
+        device_infos(&self.output_devices)
+    }
+
+    fn take_new_processes(&mut self) -> Vec<ProcessInfo> {
+        std::mem::take(&mut self.new_processes)
+    }
+}
+
+fn device_infos(devices: &[SimulatedDevice]) -> Vec<DeviceInfo> {
+    devices
+        .iter()
+        .filter(|d| d.present)
+        .map(|d| DeviceInfo {
+            id: d.id,
+            name: d.name.clone(),
+            virtual_cable: is_virtual_cable(&d.name),
+        })
+        .collect()
+}
+
+fn process_node_match(node: &Node, process: &ProcessInfo) -> bool {
+    node.kind == NodeKind::Application
+        && node.display_name == process.display_name
+        && node.filename == process.filename
+}
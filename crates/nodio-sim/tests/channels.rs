@@ -0,0 +1,52 @@
+use nodio_core::{ChannelLayout, ChannelMatrix, ChannelPreset, Context};
+use nodio_sim::fixtures::{add_generator_node, render_peaks};
+use nodio_sim::SimulatedContext;
+
+#[test]
+fn channel_matrix_is_applied_per_link() {
+    let mut ctx = SimulatedContext::default();
+    let headset = ctx.add_output_device("Headset");
+    let speakers = ctx.add_output_device("Speakers");
+    let generator = add_generator_node(&mut ctx);
+    ctx.connect_node(generator, headset).unwrap();
+    ctx.connect_node(generator, speakers).unwrap();
+
+    let left_only = ChannelMatrix::preset(
+        ChannelPreset::LeftOnly,
+        ChannelLayout::Stereo,
+        ChannelLayout::Stereo,
+    );
+    ctx.set_channel_matrix(generator, headset, Some(left_only.clone()));
+    assert_eq!(ctx.channel_matrix(generator, headset), Some(left_only));
+    assert_eq!(ctx.channel_matrix(generator, speakers), None);
+
+    let peaks = render_peaks(&mut ctx, headset);
+    assert!(peaks[0] > 0.09, "{:?}", peaks);
+    assert_eq!(peaks[1], 0.0);
+
+    let peaks = render_peaks(&mut ctx, speakers);
+    assert!(peaks.iter().all(|&peak| peak > 0.09), "{:?}", peaks);
+
+    ctx.set_channel_matrix(generator, headset, None);
+    let peaks = render_peaks(&mut ctx, headset);
+    assert!(peaks.iter().all(|&peak| peak > 0.09), "{:?}", peaks);
+}
+
+#[test]
+fn channel_matrix_is_kept_while_pending() {
+    let mut ctx = SimulatedContext::default();
+    let headset = ctx.add_output_device("Headset");
+    let generator = add_generator_node(&mut ctx);
+    ctx.connect_node(generator, headset).unwrap();
+
+    let swapped = ChannelMatrix::preset(
+        ChannelPreset::SwapLeftRight,
+        ChannelLayout::Stereo,
+        ChannelLayout::Stereo,
+    );
+    ctx.set_channel_matrix(generator, headset, Some(swapped.clone()));
+
+    ctx.unplug_device(headset);
+    ctx.plug_device(headset);
+    assert_eq!(ctx.channel_matrix(generator, headset), Some(swapped));
+}
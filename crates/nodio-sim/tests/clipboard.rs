@@ -0,0 +1,154 @@
+use nodio_core::{ConnectionState, Context, History, Link, Node, NodeKind, Snippet, Uuid};
+use nodio_sim::fixtures::{add_app_node, add_node, generator_node, output_node};
+use nodio_sim::SimulatedContext;
+
+fn add_generator_node_at(ctx: &mut SimulatedContext, pos: (f32, f32)) -> Uuid {
+    add_node(
+        ctx,
+        Node {
+            pos,
+            ..generator_node()
+        },
+    )
+}
+
+fn add_output_node_at(ctx: &mut SimulatedContext, name: &str, pos: (f32, f32)) -> Uuid {
+    let node = output_node(ctx, name);
+    add_node(ctx, Node { pos, ..node })
+}
+
+fn copy(ctx: &SimulatedContext, node_ids: &[Uuid], links: &[(Uuid, Uuid)]) -> Snippet {
+    let nodes = ctx
+        .nodes()
+        .iter()
+        .filter(|node| node_ids.contains(&node.id))
+        .cloned()
+        .collect();
+    let links = links
+        .iter()
+        .map(|&(start, end)| Link::from_context(ctx, Uuid::new_v4(), start, end))
+        .collect();
+
+    Snippet::new(nodes, links)
+}
+
+#[test]
+fn only_links_between_copied_nodes_are_kept() {
+    let mut ctx = SimulatedContext::default();
+    let speakers = add_output_node_at(&mut ctx, "Speakers", (300.0, 0.0));
+    let headset = add_output_node_at(&mut ctx, "Headset", (300.0, 100.0));
+    let generator = add_generator_node_at(&mut ctx, (0.0, 0.0));
+    ctx.connect_node(generator, speakers).unwrap();
+    ctx.connect_node(generator, headset).unwrap();
+
+    let snippet = copy(
+        &ctx,
+        &[generator, speakers],
+        &[(generator, speakers), (generator, headset)],
+    );
+
+    assert_eq!(snippet.nodes.len(), 2);
+    assert_eq!(snippet.links.len(), 1);
+    assert_eq!(snippet.links[0].dst_id, speakers);
+}
+
+#[test]
+fn snippet_survives_text() {
+    let mut ctx = SimulatedContext::default();
+    let speakers = add_output_node_at(&mut ctx, "Speakers", (300.0, 0.0));
+    let generator = add_generator_node_at(&mut ctx, (0.0, 0.0));
+    ctx.connect_node(generator, speakers).unwrap();
+    ctx.set_link_delay(generator, speakers, 25.0);
+
+    let snippet = copy(&ctx, &[generator, speakers], &[(generator, speakers)]);
+    let text = snippet.to_text();
+
+    let parsed = Snippet::from_text(&text).unwrap();
+    assert_eq!(parsed.to_text(), text);
+    assert_eq!(parsed.links, snippet.links);
+    assert_eq!(parsed.links[0].delay_ms, 25.0);
+    assert!(Snippet::from_text("hello").is_err());
+}
+
+#[test]
+fn pasted_nodes_get_new_ids_and_are_linked_to_existing_devices() {
+    let mut ctx = SimulatedContext::default();
+    let speakers = add_output_node_at(&mut ctx, "Speakers", (300.0, 0.0));
+    let generator = add_generator_node_at(&mut ctx, (100.0, 50.0));
+    ctx.connect_node(generator, speakers).unwrap();
+    ctx.set_link_delay(generator, speakers, 25.0);
+
+    let snippet = copy(&ctx, &[generator, speakers], &[(generator, speakers)]);
+    let (edit, node_ids) = snippet.paste(&ctx, (500.0, 500.0));
+    edit.apply(&mut ctx).unwrap();
+
+    assert_eq!(node_ids.len(), 2);
+    assert!(node_ids.contains(&speakers));
+
+    // The device is not added twice
+    assert_eq!(ctx.nodes().len(), 3);
+
+    let copy_id = node_ids.into_iter().find(|&id| id != speakers).unwrap();
+    assert_ne!(copy_id, generator);
+
+    let copied = ctx.nodes().iter().find(|node| node.id == copy_id).unwrap();
+    assert_eq!(copied.kind, NodeKind::Generator);
+    assert_eq!(copied.pos, (500.0, 550.0));
+
+    assert_eq!(
+        ctx.connection_state(copy_id, speakers),
+        Some(ConnectionState::Active)
+    );
+    assert_eq!(ctx.link_delay(copy_id, speakers), 25.0);
+}
+
+#[test]
+fn paste_is_undone_as_one_edit() {
+    let mut ctx = SimulatedContext::default();
+    let mut history = History::default();
+    let speakers = add_output_node_at(&mut ctx, "Speakers", (300.0, 0.0));
+    let generator = add_generator_node_at(&mut ctx, (0.0, 0.0));
+    ctx.connect_node(generator, speakers).unwrap();
+
+    let snippet = copy(&ctx, &[generator, speakers], &[(generator, speakers)]);
+    let (edit, _) = snippet.paste(&ctx, (0.0, 200.0));
+    edit.apply(&mut ctx).unwrap();
+    history.push(edit);
+    assert_eq!(ctx.routes().len(), 2);
+
+    history.undo(&mut ctx).unwrap().1.unwrap();
+    assert_eq!(ctx.nodes().len(), 2);
+    assert_eq!(ctx.routes(), &[(generator, speakers)]);
+}
+
+#[test]
+fn pasted_applications_are_not_added_twice() {
+    let mut ctx = SimulatedContext::default();
+    let mut history = History::default();
+    let speakers = add_output_node_at(&mut ctx, "Speakers", (300.0, 0.0));
+    let game = add_app_node(&mut ctx, "Game");
+    let generator = add_generator_node_at(&mut ctx, (0.0, 100.0));
+    ctx.connect_node(game, speakers).unwrap();
+    ctx.connect_node(generator, speakers).unwrap();
+
+    let snippet = copy(
+        &ctx,
+        &[game, generator, speakers],
+        &[(game, speakers), (generator, speakers)],
+    );
+    let (edit, node_ids) = snippet.paste(&ctx, (0.0, 200.0));
+    edit.apply(&mut ctx).unwrap();
+    history.push(edit);
+
+    assert!(node_ids.contains(&game));
+    assert_eq!(ctx.nodes().len(), 4);
+    assert_eq!(ctx.routes().len(), 3);
+
+    // Undoing the paste keeps the link that was there before
+    history.undo(&mut ctx).unwrap().1.unwrap();
+    assert_eq!(ctx.nodes().len(), 3);
+    assert_eq!(
+        ctx.connection_state(game, speakers),
+        Some(ConnectionState::Active)
+    );
+}
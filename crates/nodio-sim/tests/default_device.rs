@@ -0,0 +1,84 @@
+use nodio_core::{ConnectionState, Context, DefaultDevice, Node, NodeKind, Uuid};
+use nodio_sim::fixtures::add_app_node;
+use nodio_sim::SimulatedContext;
+
+fn add_default_device_node(ctx: &mut SimulatedContext, default_device: DefaultDevice) -> Uuid {
+    ctx.add_node(Node {
+        id: default_device.id(),
+        kind: NodeKind::OutputDevice,
+        display_name: default_device.display_name().to_string(),
+        ..Default::default()
+    });
+    default_device.id()
+}
+
+#[test]
+fn connections_follow_the_default_device() {
+    let mut ctx = SimulatedContext::default();
+    let speakers = ctx.add_output_device("Speakers");
+    let headset = ctx.add_output_device("USB Headset");
+    ctx.set_default_device(DefaultDevice::Output, speakers);
+
+    let app_id = add_app_node(&mut ctx, "Music");
+    let default_output = add_default_device_node(&mut ctx, DefaultDevice::Output);
+
+    ctx.connect_node(app_id, default_output).unwrap();
+    assert_eq!(ctx.routes(), &[(app_id, speakers)]);
+
+    ctx.set_default_device(DefaultDevice::Output, headset);
+
+    assert_eq!(ctx.routes(), &[(app_id, headset)]);
+    assert_eq!(
+        ctx.connection_state(app_id, default_output),
+        Some(ConnectionState::Active)
+    );
+}
+
+#[test]
+fn default_roles_are_independent() {
+    let mut ctx = SimulatedContext::default();
+    let speakers = ctx.add_output_device("Speakers");
+    let headset = ctx.add_output_device("USB Headset");
+    ctx.set_default_device(DefaultDevice::Output, speakers);
+    ctx.set_default_device(DefaultDevice::Communications, headset);
+
+    let music = add_app_node(&mut ctx, "Music");
+    let voice = add_app_node(&mut ctx, "Voice chat");
+    let default_output = add_default_device_node(&mut ctx, DefaultDevice::Output);
+    let default_communications = add_default_device_node(&mut ctx, DefaultDevice::Communications);
+
+    ctx.connect_node(music, default_output).unwrap();
+    ctx.connect_node(voice, default_communications).unwrap();
+
+    ctx.set_default_device(DefaultDevice::Communications, speakers);
+
+    assert_eq!(ctx.routes(), &[(music, speakers), (voice, speakers)]);
+}
+
+#[test]
+fn unplugged_default_device_suspends_connections_until_a_new_default_is_set() {
+    let mut ctx = SimulatedContext::default();
+    let speakers = ctx.add_output_device("Speakers");
+    let headset = ctx.add_output_device("USB Headset");
+    ctx.set_default_device(DefaultDevice::Output, headset);
+
+    let app_id = add_app_node(&mut ctx, "Music");
+    let default_output = add_default_device_node(&mut ctx, DefaultDevice::Output);
+
+    ctx.connect_node(app_id, default_output).unwrap();
+    ctx.unplug_device(headset);
+
+    assert!(ctx.routes().is_empty());
+    assert_eq!(
+        ctx.connection_state(app_id, default_output),
+        Some(ConnectionState::Pending)
+    );
+
+    ctx.set_default_device(DefaultDevice::Output, speakers);
+
+    assert_eq!(ctx.routes(), &[(app_id, speakers)]);
+    assert_eq!(
+        ctx.connection_state(app_id, default_output),
+        Some(ConnectionState::Active)
+    );
+}
@@ -0,0 +1,80 @@
+use nodio_core::{align_delays, CalibrationStatus, Context, Uuid};
+use nodio_sim::fixtures::add_generator_node;
+use nodio_sim::{SimulatedContext, SIM_CHANNELS, SIM_SAMPLE_RATE};
+
+/// Frames of silence at the start of 100 ms of the audio the device receives.
+fn leading_silence(ctx: &mut SimulatedContext, device_id: Uuid) -> usize {
+    let mut buffer = vec![0.0; SIM_SAMPLE_RATE as usize / 10 * SIM_CHANNELS];
+    ctx.render(device_id, &mut buffer);
+
+    buffer
+        .chunks(SIM_CHANNELS)
+        .position(|frame| frame.iter().any(|&sample| sample != 0.0))
+        .unwrap_or(buffer.len() / SIM_CHANNELS)
+}
+
+fn measure(ctx: &mut SimulatedContext, target_id: Uuid, capture_id: Uuid) -> f32 {
+    ctx.start_calibration(target_id, capture_id).unwrap();
+
+    match ctx.calibration_status() {
+        Some(CalibrationStatus::Measured(latency)) => latency,
+        status => panic!("{:?}", status),
+    }
+}
+
+#[test]
+fn link_delay_is_applied() {
+    let mut ctx = SimulatedContext::default();
+    let speakers = ctx.add_output_device("Speakers");
+    let generator = add_generator_node(&mut ctx);
+    ctx.connect_node(generator, speakers).unwrap();
+    ctx.set_device_latency(speakers, 15.0);
+
+    ctx.set_link_delay(generator, speakers, 10.0);
+    assert_eq!(ctx.link_delay(generator, speakers), 10.0);
+    assert_eq!(ctx.link_latency(generator, speakers), Some(25.0));
+
+    // The sine starts at zero, so its first sample is silent too
+    assert_eq!(leading_silence(&mut ctx, speakers), 481);
+}
+
+#[test]
+fn calibration_aligns_devices() {
+    let mut ctx = SimulatedContext::default();
+    let headphones = ctx.add_output_device("Bluetooth headphones");
+    let speakers = ctx.add_output_device("Speakers");
+    let microphone = ctx.add_input_device("Microphone");
+    let generator = add_generator_node(&mut ctx);
+    ctx.connect_node(generator, headphones).unwrap();
+    ctx.connect_node(generator, speakers).unwrap();
+
+    ctx.set_device_latency(headphones, 180.0);
+    ctx.set_device_latency(speakers, 20.0);
+    ctx.set_device_latency(microphone, 10.0);
+
+    let latencies = [
+        (headphones, measure(&mut ctx, headphones, microphone)),
+        (speakers, measure(&mut ctx, speakers, microphone)),
+    ];
+    assert!((latencies[0].1 - 190.0).abs() < 0.1, "{:?}", latencies);
+
+    for (target_id, delay_ms) in align_delays(&latencies) {
+        ctx.set_link_delay(generator, target_id, delay_ms);
+    }
+
+    assert!(ctx.link_delay(generator, headphones) < 0.1);
+    assert!((ctx.link_delay(generator, speakers) - 160.0).abs() < 0.1);
+
+    let headphones_latency = ctx.link_latency(generator, headphones).unwrap();
+    let speakers_latency = ctx.link_latency(generator, speakers).unwrap();
+    assert!((headphones_latency - speakers_latency).abs() < 0.1);
+}
+
+#[test]
+fn calibration_needs_a_capture_device() {
+    let mut ctx = SimulatedContext::default();
+    let speakers = ctx.add_output_device("Speakers");
+
+    assert!(ctx.start_calibration(speakers, Uuid::new_v4()).is_err());
+    assert_eq!(ctx.calibration_status(), None);
+}
@@ -0,0 +1,74 @@
+use nodio_core::{Context, EffectConfig, EffectKind};
+use nodio_sim::fixtures::{add_generator_node, render_peaks};
+use nodio_sim::SimulatedContext;
+
+/// Gain / pan effect that mutes the audio
+fn mute() -> EffectConfig {
+    let mut effect = EffectConfig::new(EffectKind::GainPan);
+    effect.set_param(0, -60.0);
+    effect
+}
+
+#[test]
+fn node_effects_apply_to_every_link() {
+    let mut ctx = SimulatedContext::default();
+    let headset = ctx.add_output_device("Headset");
+    let speakers = ctx.add_output_device("Speakers");
+    let generator = add_generator_node(&mut ctx);
+    ctx.connect_node(generator, headset).unwrap();
+    ctx.connect_node(generator, speakers).unwrap();
+
+    assert!(ctx.applies_node_effects(generator));
+    assert!(!ctx.applies_node_effects(headset));
+
+    let mut pan_right = EffectConfig::new(EffectKind::GainPan);
+    pan_right.set_param(1, 1.0);
+    ctx.set_effects(generator, vec![pan_right]);
+
+    for device in [headset, speakers] {
+        let peaks = render_peaks(&mut ctx, device);
+        assert_eq!(peaks[0], 0.0);
+        assert!(peaks[1] > 0.09, "{:?}", peaks);
+    }
+}
+
+#[test]
+fn link_effects_apply_to_their_link() {
+    let mut ctx = SimulatedContext::default();
+    let headset = ctx.add_output_device("Headset");
+    let speakers = ctx.add_output_device("Speakers");
+    let generator = add_generator_node(&mut ctx);
+    ctx.connect_node(generator, headset).unwrap();
+    ctx.connect_node(generator, speakers).unwrap();
+    assert!(ctx.processes_link(generator, headset));
+
+    let effect = mute();
+    ctx.set_link_effects(generator, headset, vec![effect.clone()]);
+    assert_eq!(ctx.link_effects(generator, headset), vec![effect.clone()]);
+    assert!(ctx.link_effects(generator, speakers).is_empty());
+
+    let peaks = render_peaks(&mut ctx, headset);
+    assert!(peaks.iter().all(|&peak| peak < 0.001), "{:?}", peaks);
+    let peaks = render_peaks(&mut ctx, speakers);
+    assert!(peaks.iter().all(|&peak| peak > 0.09), "{:?}", peaks);
+
+    ctx.set_link_effect_param(generator, headset, effect.id, 0, 0.0);
+    let peaks = render_peaks(&mut ctx, headset);
+    assert!(peaks.iter().all(|&peak| peak > 0.09), "{:?}", peaks);
+}
+
+#[test]
+fn link_effects_are_kept_while_pending() {
+    let mut ctx = SimulatedContext::default();
+    let headset = ctx.add_output_device("Headset");
+    let generator = add_generator_node(&mut ctx);
+    ctx.connect_node(generator, headset).unwrap();
+    ctx.set_link_effects(generator, headset, vec![mute()]);
+
+    ctx.unplug_device(headset);
+    assert!(!ctx.processes_link(generator, headset));
+    ctx.plug_device(headset);
+
+    let peaks = render_peaks(&mut ctx, headset);
+    assert!(peaks.iter().all(|&peak| peak < 0.001), "{:?}", peaks);
+}
@@ -0,0 +1,58 @@
+use nodio_core::{Context, GeneratorConfig, Waveform};
+use nodio_sim::fixtures::{add_generator_node, render_peak};
+use nodio_sim::SimulatedContext;
+
+#[test]
+fn link_carries_audio() {
+    let mut ctx = SimulatedContext::default();
+    let null_sink = ctx.add_output_device("Null sink");
+    let generator = add_generator_node(&mut ctx);
+
+    assert!(ctx.nodes().iter().all(|node| node.present));
+    assert_eq!(render_peak(&mut ctx, null_sink), 0.0);
+
+    ctx.connect_node(generator, null_sink).unwrap();
+    let peak = render_peak(&mut ctx, null_sink);
+    assert!((peak - 0.1).abs() < 0.005, "peak {}", peak);
+
+    ctx.disconnect_node(generator, null_sink);
+    assert_eq!(render_peak(&mut ctx, null_sink), 0.0);
+}
+
+#[test]
+fn generator_config_is_applied() {
+    let mut ctx = SimulatedContext::default();
+    let null_sink = ctx.add_output_device("Null sink");
+    let generator = add_generator_node(&mut ctx);
+    ctx.connect_node(generator, null_sink).unwrap();
+
+    let config = GeneratorConfig {
+        waveform: Waveform::WhiteNoise,
+        level_db: -6.0,
+        ..GeneratorConfig::default()
+    };
+    ctx.set_generator_config(generator, config.clone());
+    assert_eq!(ctx.nodes()[0].generator, Some(config));
+
+    // Uniform noise peaks at sqrt(3) times its RMS
+    let peak = render_peak(&mut ctx, null_sink);
+    assert!(peak > 0.6, "peak {}", peak);
+
+    ctx.set_generator_config(
+        generator,
+        GeneratorConfig {
+            enabled: false,
+            ..GeneratorConfig::default()
+        },
+    );
+    assert_eq!(render_peak(&mut ctx, null_sink), 0.0);
+}
+
+#[test]
+fn generator_cannot_be_a_target() {
+    let mut ctx = SimulatedContext::default();
+    let first = add_generator_node(&mut ctx);
+    let second = add_generator_node(&mut ctx);
+
+    assert!(ctx.connect_node(first, second).is_err());
+}
@@ -0,0 +1,241 @@
+use nodio_core::{
+    ChannelLayout, ChannelMatrix, ChannelPreset, ConnectionState, Context, Edit, History, Link,
+    Note, Uuid,
+};
+use nodio_sim::fixtures::{add_output_node, generator_node};
+use nodio_sim::SimulatedContext;
+
+fn has_node(ctx: &SimulatedContext, node_id: Uuid) -> bool {
+    ctx.nodes().iter().any(|node| node.id == node_id)
+}
+
+#[test]
+fn added_node_is_removed_on_undo_and_added_on_redo() {
+    let mut ctx = SimulatedContext::default();
+    let mut history = History::default();
+
+    let node = generator_node();
+    let edit = Edit::AddNode {
+        node: node.clone(),
+        links: Vec::new(),
+    };
+    edit.apply(&mut ctx).unwrap();
+    history.push(edit);
+    assert!(has_node(&ctx, node.id));
+
+    let (undone, result) = history.undo(&mut ctx).unwrap();
+    result.unwrap();
+    assert!(matches!(undone, Edit::RemoveNode { .. }));
+    assert!(!has_node(&ctx, node.id));
+    assert!(!history.can_undo());
+
+    history.redo(&mut ctx).unwrap().1.unwrap();
+    assert!(has_node(&ctx, node.id));
+    assert!(!history.can_redo());
+}
+
+#[test]
+fn undoing_node_removal_reconnects_its_links() {
+    let mut ctx = SimulatedContext::default();
+    let mut history = History::default();
+    let speakers = add_output_node(&mut ctx, "Speakers");
+
+    let node = generator_node();
+    ctx.add_node(node.clone());
+    ctx.connect_node(node.id, speakers).unwrap();
+    ctx.set_link_delay(node.id, speakers, 40.0);
+
+    let link = Link::from_context(&ctx, Uuid::new_v4(), node.id, speakers);
+    let edit = Edit::RemoveNode {
+        node: node.clone(),
+        links: vec![link],
+    };
+    edit.apply(&mut ctx).unwrap();
+    history.push(edit);
+    assert!(ctx.connection_state(node.id, speakers).is_none());
+
+    history.undo(&mut ctx).unwrap().1.unwrap();
+    assert!(has_node(&ctx, node.id));
+    assert_eq!(
+        ctx.connection_state(node.id, speakers),
+        Some(ConnectionState::Active)
+    );
+    assert_eq!(ctx.link_delay(node.id, speakers), 40.0);
+}
+
+#[test]
+fn undoing_removal_of_linked_nodes_reconnects_them() {
+    let mut ctx = SimulatedContext::default();
+    let mut history = History::default();
+    let speakers = add_output_node(&mut ctx, "Speakers");
+    let node = generator_node();
+    ctx.add_node(node.clone());
+    ctx.connect_node(node.id, speakers).unwrap();
+
+    // Like the editor does, the link is listed by both of the removed nodes
+    let link = Link::from_context(&ctx, Uuid::new_v4(), node.id, speakers);
+    let edits = [node.id, speakers]
+        .into_iter()
+        .map(|node_id| Edit::RemoveNode {
+            node: ctx
+                .nodes()
+                .iter()
+                .find(|n| n.id == node_id)
+                .unwrap()
+                .clone(),
+            links: vec![link.clone()],
+        })
+        .collect::<Vec<_>>();
+    let edit = Edit::Group(edits);
+    edit.apply(&mut ctx).unwrap();
+    history.push(edit);
+    assert!(ctx.nodes().is_empty());
+
+    let (_, result) = history.undo(&mut ctx).unwrap();
+    assert!(result.is_ok(), "{:?}", result);
+    assert_eq!(
+        ctx.connection_state(node.id, speakers),
+        Some(ConnectionState::Active)
+    );
+
+    history.redo(&mut ctx).unwrap().1.unwrap();
+    assert!(ctx.nodes().is_empty());
+}
+
+#[test]
+fn undoing_link_removal_reconnects_it_with_its_settings() {
+    let mut ctx = SimulatedContext::default();
+    let mut history = History::default();
+    let speakers = add_output_node(&mut ctx, "Speakers");
+    let node = generator_node();
+    ctx.add_node(node.clone());
+    ctx.connect_node(node.id, speakers).unwrap();
+
+    let swapped = ChannelMatrix::preset(
+        ChannelPreset::SwapLeftRight,
+        ChannelLayout::Stereo,
+        ChannelLayout::Stereo,
+    );
+    ctx.set_channel_matrix(node.id, speakers, Some(swapped.clone()));
+
+    let edit = Edit::Disconnect(Link::from_context(&ctx, Uuid::new_v4(), node.id, speakers));
+    edit.apply(&mut ctx).unwrap();
+    history.push(edit);
+    assert!(ctx.routes().is_empty());
+
+    history.undo(&mut ctx).unwrap().1.unwrap();
+    assert_eq!(ctx.routes(), &[(node.id, speakers)]);
+    assert_eq!(ctx.channel_matrix(node.id, speakers), Some(swapped));
+
+    history.redo(&mut ctx).unwrap().1.unwrap();
+    assert!(ctx.routes().is_empty());
+}
+
+#[test]
+fn failed_reconnect_is_reported() {
+    let mut ctx = SimulatedContext::default();
+    let mut history = History::default();
+    let node = generator_node();
+    ctx.add_node(node.clone());
+
+    // The target has gone away since the link was removed
+    history.push(Edit::Disconnect(Link {
+        id: Uuid::new_v4(),
+        src_id: node.id,
+        dst_id: Uuid::new_v4(),
+        channel_matrix: None,
+        delay_ms: 0.0,
+        effects: Vec::new(),
+    }));
+
+    let (_, result) = history.undo(&mut ctx).unwrap();
+    assert!(result.is_err());
+    assert!(history.can_redo());
+}
+
+#[test]
+fn move_and_volume_are_reverted() {
+    let mut ctx = SimulatedContext::default();
+    let mut history = History::default();
+    let node = generator_node();
+    ctx.add_node(node.clone());
+
+    let edit = Edit::Group(vec![
+        Edit::MoveNode {
+            node_id: node.id,
+            from: (0.0, 0.0),
+            to: (100.0, 50.0),
+        },
+        Edit::SetVolume {
+            node_id: node.id,
+            from: 1.0,
+            to: 0.25,
+        },
+    ]);
+    edit.apply(&mut ctx).unwrap();
+    history.push(edit);
+    assert_eq!(ctx.nodes()[0].pos, (100.0, 50.0));
+    assert_eq!(ctx.nodes()[0].volume, 0.25);
+
+    history.undo(&mut ctx).unwrap().1.unwrap();
+    assert_eq!(ctx.nodes()[0].pos, (0.0, 0.0));
+    assert_eq!(ctx.nodes()[0].volume, 1.0);
+}
+
+#[test]
+fn new_edit_clears_redo() {
+    let mut ctx = SimulatedContext::default();
+    let mut history = History::default();
+    let node = generator_node();
+    ctx.add_node(node.clone());
+
+    history.push(Edit::SetVolume {
+        node_id: node.id,
+        from: 1.0,
+        to: 0.5,
+    });
+    history.undo(&mut ctx);
+    assert!(history.can_redo());
+
+    history.push(Edit::SetVolume {
+        node_id: node.id,
+        from: 1.0,
+        to: 0.75,
+    });
+    assert!(!history.can_redo());
+
+    // Empty groups are not recorded
+    history.push(Edit::Group(Vec::new()));
+    history.undo(&mut ctx);
+    assert!(!history.can_undo());
+}
+
+#[test]
+fn removed_notes_come_back_with_their_nodes() {
+    let mut ctx = SimulatedContext::default();
+    let mut history = History::default();
+    let node = generator_node();
+    ctx.add_node(node.clone());
+    let note = Note {
+        text: "Stream mix".to_string(),
+        ..Default::default()
+    };
+
+    let edit = Edit::Group(vec![
+        Edit::RemoveNote(note.clone()),
+        Edit::RemoveNode {
+            node: node.clone(),
+            links: Vec::new(),
+        },
+    ]);
+    edit.apply(&mut ctx).unwrap();
+    history.push(edit);
+
+    let (undone, result) = history.undo(&mut ctx).unwrap();
+    result.unwrap();
+    assert!(has_node(&ctx, node.id));
+    match undone {
+        Edit::Group(edits) => assert_eq!(edits.last(), Some(&Edit::AddNote(note))),
+        edit => panic!("{:?}", edit),
+    }
+}
@@ -0,0 +1,124 @@
+use nodio_core::{ConnectionState, Context, Node, NodeKind};
+use nodio_sim::SimulatedContext;
+
+fn add_device_node(ctx: &mut SimulatedContext, id: nodio_core::Uuid, kind: NodeKind) {
+    ctx.add_node(Node {
+        id,
+        kind,
+        ..Default::default()
+    });
+}
+
+#[test]
+fn unplugged_device_suspends_and_restores_connections() {
+    let mut ctx = SimulatedContext::default();
+    let headset = ctx.add_output_device("USB Headset");
+    let speakers = ctx.add_output_device("Speakers");
+    ctx.start_process("Music", "music.exe");
+
+    let app = Node {
+        kind: NodeKind::Application,
+        display_name: "Music".to_string(),
+        filename: "music.exe".to_string(),
+        ..Default::default()
+    };
+    let app_id = app.id;
+    ctx.add_node(app);
+    add_device_node(&mut ctx, headset, NodeKind::OutputDevice);
+    add_device_node(&mut ctx, speakers, NodeKind::OutputDevice);
+
+    ctx.connect_node(app_id, headset).unwrap();
+    ctx.connect_node(app_id, speakers).unwrap();
+    assert_eq!(ctx.routes(), &[(app_id, headset), (app_id, speakers)]);
+
+    ctx.unplug_device(headset);
+
+    assert_eq!(
+        ctx.connection_state(app_id, headset),
+        Some(ConnectionState::Pending)
+    );
+    assert_eq!(
+        ctx.connection_state(app_id, speakers),
+        Some(ConnectionState::Active)
+    );
+    assert_eq!(ctx.routes(), &[(app_id, speakers)]);
+    assert!(
+        !ctx.nodes()
+            .iter()
+            .find(|n| n.id == headset)
+            .unwrap()
+            .present
+    );
+    assert!(ctx.output_devices().iter().all(|d| d.id != headset));
+
+    ctx.plug_device(headset);
+
+    assert_eq!(
+        ctx.connection_state(app_id, headset),
+        Some(ConnectionState::Active)
+    );
+    assert!(ctx.routes().contains(&(app_id, headset)));
+    assert!(
+        ctx.nodes()
+            .iter()
+            .find(|n| n.id == headset)
+            .unwrap()
+            .present
+    );
+}
+
+#[test]
+fn connection_to_missing_device_is_pending_until_it_appears() {
+    let mut ctx = SimulatedContext::default();
+    let mic = ctx.add_input_device("Microphone");
+    let headset = ctx.add_output_device("USB Headset");
+    ctx.unplug_device(headset);
+
+    add_device_node(&mut ctx, mic, NodeKind::InputDevice);
+    add_device_node(&mut ctx, headset, NodeKind::OutputDevice);
+
+    ctx.connect_node(mic, headset).unwrap();
+    assert_eq!(
+        ctx.connection_state(mic, headset),
+        Some(ConnectionState::Pending)
+    );
+    assert!(ctx.routes().is_empty());
+
+    ctx.plug_device(headset);
+    assert_eq!(ctx.routes(), &[(mic, headset)]);
+}
+
+#[test]
+fn unplugged_source_device_suspends_its_connections() {
+    let mut ctx = SimulatedContext::default();
+    let mic = ctx.add_input_device("USB Microphone");
+    let speakers = ctx.add_output_device("Speakers");
+
+    add_device_node(&mut ctx, mic, NodeKind::InputDevice);
+    add_device_node(&mut ctx, speakers, NodeKind::OutputDevice);
+    ctx.connect_node(mic, speakers).unwrap();
+
+    ctx.unplug_device(mic);
+    assert!(ctx.routes().is_empty());
+
+    ctx.plug_device(mic);
+    assert_eq!(ctx.routes(), &[(mic, speakers)]);
+}
+
+#[test]
+fn disconnecting_pending_connection_forgets_it() {
+    let mut ctx = SimulatedContext::default();
+    let mic = ctx.add_input_device("Microphone");
+    let headset = ctx.add_output_device("USB Headset");
+
+    add_device_node(&mut ctx, mic, NodeKind::InputDevice);
+    add_device_node(&mut ctx, headset, NodeKind::OutputDevice);
+    ctx.connect_node(mic, headset).unwrap();
+
+    ctx.unplug_device(headset);
+    ctx.disconnect_node(mic, headset);
+    ctx.plug_device(headset);
+
+    assert_eq!(ctx.connection_state(mic, headset), None);
+    assert!(ctx.routes().is_empty());
+}
@@ -0,0 +1,149 @@
+use std::net::UdpSocket;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use nodio_core::{Context, NetworkConfig, Node, NodeKind, RtpPayload, Uuid};
+use nodio_sim::fixtures::{add_generator_node, output_port, render_peak};
+use nodio_sim::{SimulatedContext, SIM_CHANNELS, SIM_SAMPLE_RATE};
+
+fn add_node(ctx: &mut SimulatedContext, kind: NodeKind, address: &str) -> Uuid {
+    let node = Node {
+        kind,
+        display_name: format!("{:?}", kind),
+        network: Some(NetworkConfig {
+            address: address.to_string(),
+            payload: RtpPayload::L24,
+            payload_type: None,
+            latency_ms: 20,
+            announce: false,
+        }),
+        ..Default::default()
+    };
+    let id = node.id;
+    ctx.add_node(node);
+    id
+}
+
+/// Address of a free port on the loopback interface.
+fn free_loopback_address() -> String {
+    let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    socket.local_addr().unwrap().to_string()
+}
+
+#[test]
+fn streams_between_nodes_over_loopback() {
+    let mut ctx = SimulatedContext::default();
+    let null_sink = ctx.add_output_device("Null sink");
+    let address = free_loopback_address();
+
+    let generator = Node {
+        kind: NodeKind::Generator,
+        ..Default::default()
+    };
+    let generator_id = generator.id;
+    ctx.add_node(generator);
+    let sender = add_node(&mut ctx, NodeKind::NetworkSender, &address);
+    let receiver = add_node(&mut ctx, NodeKind::NetworkReceiver, &address);
+    assert!(ctx.nodes().iter().all(|node| node.present));
+
+    ctx.connect_node(generator_id, sender).unwrap();
+    ctx.connect_node(receiver, null_sink).unwrap();
+    assert!(ctx.connect_node(sender, null_sink).is_err());
+
+    ctx.transmit(SIM_SAMPLE_RATE as usize / 10);
+    assert_eq!(ctx.network_status(sender).unwrap().packets, 100);
+
+    let start = Instant::now();
+    while ctx.network_status(receiver).unwrap().buffered_ms < 100.0 {
+        assert!(start.elapsed() < Duration::from_secs(5), "Nothing received");
+        thread::sleep(Duration::from_millis(1));
+    }
+
+    let mut buffer = vec![0.0; SIM_SAMPLE_RATE as usize / 10 * SIM_CHANNELS];
+    ctx.render(null_sink, &mut buffer);
+    let peak = buffer.iter().fold(0.0f32, |peak, s| s.abs().max(peak));
+    assert!((peak - 0.1).abs() < 0.005, "peak {}", peak);
+
+    ctx.disconnect_node(generator_id, sender);
+    ctx.transmit(SIM_SAMPLE_RATE as usize / 10);
+    assert_eq!(ctx.network_status(sender).unwrap().packets, 100);
+}
+
+#[test]
+fn streams_what_an_output_device_plays() {
+    let mut ctx = SimulatedContext::default();
+    let speakers = ctx.add_output_device("Speakers");
+    let null_sink = ctx.add_output_device("Null sink");
+    let address = free_loopback_address();
+
+    ctx.add_node(Node {
+        id: speakers,
+        kind: NodeKind::OutputDevice,
+        display_name: "Speakers".to_string(),
+        ..Default::default()
+    });
+    let generator = add_generator_node(&mut ctx);
+    let sender = add_node(&mut ctx, NodeKind::NetworkSender, &address);
+    let receiver = add_node(&mut ctx, NodeKind::NetworkReceiver, &address);
+
+    ctx.connect_node(generator, speakers).unwrap();
+    ctx.connect_node(output_port(&ctx, speakers), sender)
+        .unwrap();
+    ctx.connect_node(receiver, null_sink).unwrap();
+
+    ctx.transmit(SIM_SAMPLE_RATE as usize / 10);
+
+    let start = Instant::now();
+    while ctx.network_status(receiver).unwrap().buffered_ms < 100.0 {
+        assert!(start.elapsed() < Duration::from_secs(5), "Nothing received");
+        thread::sleep(Duration::from_millis(1));
+    }
+
+    let peak = render_peak(&mut ctx, null_sink);
+    assert!((peak - 0.1).abs() < 0.005, "peak {}", peak);
+}
+
+#[test]
+fn sender_takes_a_single_source() {
+    let mut ctx = SimulatedContext::default();
+    let sender = add_node(&mut ctx, NodeKind::NetworkSender, "127.0.0.1:5004");
+    let first = ctx.start_process("Game", "game.exe");
+    let second = ctx.start_process("Chat", "chat.exe");
+
+    let mut app_node = |name: &str, filename: &str, pid: u32| {
+        let node = Node {
+            kind: NodeKind::Application,
+            display_name: name.to_string(),
+            filename: filename.to_string(),
+            process_id: Some(pid),
+            ..Default::default()
+        };
+        let id = node.id;
+        ctx.add_node(node);
+        id
+    };
+    let game = app_node("Game", "game.exe", first);
+    let chat = app_node("Chat", "chat.exe", second);
+
+    ctx.connect_node(game, sender).unwrap();
+    assert!(ctx.connect_node(chat, sender).is_err());
+}
+
+#[test]
+fn config_is_applied() {
+    let mut ctx = SimulatedContext::default();
+    let sender = add_node(&mut ctx, NodeKind::NetworkSender, "no address");
+    assert!(ctx.network_status(sender).unwrap().error.is_some());
+
+    let config = NetworkConfig {
+        address: "127.0.0.1:5004".to_string(),
+        announce: false,
+        ..NetworkConfig::default()
+    };
+    ctx.set_network_config(sender, config.clone());
+
+    let status = ctx.network_status(sender).unwrap();
+    assert_eq!(status.error, None);
+    assert!(status.sdp.unwrap().contains("m=audio 5004 RTP/AVP 96"));
+    assert_eq!(ctx.nodes()[0].network, Some(config));
+}
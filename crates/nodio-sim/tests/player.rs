@@ -0,0 +1,202 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use hound::{SampleFormat, WavSpec, WavWriter};
+use nodio_core::{
+    Clip, Context, LoopMode, Node, NodeKind, PlayerCommand, PlayerConfig, PlayerState, Uuid,
+};
+use nodio_sim::fixtures::render_peak;
+use nodio_sim::{SimulatedContext, SIM_CHANNELS, SIM_SAMPLE_RATE};
+
+/// Empty directory for the files of a test.
+fn test_dir(name: &str) -> PathBuf {
+    let dir =
+        std::env::temp_dir().join(format!("nodio-sim-player-{}-{}", name, std::process::id()));
+    fs::remove_dir_all(&dir).ok();
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Stereo file of the given length with a constant value.
+fn constant_file(dir: &Path, name: &str, frames: usize, value: f32) -> String {
+    let path = dir.join(name);
+    let spec = WavSpec {
+        channels: SIM_CHANNELS as u16,
+        sample_rate: SIM_SAMPLE_RATE,
+        bits_per_sample: 32,
+        sample_format: SampleFormat::Float,
+    };
+
+    let mut writer = WavWriter::create(&path, spec).unwrap();
+    for _ in 0..frames * SIM_CHANNELS {
+        writer.write_sample(value).unwrap();
+    }
+    writer.finalize().unwrap();
+
+    path.to_string_lossy().into_owned()
+}
+
+fn add_player_node(ctx: &mut SimulatedContext, playlist: Vec<String>) -> Uuid {
+    let node = Node {
+        kind: NodeKind::FilePlayer,
+        display_name: "Player".to_string(),
+        player: Some(PlayerConfig {
+            playlist,
+            loop_mode: LoopMode::Off,
+            gain_db: 0.0,
+        }),
+        ..Default::default()
+    };
+    let id = node.id;
+    ctx.add_node(node);
+    id
+}
+
+fn wait_for(ctx: &mut SimulatedContext, condition: impl Fn(&mut SimulatedContext) -> bool) {
+    let start = Instant::now();
+
+    while !condition(ctx) {
+        assert!(start.elapsed() < Duration::from_secs(10), "Timed out");
+        std::thread::sleep(Duration::from_millis(1));
+    }
+}
+
+/// Renders the device in blocks of 10 ms until the player stops, and returns the audio.
+fn render_until_stopped(ctx: &mut SimulatedContext, device_id: Uuid, player: Uuid) -> Vec<f32> {
+    let start = Instant::now();
+    let mut output = Vec::new();
+    let mut buffer = vec![0.0; SIM_SAMPLE_RATE as usize / 100 * SIM_CHANNELS];
+
+    while ctx.player_status(player).unwrap().state != PlayerState::Stopped {
+        assert!(start.elapsed() < Duration::from_secs(10), "Timed out");
+
+        ctx.render(device_id, &mut buffer);
+        output.extend_from_slice(&buffer);
+
+        if buffer.iter().all(|&sample| sample == 0.0) {
+            std::thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    output
+}
+
+fn clip(path: String, choke_group: Option<u32>) -> Clip {
+    Clip {
+        path,
+        choke_group,
+        ..Clip::default()
+    }
+}
+
+#[test]
+fn player_is_a_source() {
+    let mut ctx = SimulatedContext::default();
+    let speakers = ctx.add_output_device("Speakers");
+    let headphones = ctx.add_output_device("Headphones");
+    let player = add_player_node(&mut ctx, vec!["a.wav".to_string()]);
+
+    ctx.connect_node(player, speakers).unwrap();
+    ctx.connect_node(player, headphones).unwrap();
+    assert_eq!(ctx.routes(), &[(player, speakers), (player, headphones)]);
+    assert!(ctx.nodes().iter().all(|node| node.present));
+}
+
+#[test]
+fn player_plays_its_playlist_to_the_device() {
+    let dir = test_dir("playlist");
+    let playlist = vec![
+        constant_file(&dir, "a.wav", 4800, 0.5),
+        constant_file(&dir, "b.wav", 2400, 0.25),
+    ];
+
+    let mut ctx = SimulatedContext::default();
+    let speakers = ctx.add_output_device("Speakers");
+    let player = add_player_node(&mut ctx, playlist);
+    ctx.connect_node(player, speakers).unwrap();
+
+    assert_eq!(
+        ctx.player_status(player).unwrap().state,
+        PlayerState::Stopped
+    );
+    assert!(ctx.player_status(speakers).is_none());
+
+    ctx.player_command(player, PlayerCommand::Play).unwrap();
+    wait_for(&mut ctx, |ctx| {
+        ctx.player_status(player).unwrap().track == Some(0)
+    });
+
+    let output = render_until_stopped(&mut ctx, speakers, player);
+    let count = |value: f32| output.iter().filter(|&&sample| sample == value).count();
+    assert_eq!(count(0.5), 4800 * SIM_CHANNELS);
+    assert_eq!(count(0.25), 2400 * SIM_CHANNELS);
+
+    fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn paused_player_is_silent() {
+    let dir = test_dir("pause");
+    let playlist = vec![constant_file(&dir, "a.wav", 48000, 0.5)];
+
+    let mut ctx = SimulatedContext::default();
+    let speakers = ctx.add_output_device("Speakers");
+    let player = add_player_node(&mut ctx, playlist);
+    ctx.connect_node(player, speakers).unwrap();
+
+    ctx.player_command(player, PlayerCommand::Play).unwrap();
+    wait_for(&mut ctx, |ctx| render_peak(ctx, speakers) == 0.5);
+
+    ctx.player_command(player, PlayerCommand::Pause).unwrap();
+    assert_eq!(
+        ctx.player_status(player).unwrap().state,
+        PlayerState::Paused
+    );
+    assert_eq!(render_peak(&mut ctx, speakers), 0.0);
+
+    ctx.player_command(player, PlayerCommand::Stop).unwrap();
+    wait_for(&mut ctx, |ctx| {
+        ctx.player_status(player).unwrap().state == PlayerState::Stopped
+    });
+    assert_eq!(render_peak(&mut ctx, speakers), 0.0);
+
+    fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn clips_are_mixed_and_choked() {
+    let dir = test_dir("clips");
+    let (a, b, c) = (
+        clip(constant_file(&dir, "a.wav", 48000, 0.5), Some(1)),
+        clip(constant_file(&dir, "b.wav", 48000, 0.25), Some(1)),
+        clip(constant_file(&dir, "c.wav", 48000, 0.125), None),
+    );
+
+    let mut ctx = SimulatedContext::default();
+    let speakers = ctx.add_output_device("Speakers");
+    let player = add_player_node(&mut ctx, Vec::new());
+    ctx.connect_node(player, speakers).unwrap();
+
+    assert!(ctx
+        .play_clip(
+            player,
+            &clip(dir.join("missing.wav").to_string_lossy().into_owned(), None)
+        )
+        .is_err());
+
+    ctx.play_clip(player, &a).unwrap();
+    ctx.play_clip(player, &c).unwrap();
+    wait_for(&mut ctx, |ctx| ctx.playing_clips(player) == [a.id, c.id]);
+    assert_eq!(render_peak(&mut ctx, speakers), 0.625);
+
+    ctx.play_clip(player, &b).unwrap();
+    wait_for(&mut ctx, |ctx| ctx.playing_clips(player) == [c.id, b.id]);
+    assert_eq!(render_peak(&mut ctx, speakers), 0.375);
+
+    ctx.stop_clips(player);
+    assert!(ctx.playing_clips(player).is_empty());
+    assert_eq!(render_peak(&mut ctx, speakers), 0.0);
+
+    fs::remove_dir_all(&dir).ok();
+}
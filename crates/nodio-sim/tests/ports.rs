@@ -0,0 +1,224 @@
+use nodio_core::{
+    validate_link, ConnectionState, Context, Edit, EffectConfig, EffectKind, Link, MediaType, Node,
+    NodeKind, Port, PortDirection, Snippet, Uuid,
+};
+use nodio_sim::fixtures::{add_output_node, generator_node};
+use nodio_sim::{SimulatedContext, SIM_CHANNELS, SIM_SAMPLE_RATE};
+
+/// A generator with a mono output next to the port of its kind
+fn add_generator_node(ctx: &mut SimulatedContext) -> (Uuid, Uuid) {
+    let node = generator_node();
+    let mono_port_id = Uuid::new_v4();
+    let node = Node {
+        ports: vec![
+            node.default_port(),
+            Port {
+                id: mono_port_id,
+                name: "Mono".to_string(),
+                direction: PortDirection::Output,
+                channels: 1,
+                media: MediaType::Audio,
+            },
+        ],
+        ..node
+    };
+    let id = node.id;
+    ctx.add_node(node);
+    (id, mono_port_id)
+}
+
+#[test]
+fn nodes_get_the_port_of_their_kind() {
+    let mut ctx = SimulatedContext::default();
+    let speakers = add_output_node(&mut ctx, "Speakers");
+
+    let node = &ctx.nodes()[0];
+    assert_eq!(node.ports.len(), 2);
+    assert_eq!(node.ports[0].id, speakers);
+    assert_eq!(node.ports[0].direction, PortDirection::Input);
+    assert_eq!(node.ports[1].name, "Monitor");
+    assert_eq!(node.ports[1].direction, PortDirection::Output);
+}
+
+#[test]
+fn links_connect_the_nodes_of_their_ports() {
+    let mut ctx = SimulatedContext::default();
+    let speakers = add_output_node(&mut ctx, "Speakers");
+    let (generator, mono_port) = add_generator_node(&mut ctx);
+
+    ctx.connect_node(mono_port, speakers).unwrap();
+
+    assert_eq!(ctx.routes(), &[(generator, speakers)]);
+    assert_eq!(
+        ctx.connection_state(mono_port, speakers),
+        Some(ConnectionState::Active)
+    );
+}
+
+#[test]
+fn links_of_different_ports_are_kept_apart() {
+    let mut ctx = SimulatedContext::default();
+    let headset = add_output_node(&mut ctx, "Headset");
+    let speakers = add_output_node(&mut ctx, "Speakers");
+    let (generator, mono_port) = add_generator_node(&mut ctx);
+
+    ctx.connect_node(generator, headset).unwrap();
+    ctx.connect_node(mono_port, speakers).unwrap();
+    assert_eq!(ctx.routes().len(), 2);
+
+    ctx.disconnect_node(mono_port, speakers);
+
+    assert_eq!(ctx.connection_state(mono_port, speakers), None);
+    assert_eq!(
+        ctx.connection_state(generator, headset),
+        Some(ConnectionState::Active)
+    );
+    assert_eq!(ctx.routes(), &[(generator, headset)]);
+}
+
+#[test]
+fn nodes_are_linked_through_a_single_pair_of_ports() {
+    let mut ctx = SimulatedContext::default();
+    let speakers = add_output_node(&mut ctx, "Speakers");
+    let (generator, mono_port) = add_generator_node(&mut ctx);
+
+    ctx.connect_node(generator, speakers).unwrap();
+    assert!(ctx.connect_node(mono_port, speakers).is_err());
+    assert_eq!(ctx.routes(), &[(generator, speakers)]);
+
+    // Connecting the same ports again is fine
+    ctx.connect_node(generator, speakers).unwrap();
+}
+
+#[test]
+fn mono_ports_carry_the_sum_of_the_channels() {
+    let mut ctx = SimulatedContext::default();
+    let headset = add_output_node(&mut ctx, "Headset");
+    let speakers = add_output_node(&mut ctx, "Speakers");
+    let (generator, mono_port) = add_generator_node(&mut ctx);
+    ctx.connect_node(generator, headset).unwrap();
+    ctx.connect_node(mono_port, speakers).unwrap();
+
+    let mut pan_right = EffectConfig::new(EffectKind::GainPan);
+    pan_right.set_param(1, 1.0);
+    ctx.set_effects(generator, vec![pan_right]);
+
+    let mut buffer = vec![0.0; SIM_SAMPLE_RATE as usize / 10 * SIM_CHANNELS];
+    ctx.render(headset, &mut buffer);
+    assert!(buffer.chunks(2).all(|frame| frame[0] == 0.0));
+    assert!(buffer.chunks(2).any(|frame| frame[1] != 0.0));
+
+    ctx.render(speakers, &mut buffer);
+    assert!(buffer.chunks(2).all(|frame| frame[0] == frame[1]));
+    assert!(buffer.chunks(2).any(|frame| frame[0] != 0.0));
+}
+
+#[test]
+fn links_go_from_an_output_to_an_input() {
+    let mut ctx = SimulatedContext::default();
+    let speakers = add_output_node(&mut ctx, "Speakers");
+    let (generator, mono_port) = add_generator_node(&mut ctx);
+
+    assert!(ctx.connect_node(speakers, mono_port).is_err());
+    assert!(ctx.connect_node(generator, mono_port).is_err());
+    assert!(ctx.routes().is_empty());
+}
+
+#[test]
+fn links_are_validated_before_they_are_made() {
+    let mut ctx = SimulatedContext::default();
+    let speakers = add_output_node(&mut ctx, "Speakers");
+    let (generator, mono_port) = add_generator_node(&mut ctx);
+    let headset = add_output_node(&mut ctx, "Headset");
+    let links = [(generator, speakers)];
+
+    assert!(validate_link(ctx.nodes(), &links, mono_port, headset).is_ok());
+    assert!(validate_link(ctx.nodes(), &links, speakers, mono_port).is_err());
+    assert!(validate_link(ctx.nodes(), &links, generator, speakers).is_err());
+    assert!(validate_link(ctx.nodes(), &links, mono_port, speakers).is_err());
+}
+
+#[test]
+fn links_need_known_ports() {
+    let mut ctx = SimulatedContext::default();
+    let speakers = add_output_node(&mut ctx, "Speakers");
+    let (generator, _) = add_generator_node(&mut ctx);
+
+    assert!(validate_link(ctx.nodes(), &[], Uuid::new_v4(), speakers).is_err());
+    assert!(validate_link(ctx.nodes(), &[], generator, Uuid::new_v4()).is_err());
+}
+
+/// A node with an input and an output, as (input, output)
+fn add_effect_node(ctx: &mut SimulatedContext) -> (Uuid, Uuid) {
+    let port = |direction| Port {
+        id: Uuid::new_v4(),
+        name: String::new(),
+        direction,
+        channels: 2,
+        media: MediaType::Audio,
+    };
+    let (input, output) = (port(PortDirection::Input), port(PortDirection::Output));
+    let ports = (input.id, output.id);
+
+    ctx.add_node(Node {
+        kind: NodeKind::Generator,
+        display_name: "Effect".to_string(),
+        ports: vec![input, output],
+        ..Default::default()
+    });
+    ports
+}
+
+#[test]
+fn links_do_not_make_loops() {
+    let mut ctx = SimulatedContext::default();
+    let (a_in, a_out) = add_effect_node(&mut ctx);
+    let (b_in, b_out) = add_effect_node(&mut ctx);
+    let (c_in, c_out) = add_effect_node(&mut ctx);
+    let links = [(a_out, b_in), (b_out, c_in)];
+
+    assert!(validate_link(ctx.nodes(), &links, a_out, c_in).is_ok());
+    assert!(validate_link(ctx.nodes(), &links, c_out, a_in).is_err());
+    assert!(validate_link(ctx.nodes(), &links, a_out, a_in).is_err());
+}
+
+#[test]
+fn pasted_ports_get_new_ids() {
+    let mut ctx = SimulatedContext::default();
+    let speakers = add_output_node(&mut ctx, "Speakers");
+    let (generator, mono_port) = add_generator_node(&mut ctx);
+    ctx.connect_node(mono_port, speakers).unwrap();
+
+    let nodes = ctx
+        .nodes()
+        .iter()
+        .filter(|node| node.id == generator || node.id == speakers)
+        .cloned()
+        .collect();
+    let link = Link::from_context(&ctx, Uuid::new_v4(), mono_port, speakers);
+    let snippet = Snippet::new(nodes, vec![link]);
+
+    let (edit, node_ids) = snippet.paste(&ctx, (0.0, 200.0));
+    edit.apply(&mut ctx).unwrap();
+
+    let copy = ctx
+        .nodes()
+        .iter()
+        .find(|node| node_ids.contains(&node.id) && node.id != speakers)
+        .unwrap()
+        .clone();
+    assert_eq!(copy.ports[0].id, copy.id);
+    assert_ne!(copy.ports[1].id, mono_port);
+
+    let pasted_links = match edit {
+        Edit::Group(edits) => edits
+            .into_iter()
+            .filter_map(|edit| match edit {
+                Edit::Connect(link) => Some((link.src_id, link.dst_id)),
+                _ => None,
+            })
+            .collect::<Vec<_>>(),
+        _ => Vec::new(),
+    };
+    assert_eq!(pasted_links, &[(copy.ports[1].id, speakers)]);
+}
@@ -0,0 +1,152 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use nodio_core::{
+    ConnectionState, Context, Node, NodeKind, RecorderConfig, RecorderState, RecordingFormat, Uuid,
+};
+use nodio_sim::fixtures::{add_app_node, add_generator_node, add_output_node, output_port};
+use nodio_sim::{SimulatedContext, SIM_CHANNELS, SIM_SAMPLE_RATE};
+
+/// Empty directory for the recordings of a test.
+fn recording_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("nodio-sim-{}-{}", name, std::process::id()));
+    fs::remove_dir_all(&dir).ok();
+    dir
+}
+
+fn recordings(dir: &Path) -> Vec<PathBuf> {
+    fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .collect()
+}
+
+fn add_recorder_node(ctx: &mut SimulatedContext, dir: &Path) -> Uuid {
+    let node = Node {
+        kind: NodeKind::Recorder,
+        display_name: "Recorder".to_string(),
+        recorder: Some(RecorderConfig {
+            directory: dir.to_string_lossy().into_owned(),
+            format: RecordingFormat::WavFloat,
+            ..RecorderConfig::default()
+        }),
+        ..Default::default()
+    };
+    let id = node.id;
+    ctx.add_node(node);
+    id
+}
+
+#[test]
+fn recorder_takes_a_single_source() {
+    let mut ctx = SimulatedContext::default();
+    let call = add_app_node(&mut ctx, "Call");
+    let music = add_app_node(&mut ctx, "Music");
+    let recorder = add_recorder_node(&mut ctx, &recording_dir("single-source"));
+
+    ctx.connect_node(call, recorder).unwrap();
+    assert_eq!(
+        ctx.connection_state(call, recorder),
+        Some(ConnectionState::Active)
+    );
+    assert_eq!(ctx.routes(), &[(call, recorder)]);
+
+    assert!(ctx.connect_node(music, recorder).is_err());
+    assert!(ctx.connect_node(recorder, call).is_err());
+
+    ctx.disconnect_node(call, recorder);
+    ctx.connect_node(music, recorder).unwrap();
+    assert_eq!(ctx.routes(), &[(music, recorder)]);
+}
+
+#[test]
+fn recorder_state_is_reported() {
+    let mut ctx = SimulatedContext::default();
+    let call = add_app_node(&mut ctx, "Call");
+    let dir = recording_dir("state");
+    let recorder = add_recorder_node(&mut ctx, &dir);
+
+    assert_eq!(
+        ctx.recorder_status(recorder).unwrap().state,
+        RecorderState::Stopped
+    );
+    assert!(ctx.recorder_status(call).is_none());
+    assert!(ctx
+        .set_recorder_state(call, RecorderState::Recording)
+        .is_err());
+
+    ctx.set_recorder_state(recorder, RecorderState::Recording)
+        .unwrap();
+    assert_eq!(
+        ctx.recorder_status(recorder).unwrap().state,
+        RecorderState::Recording
+    );
+
+    ctx.remove_node(recorder);
+    assert!(ctx.recorder_status(recorder).is_none());
+
+    fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn recorder_writes_its_source_into_a_file() {
+    let mut ctx = SimulatedContext::default();
+    let generator = add_generator_node(&mut ctx);
+    let dir = recording_dir("write");
+    let recorder = add_recorder_node(&mut ctx, &dir);
+    ctx.connect_node(generator, recorder).unwrap();
+
+    // Nothing is written before the recording starts
+    ctx.record(4800);
+    ctx.set_recorder_state(recorder, RecorderState::Recording)
+        .unwrap();
+    ctx.record(4800);
+
+    let status = ctx.recorder_status(recorder).unwrap();
+    assert_eq!(status.elapsed_secs, 0.1);
+    assert!(status.bytes > 0);
+
+    ctx.set_recorder_state(recorder, RecorderState::Stopped)
+        .unwrap();
+
+    let files = recordings(&dir);
+    assert_eq!(files.len(), 1);
+    // Recordings are named after their source
+    assert!(files[0]
+        .file_name()
+        .unwrap()
+        .to_string_lossy()
+        .starts_with("Generator "));
+
+    let mut reader = hound::WavReader::open(&files[0]).unwrap();
+    assert_eq!(reader.spec().sample_rate, SIM_SAMPLE_RATE);
+    assert_eq!(reader.spec().channels as usize, SIM_CHANNELS);
+
+    let samples: Vec<f32> = reader.samples::<f32>().map(Result::unwrap).collect();
+    assert_eq!(samples.len(), 4800 * SIM_CHANNELS);
+    // The default signal of the generator peaks at -20 dB
+    let peak = samples
+        .iter()
+        .fold(0.0f32, |peak, sample| peak.max(sample.abs()));
+    assert!((peak - 0.1).abs() < 0.005, "peak {}", peak);
+
+    fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn output_device_is_recorded_through_its_monitor_port() {
+    let mut ctx = SimulatedContext::default();
+    let speakers = add_output_node(&mut ctx, "Speakers");
+    let headset = add_output_node(&mut ctx, "Headset");
+    let recorder = add_recorder_node(&mut ctx, &recording_dir("monitor"));
+    let monitor = output_port(&ctx, speakers);
+
+    assert!(ctx.connect_node(monitor, headset).is_err());
+
+    ctx.connect_node(monitor, recorder).unwrap();
+    assert_eq!(
+        ctx.connection_state(monitor, recorder),
+        Some(ConnectionState::Active)
+    );
+    assert_eq!(ctx.routes(), &[(speakers, recorder)]);
+}
@@ -0,0 +1,90 @@
+use nodio_core::{ConnectionState, Context};
+use nodio_sim::fixtures::{add_node, add_output_node, app_node};
+use nodio_sim::SimulatedContext;
+
+#[test]
+fn restarted_application_gets_its_routes_back() {
+    let mut ctx = SimulatedContext::default();
+    let headset = add_output_node(&mut ctx, "USB Headset");
+    let speakers = add_output_node(&mut ctx, "Speakers");
+    let pid = ctx.start_process("Music", "music.exe");
+    let app_id = add_node(&mut ctx, app_node("Music", "music.exe"));
+
+    ctx.connect_node(app_id, headset).unwrap();
+    ctx.connect_node(app_id, speakers).unwrap();
+    assert_eq!(ctx.routes(), &[(app_id, headset), (app_id, speakers)]);
+
+    ctx.stop_process(pid);
+
+    assert!(ctx.routes().is_empty());
+    assert_eq!(
+        ctx.connection_state(app_id, headset),
+        Some(ConnectionState::Pending)
+    );
+    assert_eq!(
+        ctx.connection_state(app_id, speakers),
+        Some(ConnectionState::Pending)
+    );
+
+    let new_pid = ctx.start_process("Music", "music.exe");
+
+    assert_ne!(pid, new_pid);
+    assert_eq!(
+        ctx.nodes()
+            .iter()
+            .find(|n| n.id == app_id)
+            .unwrap()
+            .process_id,
+        Some(new_pid)
+    );
+    assert_eq!(ctx.routes(), &[(app_id, headset), (app_id, speakers)]);
+    assert_eq!(
+        ctx.connection_state(app_id, headset),
+        Some(ConnectionState::Active)
+    );
+    assert_eq!(
+        ctx.connection_state(app_id, speakers),
+        Some(ConnectionState::Active)
+    );
+}
+
+#[test]
+fn connection_to_application_without_process_is_pending() {
+    let mut ctx = SimulatedContext::default();
+    let speakers = add_output_node(&mut ctx, "Speakers");
+    let app_id = add_node(&mut ctx, app_node("Music", "music.exe"));
+
+    ctx.connect_node(app_id, speakers).unwrap();
+
+    assert!(ctx.routes().is_empty());
+    assert_eq!(
+        ctx.connection_state(app_id, speakers),
+        Some(ConnectionState::Pending)
+    );
+
+    ctx.start_process("Music", "music.exe");
+
+    assert_eq!(ctx.routes(), &[(app_id, speakers)]);
+    assert_eq!(
+        ctx.connection_state(app_id, speakers),
+        Some(ConnectionState::Active)
+    );
+}
+
+#[test]
+fn other_applications_do_not_restore_routes() {
+    let mut ctx = SimulatedContext::default();
+    let speakers = add_output_node(&mut ctx, "Speakers");
+    let pid = ctx.start_process("Music", "music.exe");
+    let app_id = add_node(&mut ctx, app_node("Music", "music.exe"));
+
+    ctx.connect_node(app_id, speakers).unwrap();
+    ctx.stop_process(pid);
+    ctx.start_process("Video", "video.exe");
+
+    assert!(ctx.routes().is_empty());
+    assert_eq!(
+        ctx.connection_state(app_id, speakers),
+        Some(ConnectionState::Pending)
+    );
+}
@@ -0,0 +1,97 @@
+use nodio_core::{
+    ConnectionState, Context, GeneratorConfig, Node, NodeKind, Uuid, VirtualMicConfig,
+};
+use nodio_sim::fixtures::{add_node, generator_node, render_peak};
+use nodio_sim::SimulatedContext;
+
+fn add_generator_node(ctx: &mut SimulatedContext, frequency_hz: f32) -> Uuid {
+    add_node(
+        ctx,
+        Node {
+            generator: Some(GeneratorConfig {
+                frequency_hz,
+                ..GeneratorConfig::default()
+            }),
+            ..generator_node()
+        },
+    )
+}
+
+fn add_virtual_mic_node(ctx: &mut SimulatedContext, cable_id: Option<Uuid>) -> Uuid {
+    add_node(
+        ctx,
+        Node {
+            kind: NodeKind::VirtualMicrophone,
+            display_name: "Stream mic".to_string(),
+            virtual_mic: Some(VirtualMicConfig { cable_id }),
+            ..Default::default()
+        },
+    )
+}
+
+#[test]
+fn mixes_linked_sources() {
+    let mut ctx = SimulatedContext::default();
+    let first = add_generator_node(&mut ctx, 1000.0);
+    let second = add_generator_node(&mut ctx, 1000.0);
+    let mic = add_virtual_mic_node(&mut ctx, None);
+
+    assert!(ctx.nodes().iter().all(|node| node.present));
+
+    ctx.connect_node(first, mic).unwrap();
+    let peak = render_peak(&mut ctx, mic);
+    assert!((peak - 0.1).abs() < 0.005, "peak {}", peak);
+
+    // Both generators start at the same phase, so their tones add up
+    ctx.connect_node(second, mic).unwrap();
+    let peak = render_peak(&mut ctx, mic);
+    assert!((peak - 0.2).abs() < 0.01, "peak {}", peak);
+}
+
+#[test]
+fn follows_the_virtual_cable() {
+    let mut ctx = SimulatedContext::default();
+    let cable = ctx.add_output_device("CABLE Input (VB-Audio Virtual Cable)");
+    let speakers = ctx.add_output_device("Speakers");
+    let generator = add_generator_node(&mut ctx, 1000.0);
+    let mic = add_virtual_mic_node(&mut ctx, Some(cable));
+
+    let cables = ctx
+        .output_devices()
+        .into_iter()
+        .filter(|device| device.virtual_cable)
+        .map(|device| device.id)
+        .collect::<Vec<_>>();
+    assert_eq!(cables, vec![cable]);
+
+    ctx.connect_node(generator, mic).unwrap();
+    assert_eq!(ctx.routes(), vec![(generator, cable)]);
+
+    ctx.unplug_device(cable);
+    assert_eq!(
+        ctx.connection_state(generator, mic),
+        Some(ConnectionState::Pending)
+    );
+
+    ctx.plug_device(cable);
+    ctx.set_virtual_mic_config(
+        mic,
+        VirtualMicConfig {
+            cable_id: Some(speakers),
+        },
+    );
+    assert_eq!(ctx.routes(), vec![(generator, speakers)]);
+    assert_eq!(
+        ctx.connection_state(generator, mic),
+        Some(ConnectionState::Active)
+    );
+}
+
+#[test]
+fn virtual_mic_cannot_be_a_source() {
+    let mut ctx = SimulatedContext::default();
+    let speakers = ctx.add_output_device("Speakers");
+    let mic = add_virtual_mic_node(&mut ctx, None);
+
+    assert!(ctx.connect_node(mic, speakers).is_err());
+}
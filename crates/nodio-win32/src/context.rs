@@ -6,26 +6,43 @@ use std::time::Duration;
 
 use log::{debug, error, info, trace, warn};
 use notify_thread::JoinHandle;
-use parking_lot::RwLock;
+use parking_lot::{Mutex, RwLock};
 use windows::core::HSTRING;
 use windows::Win32::Media::Audio::{
-    eCapture, eConsole, eMultimedia, eRender, EDataFlow, DEVICE_STATEMASK_ALL,
+    eCapture, eCommunications, eConsole, eMultimedia, eRender, EDataFlow, ERole,
+    DEVICE_STATEMASK_ALL,
 };
 use windows::Win32::System::Threading::GetCurrentProcessId;
 
-use nodio_core::{Context, DeviceInfo, Node, NodeKind, ProcessInfo, Uuid};
+use nodio_core::{
+    apply_effect_states, find_port, is_virtual_cable, port_node_id, CalibrationStatus,
+    ChannelMatrix, Clip, ConnectionState, Connections, Context, DefaultDevice, DeviceInfo,
+    EffectConfig, GeneratorConfig, NetworkConfig, NetworkStatus, Node, NodeKind, PlayerCommand,
+    PlayerConfig, PlayerStatus, ProcessInfo, RecorderConfig, RecorderState, RecorderStatus, Uuid,
+    VirtualMicConfig,
+};
 use nodio_core::{Error, Result};
+use nodio_engine::{
+    AudioSink, BufferedSink, Calibrator, FilePlayer, Recorder, RtpReceiver, RtpSender,
+    SignalGenerator,
+};
 
 use crate::com::ensure_com_initialized;
 use crate::custom::{
-    create_audio_policy_config, AudioPolicyConfig, AudioSessionEvent, SessionState,
+    create_audio_policy_config, AudioPolicyConfig, AudioSessionEvent, DeviceNotification,
+    SessionState,
 };
 use crate::device::{
     AudioDevice, DEVINTERFACE_AUDIO_CAPTURE, DEVINTERFACE_AUDIO_RENDER, MMDEVAPI_TOKEN,
 };
 use crate::enumerator::AudioDeviceEnumerator;
+use crate::listen::Listen;
 use crate::loopback::LoopbackSession;
 use crate::node::{NodeConnectionInfo, NodeConnectionKind};
+use crate::playback::{Playback, RenderTargets, PLAYBACK_CHANNELS, PLAYBACK_SAMPLE_RATE};
+use crate::recording::{
+    RecordingSession, RECORDING_BUFFER_SECS, RECORDING_CHANNELS, RECORDING_SAMPLE_RATE,
+};
 use crate::session::{session_node_match, AudioSession, AudioSessionKind};
 
 pub struct Win32Context {
@@ -34,13 +51,41 @@ pub struct Win32Context {
 
     nodes: Vec<Node>,
 
+    /// Connections requested by the user, including the ones waiting for a device
+    connections: Connections,
+    /// Ports of the links of the connections. Windows routes the audio of a node to a device
+    /// once, so there is one link per pair of nodes.
+    link_ports: Vec<(Uuid, Uuid)>,
+    /// Connections currently applied to the system
     node_connections: Vec<NodeConnectionInfo>,
 
     loopback_sessions: Arc<RwLock<Vec<LoopbackSession>>>,
 
+    /// Recorders of the recorder nodes, by node id
+    recorders: Vec<(Uuid, Arc<Mutex<Recorder>>)>,
+    recording_sessions: Vec<RecordingSession>,
+
+    /// Playbacks of the file player nodes, by node id
+    playbacks: Vec<(Uuid, Playback<FilePlayer>)>,
+    /// Playbacks of the generator nodes, by node id
+    generators: Vec<(Uuid, Playback<SignalGenerator>)>,
+    /// Streams of the network sender nodes, by node id
+    network_senders: Vec<(Uuid, Arc<Mutex<RtpSender>>)>,
+    /// Playbacks of the network receiver nodes, by node id
+    network_receivers: Vec<(Uuid, Playback<RtpReceiver>)>,
+    /// Playbacks of the input devices that are listened to, by node id
+    listens: Vec<(Uuid, Listen)>,
+
+    /// Click played on an output device and the capture listening for it
+    calibration: Option<(Playback<Calibrator>, RecordingSession)>,
+
     sessions: Arc<RwLock<Vec<AudioSession>>>,
     input_devices: Arc<RwLock<Vec<AudioDevice>>>,
     output_devices: Arc<RwLock<Vec<AudioDevice>>>,
+    /// Current system default output devices
+    default_devices: Vec<(DefaultDevice, Uuid)>,
+
+    new_processes: Vec<ProcessInfo>,
 
     session_update_thread: Option<JoinHandle<()>>,
 }
@@ -70,36 +115,29 @@ impl Win32Context {
             sessions: Default::default(),
             input_devices: Default::default(),
             output_devices: Default::default(),
+            default_devices: Default::default(),
+            connections: Default::default(),
+            link_ports: Default::default(),
             node_connections: Default::default(),
             loopback_sessions: Default::default(),
+            recorders: Default::default(),
+            recording_sessions: Default::default(),
+            playbacks: Default::default(),
+            generators: Default::default(),
+            network_senders: Default::default(),
+            network_receivers: Default::default(),
+            listens: Default::default(),
+            calibration: None,
+            new_processes: Default::default(),
             session_update_thread: None,
         }));
 
-        let mut output_devices = ctx
-            .read()
-            .device_enumerator
-            .enumerate_audio_endpoints(eRender, DEVICE_STATEMASK_ALL)
-            .unwrap();
-
-        let mut input_devices = ctx
-            .read()
-            .device_enumerator
-            .enumerate_audio_endpoints(eCapture, DEVICE_STATEMASK_ALL)
-            .unwrap();
-
-        for device in input_devices.iter_mut().chain(output_devices.iter_mut()) {
-            let ctx = ctx.clone();
-            let name = device.name().to_string();
-
-            device.set_session_notification_callback(move |event| {
-                trace!("Session notification in {}: {:?}", name, event);
-
-                Self::refresh_sessions(ctx.clone());
-            });
-        }
+        let output_devices = Self::enumerate_devices(&ctx, eRender).unwrap();
+        let input_devices = Self::enumerate_devices(&ctx, eCapture).unwrap();
 
         ctx.write().input_devices = Arc::new(RwLock::new(input_devices));
         ctx.write().output_devices = Arc::new(RwLock::new(output_devices));
+        ctx.write().refresh_default_devices();
 
         Self::refresh_sessions(ctx.clone());
 
@@ -110,11 +148,14 @@ impl Win32Context {
                 trace!("Session update thread started");
 
                 while !thread.notified() {
-                    let sessions: Arc<_> = ctx.read().sessions.clone();
+                    // Locks are taken in the order context, then sessions and devices, like
+                    // the notification callbacks do. The sessions are copied so their lock is
+                    // not held while the context is written.
+                    let sessions = ctx.read().sessions.read().clone();
                     let input_devices: Arc<_> = ctx.read().input_devices.clone();
                     let output_devices: Arc<_> = ctx.read().output_devices.clone();
 
-                    for session in sessions.read().iter() {
+                    for session in sessions.iter() {
                         if let Some(node) = ctx
                             .write()
                             .nodes
@@ -129,18 +170,47 @@ impl Win32Context {
                         }
                     }
 
-                    for device in input_devices
-                        .read()
-                        .iter()
-                        .chain(output_devices.read().iter())
                     {
-                        if let Some(node) =
-                            ctx.write().nodes.iter_mut().find(|n| n.id == device.id())
+                        let mut ctx = ctx.write();
+                        let input_devices = input_devices.read();
+                        let output_devices = output_devices.read();
+
+                        let device_ids = ctx
+                            .nodes
+                            .iter()
+                            .map(|n| ctx.resolve_device_id(n.id))
+                            .collect::<Vec<_>>();
+
+                        for (node, device_id) in
+                            ctx.nodes.iter_mut().zip(device_ids).filter(|(n, _)| {
+                                !matches!(
+                                    n.kind,
+                                    NodeKind::Application
+                                        | NodeKind::Recorder
+                                        | NodeKind::FilePlayer
+                                        | NodeKind::Generator
+                                        | NodeKind::NetworkSender
+                                        | NodeKind::NetworkReceiver
+                                )
+                            })
                         {
-                            node.peak_values = device.peak_values().unwrap_or((0.0, 0.0));
-                            node.volume = device.master_volume();
-                            node.active = device.is_active();
-                            node.present = true;
+                            match input_devices
+                                .iter()
+                                .chain(output_devices.iter())
+                                .find(|d| Some(d.id()) == device_id)
+                            {
+                                Some(device) => {
+                                    node.peak_values = device.peak_values().unwrap_or((0.0, 0.0));
+                                    node.volume = device.master_volume();
+                                    node.active = device.is_active();
+                                    node.present = device.is_active();
+                                }
+                                None => {
+                                    node.peak_values = (0.0, 0.0);
+                                    node.active = false;
+                                    node.present = false;
+                                }
+                            }
                         }
                     }
 
@@ -153,12 +223,169 @@ impl Win32Context {
 
         ctx.write().session_update_thread = Some(session_update_thread);
 
+        // Registered once the context is complete, so a device change during startup does not
+        // act on a context that is still being set up
+        ctx.write()
+            .device_enumerator
+            .set_device_notification_callback({
+                let ctx = ctx.clone();
+
+                move |event| match event {
+                    DeviceNotification::DefaultDeviceChanged { .. } => {
+                        Self::default_device_changed(ctx.clone());
+                    }
+                    DeviceNotification::DeviceAdded { .. }
+                    | DeviceNotification::DeviceRemoved { .. }
+                    | DeviceNotification::StateChanged { .. } => {
+                        Self::refresh_devices(ctx.clone());
+                    }
+                    _ => {}
+                }
+            });
+
         ctx
     }
 
+    fn enumerate_devices(
+        ctx: &Arc<RwLock<Win32Context>>,
+        data_flow: EDataFlow,
+    ) -> windows::core::Result<Vec<AudioDevice>> {
+        let mut devices = ctx
+            .read()
+            .device_enumerator
+            .enumerate_audio_endpoints(data_flow, DEVICE_STATEMASK_ALL)?;
+
+        for device in devices.iter_mut() {
+            let ctx = ctx.clone();
+            let name = device.name().to_string();
+
+            device.set_session_notification_callback(move |event| {
+                trace!("Session notification in {}: {:?}", name, event);
+
+                Self::refresh_sessions(ctx.clone());
+            });
+        }
+
+        Ok(devices)
+    }
+
+    /// Re-enumerates the devices after a device has been added, removed or its state
+    /// has changed, and restores or suspends the affected connections.
+    fn refresh_devices(ctx: Arc<RwLock<Win32Context>>) {
+        debug!("Refreshing devices");
+
+        ensure_com_initialized();
+
+        let devices = Self::enumerate_devices(&ctx, eCapture)
+            .and_then(|input| Self::enumerate_devices(&ctx, eRender).map(|output| (input, output)));
+
+        match devices {
+            Ok((input_devices, output_devices)) => {
+                let mut ctx = ctx.write();
+                ctx.input_devices = Arc::new(RwLock::new(input_devices));
+                ctx.output_devices = Arc::new(RwLock::new(output_devices));
+                ctx.refresh_default_devices();
+            }
+            Err(err) => {
+                error!("Failed to enumerate devices: {}", err);
+                return;
+            }
+        }
+
+        // Also reconciles the connections
+        Self::refresh_sessions(ctx);
+    }
+
+    /// Moves the connections of the default device nodes to the new default devices.
+    fn default_device_changed(ctx: Arc<RwLock<Win32Context>>) {
+        ensure_com_initialized();
+
+        let mut ctx = ctx.write();
+        let previous_default_devices = ctx.default_devices.clone();
+
+        ctx.refresh_default_devices();
+
+        for (default_device, device_id) in previous_default_devices {
+            if ctx.default_device_id(default_device) == Some(device_id) {
+                continue;
+            }
+
+            info!(
+                "{} changed, moving connections",
+                default_device.display_name()
+            );
+
+            for conn in ctx.connections.of_node(default_device.id()) {
+                if conn.dst_id == default_device.id() && conn.state == ConnectionState::Active {
+                    ctx.remove_node_connection(conn.src_id, conn.dst_id);
+                    ctx.connections
+                        .set_state(conn.src_id, conn.dst_id, ConnectionState::Pending);
+                }
+            }
+        }
+
+        ctx.reconcile_connections();
+    }
+
+    fn refresh_default_devices(&mut self) {
+        self.default_devices = DefaultDevice::ALL
+            .into_iter()
+            .filter_map(|default_device| {
+                let role: ERole = match default_device {
+                    DefaultDevice::Output => eMultimedia,
+                    DefaultDevice::Communications => eCommunications,
+                };
+
+                match self
+                    .device_enumerator
+                    .default_audio_endpoint_id(eRender, role)
+                {
+                    Ok(device_id) => Some((default_device, device_id)),
+                    Err(err) => {
+                        debug!("No device for {}: {}", default_device.display_name(), err);
+                        None
+                    }
+                }
+            })
+            .collect();
+    }
+
+    fn default_device_id(&self, default_device: DefaultDevice) -> Option<Uuid> {
+        self.default_devices
+            .iter()
+            .find(|(d, _)| *d == default_device)
+            .map(|(_, device_id)| *device_id)
+    }
+
+    /// Maps the default device nodes to the current default devices, and the virtual microphone
+    /// nodes to the output devices of their virtual cables.
+    fn resolve_device_id(&self, id: Uuid) -> Option<Uuid> {
+        match DefaultDevice::from_id(id) {
+            Some(default_device) => self.default_device_id(default_device),
+            None => match self.virtual_mic(id) {
+                Some(node) => node.virtual_mic.as_ref().and_then(|config| config.cable_id),
+                None => Some(id),
+            },
+        }
+    }
+
+    fn virtual_mic(&self, id: Uuid) -> Option<&Node> {
+        self.nodes
+            .iter()
+            .find(|n| n.id == id && n.kind == NodeKind::VirtualMicrophone)
+    }
+
     fn refresh_sessions(ctx: Arc<RwLock<Win32Context>>) {
         debug!("Refreshing sessions");
 
+        let known_pids = ctx
+            .read()
+            .sessions
+            .read()
+            .iter()
+            .map(|session| session.process_id())
+            .collect::<HashSet<_>>();
+
         let mut sessions = Vec::new();
 
         for device in ctx
@@ -211,6 +438,10 @@ impl Win32Context {
                                     node.active = state == SessionState::Active;
                                     node.present = state != SessionState::Expired;
                                 }
+
+                                if state == SessionState::Expired {
+                                    Self::refresh_sessions(ctx.clone());
+                                }
                             }
                             AudioSessionEvent::Disconnect(reason) => {
                                 trace!("Session disconnected. Reason: {:?}", reason);
@@ -228,7 +459,60 @@ impl Win32Context {
             }
         }
 
-        ctx.write().sessions = Arc::new(RwLock::new(sessions));
+        let my_pid = unsafe { GetCurrentProcessId() };
+        let mut new_pids = HashSet::new();
+
+        let new_processes = sessions
+            .iter()
+            .filter(|s| s.kind() == AudioSessionKind::Application && s.process_id() != my_pid)
+            .filter(|s| !known_pids.contains(&s.process_id()) && new_pids.insert(s.process_id()))
+            .map(|s| ProcessInfo {
+                pid: s.process_id(),
+                display_name: s.display_name().to_string(),
+                filename: s.filename().to_string(),
+            })
+            .collect::<Vec<_>>();
+
+        let mut ctx = ctx.write();
+
+        let mut released_nodes = Vec::new();
+
+        for node in ctx
+            .nodes
+            .iter_mut()
+            .filter(|n| n.kind == NodeKind::Application)
+        {
+            let process_id = sessions
+                .iter()
+                .find(|s| {
+                    s.kind() == AudioSessionKind::Application
+                        && s.display_name() == node.display_name
+                        && s.filename() == node.filename
+                })
+                .map(|s| s.process_id());
+
+            if node.process_id != process_id {
+                debug!(
+                    "Process of {} changed from {:?} to {:?}",
+                    node.display_name, node.process_id, process_id
+                );
+
+                if node.process_id.is_some() {
+                    released_nodes.push(node.id);
+                }
+
+                node.process_id = process_id;
+            }
+        }
+
+        ctx.sessions = Arc::new(RwLock::new(sessions));
+        ctx.new_processes.extend(new_processes);
+
+        for node_id in released_nodes {
+            ctx.release_process_connections(node_id);
+        }
+
+        ctx.reconcile_connections();
     }
 
     fn parse_mmdevice_id(mmdevice_id: &str) -> Option<(Uuid, EDataFlow)> {
@@ -289,6 +573,250 @@ impl Win32Context {
         }
     }
 
+    fn endpoint_available(&self, id: Uuid) -> bool {
+        match self.nodes.iter().find(|n| n.id == id) {
+            Some(node) if node.kind == NodeKind::Application => node.process_id.is_some(),
+            Some(node)
+                if matches!(
+                    node.kind,
+                    NodeKind::Recorder
+                        | NodeKind::FilePlayer
+                        | NodeKind::Generator
+                        | NodeKind::NetworkSender
+                        | NodeKind::NetworkReceiver
+                ) =>
+            {
+                true
+            }
+            _ => {
+                let device_id = self.resolve_device_id(id);
+
+                self.input_devices
+                    .read()
+                    .iter()
+                    .chain(self.output_devices.read().iter())
+                    .any(|d| Some(d.id()) == device_id && d.is_active())
+            }
+        }
+    }
+
+    /// Forgets the applied connections of an application node whose process has exited or
+    /// restarted, as the routes were bound to the old process id. The connections are
+    /// re-applied once the node has a process again.
+    fn release_process_connections(&mut self, node_id: Uuid) {
+        self.node_connections.retain(|conn| conn.src_id != node_id);
+        self.loopback_sessions
+            .write()
+            .retain(|s| s.src_id != node_id);
+        self.recording_sessions.retain(|s| s.src_id != node_id);
+
+        for conn in self.connections.of_node(node_id) {
+            if conn.src_id == node_id && conn.state == ConnectionState::Active {
+                self.connections
+                    .set_state(conn.src_id, conn.dst_id, ConnectionState::Pending);
+            }
+        }
+    }
+
+    /// Suspends the connections whose devices have gone away, and applies the pending
+    /// connections whose devices are available again.
+    fn reconcile_connections(&mut self) {
+        let reconciliation = self.connections.reconcile(|id| self.endpoint_available(id));
+
+        for (src_id, dst_id) in reconciliation.suspend {
+            info!("Suspending connection {} => {}", src_id, dst_id);
+
+            self.remove_node_connection(src_id, dst_id);
+            self.connections
+                .set_state(src_id, dst_id, ConnectionState::Pending);
+        }
+
+        for (src_id, dst_id) in reconciliation.apply {
+            match self.apply_connection(src_id, dst_id) {
+                Ok(()) => {
+                    info!("Restored connection {} => {}", src_id, dst_id);
+                    self.connections
+                        .set_state(src_id, dst_id, ConnectionState::Active);
+                }
+                Err(err) => {
+                    warn!(
+                        "Could not restore connection {} => {}: {}",
+                        src_id, dst_id, err
+                    );
+                }
+            }
+        }
+    }
+
+    fn apply_connection(&mut self, node_id: Uuid, target_id: Uuid) -> Result<()> {
+        let node_kind = match self.nodes.iter().find(|n| n.id == node_id) {
+            Some(node) => node.kind,
+            None => return Err(Error::CouldNotConnect("No such node found".to_string())),
+        };
+
+        if self.recorder(target_id).is_some() {
+            return self.connect_recorder(node_id, target_id);
+        }
+
+        if self.network_sender(target_id).is_some() {
+            return self.connect_network_sender(node_id, target_id);
+        }
+
+        match node_kind {
+            NodeKind::Application => self.connect_application_node(node_id, target_id),
+            NodeKind::InputDevice => self.connect_input_device(node_id, target_id),
+            NodeKind::FilePlayer | NodeKind::Generator | NodeKind::NetworkReceiver => {
+                self.connect_playback(node_id, target_id)
+            }
+            NodeKind::OutputDevice => Err(Error::CouldNotConnect(
+                "Output device cannot be used as an input!".to_string(),
+            )),
+            NodeKind::Recorder => Err(Error::CouldNotConnect(
+                "Recorder cannot be used as an input!".to_string(),
+            )),
+            NodeKind::NetworkSender => Err(Error::CouldNotConnect(
+                "Network sender cannot be used as an input!".to_string(),
+            )),
+            NodeKind::VirtualMicrophone => Err(Error::CouldNotConnect(
+                "Virtual microphone cannot be used as an input!".to_string(),
+            )),
+        }
+    }
+
+    /// Restarts a connection whose stream Nodio renders itself, to apply its channel matrix and
+    /// delay.
+    fn restart_connection(&mut self, node_id: Uuid, target_id: Uuid) {
+        let rendered = self.node_connections.iter().any(|conn| {
+            conn.src_id == node_id
+                && conn.dst_id == target_id
+                && matches!(
+                    conn.kind,
+                    NodeConnectionKind::Loopback
+                        | NodeConnectionKind::Play
+                        | NodeConnectionKind::Record
+                        | NodeConnectionKind::Listen
+                )
+        });
+
+        if rendered {
+            self.remove_node_connection(node_id, target_id);
+
+            if let Err(err) = self.apply_connection(node_id, target_id) {
+                warn!(
+                    "Could not restart connection {} => {}: {}",
+                    node_id, target_id, err
+                );
+                self.connections
+                    .set_state(node_id, target_id, ConnectionState::Pending);
+            }
+        }
+    }
+
+    /// Removes an applied connection from the system.
+    fn remove_node_connection(&mut self, src_id: Uuid, dst_id: Uuid) {
+        let removed_connection = match self
+            .node_connections
+            .iter()
+            .position(|conn| conn.src_id == src_id && conn.dst_id == dst_id)
+            .map(|idx| self.node_connections.remove(idx))
+        {
+            Some(conn) => conn,
+            None => {
+                warn!("No such connection found");
+                return;
+            }
+        };
+
+        info!("Removed connection {} => {}", src_id, dst_id);
+
+        if removed_connection.kind == NodeConnectionKind::Record {
+            self.recording_sessions
+                .retain(|s| s.src_id != src_id || s.dst_id != dst_id);
+            return;
+        }
+
+        if matches!(
+            removed_connection.kind,
+            NodeConnectionKind::Play | NodeConnectionKind::Listen
+        ) {
+            if let Some(targets) = self.render_targets(src_id) {
+                targets.remove(dst_id);
+            }
+            // An input device is captured for as long as it is listened to
+            self.listens
+                .retain(|(_, listen)| !listen.playback().targets().is_empty());
+            return;
+        }
+
+        let node = match self.nodes.iter().find(|node| node.id == src_id) {
+            Some(node) => node,
+            None => {
+                warn!("No such node found");
+                return;
+            }
+        };
+
+        match node.kind {
+            NodeKind::Application => {
+                if node.process_id.is_none() {
+                    return;
+                }
+
+                match removed_connection.kind {
+                    NodeConnectionKind::DefaultEndpoint => {
+                        let next_src_connection = self.node_connections.iter_mut().find(|conn| {
+                            conn.src_id == src_id && conn.kind != NodeConnectionKind::Record
+                        });
+
+                        if let Some(next_conn) = next_src_connection {
+                            if next_conn.kind == NodeConnectionKind::Loopback {
+                                self.loopback_sessions.write().retain(|s| {
+                                    s.src_id != next_conn.src_id || s.dst_id != next_conn.dst_id
+                                });
+                            }
+
+                            next_conn.kind = NodeConnectionKind::DefaultEndpoint;
+
+                            let next_device_id = self.resolve_device_id(next_conn.dst_id);
+                            let target_mmdevice_id = self
+                                .output_devices
+                                .read()
+                                .iter()
+                                .find(|d| Some(d.id()) == next_device_id)
+                                .map(|d| d.mmdevice_id(eRender));
+
+                            match target_mmdevice_id {
+                                Some(target_mmdevice_id) => self
+                                    .set_default_audio_endpoint_for_process(
+                                        node.process_id.unwrap(),
+                                        target_mmdevice_id,
+                                    )
+                                    .ok(),
+                                None => self
+                                    .use_system_default_audio_endpoint_for_process(
+                                        node.process_id.unwrap(),
+                                    )
+                                    .ok(),
+                            };
+                        } else {
+                            self.use_system_default_audio_endpoint_for_process(
+                                node.process_id.unwrap(),
+                            )
+                            .ok();
+                        }
+                    }
+                    NodeConnectionKind::Loopback => {
+                        self.loopback_sessions
+                            .write()
+                            .retain(|s| s.src_id != src_id || s.dst_id != dst_id);
+                    }
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+    }
+
     fn connect_application_node(&mut self, node_id: Uuid, target_id: Uuid) -> Result<()> {
         let node = self.nodes.iter().find(|n| n.id == node_id).unwrap();
 
@@ -296,8 +824,12 @@ impl Win32Context {
             return Err(Error::CouldNotConnect("No such process".to_string()));
         }
 
+        let target_device_id = self.resolve_device_id(target_id);
         let output_devices = self.output_devices.read();
-        let target_device = output_devices.iter().find(|d| d.id() == target_id).unwrap();
+        let target_device = output_devices
+            .iter()
+            .find(|d| Some(d.id()) == target_device_id)
+            .ok_or(Error::NoSuchDevice)?;
 
         let mut conn_info = NodeConnectionInfo {
             id: Uuid::new_v4(),
@@ -309,7 +841,7 @@ impl Win32Context {
         if self
             .node_connections
             .iter()
-            .any(|conn| conn.src_id == node_id)
+            .any(|conn| conn.src_id == node_id && conn.kind != NodeConnectionKind::Record)
         {
             info!("Already connected, using loopback for stream duplication");
 
@@ -318,6 +850,9 @@ impl Win32Context {
                 target_id,
                 node.process_id.unwrap(),
                 target_device.mmdevice(),
+                &self.connection_effects(node_id, target_id),
+                self.link_matrix(node_id, target_id).as_ref(),
+                self.connections.delay(node_id, target_id),
             )
             .map_err(|err| {
                 error!("Could not start loopback session: {}", err);
@@ -371,9 +906,18 @@ impl Win32Context {
         Ok(())
     }
 
+    /// Plays an input device on an output device. The device is captured and played by Nodio
+    /// rather than listened to in Windows, so that the effects apply.
     fn connect_input_device(&mut self, node_id: Uuid, target_id: Uuid) -> Result<()> {
-        let input_devices = self.input_devices.write();
-        let output_devices = self.output_devices.read();
+        let target_device_id = self.resolve_device_id(target_id);
+        let channel_matrix = self.link_matrix(node_id, target_id);
+        let delay_ms = self.connections.delay(node_id, target_id);
+        let effects = self.connection_effects(node_id, target_id);
+
+        let input_devices = self.input_devices.clone();
+        let input_devices = input_devices.read();
+        let output_devices = self.output_devices.clone();
+        let output_devices = output_devices.read();
 
         let input_device = input_devices
             .iter()
@@ -382,15 +926,40 @@ impl Win32Context {
 
         let output_device = output_devices
             .iter()
-            .find(|device| device.id() == target_id)
+            .find(|device| Some(device.id()) == target_device_id)
             .ok_or_else(|| Error::CouldNotConnect("no such output device found".to_string()))?;
 
-        if let Err(err) = input_device.set_listen(Some(output_device)) {
-            warn!(
-                "Failed to enable listening on device {}: {}",
-                input_device.name(),
-                err
-            );
+        if self.listen(node_id).is_none() {
+            // Listening in Windows would play the device a second time, without the effects
+            if let Err(err) = input_device.set_listen(None) {
+                warn!(
+                    "Failed to disable listening on device {}: {}",
+                    input_device.name(),
+                    err
+                );
+            }
+
+            let listen = Listen::start(node_id, input_device.mmdevice()).map_err(|err| {
+                error!("Could not start device capture: {}", err);
+                Error::CouldNotConnect(err.to_string())
+            })?;
+            self.listens.push((node_id, listen));
+        }
+
+        let added = self.listen(node_id).map(|listen| {
+            listen.playback().targets().add(
+                target_id,
+                output_device.mmdevice(),
+                channel_matrix,
+                delay_ms,
+                &effects,
+            )
+        });
+
+        if let Some(Err(err)) = added {
+            error!("Could not start playback: {}", err);
+            self.listens
+                .retain(|(_, listen)| !listen.playback().targets().is_empty());
             return Err(Error::CouldNotConnect(err.to_string()));
         }
 
@@ -404,17 +973,380 @@ impl Win32Context {
         Ok(())
     }
 
-    fn output_device_exists(&self, id: Uuid) -> bool {
-        self.output_devices.read().iter().any(|d| d.id() == id)
-    }
-}
+    /// Captures the audio of a source node into a recorder.
+    fn connect_recorder(&mut self, node_id: Uuid, target_id: Uuid) -> Result<()> {
+        let recorder = self.recorder(target_id).ok_or(Error::NoSuchDevice)?;
+        let node = self.nodes.iter().find(|n| n.id == node_id).unwrap();
 
-impl Context for Win32Context {
-    fn add_node(&mut self, mut node: Node) {
-        if self.nodes.iter().any(|other| other.id == node.id) {
-            info!("Node already added: {}", &node.display_name);
-            return;
-        }
+        // Recordings are named after their source
+        recorder.lock().set_name(&node.display_name);
+
+        // Files are encoded and written on a thread of their own, not on the capture thread
+        let channels = RECORDING_CHANNELS as usize;
+        let capacity = (RECORDING_SAMPLE_RATE as f64 * RECORDING_BUFFER_SECS) as usize * channels;
+        let sink = BufferedSink::new(recorder, channels, capacity);
+
+        self.start_capture(node_id, target_id, Arc::new(Mutex::new(sink)))
+    }
+
+    /// Sends the audio of an application or device to the network.
+    fn connect_network_sender(&mut self, node_id: Uuid, target_id: Uuid) -> Result<()> {
+        let sender = self.network_sender(target_id).ok_or(Error::NoSuchDevice)?;
+
+        self.start_capture(node_id, target_id, sender)
+    }
+
+    /// Captures the audio of an application, input device or output device into a recorder or
+    /// network sender.
+    fn start_capture<S: AudioSink + Send + 'static>(
+        &mut self,
+        node_id: Uuid,
+        target_id: Uuid,
+        sink: Arc<Mutex<S>>,
+    ) -> Result<()> {
+        let node = self.nodes.iter().find(|n| n.id == node_id).unwrap();
+        let channel_matrix = self.link_matrix(node_id, target_id);
+        let delay_ms = self.connections.delay(node_id, target_id);
+        let effects = self.connection_effects(node_id, target_id);
+
+        let recording_session = match node.kind {
+            NodeKind::Application => {
+                let process_id = node
+                    .process_id
+                    .ok_or_else(|| Error::CouldNotConnect("No such process".to_string()))?;
+
+                RecordingSession::application(
+                    node_id,
+                    target_id,
+                    process_id,
+                    sink,
+                    channel_matrix,
+                    delay_ms,
+                    &effects,
+                )
+            }
+            NodeKind::InputDevice => {
+                let input_devices = self.input_devices.read();
+                let input_device = input_devices
+                    .iter()
+                    .find(|device| device.id() == node_id)
+                    .ok_or_else(|| {
+                        Error::CouldNotConnect("no such input device found".to_string())
+                    })?;
+
+                RecordingSession::device(
+                    node_id,
+                    target_id,
+                    input_device.mmdevice(),
+                    sink,
+                    channel_matrix,
+                    delay_ms,
+                    &effects,
+                )
+                .map_err(|err| {
+                    error!("Could not start device capture: {}", err);
+                    Error::CouldNotConnect(err.to_string())
+                })?
+            }
+            NodeKind::OutputDevice => {
+                let device_id = self.resolve_device_id(node_id);
+                let output_devices = self.output_devices.read();
+                let output_device = output_devices
+                    .iter()
+                    .find(|device| Some(device.id()) == device_id)
+                    .ok_or_else(|| {
+                        Error::CouldNotConnect("no such output device found".to_string())
+                    })?;
+
+                RecordingSession::output_device(
+                    node_id,
+                    target_id,
+                    output_device.mmdevice(),
+                    sink,
+                    channel_matrix,
+                    delay_ms,
+                    &effects,
+                )
+                .map_err(|err| {
+                    error!("Could not start loopback capture: {}", err);
+                    Error::CouldNotConnect(err.to_string())
+                })?
+            }
+            _ => {
+                return Err(Error::CouldNotConnect(
+                    "Only applications and devices can be captured".to_string(),
+                ))
+            }
+        };
+
+        self.recording_sessions.push(recording_session);
+        self.node_connections.push(NodeConnectionInfo {
+            id: Uuid::new_v4(),
+            src_id: node_id,
+            dst_id: target_id,
+            kind: NodeConnectionKind::Record,
+        });
+
+        Ok(())
+    }
+
+    /// Renders a file player, generator or network receiver node to an output device.
+    fn connect_playback(&mut self, node_id: Uuid, target_id: Uuid) -> Result<()> {
+        let target_device_id = self.resolve_device_id(target_id);
+
+        let output_devices = self.output_devices.read();
+        let output_device = output_devices
+            .iter()
+            .find(|device| Some(device.id()) == target_device_id)
+            .ok_or_else(|| Error::CouldNotConnect("no such output device found".to_string()))?;
+
+        let channel_matrix = self.link_matrix(node_id, target_id);
+        let delay_ms = self.connections.delay(node_id, target_id);
+        let effects = self.connection_effects(node_id, target_id);
+        let added = match self.render_targets(node_id) {
+            Some(targets) => targets.add(
+                target_id,
+                output_device.mmdevice(),
+                channel_matrix,
+                delay_ms,
+                &effects,
+            ),
+            None => {
+                return Err(Error::CouldNotConnect(
+                    "No playback found for this node".to_string(),
+                ))
+            }
+        };
+
+        added.map_err(|err| {
+            error!("Could not start playback: {}", err);
+            Error::CouldNotConnect(err.to_string())
+        })?;
+
+        drop(output_devices);
+
+        self.node_connections.push(NodeConnectionInfo {
+            id: Uuid::new_v4(),
+            src_id: node_id,
+            dst_id: target_id,
+            kind: NodeConnectionKind::Play,
+        });
+
+        Ok(())
+    }
+
+    fn playback(&self, node_id: Uuid) -> Option<&Playback<FilePlayer>> {
+        self.playbacks
+            .iter()
+            .find(|(id, _)| *id == node_id)
+            .map(|(_, playback)| playback)
+    }
+
+    fn network_receiver(&self, node_id: Uuid) -> Option<&Playback<RtpReceiver>> {
+        self.network_receivers
+            .iter()
+            .find(|(id, _)| *id == node_id)
+            .map(|(_, receiver)| receiver)
+    }
+
+    fn network_sender(&self, node_id: Uuid) -> Option<Arc<Mutex<RtpSender>>> {
+        self.network_senders
+            .iter()
+            .find(|(id, _)| *id == node_id)
+            .map(|(_, sender)| sender.clone())
+    }
+
+    fn generator(&self, node_id: Uuid) -> Option<&Playback<SignalGenerator>> {
+        self.generators
+            .iter()
+            .find(|(id, _)| *id == node_id)
+            .map(|(_, generator)| generator)
+    }
+
+    fn listen(&self, node_id: Uuid) -> Option<&Listen> {
+        self.listens
+            .iter()
+            .find(|(id, _)| *id == node_id)
+            .map(|(_, listen)| listen)
+    }
+
+    /// Output devices of a node that Nodio plays itself
+    fn render_targets(&self, node_id: Uuid) -> Option<&RenderTargets> {
+        if let Some(playback) = self.playback(node_id) {
+            Some(playback.targets())
+        } else if let Some(generator) = self.generator(node_id) {
+            Some(generator.targets())
+        } else if let Some(receiver) = self.network_receiver(node_id) {
+            Some(receiver.targets())
+        } else {
+            self.listen(node_id)
+                .map(|listen| listen.playback().targets())
+        }
+    }
+
+    /// Effects the audio of a connection passes through, those of the source node first.
+    fn connection_effects(&self, node_id: Uuid, target_id: Uuid) -> Vec<EffectConfig> {
+        let node_effects = self
+            .nodes
+            .iter()
+            .filter(|n| n.id == node_id && self.applies_node_effects(n.id))
+            .flat_map(|n| n.effects.iter());
+
+        node_effects
+            .chain(self.connections.effects(node_id, target_id))
+            .cloned()
+            .collect()
+    }
+
+    /// Updates the effects of the applied connections of a node after its effects or the
+    /// effects of one of its links changed.
+    fn update_connection_effects(&self, node_id: Uuid) {
+        for conn in self
+            .node_connections
+            .iter()
+            .filter(|conn| conn.src_id == node_id)
+        {
+            let effects = self.connection_effects(conn.src_id, conn.dst_id);
+
+            match conn.kind {
+                NodeConnectionKind::Loopback => {
+                    for session in self.loopback_sessions.read().iter().filter(|session| {
+                        session.src_id == conn.src_id && session.dst_id == conn.dst_id
+                    }) {
+                        session.set_effects(&effects);
+                    }
+                }
+                NodeConnectionKind::Record => {
+                    for session in self.recording_sessions.iter().filter(|session| {
+                        session.src_id == conn.src_id && session.dst_id == conn.dst_id
+                    }) {
+                        session.set_effects(&effects);
+                    }
+                }
+                NodeConnectionKind::Play | NodeConnectionKind::Listen => {
+                    if let Some(targets) = self.render_targets(conn.src_id) {
+                        targets.set_effects(conn.dst_id, &effects);
+                    }
+                }
+                NodeConnectionKind::DefaultEndpoint => {}
+            }
+        }
+    }
+
+    /// Changes a parameter of an effect on the applied connections of a node, or on one of
+    /// them.
+    fn set_connection_effect_param(
+        &self,
+        node_id: Uuid,
+        target_id: Option<Uuid>,
+        effect_id: Uuid,
+        param_idx: usize,
+        value: f32,
+    ) {
+        for conn in self.node_connections.iter().filter(|conn| {
+            conn.src_id == node_id && (target_id.is_none() || target_id == Some(conn.dst_id))
+        }) {
+            match conn.kind {
+                NodeConnectionKind::Loopback => {
+                    for session in self.loopback_sessions.read().iter().filter(|session| {
+                        session.src_id == conn.src_id && session.dst_id == conn.dst_id
+                    }) {
+                        session.set_effect_param(effect_id, param_idx, value);
+                    }
+                }
+                NodeConnectionKind::Record => {
+                    for session in self.recording_sessions.iter().filter(|session| {
+                        session.src_id == conn.src_id && session.dst_id == conn.dst_id
+                    }) {
+                        session.set_effect_param(effect_id, param_idx, value);
+                    }
+                }
+                NodeConnectionKind::Play | NodeConnectionKind::Listen => {
+                    if let Some(targets) = self.render_targets(conn.src_id) {
+                        targets.set_effect_param(conn.dst_id, effect_id, param_idx, value);
+                    }
+                }
+                NodeConnectionKind::DefaultEndpoint => {}
+            }
+        }
+    }
+
+    /// States of the effects of an applied connection, by effect id
+    fn connection_effect_states(&self, conn: &NodeConnectionInfo) -> Vec<(Uuid, Vec<u8>)> {
+        match conn.kind {
+            NodeConnectionKind::Loopback => self
+                .loopback_sessions
+                .read()
+                .iter()
+                .filter(|session| session.src_id == conn.src_id && session.dst_id == conn.dst_id)
+                .flat_map(|session| session.save_effect_states())
+                .collect(),
+            NodeConnectionKind::Record => self
+                .recording_sessions
+                .iter()
+                .filter(|session| session.src_id == conn.src_id && session.dst_id == conn.dst_id)
+                .flat_map(|session| session.save_effect_states())
+                .collect(),
+            NodeConnectionKind::Play | NodeConnectionKind::Listen => self
+                .render_targets(conn.src_id)
+                .map(|targets| targets.save_effect_states(conn.dst_id))
+                .unwrap_or_default(),
+            NodeConnectionKind::DefaultEndpoint => Vec::new(),
+        }
+    }
+
+    fn recorder(&self, node_id: Uuid) -> Option<Arc<Mutex<Recorder>>> {
+        self.recorders
+            .iter()
+            .find(|(id, _)| *id == node_id)
+            .map(|(_, recorder)| recorder.clone())
+    }
+
+    fn output_device_exists(&self, id: Uuid) -> bool {
+        DefaultDevice::from_id(id).is_some()
+            || self.output_devices.read().iter().any(|d| d.id() == id)
+            || self
+                .nodes
+                .iter()
+                .any(|n| n.id == id && n.kind == NodeKind::OutputDevice)
+            || self.virtual_mic(id).is_some()
+    }
+
+    /// The nodes of the ports at the ends of a link. Audio is routed between nodes.
+    fn link_nodes(&self, port_id: Uuid, target_port_id: Uuid) -> (Uuid, Uuid) {
+        (
+            port_node_id(&self.nodes, port_id),
+            port_node_id(&self.nodes, target_port_id),
+        )
+    }
+
+    /// Channel matrix of the connection between two nodes, fitted to the port it is linked from.
+    fn link_matrix(&self, node_id: Uuid, target_id: Uuid) -> Option<ChannelMatrix> {
+        let channel_matrix = self.connections.channel_matrix(node_id, target_id);
+
+        let port = self
+            .link_ports
+            .iter()
+            .find(|&&(port_id, target_port_id)| {
+                self.link_nodes(port_id, target_port_id) == (node_id, target_id)
+            })
+            .and_then(|&(port_id, _)| find_port(&self.nodes, port_id));
+
+        match port {
+            Some((_, port)) => port.link_matrix(channel_matrix),
+            None => channel_matrix.cloned(),
+        }
+    }
+}
+
+impl Context for Win32Context {
+    fn add_node(&mut self, mut node: Node) {
+        if self.nodes.iter().any(|other| other.id == node.id) {
+            info!("Node already added: {}", &node.display_name);
+            return;
+        }
+
+        node.add_missing_ports();
 
         if let Some(session) = self
             .sessions
@@ -425,22 +1357,84 @@ impl Context for Win32Context {
             node.process_id = Some(session.process_id());
         }
 
+        if node.kind == NodeKind::Recorder {
+            let recorder = Recorder::new(
+                node.recorder.clone().unwrap_or_default(),
+                &node.display_name,
+                RECORDING_SAMPLE_RATE,
+                RECORDING_CHANNELS,
+            );
+            self.recorders
+                .push((node.id, Arc::new(Mutex::new(recorder))));
+            node.present = true;
+        }
+
+        if node.kind == NodeKind::FilePlayer {
+            let player = FilePlayer::new(
+                node.player.clone().unwrap_or_default(),
+                PLAYBACK_SAMPLE_RATE,
+                PLAYBACK_CHANNELS as usize,
+            );
+            self.playbacks.push((node.id, Playback::new(player)));
+            node.present = true;
+        }
+
+        if node.kind == NodeKind::Generator {
+            let generator = SignalGenerator::new(
+                node.generator.clone().unwrap_or_default(),
+                PLAYBACK_SAMPLE_RATE,
+                PLAYBACK_CHANNELS as usize,
+            );
+            self.generators.push((node.id, Playback::new(generator)));
+            node.present = true;
+        }
+
+        if node.kind == NodeKind::NetworkSender {
+            let sender = RtpSender::new(
+                node.network.clone().unwrap_or_default(),
+                &node.display_name,
+                RECORDING_SAMPLE_RATE,
+                RECORDING_CHANNELS as usize,
+            );
+            self.network_senders
+                .push((node.id, Arc::new(Mutex::new(sender))));
+            node.present = true;
+        }
+
+        if node.kind == NodeKind::NetworkReceiver {
+            let receiver = RtpReceiver::new(
+                node.network.clone().unwrap_or_default(),
+                PLAYBACK_SAMPLE_RATE,
+                PLAYBACK_CHANNELS as usize,
+            );
+            self.network_receivers
+                .push((node.id, Playback::new(receiver)));
+            node.present = true;
+        }
+
         self.nodes.push(node);
     }
 
     fn remove_node(&mut self, node_id: Uuid) {
-        let connections = self
-            .node_connections
+        let links = self
+            .link_ports
             .iter()
-            .filter(|conn| conn.src_id == node_id || conn.dst_id == node_id)
             .copied()
+            .filter(|&(port_id, target_port_id)| {
+                let (src_id, dst_id) = self.link_nodes(port_id, target_port_id);
+                src_id == node_id || dst_id == node_id
+            })
             .collect::<Vec<_>>();
-
-        for conn in connections {
-            self.disconnect_node(conn.src_id, conn.dst_id);
+        for (port_id, target_port_id) in links {
+            self.disconnect_node(port_id, target_port_id);
         }
 
         self.nodes.retain(|node| node.id != node_id);
+        self.recorders.retain(|(id, _)| *id != node_id);
+        self.playbacks.retain(|(id, _)| *id != node_id);
+        self.generators.retain(|(id, _)| *id != node_id);
+        self.network_senders.retain(|(id, _)| *id != node_id);
+        self.network_receivers.retain(|(id, _)| *id != node_id);
     }
 
     fn nodes(&self) -> &[Node] {
@@ -451,7 +1445,15 @@ impl Context for Win32Context {
         &mut self.nodes
     }
 
-    fn connect_node(&mut self, node_id: Uuid, target_id: Uuid) -> Result<()> {
+    fn connect_node(&mut self, port_id: Uuid, target_port_id: Uuid) -> Result<()> {
+        if let (Some((_, port)), Some((_, target_port))) = (
+            find_port(&self.nodes, port_id),
+            find_port(&self.nodes, target_port_id),
+        ) {
+            port.check_link(target_port)?;
+        }
+        let (node_id, target_id) = self.link_nodes(port_id, target_port_id);
+
         let node_kind = match self.nodes.iter().find(|n| n.id == node_id) {
             Some(node) => node.kind,
             None => {
@@ -460,123 +1462,291 @@ impl Context for Win32Context {
             }
         };
 
-        if !self.output_device_exists(target_id) {
+        // Recorders and network senders take the audio of a single source
+        let target_sink = if self.recorder(target_id).is_some() {
+            Some("Recorder")
+        } else if self.network_sender(target_id).is_some() {
+            Some("Network sender")
+        } else {
+            None
+        };
+
+        if target_sink.is_none() && !self.output_device_exists(target_id) {
             warn!("No output device found for node id: {}", target_id);
             return Err(Error::NoSuchDevice);
         }
 
         match node_kind {
-            NodeKind::Application => self.connect_application_node(node_id, target_id)?,
-            NodeKind::InputDevice => self.connect_input_device(node_id, target_id)?,
-
-            NodeKind::OutputDevice => {
-                warn!("Output device cannot be used as an input!");
+            // What an output device plays is only captured into recorders and network senders
+            NodeKind::OutputDevice if target_sink.is_none() => {
+                warn!("Output device can only be recorded or sent to the network");
+                return Err(Error::CouldNotConnect(
+                    "Output device can only be recorded or sent to the network".to_string(),
+                ));
+            }
+            NodeKind::Recorder => {
+                return Err(Error::CouldNotConnect(
+                    "Recorder cannot be used as an input!".to_string(),
+                ));
+            }
+            NodeKind::NetworkSender => {
                 return Err(Error::CouldNotConnect(
-                    "Output device cannot be used as an input!".to_string(),
+                    "Network sender cannot be used as an input!".to_string(),
                 ));
             }
+            NodeKind::VirtualMicrophone => {
+                return Err(Error::CouldNotConnect(
+                    "Virtual microphone cannot be used as an input!".to_string(),
+                ));
+            }
+            _ => {}
         }
 
-        Ok(())
-    }
-
-    fn disconnect_node(&mut self, src_id: Uuid, dst_id: Uuid) {
-        let removed_connection = match self
-            .node_connections
+        if self
+            .link_ports
             .iter()
-            .position(|conn| conn.src_id == src_id && conn.dst_id == dst_id)
-            .map(|idx| self.node_connections.remove(idx))
+            .any(|&(other_port_id, other_target_port_id)| {
+                (other_port_id, other_target_port_id) != (port_id, target_port_id)
+                    && self.link_nodes(other_port_id, other_target_port_id) == (node_id, target_id)
+            })
         {
-            Some(conn) => conn,
-            None => {
-                warn!("No such connection found");
-                return;
+            return Err(Error::CouldNotConnect(
+                "The nodes are linked already through other ports".to_string(),
+            ));
+        }
+
+        if let Some(sink) = target_sink {
+            if self
+                .connections
+                .of_node(target_id)
+                .iter()
+                .any(|conn| conn.dst_id == target_id && conn.src_id != node_id)
+            {
+                return Err(Error::CouldNotConnect(format!(
+                    "{} already has a source",
+                    sink
+                )));
             }
-        };
+        }
 
-        info!("Removed connection {} => {}", src_id, dst_id);
+        // Known before the connection is applied, which fits the audio to the port
+        let linked = self.link_ports.contains(&(port_id, target_port_id));
+        if !linked {
+            self.link_ports.push((port_id, target_port_id));
+        }
 
-        let node = match self.nodes.iter().find(|node| node.id == src_id) {
-            Some(node) => node,
-            None => {
-                warn!("No such node found");
-                return;
+        if self.endpoint_available(node_id) && self.endpoint_available(target_id) {
+            if let Err(err) = self.apply_connection(node_id, target_id) {
+                if !linked {
+                    self.link_ports.pop();
+                }
+                return Err(err);
             }
-        };
+            self.connections
+                .insert(node_id, target_id, ConnectionState::Active);
+        } else {
+            info!(
+                "Node not available, connection {} => {} is pending",
+                node_id, target_id
+            );
+            self.connections
+                .insert(node_id, target_id, ConnectionState::Pending);
+        }
 
-        match node.kind {
-            NodeKind::Application => {
-                if node.process_id.is_none() {
-                    return;
-                }
+        Ok(())
+    }
 
-                match removed_connection.kind {
-                    NodeConnectionKind::DefaultEndpoint => {
-                        let next_src_connection = self
-                            .node_connections
-                            .iter_mut()
-                            .find(|conn| conn.src_id == src_id);
+    fn disconnect_node(&mut self, port_id: Uuid, target_port_id: Uuid) {
+        // The connection of the nodes may be linked from other ports
+        if !self.link_ports.contains(&(port_id, target_port_id)) {
+            warn!("No such connection found");
+            return;
+        }
+        self.link_ports
+            .retain(|&link| link != (port_id, target_port_id));
 
-                        if let Some(next_conn) = next_src_connection {
-                            if next_conn.kind == NodeConnectionKind::Loopback {
-                                self.loopback_sessions.write().retain(|s| {
-                                    s.src_id != next_conn.src_id || s.dst_id != next_conn.dst_id
-                                });
-                            }
+        let (src_id, dst_id) = self.link_nodes(port_id, target_port_id);
+        match self.connections.remove(src_id, dst_id) {
+            Some(conn) if conn.state == ConnectionState::Pending => {
+                info!("Removed pending connection {} => {}", src_id, dst_id);
+            }
+            Some(_) => self.remove_node_connection(src_id, dst_id),
+            None => warn!("No such connection found"),
+        }
+    }
 
-                            next_conn.kind = NodeConnectionKind::DefaultEndpoint;
+    fn connection_state(&self, port_id: Uuid, target_port_id: Uuid) -> Option<ConnectionState> {
+        if !self.link_ports.contains(&(port_id, target_port_id)) {
+            return None;
+        }
 
-                            let target_mmdevice_id = self
-                                .output_devices
-                                .read()
-                                .iter()
-                                .find(|d| d.id() == next_conn.dst_id)
-                                .map(|d| d.mmdevice_id(eRender))
-                                .unwrap();
+        let (node_id, target_id) = self.link_nodes(port_id, target_port_id);
+        self.connections.state(node_id, target_id)
+    }
 
-                            self.set_default_audio_endpoint_for_process(
-                                node.process_id.unwrap(),
-                                target_mmdevice_id,
-                            )
-                            .ok();
-                        } else {
-                            self.use_system_default_audio_endpoint_for_process(
-                                node.process_id.unwrap(),
-                            )
-                            .ok();
-                        }
-                    }
-                    NodeConnectionKind::Loopback => {
-                        self.loopback_sessions
-                            .write()
-                            .retain(|s| s.src_id != src_id || s.dst_id != dst_id);
-                    }
-                    _ => {}
-                }
-            }
+    /// Restarts the stream of an applied connection, as it is set up for the channel matrix.
+    /// Connections that Windows routes itself are not mapped.
+    fn set_channel_matrix(
+        &mut self,
+        port_id: Uuid,
+        target_port_id: Uuid,
+        channel_matrix: Option<ChannelMatrix>,
+    ) {
+        let (node_id, target_id) = self.link_nodes(port_id, target_port_id);
+        self.connections
+            .set_channel_matrix(node_id, target_id, channel_matrix);
+        self.restart_connection(node_id, target_id);
+    }
 
-            NodeKind::InputDevice => {
-                if let Some(device) = self
-                    .input_devices
-                    .write()
-                    .iter_mut()
-                    .find(|device| device.id() == src_id)
-                {
-                    if let Err(err) = device.set_listen(None) {
-                        warn!(
-                            "Failed to enable listening on device {}: {}",
-                            &device.name(),
-                            err
-                        )
-                    }
-                } else {
-                    warn!("No input device found for id {}", src_id);
-                }
+    fn channel_matrix(&self, port_id: Uuid, target_port_id: Uuid) -> Option<ChannelMatrix> {
+        let (node_id, target_id) = self.link_nodes(port_id, target_port_id);
+        self.connections.channel_matrix(node_id, target_id).cloned()
+    }
+
+    /// Restarts the stream of an applied connection, as it is set up for the delay.
+    /// Connections that Windows routes itself are not delayed.
+    fn set_link_delay(&mut self, port_id: Uuid, target_port_id: Uuid, delay_ms: f32) {
+        let (node_id, target_id) = self.link_nodes(port_id, target_port_id);
+        self.connections.set_delay(node_id, target_id, delay_ms);
+        self.restart_connection(node_id, target_id);
+    }
+
+    fn link_delay(&self, port_id: Uuid, target_port_id: Uuid) -> f32 {
+        let (node_id, target_id) = self.link_nodes(port_id, target_port_id);
+        self.connections.delay(node_id, target_id)
+    }
+
+    /// Updates the effects of an applied connection in place, without restarting its stream.
+    fn set_link_effects(
+        &mut self,
+        port_id: Uuid,
+        target_port_id: Uuid,
+        effects: Vec<EffectConfig>,
+    ) {
+        let (node_id, target_id) = self.link_nodes(port_id, target_port_id);
+        self.connections.set_effects(node_id, target_id, effects);
+        self.update_connection_effects(node_id);
+    }
+
+    fn link_effects(&self, port_id: Uuid, target_port_id: Uuid) -> Vec<EffectConfig> {
+        let (node_id, target_id) = self.link_nodes(port_id, target_port_id);
+        self.connections.effects(node_id, target_id).to_vec()
+    }
+
+    fn set_link_effect_param(
+        &mut self,
+        port_id: Uuid,
+        target_port_id: Uuid,
+        effect_id: Uuid,
+        param_idx: usize,
+        value: f32,
+    ) {
+        let (node_id, target_id) = self.link_nodes(port_id, target_port_id);
+        self.set_connection_effect_param(node_id, Some(target_id), effect_id, param_idx, value);
+
+        if let Some(effect) = self
+            .connections
+            .effects_mut(node_id, target_id)
+            .and_then(|effects| effects.iter_mut().find(|effect| effect.id == effect_id))
+        {
+            effect.set_param(param_idx, value);
+        }
+    }
+
+    /// The first link of an application is routed by Windows, every other active link is
+    /// rendered by Nodio.
+    fn processes_link(&self, port_id: Uuid, target_port_id: Uuid) -> bool {
+        let (node_id, target_id) = self.link_nodes(port_id, target_port_id);
+        self.node_connections.iter().any(|conn| {
+            conn.src_id == node_id
+                && conn.dst_id == target_id
+                && conn.kind != NodeConnectionKind::DefaultEndpoint
+        })
+    }
+
+    /// Latency from the padding of the render buffers of the streams Nodio renders itself
+    fn link_latency(&self, port_id: Uuid, target_port_id: Uuid) -> Option<f32> {
+        let (node_id, target_id) = self.link_nodes(port_id, target_port_id);
+        let conn = self
+            .node_connections
+            .iter()
+            .find(|conn| conn.src_id == node_id && conn.dst_id == target_id)?;
+
+        match conn.kind {
+            NodeConnectionKind::Loopback => self
+                .loopback_sessions
+                .read()
+                .iter()
+                .find(|session| session.src_id == node_id && session.dst_id == target_id)?
+                .latency_ms(),
+            NodeConnectionKind::Play | NodeConnectionKind::Listen => {
+                self.render_targets(node_id)?.latency_ms(target_id)
             }
-            _ => {}
+            NodeConnectionKind::Record => Some(self.connections.delay(node_id, target_id)),
+            _ => None,
         }
     }
 
+    /// Plays the click on the output device and captures it with the input device
+    fn start_calibration(&mut self, target_id: Uuid, capture_id: Uuid) -> Result<()> {
+        self.calibration = None;
+
+        let target_device_id = self.resolve_device_id(target_id);
+        let output_devices = self.output_devices.read();
+        let output_device = output_devices
+            .iter()
+            .find(|device| Some(device.id()) == target_device_id)
+            .ok_or(Error::NoSuchDevice)?;
+
+        let input_devices = self.input_devices.read();
+        let input_device = input_devices
+            .iter()
+            .find(|device| device.id() == capture_id)
+            .ok_or(Error::NoSuchDevice)?;
+
+        let playback = Playback::new(Calibrator::new(
+            PLAYBACK_SAMPLE_RATE,
+            PLAYBACK_CHANNELS as usize,
+        ));
+
+        // Listen before the click is played
+        let recording_session = RecordingSession::device(
+            capture_id,
+            target_id,
+            input_device.mmdevice(),
+            playback.source().clone(),
+            None,
+            0.0,
+            &[],
+        )
+        .and_then(|recording_session| {
+            playback
+                .targets()
+                .add(target_id, output_device.mmdevice(), None, 0.0, &[])?;
+            Ok(recording_session)
+        })
+        .map_err(|err| {
+            error!("Could not start calibration: {}", err);
+            Error::Other(err.to_string())
+        })?;
+
+        drop(input_devices);
+        drop(output_devices);
+        self.calibration = Some((playback, recording_session));
+
+        Ok(())
+    }
+
+    fn calibration_status(&self) -> Option<CalibrationStatus> {
+        self.calibration
+            .as_ref()
+            .map(|(playback, _)| playback.source().lock().status())
+    }
+
+    fn stop_calibration(&mut self) {
+        self.calibration = None;
+    }
+
     fn set_volume(&mut self, node_id: Uuid, volume: f32) {
         if let Some(node) = self.nodes.iter().find(|n| n.id == node_id) {
             for matching_session in self
@@ -588,17 +1758,208 @@ impl Context for Win32Context {
                 matching_session.set_master_volume(volume);
             }
 
+            let device_id = self.resolve_device_id(node_id);
+
             for matching_device in self
                 .output_devices
                 .read()
                 .iter()
-                .filter(|device| device.id() == node_id)
+                .filter(|device| Some(device.id()) == device_id)
             {
                 matching_device.set_master_volume(volume);
             }
         }
     }
 
+    fn set_effects(&mut self, node_id: Uuid, effects: Vec<EffectConfig>) {
+        if let Some(node) = self.nodes.iter_mut().find(|n| n.id == node_id) {
+            node.effects = effects;
+        }
+
+        self.update_connection_effects(node_id);
+    }
+
+    /// The effects of an application are applied to the links Nodio renders, but not to the
+    /// first one, which Windows routes. Sinks take the audio of their links as it comes.
+    fn applies_node_effects(&self, node_id: Uuid) -> bool {
+        self.nodes.iter().any(|n| {
+            n.id == node_id
+                && !matches!(
+                    n.kind,
+                    NodeKind::OutputDevice
+                        | NodeKind::Recorder
+                        | NodeKind::NetworkSender
+                        | NodeKind::VirtualMicrophone
+                )
+        })
+    }
+
+    fn set_effect_param(&mut self, node_id: Uuid, effect_id: Uuid, param_idx: usize, value: f32) {
+        self.set_connection_effect_param(node_id, None, effect_id, param_idx, value);
+
+        if let Some(effect) = self
+            .nodes
+            .iter_mut()
+            .filter(|n| n.id == node_id)
+            .flat_map(|n| n.effects.iter_mut())
+            .find(|effect| effect.id == effect_id)
+        {
+            effect.set_param(param_idx, value);
+        }
+    }
+
+    fn store_effect_states(&mut self) {
+        let states = self
+            .node_connections
+            .iter()
+            .map(|conn| {
+                (
+                    conn.src_id,
+                    conn.dst_id,
+                    self.connection_effect_states(conn),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        for (src_id, dst_id, states) in states {
+            if let Some(node) = self.nodes.iter_mut().find(|n| n.id == src_id) {
+                apply_effect_states(&mut node.effects, &states);
+            }
+            if let Some(effects) = self.connections.effects_mut(src_id, dst_id) {
+                apply_effect_states(effects, &states);
+            }
+        }
+    }
+
+    fn set_recorder_config(&mut self, node_id: Uuid, config: RecorderConfig) {
+        if let Some(recorder) = self.recorder(node_id) {
+            if let Err(err) = recorder.lock().set_config(config.clone()) {
+                error!("Could not continue recording: {}", err);
+            }
+        }
+
+        if let Some(node) = self.nodes.iter_mut().find(|n| n.id == node_id) {
+            node.recorder = Some(config);
+        }
+    }
+
+    fn set_recorder_state(&mut self, node_id: Uuid, state: RecorderState) -> Result<()> {
+        self.recorder(node_id)
+            .ok_or_else(|| Error::Other("No such recorder".to_string()))?
+            .lock()
+            .set_state(state)
+    }
+
+    fn recorder_status(&self, node_id: Uuid) -> Option<RecorderStatus> {
+        self.recorder(node_id)
+            .map(|recorder| recorder.lock().status())
+    }
+
+    fn set_player_config(&mut self, node_id: Uuid, config: PlayerConfig) {
+        if let Some(playback) = self.playback(node_id) {
+            playback.source().lock().set_config(config.clone());
+        }
+
+        if let Some(node) = self.nodes.iter_mut().find(|n| n.id == node_id) {
+            node.player = Some(config);
+        }
+    }
+
+    fn player_command(&mut self, node_id: Uuid, command: PlayerCommand) -> Result<()> {
+        self.playback(node_id)
+            .ok_or_else(|| Error::Other("No such player".to_string()))?
+            .source()
+            .lock()
+            .command(command);
+
+        Ok(())
+    }
+
+    fn player_status(&self, node_id: Uuid) -> Option<PlayerStatus> {
+        self.playback(node_id)
+            .map(|playback| playback.source().lock().status())
+    }
+
+    fn play_clip(&mut self, node_id: Uuid, clip: &Clip) -> Result<()> {
+        if !std::path::Path::new(&clip.path).is_file() {
+            return Err(Error::Other(format!("No such file: {}", clip.path)));
+        }
+
+        self.playback(node_id)
+            .ok_or_else(|| Error::Other("No such player".to_string()))?
+            .source()
+            .lock()
+            .play_clip(clip);
+
+        Ok(())
+    }
+
+    fn stop_clips(&mut self, node_id: Uuid) {
+        if let Some(playback) = self.playback(node_id) {
+            playback.source().lock().stop_clips();
+        }
+    }
+
+    fn playing_clips(&self, node_id: Uuid) -> Vec<Uuid> {
+        self.playback(node_id)
+            .map(|playback| playback.source().lock().playing_clips())
+            .unwrap_or_default()
+    }
+
+    fn set_generator_config(&mut self, node_id: Uuid, config: GeneratorConfig) {
+        if let Some(generator) = self.generator(node_id) {
+            generator.source().lock().set_config(config.clone());
+        }
+
+        if let Some(node) = self.nodes.iter_mut().find(|n| n.id == node_id) {
+            node.generator = Some(config);
+        }
+    }
+
+    fn set_network_config(&mut self, node_id: Uuid, config: NetworkConfig) {
+        if let Some(sender) = self.network_sender(node_id) {
+            sender.lock().set_config(config.clone());
+        }
+
+        if let Some(receiver) = self.network_receiver(node_id) {
+            receiver.source().lock().set_config(config.clone());
+        }
+
+        if let Some(node) = self.nodes.iter_mut().find(|n| n.id == node_id) {
+            node.network = Some(config);
+        }
+    }
+
+    fn network_status(&self, node_id: Uuid) -> Option<NetworkStatus> {
+        self.network_sender(node_id)
+            .map(|sender| sender.lock().status())
+            .or_else(|| {
+                self.network_receiver(node_id)
+                    .map(|receiver| receiver.source().lock().status())
+            })
+    }
+
+    /// Plays the mix into the new cable, moving the connections like a default device change.
+    fn set_virtual_mic_config(&mut self, node_id: Uuid, config: VirtualMicConfig) {
+        if self.virtual_mic(node_id).is_none() {
+            return;
+        }
+
+        for conn in self.connections.of_node(node_id) {
+            if conn.dst_id == node_id && conn.state == ConnectionState::Active {
+                self.remove_node_connection(conn.src_id, conn.dst_id);
+                self.connections
+                    .set_state(conn.src_id, conn.dst_id, ConnectionState::Pending);
+            }
+        }
+
+        if let Some(node) = self.nodes.iter_mut().find(|n| n.id == node_id) {
+            node.virtual_mic = Some(config);
+        }
+
+        self.reconcile_connections();
+    }
+
     fn application_processes(&self) -> Vec<ProcessInfo> {
         let mut added_pids = HashSet::new();
         let mut processes = Vec::new();
@@ -631,6 +1992,7 @@ impl Context for Win32Context {
             .map(|d| DeviceInfo {
                 id: d.id(),
                 name: d.name().to_string(),
+                virtual_cable: is_virtual_cable(d.name()),
             })
             .collect::<Vec<_>>()
     }
@@ -643,7 +2005,12 @@ impl Context for Win32Context {
             .map(|d| DeviceInfo {
                 id: d.id(),
                 name: d.name().to_string(),
+                virtual_cable: is_virtual_cable(d.name()),
             })
             .collect::<Vec<_>>()
     }
+
+    fn take_new_processes(&mut self) -> Vec<ProcessInfo> {
+        std::mem::take(&mut self.new_processes)
+    }
 }
@@ -102,17 +102,7 @@ impl AudioDevice {
             let name: PROPVARIANT = properties.GetValue(&PKEY_Device_FriendlyName)?;
             let name = U16Str::from_slice(PropVariantToBSTR(&name)?.as_wide()).to_string_lossy();
 
-            let id = mmdevice.GetId().map(|id| {
-                if id.is_null() {
-                    Uuid::nil()
-                } else {
-                    pwstr_to_string(id)
-                        .split_once("}.{")
-                        .and_then(|(_, s)| s.split('}').next())
-                        .and_then(|s| Uuid::from_str(s).ok())
-                        .unwrap_or_else(Uuid::nil)
-                }
-            })?;
+            let id = mmdevice_uuid(&mmdevice)?;
 
             let endpoint_volume: Option<IAudioEndpointVolume> = mmdevice
                 .GetState()
@@ -318,6 +308,22 @@ impl AudioDevice {
     }
 }
 
+pub fn mmdevice_uuid(mmdevice: &IMMDevice) -> windows::core::Result<Uuid> {
+    unsafe {
+        mmdevice.GetId().map(|id| {
+            if id.is_null() {
+                Uuid::nil()
+            } else {
+                pwstr_to_string(id)
+                    .split_once("}.{")
+                    .and_then(|(_, s)| s.split('}').next())
+                    .and_then(|s| Uuid::from_str(s).ok())
+                    .unwrap_or_else(Uuid::nil)
+            }
+        })
+    }
+}
+
 pub trait MMDeviceExt {
     fn activate<T: Interface>(&self) -> windows::core::Result<T>;
 }
@@ -1,8 +1,12 @@
 use crate::com::ensure_com_initialized;
 use crate::custom::{DeviceNotification, DeviceNotifications};
-use crate::device::AudioDevice;
+use crate::device::{mmdevice_uuid, AudioDevice};
+use crate::Callback;
 use log::{trace, warn};
+use nodio_core::Uuid;
+use parking_lot::Mutex;
 use std::sync::mpsc::channel;
+use std::sync::Arc;
 use std::thread;
 use windows::Win32::Media::Audio::{
     EDataFlow, ERole, IMMDeviceCollection, IMMDeviceEnumerator, IMMNotificationClient,
@@ -13,6 +17,7 @@ use windows::Win32::System::Com::{CoCreateInstance, CLSCTX_ALL};
 pub struct AudioDeviceEnumerator {
     enumerator: IMMDeviceEnumerator,
     _device_notifications: IMMNotificationClient,
+    device_notification_callback: Arc<Mutex<Option<Callback<DeviceNotification>>>>,
 }
 
 impl AudioDeviceEnumerator {
@@ -30,16 +35,19 @@ impl AudioDeviceEnumerator {
                 .RegisterEndpointNotificationCallback(device_notifications.clone())
                 .expect("Failed to register endpoint notification callback");
 
-            thread::spawn(move || {
-                while let Ok(event) = device_notification_rx.recv() {
-                    trace!("Device event: {:?}", event);
+            let device_notification_callback: Arc<Mutex<Option<Callback<DeviceNotification>>>> =
+                Arc::new(Mutex::new(None));
 
-                    match event {
-                        DeviceNotification::DefaultDeviceChanged { .. } => {}
-                        DeviceNotification::DeviceAdded { .. } => {}
-                        DeviceNotification::DeviceRemoved { .. } => {}
-                        DeviceNotification::StateChanged { .. } => {}
-                        DeviceNotification::PropertyChanged { .. } => {}
+            thread::spawn({
+                let device_notification_callback = device_notification_callback.clone();
+
+                move || {
+                    while let Ok(event) = device_notification_rx.recv() {
+                        trace!("Device event: {:?}", event);
+
+                        if let Some(cb) = device_notification_callback.lock().as_ref() {
+                            cb(event);
+                        }
                     }
                 }
             });
@@ -47,19 +55,30 @@ impl AudioDeviceEnumerator {
             Ok(Self {
                 enumerator,
                 _device_notifications: device_notifications,
+                device_notification_callback,
             })
         }
     }
 
-    pub fn _default_audio_endpoint(
+    pub fn set_device_notification_callback<T>(&mut self, cb: T)
+    where
+        T: Fn(DeviceNotification) + Send + Sync + 'static,
+    {
+        let _ = self
+            .device_notification_callback
+            .lock()
+            .insert(Box::new(cb));
+    }
+
+    pub fn default_audio_endpoint_id(
         &self,
         data_flow: EDataFlow,
         role: ERole,
-    ) -> windows::core::Result<AudioDevice> {
+    ) -> windows::core::Result<Uuid> {
         unsafe {
             self.enumerator
                 .GetDefaultAudioEndpoint(data_flow, role)
-                .and_then(AudioDevice::new)
+                .and_then(|mmdevice| mmdevice_uuid(&mmdevice))
         }
     }
 
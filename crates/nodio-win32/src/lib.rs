@@ -4,8 +4,11 @@ mod context;
 mod custom;
 mod device;
 mod enumerator;
+mod listen;
 mod loopback;
 mod node;
+mod playback;
+mod recording;
 mod render;
 mod session;
 
@@ -0,0 +1,103 @@
+use std::sync::Arc;
+
+use nodio_core::Uuid;
+use nodio_engine::{ring_buffer, AudioSink, AudioSource, Consumer, Producer};
+use parking_lot::Mutex;
+use windows::core::Result;
+use windows::Win32::Media::Audio::IMMDevice;
+
+use crate::playback::{Playback, PLAYBACK_CHANNELS, PLAYBACK_SAMPLE_RATE};
+use crate::recording::RecordingSession;
+
+/// Captured audio buffered before it is played, so that the capture and the playback, which
+/// poll at the same interval, do not run each other dry (ms)
+const LISTEN_BUFFER_MS: usize = 20;
+/// Captured audio beyond which the oldest is dropped, for input devices that run faster than
+/// the output devices (ms)
+const LISTEN_MAX_BUFFER_MS: usize = 100;
+
+fn buffer_samples(duration_ms: usize) -> usize {
+    PLAYBACK_SAMPLE_RATE as usize * duration_ms / 1000 * PLAYBACK_CHANNELS as usize
+}
+
+/// Plays the audio of an input device on the output devices it is linked to. Unlike listening
+/// in Windows, the audio passes through the effects of the device and of its links.
+pub struct Listen {
+    playback: Playback<CapturedAudio>,
+    // Captures the device for as long as it is listened to
+    _capture: RecordingSession,
+}
+
+impl Listen {
+    pub fn start(src_id: Uuid, device: &IMMDevice) -> Result<Self> {
+        let (producer, consumer) = ring_buffer(buffer_samples(LISTEN_MAX_BUFFER_MS) * 2);
+
+        let capture = RecordingSession::device(
+            src_id,
+            src_id,
+            device,
+            Arc::new(Mutex::new(CaptureBuffer { producer })),
+            None,
+            0.0,
+            &[],
+        )?;
+        let playback = Playback::new(CapturedAudio {
+            consumer,
+            buffering: true,
+        });
+
+        Ok(Self {
+            playback,
+            _capture: capture,
+        })
+    }
+
+    pub fn playback(&self) -> &Playback<CapturedAudio> {
+        &self.playback
+    }
+}
+
+/// Passes the captured audio to the playback, in whole frames.
+struct CaptureBuffer {
+    producer: Producer,
+}
+
+impl AudioSink for CaptureBuffer {
+    fn write(&mut self, samples: &[f32]) {
+        let channels = PLAYBACK_CHANNELS as usize;
+        let free = self.producer.free() / channels * channels;
+
+        // The playback is behind, the rest is dropped
+        self.producer.push(&samples[..samples.len().min(free)]);
+    }
+}
+
+/// Audio of an input device, read by the playback of its listen links
+pub struct CapturedAudio {
+    consumer: Consumer,
+    /// Waiting for enough audio to start playing, after starting or running dry
+    buffering: bool,
+}
+
+impl AudioSource for CapturedAudio {
+    fn read(&mut self, buffer: &mut [f32]) -> usize {
+        let channels = PLAYBACK_CHANNELS as usize;
+
+        let excess = self
+            .consumer
+            .available()
+            .saturating_sub(buffer_samples(LISTEN_MAX_BUFFER_MS) + buffer.len());
+        self.consumer.skip(excess / channels * channels);
+
+        if self.buffering && self.consumer.available() < buffer_samples(LISTEN_BUFFER_MS) {
+            buffer.iter_mut().for_each(|sample| *sample = 0.0);
+            return 0;
+        }
+
+        let count = self.consumer.pop(buffer);
+        buffer[count..].iter_mut().for_each(|sample| *sample = 0.0);
+        self.buffering = count < buffer.len();
+
+        count / channels
+    }
+}
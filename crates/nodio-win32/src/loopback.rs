@@ -8,8 +8,10 @@ use std::sync::mpsc::Receiver;
 use std::sync::{mpsc, Arc};
 use std::task::{Context, Poll, Waker};
 
+use crate::recording::float_format;
 use crate::render::RenderClient;
-use nodio_core::Uuid;
+use nodio_core::{ChannelMatrix, EffectConfig, Uuid};
+use nodio_engine::{delay_frames, DelayLine, EffectChain};
 use pollster::FutureExt as _;
 use windows::core::{implement, IUnknown, Interface, Result, GUID, HRESULT};
 use windows::Win32::Foundation::HANDLE;
@@ -51,7 +53,7 @@ pub struct LoopbackCapture {
 }
 
 impl LoopbackCapture {
-    fn new(target_pid: u32, format: WAVEFORMATEXTENSIBLE) -> Self {
+    pub fn new(target_pid: u32, format: WAVEFORMATEXTENSIBLE) -> Self {
         Self {
             format,
             target_pid,
@@ -358,6 +360,9 @@ pub struct LoopbackSession {
     pub src_id: Uuid,
     pub dst_id: Uuid,
     capture: Box<LoopbackCapture>,
+    effect_chain: Arc<Mutex<EffectChain>>,
+    render_client: Arc<RenderClient>,
+    delay_ms: f32,
 }
 
 impl Drop for LoopbackSession {
@@ -374,12 +379,41 @@ impl LoopbackSession {
         dst_id: Uuid,
         process_id: u32,
         target_device: &IMMDevice,
+        effects: &[EffectConfig],
+        channel_matrix: Option<&ChannelMatrix>,
+        delay_ms: f32,
     ) -> Result<Self> {
-        let render_client = RenderClient::new(target_device)?;
-        let mut capture = Box::new(LoopbackCapture::new(
-            process_id,
-            *render_client.wave_format(),
-        ));
+        let render_client = Arc::new(RenderClient::new(target_device)?);
+        let wave_format = *render_client.wave_format();
+
+        let channels = wave_format.Format.nChannels as usize;
+        // The shared mode mix format is 32-bit float, which the effects operate on
+        let is_float = wave_format.Format.wBitsPerSample == 32;
+
+        // With a channel matrix the audio is captured in the layout of its inputs, and mapped to
+        // the channels of the device
+        let channel_matrix = channel_matrix.filter(|_| is_float).cloned();
+        let capture_format = match &channel_matrix {
+            Some(matrix) => float_format(
+                wave_format.Format.nSamplesPerSec,
+                matrix.inputs.channels() as u16,
+            ),
+            None => wave_format,
+        };
+        let capture_channels = capture_format.Format.nChannels as usize;
+        let mut capture = Box::new(LoopbackCapture::new(process_id, capture_format));
+
+        let mut effect_chain = EffectChain::new(wave_format.Format.nSamplesPerSec as f32);
+        effect_chain.set_effects(effects);
+        let effect_chain = Arc::new(Mutex::new(effect_chain));
+
+        let callback_effect_chain = effect_chain.clone();
+        let callback_render_client = render_client.clone();
+        let buffer = Mutex::new(Vec::<f32>::new());
+
+        let sample_rate = wave_format.Format.nSamplesPerSec;
+        let delay_line = Mutex::new(DelayLine::with_delay_ms(channels, sample_rate, delay_ms));
+        let delay = delay_frames(delay_ms, sample_rate) > 0;
 
         let frame_callback = Box::new(move |capture: &mut LoopbackCapture| unsafe {
             let frames = capture
@@ -392,7 +426,37 @@ impl LoopbackSession {
 
             let packet = capture.get_buffer().expect("Failed to get buffer");
 
-            render_client.render_frames(packet.data, packet.frames).ok();
+            let mut effect_chain = callback_effect_chain.lock();
+
+            if is_float && (!effect_chain.is_empty() || channel_matrix.is_some() || delay) {
+                let mut buffer = buffer.lock();
+                let samples = std::slice::from_raw_parts(
+                    packet.data as *const f32,
+                    packet.frames as usize * capture_channels,
+                );
+
+                match &channel_matrix {
+                    Some(matrix) => {
+                        buffer.resize(packet.frames as usize * channels, 0.0);
+                        matrix.apply(samples, capture_channels, &mut buffer, channels);
+                    }
+                    None => {
+                        buffer.clear();
+                        buffer.extend_from_slice(samples);
+                    }
+                }
+
+                effect_chain.process(&mut buffer, channels);
+                delay_line.lock().process(&mut buffer);
+
+                callback_render_client
+                    .render_frames(buffer.as_ptr() as *const u8, packet.frames)
+                    .ok();
+            } else {
+                callback_render_client
+                    .render_frames(packet.data, packet.frames)
+                    .ok();
+            }
 
             capture
                 .release_buffer(frames)
@@ -407,6 +471,28 @@ impl LoopbackSession {
             src_id,
             dst_id,
             capture,
+            effect_chain,
+            render_client,
+            delay_ms,
         })
     }
+
+    pub fn set_effects(&self, effects: &[EffectConfig]) {
+        self.effect_chain.lock().set_effects(effects);
+    }
+
+    pub fn set_effect_param(&self, effect_id: Uuid, param_idx: usize, value: f32) {
+        self.effect_chain
+            .lock()
+            .set_param(effect_id, param_idx, value);
+    }
+
+    pub fn save_effect_states(&self) -> Vec<(Uuid, Vec<u8>)> {
+        self.effect_chain.lock().save_states()
+    }
+
+    /// Time from capturing the audio until it is played by the target device (ms)
+    pub fn latency_ms(&self) -> Option<f32> {
+        Some(self.render_client.padding_ms()? + self.delay_ms)
+    }
 }
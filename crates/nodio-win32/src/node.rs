@@ -5,6 +5,10 @@ pub enum NodeConnectionKind {
     DefaultEndpoint,
     Loopback,
     Listen,
+    /// Captured into a recorder
+    Record,
+    /// Rendered by a file player
+    Play,
 }
 
 #[derive(Debug, Copy, Clone)]
@@ -0,0 +1,281 @@
+use std::ptr::null;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use log::warn;
+use nodio_core::{ChannelMatrix, EffectConfig, Uuid};
+use nodio_engine::{AudioSource, DelayLine, EffectChain};
+use parking_lot::Mutex;
+use windows::core::Result;
+use windows::Win32::Media::Audio::{
+    IAudioClient, IAudioRenderClient, IMMDevice, AUDCLNT_SHAREMODE_SHARED,
+    AUDCLNT_STREAMFLAGS_AUTOCONVERTPCM, AUDCLNT_STREAMFLAGS_SRC_DEFAULT_QUALITY,
+    WAVEFORMATEXTENSIBLE,
+};
+
+use crate::com::ensure_com_initialized;
+use crate::device::MMDeviceExt;
+use crate::recording::float_format;
+
+/// Format the file players and generators render in. The audio is converted to the devices by
+/// the system.
+pub const PLAYBACK_SAMPLE_RATE: u32 = 48000;
+pub const PLAYBACK_CHANNELS: u16 = 2;
+
+/// Requested buffer duration of the render streams, in 100 ns units
+const RENDER_BUFFER_DURATION: i64 = 1_000_000;
+
+/// Renders a file player or generator to the output devices it is connected to.
+///
+/// All devices get the same audio, the amount of which is taken from the device that needs the
+/// most. Devices with diverging clocks are not resampled against each other.
+pub struct Playback<S> {
+    source: Arc<Mutex<S>>,
+    targets: Arc<RenderTargets>,
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+/// Output devices a playback renders to, each with the settings of its link.
+#[derive(Default)]
+pub struct RenderTargets {
+    targets: Mutex<Vec<RenderTarget>>,
+}
+
+struct RenderTarget {
+    dst_id: Uuid,
+    audio_client: IAudioClient,
+    render_client: IAudioRenderClient,
+    buffer_frames: u32,
+    /// Maps the channels of the source to the ones the target is opened with
+    channel_matrix: Option<ChannelMatrix>,
+    channels: usize,
+    mapped: Vec<f32>,
+    /// Effects of the source node, followed by the effects of the link
+    effects: EffectChain,
+    delay: DelayLine,
+}
+
+// The audio clients are free-threaded
+unsafe impl Send for RenderTarget {}
+
+impl Drop for RenderTarget {
+    fn drop(&mut self) {
+        if let Err(err) = unsafe { self.audio_client.Stop() } {
+            warn!("Could not stop render stream: {}", err);
+        }
+    }
+}
+
+impl<S> Drop for Playback<S> {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+
+        if let Some(thread) = self.thread.take() {
+            thread.join().ok();
+        }
+    }
+}
+
+impl<S: AudioSource + Send + 'static> Playback<S> {
+    pub fn new(source: S) -> Self {
+        let source = Arc::new(Mutex::new(source));
+        let targets = Arc::new(RenderTargets::default());
+        let stop = Arc::new(AtomicBool::new(false));
+
+        let thread = thread::spawn({
+            let source = source.clone();
+            let targets = targets.clone();
+            let stop = stop.clone();
+
+            move || {
+                ensure_com_initialized();
+
+                let mut buffer = Vec::new();
+
+                while !stop.load(Ordering::Relaxed) {
+                    let mut targets = targets.targets.lock();
+
+                    if let Err(err) = unsafe { render(&*source, &mut targets, &mut buffer) } {
+                        warn!("Playback failed: {}", err);
+                        targets.clear();
+                    }
+
+                    drop(targets);
+                    thread::sleep(Duration::from_millis(10));
+                }
+            }
+        });
+
+        Self {
+            source,
+            targets,
+            stop,
+            thread: Some(thread),
+        }
+    }
+
+    pub fn source(&self) -> &Arc<Mutex<S>> {
+        &self.source
+    }
+
+    pub fn targets(&self) -> &RenderTargets {
+        &self.targets
+    }
+}
+
+impl RenderTargets {
+    pub fn add(
+        &self,
+        dst_id: Uuid,
+        device: &IMMDevice,
+        channel_matrix: Option<ChannelMatrix>,
+        delay_ms: f32,
+        effects: &[EffectConfig],
+    ) -> Result<()> {
+        let channels = channel_matrix
+            .as_ref()
+            .map_or(PLAYBACK_CHANNELS as usize, |matrix| {
+                matrix.outputs.channels()
+            });
+        let format = float_format(PLAYBACK_SAMPLE_RATE, channels as u16);
+
+        let target = unsafe {
+            let audio_client = device.activate::<IAudioClient>()?;
+            audio_client.Initialize(
+                AUDCLNT_SHAREMODE_SHARED,
+                AUDCLNT_STREAMFLAGS_AUTOCONVERTPCM | AUDCLNT_STREAMFLAGS_SRC_DEFAULT_QUALITY,
+                RENDER_BUFFER_DURATION,
+                0,
+                &format as *const WAVEFORMATEXTENSIBLE as _,
+                null(),
+            )?;
+            let render_client = audio_client.GetService::<IAudioRenderClient>()?;
+            let buffer_frames = audio_client.GetBufferSize()?;
+            audio_client.Start()?;
+
+            let mut effect_chain = EffectChain::new(PLAYBACK_SAMPLE_RATE as f32);
+            effect_chain.set_effects(effects);
+
+            RenderTarget {
+                dst_id,
+                audio_client,
+                render_client,
+                buffer_frames,
+                channel_matrix,
+                channels,
+                mapped: Vec::new(),
+                effects: effect_chain,
+                delay: DelayLine::with_delay_ms(channels, PLAYBACK_SAMPLE_RATE, delay_ms),
+            }
+        };
+
+        let mut targets = self.targets.lock();
+        targets.retain(|target| target.dst_id != dst_id);
+        targets.push(target);
+
+        Ok(())
+    }
+
+    pub fn remove(&self, dst_id: Uuid) {
+        self.targets.lock().retain(|target| target.dst_id != dst_id);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.targets.lock().is_empty()
+    }
+
+    pub fn set_effects(&self, dst_id: Uuid, effects: &[EffectConfig]) {
+        if let Some(target) = self
+            .targets
+            .lock()
+            .iter_mut()
+            .find(|target| target.dst_id == dst_id)
+        {
+            target.effects.set_effects(effects);
+        }
+    }
+
+    pub fn set_effect_param(&self, dst_id: Uuid, effect_id: Uuid, param_idx: usize, value: f32) {
+        if let Some(target) = self
+            .targets
+            .lock()
+            .iter_mut()
+            .find(|target| target.dst_id == dst_id)
+        {
+            target.effects.set_param(effect_id, param_idx, value);
+        }
+    }
+
+    pub fn save_effect_states(&self, dst_id: Uuid) -> Vec<(Uuid, Vec<u8>)> {
+        self.targets
+            .lock()
+            .iter_mut()
+            .find(|target| target.dst_id == dst_id)
+            .map(|target| target.effects.save_states())
+            .unwrap_or_default()
+    }
+
+    /// Time from reading the audio until it is played by the target device (ms)
+    pub fn latency_ms(&self, dst_id: Uuid) -> Option<f32> {
+        let targets = self.targets.lock();
+        let target = targets.iter().find(|target| target.dst_id == dst_id)?;
+
+        let padding = unsafe { target.audio_client.GetCurrentPadding() }.ok()?;
+        let frames = padding as usize + target.delay.delay();
+        Some(frames as f32 / PLAYBACK_SAMPLE_RATE as f32 * 1000.0)
+    }
+}
+
+unsafe fn render<S: AudioSource>(
+    source: &Mutex<S>,
+    targets: &mut [RenderTarget],
+    buffer: &mut Vec<f32>,
+) -> Result<()> {
+    let channels = PLAYBACK_CHANNELS as usize;
+
+    let mut free = Vec::with_capacity(targets.len());
+    for target in targets.iter() {
+        free.push(target.buffer_frames - target.audio_client.GetCurrentPadding()?);
+    }
+
+    let frames = match free.iter().max() {
+        Some(&frames) if frames > 0 => frames,
+        _ => return Ok(()),
+    };
+
+    buffer.resize(frames as usize * channels, 0.0);
+    source.lock().read(buffer);
+
+    for (target, free) in targets.iter_mut().zip(free) {
+        let frames = frames.min(free);
+        if frames == 0 {
+            continue;
+        }
+
+        let input = &buffer[..frames as usize * channels];
+        let samples = if target.channel_matrix.is_some()
+            || target.delay.delay() > 0
+            || !target.effects.is_empty()
+        {
+            target.mapped.resize(frames as usize * target.channels, 0.0);
+            match &target.channel_matrix {
+                Some(matrix) => matrix.apply(input, channels, &mut target.mapped, target.channels),
+                None => target.mapped.copy_from_slice(input),
+            }
+            target.effects.process(&mut target.mapped, target.channels);
+            target.delay.process(&mut target.mapped);
+            &target.mapped
+        } else {
+            input
+        };
+
+        let data = target.render_client.GetBuffer(frames)?;
+        std::ptr::copy_nonoverlapping(samples.as_ptr(), data as *mut f32, samples.len());
+        target.render_client.ReleaseBuffer(frames, 0)?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,381 @@
+use std::ptr::{null, null_mut};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use log::warn;
+use nodio_core::{ChannelMatrix, EffectConfig, Uuid};
+use nodio_engine::{AudioSink, DelayLine, EffectChain};
+use parking_lot::Mutex;
+use windows::core::Result;
+use windows::Win32::Media::Audio::{
+    IAudioCaptureClient, IAudioClient, IMMDevice, AUDCLNT_BUFFERFLAGS_SILENT,
+    AUDCLNT_SHAREMODE_SHARED, AUDCLNT_STREAMFLAGS_AUTOCONVERTPCM, AUDCLNT_STREAMFLAGS_LOOPBACK,
+    AUDCLNT_STREAMFLAGS_SRC_DEFAULT_QUALITY, WAVEFORMATEX, WAVEFORMATEXTENSIBLE,
+};
+use windows::Win32::Media::Multimedia::WAVE_FORMAT_IEEE_FLOAT;
+
+use crate::com::ensure_com_initialized;
+use crate::device::MMDeviceExt;
+use crate::loopback::LoopbackCapture;
+
+/// Sample rate of all recordings. The captured audio is converted by the system.
+pub const RECORDING_SAMPLE_RATE: u32 = 48000;
+pub const RECORDING_CHANNELS: u16 = 2;
+/// Length of the audio buffered for the writer thread of a recorder, in seconds
+pub const RECORDING_BUFFER_SECS: f64 = 2.0;
+
+/// Requested buffer duration of device captures, in 100 ns units
+const DEVICE_BUFFER_DURATION: i64 = 2_000_000;
+
+/// Interleaved 32-bit float format, which the system converts the audio streams from and to.
+pub fn float_format(sample_rate: u32, channels: u16) -> WAVEFORMATEXTENSIBLE {
+    let block_align = channels * 4;
+
+    let mut format: WAVEFORMATEXTENSIBLE = unsafe { std::mem::zeroed() };
+    format.Format = WAVEFORMATEX {
+        wFormatTag: WAVE_FORMAT_IEEE_FLOAT as u16,
+        nChannels: channels,
+        nSamplesPerSec: sample_rate,
+        nAvgBytesPerSec: sample_rate * block_align as u32,
+        nBlockAlign: block_align,
+        wBitsPerSample: 32,
+        cbSize: 0,
+    };
+
+    format
+}
+
+enum Capture {
+    Loopback(Box<LoopbackCapture>),
+    Device(DeviceCapture),
+}
+
+/// Captures the audio of a node into a recorder or network sender.
+pub struct RecordingSession {
+    pub src_id: Uuid,
+    pub dst_id: Uuid,
+    capture: Capture,
+    effect_chain: Arc<Mutex<EffectChain>>,
+}
+
+impl Drop for RecordingSession {
+    fn drop(&mut self) {
+        if let Capture::Loopback(capture) = &mut self.capture {
+            unsafe {
+                capture.stop();
+            }
+        }
+    }
+}
+
+impl RecordingSession {
+    /// Captures the audio of an application with process loopback capture.
+    pub fn application<S: AudioSink + Send + 'static>(
+        src_id: Uuid,
+        dst_id: Uuid,
+        process_id: u32,
+        sink: Arc<Mutex<S>>,
+        channel_matrix: Option<ChannelMatrix>,
+        delay_ms: f32,
+        effects: &[EffectConfig],
+    ) -> Self {
+        let mut capture = Box::new(LoopbackCapture::new(
+            process_id,
+            float_format(RECORDING_SAMPLE_RATE, RECORDING_CHANNELS),
+        ));
+        let channels = RECORDING_CHANNELS as usize;
+        let effect_chain = effect_chain(effects);
+        let sink = Mutex::new(MappedSink::new(
+            sink,
+            channel_matrix,
+            delay_ms,
+            effect_chain.clone(),
+        ));
+
+        let frame_callback = Box::new(move |capture: &mut LoopbackCapture| unsafe {
+            let frames = capture
+                .get_next_packet_size()
+                .expect("Failed to get next packet size");
+
+            if frames == 0 {
+                return;
+            }
+
+            let packet = capture.get_buffer().expect("Failed to get buffer");
+            let samples = std::slice::from_raw_parts(
+                packet.data as *const f32,
+                packet.frames as usize * channels,
+            );
+
+            sink.lock().write(samples);
+
+            capture
+                .release_buffer(frames)
+                .expect("Failed to release buffer");
+        });
+
+        unsafe {
+            capture.start(frame_callback);
+        }
+
+        Self {
+            src_id,
+            dst_id,
+            capture: Capture::Loopback(capture),
+            effect_chain,
+        }
+    }
+
+    /// Captures the audio of an input device.
+    pub fn device<S: AudioSink + Send + 'static>(
+        src_id: Uuid,
+        dst_id: Uuid,
+        device: &IMMDevice,
+        sink: Arc<Mutex<S>>,
+        channel_matrix: Option<ChannelMatrix>,
+        delay_ms: f32,
+        effects: &[EffectConfig],
+    ) -> Result<Self> {
+        let effect_chain = effect_chain(effects);
+        let mut sink = MappedSink::new(sink, channel_matrix, delay_ms, effect_chain.clone());
+        let capture = DeviceCapture::start(device, false, move |samples| sink.write(samples))?;
+
+        Ok(Self {
+            src_id,
+            dst_id,
+            capture: Capture::Device(capture),
+            effect_chain,
+        })
+    }
+
+    /// Captures what an output device plays with loopback capture. Nothing is captured while the
+    /// device plays nothing.
+    pub fn output_device<S: AudioSink + Send + 'static>(
+        src_id: Uuid,
+        dst_id: Uuid,
+        device: &IMMDevice,
+        sink: Arc<Mutex<S>>,
+        channel_matrix: Option<ChannelMatrix>,
+        delay_ms: f32,
+        effects: &[EffectConfig],
+    ) -> Result<Self> {
+        let effect_chain = effect_chain(effects);
+        let mut sink = MappedSink::new(sink, channel_matrix, delay_ms, effect_chain.clone());
+        let capture = DeviceCapture::start(device, true, move |samples| sink.write(samples))?;
+
+        Ok(Self {
+            src_id,
+            dst_id,
+            capture: Capture::Device(capture),
+            effect_chain,
+        })
+    }
+
+    pub fn set_effects(&self, effects: &[EffectConfig]) {
+        self.effect_chain.lock().set_effects(effects);
+    }
+
+    pub fn set_effect_param(&self, effect_id: Uuid, param_idx: usize, value: f32) {
+        self.effect_chain
+            .lock()
+            .set_param(effect_id, param_idx, value);
+    }
+
+    pub fn save_effect_states(&self) -> Vec<(Uuid, Vec<u8>)> {
+        self.effect_chain.lock().save_states()
+    }
+}
+
+fn effect_chain(effects: &[EffectConfig]) -> Arc<Mutex<EffectChain>> {
+    let mut effect_chain = EffectChain::new(RECORDING_SAMPLE_RATE as f32);
+    effect_chain.set_effects(effects);
+    Arc::new(Mutex::new(effect_chain))
+}
+
+/// Writes the captured audio to a sink, through the channel matrix, effects and delay of the
+/// connection.
+struct MappedSink<S> {
+    sink: Arc<Mutex<S>>,
+    channel_matrix: Option<ChannelMatrix>,
+    effect_chain: Arc<Mutex<EffectChain>>,
+    delay: DelayLine,
+    mapped: Vec<f32>,
+}
+
+impl<S: AudioSink> MappedSink<S> {
+    fn new(
+        sink: Arc<Mutex<S>>,
+        channel_matrix: Option<ChannelMatrix>,
+        delay_ms: f32,
+        effect_chain: Arc<Mutex<EffectChain>>,
+    ) -> Self {
+        let channels = RECORDING_CHANNELS as usize;
+
+        Self {
+            sink,
+            channel_matrix,
+            effect_chain,
+            delay: DelayLine::with_delay_ms(channels, RECORDING_SAMPLE_RATE, delay_ms),
+            mapped: Vec::new(),
+        }
+    }
+
+    fn write(&mut self, samples: &[f32]) {
+        let channels = RECORDING_CHANNELS as usize;
+        let mut effect_chain = self.effect_chain.lock();
+
+        if self.channel_matrix.is_none() && self.delay.delay() == 0 && effect_chain.is_empty() {
+            self.sink.lock().write(samples);
+            return;
+        }
+
+        self.mapped.resize(samples.len(), 0.0);
+        match &self.channel_matrix {
+            Some(matrix) => matrix.apply(samples, channels, &mut self.mapped, channels),
+            None => self.mapped.copy_from_slice(samples),
+        }
+        effect_chain.process(&mut self.mapped, channels);
+        self.delay.process(&mut self.mapped);
+        self.sink.lock().write(&self.mapped);
+    }
+}
+
+struct CaptureClients {
+    audio_client: IAudioClient,
+    capture_client: IAudioCaptureClient,
+}
+
+// The audio clients are free-threaded
+unsafe impl Send for CaptureClients {}
+
+/// Polls the captured audio of a device on a thread of its own. With loopback, the device is an
+/// output device, and what it plays is captured.
+struct DeviceCapture {
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Drop for DeviceCapture {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+
+        if let Some(thread) = self.thread.take() {
+            thread.join().ok();
+        }
+    }
+}
+
+impl DeviceCapture {
+    fn start<F>(device: &IMMDevice, loopback: bool, mut callback: F) -> Result<Self>
+    where
+        F: FnMut(&[f32]) + Send + 'static,
+    {
+        let format = float_format(RECORDING_SAMPLE_RATE, RECORDING_CHANNELS);
+
+        let mut stream_flags =
+            AUDCLNT_STREAMFLAGS_AUTOCONVERTPCM | AUDCLNT_STREAMFLAGS_SRC_DEFAULT_QUALITY;
+        if loopback {
+            stream_flags |= AUDCLNT_STREAMFLAGS_LOOPBACK;
+        }
+
+        let clients = unsafe {
+            let audio_client = device.activate::<IAudioClient>()?;
+            audio_client.Initialize(
+                AUDCLNT_SHAREMODE_SHARED,
+                stream_flags,
+                DEVICE_BUFFER_DURATION,
+                0,
+                &format as *const WAVEFORMATEXTENSIBLE as _,
+                null(),
+            )?;
+            let capture_client = audio_client.GetService::<IAudioCaptureClient>()?;
+            audio_client.Start()?;
+
+            CaptureClients {
+                audio_client,
+                capture_client,
+            }
+        };
+
+        let stop = Arc::new(AtomicBool::new(false));
+
+        let thread = thread::spawn({
+            let stop = stop.clone();
+            let channels = format.Format.nChannels as usize;
+
+            move || {
+                ensure_com_initialized();
+
+                let clients = clients;
+                let mut silence = Vec::new();
+
+                while !stop.load(Ordering::Relaxed) {
+                    if let Err(err) = unsafe {
+                        read_packets(
+                            &clients.capture_client,
+                            channels,
+                            &mut silence,
+                            &mut callback,
+                        )
+                    } {
+                        warn!("Device capture failed: {}", err);
+                        break;
+                    }
+
+                    thread::sleep(Duration::from_millis(10));
+                }
+
+                if let Err(err) = unsafe { clients.audio_client.Stop() } {
+                    warn!("Could not stop device capture: {}", err);
+                }
+            }
+        });
+
+        Ok(Self {
+            stop,
+            thread: Some(thread),
+        })
+    }
+}
+
+unsafe fn read_packets<F>(
+    capture_client: &IAudioCaptureClient,
+    channels: usize,
+    silence: &mut Vec<f32>,
+    callback: &mut F,
+) -> Result<()>
+where
+    F: FnMut(&[f32]),
+{
+    while capture_client.GetNextPacketSize()? > 0 {
+        let mut data_ptr = null_mut::<u8>();
+        let mut frames: u32 = 0;
+        let mut flags: u32 = 0;
+        let mut device_position: u64 = 0;
+        let mut qpc_position: u64 = 0;
+
+        capture_client.GetBuffer(
+            &mut data_ptr as *mut *mut u8,
+            &mut frames as *mut u32,
+            &mut flags as *mut u32,
+            &mut device_position as *mut u64,
+            &mut qpc_position as *mut u64,
+        )?;
+
+        let len = frames as usize * channels;
+
+        if flags & AUDCLNT_BUFFERFLAGS_SILENT.0 as u32 != 0 {
+            silence.clear();
+            silence.resize(len, 0.0);
+            callback(silence);
+        } else {
+            callback(std::slice::from_raw_parts(data_ptr as *const f32, len));
+        }
+
+        capture_client.ReleaseBuffer(frames)?;
+    }
+
+    Ok(())
+}
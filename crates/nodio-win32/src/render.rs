@@ -51,6 +51,12 @@ impl RenderClient {
         &self.wave_format
     }
 
+    /// Time it takes until the audio that is queued in the buffer is played (ms)
+    pub fn padding_ms(&self) -> Option<f32> {
+        let padding = unsafe { self.audio_client.GetCurrentPadding() }.ok()?;
+        Some(padding as f32 / self.wave_format.Format.nSamplesPerSec as f32 * 1000.0)
+    }
+
     pub fn render_frames(&self, data_in: *const u8, frames: u32) -> windows::core::Result<()> {
         unsafe {
             let padding = self.audio_client.GetCurrentPadding()?;